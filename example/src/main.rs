@@ -1,57 +1,55 @@
 extern crate ggez;
+extern crate nalgebra;
 extern crate markedly;
 extern crate markedly_ggez;
 
 use std::env;
 use std::path;
 
-use ggez::{Context, GameResult, GameError};
-use ggez::conf::{Conf, WindowMode, WindowSetup};
-use ggez::event::{self, EventHandler, MouseButton, MouseState};
-use ggez::graphics::{self, Point2, Vector2};
+use ggez::{Context, ContextBuilder, GameResult, GameError};
+use ggez::conf::{WindowMode, WindowSetup};
+use ggez::event::{self, EventHandler, MouseButton};
+use ggez::graphics::{Canvas, Color, Rect};
+use ggez::input::keyboard::{KeyCode, KeyInput};
+use nalgebra::{Point2, Vector2};
 
 use markedly::class::{ComponentClasses};
-use markedly::input::{Input};
+use markedly::input::{Input, Modifiers, RawEvent};
 use markedly::scripting::{ScriptRuntime, ScriptTable};
 use markedly::template::{Template, Style};
-use markedly::{Context as UiContext, Ui, Tree};
+use markedly::{Context as UiContext, Ui, Tree, Warnings, Localization};
 
-use markedly_ggez::{GgezRenderer, GgezCache, emtg};
+use markedly_ggez::{
+    GgezRenderer, GgezCache, GgezClipboard, emtg,
+    modifiers_convert, keycode_convert, mouse_button_convert,
+};
 
 fn main() {
     // Set up the ggez context
-    let mut c = Conf::new();
-    c.window_mode = WindowMode {
-        width: 1280,
-        height: 720,
-        .. Default::default()
-    };
-    c.window_setup = WindowSetup {
-        title: "Markedly Example".into(),
-        .. Default::default()
-    };
-    let ctx = &mut Context::load_from_conf("example", "markedly", c).unwrap();
+    let mut builder = ContextBuilder::new("example", "markedly")
+        .window_mode(WindowMode::default().dimensions(1280.0, 720.0))
+        .window_setup(WindowSetup::default().title("Markedly Example"));
 
     // We add the CARGO_MANIFEST_DIR/resources do the filesystems paths so we we look in the cargo
     // project for files.
     if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
         let mut path = path::PathBuf::from(manifest_dir);
         path.push("resources");
-        ctx.filesystem.mount(&path, true);
+        builder = builder.add_resource_path(path);
     }
 
-    // Initialize and run the game
-    let result = MainState::new(ctx)
-        .and_then(|mut s| event::run(ctx, &mut s));
-
-    // Check if it ran successfully
-    if let Err(e) = result {
-        match e {
-            GameError::UnknownError(text) => println!("Fatal:\n{}", text),
-            e => println!("Fatal: {}", e)
-        }
-    } else {
-        println!("Game exited cleanly");
+    // Initialize the game
+    let result = builder.build()
+        .and_then(|(mut ctx, event_loop)| {
+            let state = MainState::new(&mut ctx)?;
+            Ok((ctx, event_loop, state))
+        });
+
+    // Check if it initialized successfully and run it, which exits when the game is closed
+    match result {
+        Ok((ctx, event_loop, state)) => event::run(ctx, event_loop, state),
+        Err(GameError::CustomError(text)) => println!("Fatal:\n{}", text),
+        Err(e) => println!("Fatal: {}", e),
     }
 }
 
@@ -63,6 +61,9 @@ struct MainState {
     ui_input: Input,
     ui_cache: GgezCache,
     ui_root: Tree,
+    modifiers: Modifiers,
+    /// The number of window pixels per UI unit, which is more than one on high DPI displays.
+    scale_factor: f32,
 
     model: ScriptTable,
     are_you_sure: bool,
@@ -70,23 +71,41 @@ struct MainState {
 
 impl MainState {
     pub fn new(ctx: &mut Context) -> GameResult<Self> {
-        let screen_size = Vector2::new(1280.0, 720.0);
+        // The UI is laid out in logical units rather than pixels, so it's the same size on high
+        // DPI displays and the renderer draws it at the display's resolution
+        let scale_factor = ctx.gfx.window().scale_factor() as f32;
+        let (width, height) = ctx.gfx.drawable_size();
+        let screen_size = Vector2::new(width / scale_factor, height / scale_factor);
 
         // Register all the component classes, this makes them available to be used in templates.
         let mut classes = ComponentClasses::new();
         classes.register::<markedly::class::ContainerClass>("container");
         classes.register::<markedly::class::ButtonClass>("button");
+        classes.register::<markedly::class::ImageClass>("image");
+        classes.register::<markedly::class::ScrollbarClass>("scrollbar");
+        classes.register::<markedly::class::ScrollViewClass>("scroll-view");
+        classes.register::<markedly::class::TextInputClass>("text-input");
+        classes.register::<markedly::class::RadialMenuClass>("radial-menu");
+        classes.register::<markedly::class::SplitPaneClass>("split-pane");
+        classes.register::<markedly::class::CollapsibleClass>("collapsible");
+        classes.register::<markedly::class::VectorImageClass>("vector-image");
+        classes.register::<markedly::class::GaugeClass>("gauge");
+        classes.register::<markedly::class::ChartClass>("chart");
 
         // Set up the scripting runtime.
         // TODO: Here you can make custom helper functions available to templates.
         let runtime = ScriptRuntime::new();
 
         // The context is a bundle of the systems needed for a UI to function.
-        let ui_context = UiContext { classes, runtime, };
+        let warnings = Warnings::new();
+        let localization = Localization::new("en");
+        let ui_context = UiContext { classes, runtime, warnings, localization, };
 
         // This UI will make use of input. If your UI will not use input, for example if your UI is
         // an in-game screen, you don't need this.
-        let ui_input = Input::new();
+        // Giving it the system clipboard lets text inputs copy and paste to other applications.
+        let mut ui_input = Input::new();
+        ui_input.set_clipboard(Box::new(GgezClipboard::new().map_err(emtg)?));
 
         // Set up the UI cache.
         // This will keep track of rendering data, as well as resources to be used by templates.
@@ -96,12 +115,17 @@ impl MainState {
 
         // Load in a style template.
         // This defines some default styles and style classes to be used when displaying templates.
-        let style = Style::from_reader(ctx.filesystem.open("/mark/_style.mark")?)?;
+        // Naming its source lets errors point at the file an attribute was set in.
+        let mut style = Style::from_reader(ctx.fs.open("/mark/_style.mark")?)
+            .map_err(GameError::CustomError)?;
+        style.set_source_name("/mark/_style.mark");
 
         // Load in the root template.
         // This template defines what the actual UI will look like, it contains components in the
         // layout you want them to be in, and with the attributes you want them to have.
-        let root_template = Template::from_reader(ctx.filesystem.open("/mark/ui.mark")?)?;
+        let mut root_template = Template::from_reader(ctx.fs.open("/mark/ui.mark")?)
+            .map_err(GameError::CustomError)?;
+        root_template.set_source_name("/mark/ui.mark");
 
         // Optionally we can provide a model with data to be used by the template.
         let mut model = ScriptTable::new();
@@ -121,11 +145,18 @@ impl MainState {
             ui_input,
             ui_cache,
             ui_root,
+            modifiers: Modifiers::none(),
+            scale_factor,
 
             model,
             are_you_sure: false,
         })
     }
+
+    /// Converts a position in window pixels, as ggez gives them, to UI units.
+    fn ui_position(&self, x: f32, y: f32) -> Point2<f32> {
+        Point2::new(x, y) / self.scale_factor
+    }
 }
 
 impl EventHandler for MainState {
@@ -135,7 +166,7 @@ impl EventHandler for MainState {
                 "hello-pressed" => println!("Hello From UI!"),
                 "goodbye-pressed" => {
                     if self.are_you_sure {
-                        ctx.quit()?;
+                        ctx.request_quit();
                     } else {
                         self.are_you_sure = true;
 
@@ -149,41 +180,108 @@ impl EventHandler for MainState {
             }
         }
 
+        // Mistakes in the markup that don't stop the UI from working are reported as warnings
+        while let Some(warning) = self.ui.warnings().next() {
+            println!("UI warning: {:?}", warning);
+        }
+
+        let delta = ctx.time.delta().as_secs_f32();
+        self.ui_input.update(delta, &mut self.ui);
+        self.ui.update(delta, &self.ui_context).map_err(emtg)?;
+
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-        graphics::set_background_color(ctx, (0, 0, 0).into());
-        graphics::clear(ctx);
+        let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
+        let size = self.ui.target_size();
+        canvas.set_screen_coordinates(Rect::new(0.0, 0.0, size.x, size.y));
 
         // Draw the UI
         {
-            let mut renderer = GgezRenderer::new(ctx, &mut self.ui_cache);
+            let mut renderer = GgezRenderer::new(ctx, &mut self.ui_cache, &mut canvas);
             markedly::render::render(&mut renderer, &mut self.ui).map_err(emtg)?;
         }
 
-        graphics::present(ctx);
-        Ok(())
+        canvas.finish(ctx)
     }
 
     fn mouse_button_down_event(
         &mut self, _ctx: &mut Context,
-        _button: MouseButton, x: i32, y: i32
-    ) {
-        self.ui_input.handle_drag_started(Point2::new(x as f32, y as f32), &mut self.ui);
+        button: MouseButton, x: f32, y: f32
+    ) -> GameResult<()> {
+        self.ui_input.handle_event(RawEvent::Button {
+            position: self.ui_position(x, y),
+            button: mouse_button_convert(button),
+            pressed: true,
+            modifiers: self.modifiers,
+        }, &mut self.ui);
+        Ok(())
     }
 
     fn mouse_button_up_event(
         &mut self, _ctx: &mut Context,
-        _button: MouseButton, x: i32, y: i32
-    ) {
-        self.ui_input.handle_drag_ended(Point2::new(x as f32, y as f32), &mut self.ui);
+        button: MouseButton, x: f32, y: f32
+    ) -> GameResult<()> {
+        self.ui_input.handle_event(RawEvent::Button {
+            position: self.ui_position(x, y),
+            button: mouse_button_convert(button),
+            pressed: false,
+            modifiers: self.modifiers,
+        }, &mut self.ui);
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self, _ctx: &mut Context,
+        input: KeyInput, repeat: bool
+    ) -> GameResult<()> {
+        self.modifiers = modifiers_convert(input.mods);
+
+        let keycode = match input.keycode {
+            Some(keycode) => keycode,
+            None => return Ok(()),
+        };
+
+        // Shows the bounds of all components, to help with finding layout problems
+        if keycode == KeyCode::F12 && !repeat {
+            let enabled = !self.ui.debug_overlay();
+            self.ui.set_debug_overlay(enabled);
+        }
+
+        self.ui_input.handle_event(RawEvent::Key {
+            key: keycode_convert(keycode),
+            pressed: true,
+            repeat,
+            modifiers: self.modifiers,
+        }, &mut self.ui);
+        Ok(())
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut Context, character: char) -> GameResult<()> {
+        self.ui_input.handle_event(RawEvent::Text { text: character.to_string() }, &mut self.ui);
+        Ok(())
+    }
+
+    fn key_up_event(&mut self, _ctx: &mut Context, input: KeyInput) -> GameResult<()> {
+        self.modifiers = modifiers_convert(input.mods);
+        Ok(())
+    }
+
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) -> GameResult<()> {
+        // The window may have moved to a display with a different scale factor
+        self.scale_factor = ctx.gfx.window().scale_factor() as f32;
+        self.ui.set_target_size(Vector2::new(width, height) / self.scale_factor);
+        Ok(())
     }
 
     fn mouse_motion_event(
         &mut self, _ctx: &mut Context,
-        _state: MouseState, x: i32, y: i32, _xrel: i32, _yrel: i32
-    ) {
-        self.ui_input.handle_cursor_moved(Point2::new(x as f32, y as f32), &mut self.ui);
+        x: f32, y: f32, _dx: f32, _dy: f32
+    ) -> GameResult<()> {
+        self.ui_input.handle_event(RawEvent::CursorMoved {
+            position: self.ui_position(x, y),
+        }, &mut self.ui);
+        Ok(())
     }
 }
@@ -0,0 +1,115 @@
+//! Derives markedly's `ScriptModel` for structs, so game state can be synced into a UI's model.
+//!
+//! ```rust,ignore
+//! #[derive(ScriptModel)]
+//! struct Hud {
+//!     health: f32,
+//!     #[script_model(rename = "player-name")]
+//!     name: String,
+//!     #[script_model(skip)]
+//!     internal: u64,
+//! }
+//!
+//! // Every frame, only pushes the fields that changed since the last sync
+//! ui.sync_model(&tree, &hud, &context)?;
+//! ```
+//!
+//! Every field that isn't skipped has to implement markedly's `ScriptField`. Syncing compares
+//! every field against the value already in the model without converting or copying it, and
+//! only converts and sets the fields that differ. Besides `ScriptModel`, `Into<ScriptTable>` is
+//! implemented to create a model for the struct.
+
+extern crate proc_macro;
+extern crate syn;
+#[macro_use]
+extern crate quote;
+
+use proc_macro::{TokenStream};
+use syn::{DeriveInput, Data, Fields, Field, Meta, NestedMeta, Lit};
+
+#[proc_macro_derive(ScriptModel, attributes(script_model))]
+pub fn derive_script_model(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = syn::parse(input).expect("Unable to parse derive input");
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => panic!("ScriptModel can only be derived for structs with named fields"),
+        },
+        _ => panic!("ScriptModel can only be derived for structs"),
+    };
+
+    let mut keys = Vec::new();
+    let mut idents = Vec::new();
+    for field in fields {
+        if let Some(key) = field_key(field) {
+            keys.push(key);
+            idents.push(field.ident.clone().unwrap());
+        }
+    }
+
+    // The same lists are repeated in both methods, which quote needs separate bindings for
+    let (keys2, idents2) = (keys.clone(), idents.clone());
+    let expanded = quote! {
+        impl #impl_generics ::markedly::scripting::ScriptModel for #name #ty_generics
+            #where_clause
+        {
+            fn changed_fields(&self, model: &::markedly::scripting::ScriptTable) -> Vec<bool> {
+                vec![#(model.field_changed(#keys, &self.#idents)),*]
+            }
+
+            #[allow(unused_mut)]
+            fn sync_model(&self, model: &mut ::markedly::scripting::ScriptTable) -> bool {
+                let mut changed = false;
+                #(changed |= model.sync_field(#keys2, &self.#idents2);)*
+                changed
+            }
+        }
+
+        impl #impl_generics From<#name #ty_generics> for ::markedly::scripting::ScriptTable
+            #where_clause
+        {
+            fn from(value: #name #ty_generics) -> Self {
+                ::markedly::scripting::ScriptModel::to_model(&value)
+            }
+        }
+    };
+
+    expanded.into()
+}
+
+/// Gets the key a field has in the model, or None if the field is skipped.
+fn field_key(field: &Field) -> Option<String> {
+    let mut key = field.ident.as_ref().unwrap().to_string();
+
+    for attribute in &field.attrs {
+        let meta = match attribute.interpret_meta() {
+            Some(meta) => meta,
+            None => continue,
+        };
+        if meta.name() != "script_model" {
+            continue
+        }
+
+        let list = match meta {
+            Meta::List(list) => list,
+            _ => panic!("Expected a list of options, such as #[script_model(skip)]"),
+        };
+        for option in list.nested {
+            match option {
+                NestedMeta::Meta(Meta::Word(ref word)) if word == "skip" =>
+                    return None,
+                NestedMeta::Meta(Meta::NameValue(ref value)) if value.ident == "rename" =>
+                    match value.lit {
+                        Lit::Str(ref rename) => key = rename.value(),
+                        _ => panic!("The rename option has to be a string"),
+                    },
+                _ => panic!("Unknown script_model option, expected skip or rename"),
+            }
+        }
+    }
+
+    Some(key)
+}
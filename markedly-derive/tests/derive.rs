@@ -0,0 +1,53 @@
+extern crate markedly;
+#[macro_use]
+extern crate markedly_derive;
+
+use markedly::scripting::{ScriptModel, ScriptTable, ScriptValue};
+
+#[derive(ScriptModel)]
+struct Hud {
+    health: f32,
+    #[script_model(rename = "player-name")]
+    name: String,
+    items: Vec<String>,
+    #[script_model(skip)]
+    #[allow(dead_code)]
+    frame: u64,
+}
+
+fn hud() -> Hud {
+    Hud {
+        health: 10.0,
+        name: "Layl".into(),
+        items: vec!["sword".into()],
+        frame: 0,
+    }
+}
+
+#[test]
+fn it_renames_and_skips_fields() {
+    let model: ScriptTable = hud().into();
+    assert_eq!(model.get("health"), Some(&ScriptValue::Number(10.0)));
+    assert_eq!(model.get("player-name"), Some(&ScriptValue::String("Layl".into())));
+    assert_eq!(model.get("items"), Some(&ScriptValue::List(vec!["sword".into()])));
+    assert_eq!(model.get("name"), None);
+    assert_eq!(model.get("frame"), None);
+}
+
+#[test]
+fn it_syncs_only_changed_fields() {
+    let mut hud = hud();
+    let mut model = hud.to_model();
+    assert_eq!(hud.changed_fields(&model), vec![false, false, false]);
+    assert!(!hud.sync_model(&mut model));
+
+    // Skipped fields never count as changes
+    hud.frame += 1;
+    hud.items.push("shield".into());
+    assert_eq!(hud.changed_fields(&model), vec![false, false, true]);
+    assert!(hud.sync_model(&mut model));
+    assert_eq!(model.get("items"), Some(&ScriptValue::List(vec![
+        "sword".into(), "shield".into(),
+    ])));
+    assert!(!hud.sync_model(&mut model));
+}
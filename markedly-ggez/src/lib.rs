@@ -1,52 +1,249 @@
 extern crate ggez;
+extern crate copypasta;
 extern crate nalgebra;
 extern crate markedly;
 extern crate metrohash;
+extern crate toml;
 
+use std::any::{Any};
+use std::io::{Read};
 use std::path::{PathBuf};
 
 use nalgebra::{Point2, Vector2};
 use metrohash::{MetroHashMap};
-use ggez::conf::{NumSamples};
-use ggez::graphics::{self, Rect, Font, Text, Canvas, Mesh};
+use copypasta::{ClipboardContext, ClipboardProvider};
+use ggez::graphics::{
+    self, Rect, FontData, Text, TextFragment, PxScale, Canvas, Image, ImageFormat, Mesh,
+    DrawParam, DrawMode,
+};
+use ggez::input::keyboard::{KeyCode, KeyMods};
+use ggez::input::mouse::{MouseButton as GMouseButton};
 use ggez::{Context, GameError};
 
-use markedly::render::{Renderer};
+use markedly::input::{Modifiers, MouseButton, Clipboard};
+use markedly::render::{
+    self, Renderer, Rect as MRect, Effect, BlendMode, PixelBuffer, RootTransform,
+};
 use markedly::template::{Color};
-use markedly::{Error, ComponentId};
+use markedly::{Error, ComponentId, Ui};
+
+/// The name of the font embedded with the `default-font` feature.
+#[cfg(feature = "default-font")]
+const EMBEDDED_FONT_NAME: &str = "markedly-default";
+
+/// The prefix of image identifiers that refer to a region of a texture atlas.
+const ATLAS_PREFIX: &str = "atlas:";
+
+/// The format of component canvases, which `read_cache_pixels` relies on being RGBA.
+const CANVAS_FORMAT: ImageFormat = ImageFormat::Rgba8UnormSrgb;
+
+/// Where a font is loaded from.
+enum FontSource {
+    /// A path in ggez's filesystem.
+    Path(PathBuf),
+    Bytes(Vec<u8>),
+}
 
 struct FontCache {
-    path: PathBuf,
-    sizes: MetroHashMap<u32, Font>,
+    source: FontSource,
+    /// If the font has been added to ggez, which renders it at any size.
+    loaded: bool,
+}
+
+/// Laid out text, kept around so the same text doesn't have to be measured again every draw.
+struct TextCache {
+    text: Text,
+    /// The size of the text in pixels.
+    size: Vector2<f32>,
+    last_used: u64,
+}
+
+/// A named part of a texture atlas image.
+struct AtlasRegion {
+    image: String,
+    /// The part of the image in pixels.
+    rect: MRect,
+}
+
+/// The canvas image a component's cache is rendered to, which may be larger than the component.
+struct ComponentCanvas {
+    image: Image,
+    size: Vector2<u32>,
+    /// The number of canvas pixels per UI unit.
+    scale: f32,
+}
+
+impl ComponentCanvas {
+    /// The size of the canvas in UI units.
+    fn canvas_size(&self) -> Vector2<f32> {
+        Vector2::new(self.image.width() as f32, self.image.height() as f32) / self.scale
+    }
+
+    /// The part of the canvas in use by the component, relative to the canvas size.
+    fn source(&self) -> Rect {
+        let canvas_size = self.canvas_size();
+        Rect::new(
+            0.0, 0.0, self.size.x as f32 / canvas_size.x, self.size.y as f32 / canvas_size.y,
+        )
+    }
 }
 
 /// A persistent resource cache for the ggez markedly renderer.
+///
+/// Canvases of removed components are kept in a pool to be reused by new or resized components,
+/// which are given canvases rounded up in size so that similarly sized components can share them.
+/// One cache can be shared by several UIs, as their components never have the same ID.
 pub struct GgezCache {
-    data: MetroHashMap<ComponentId, Canvas>,
+    data: MetroHashMap<ComponentId, ComponentCanvas>,
+    /// Unused canvas images, the least recently used first.
+    pool: Vec<Image>,
+    memory_limit: Option<usize>,
     fonts: MetroHashMap<String, FontCache>,
+    images: MetroHashMap<String, Image>,
+    atlas_regions: MetroHashMap<String, AtlasRegion>,
+    /// Keyed by the text, font name, and size.
+    texts: MetroHashMap<(String, String, u32), TextCache>,
+    frame: u64,
 
     default_font: Option<String>,
     default_text_size: u32,
+    text_cache_frames: u64,
+    custom_handlers: Vec<Box<CustomHandler>>,
 }
 
+/// Draws custom commands from component classes, see `GgezCache::add_custom_handler`.
+pub type CustomHandler = FnMut(&mut Context, &mut Canvas, &Any) -> Result<bool, GameError>;
+
 impl GgezCache {
+    /// Creates a new cache. With the `default-font` feature enabled, an embedded font is used for
+    /// text if no fonts are added.
     pub fn new() -> Self {
-        GgezCache {
+        #[allow(unused_mut)]
+        let mut cache = GgezCache {
             data: MetroHashMap::default(),
+            pool: Vec::new(),
+            memory_limit: None,
             fonts: MetroHashMap::default(),
+            images: MetroHashMap::default(),
+            atlas_regions: MetroHashMap::default(),
+            texts: MetroHashMap::default(),
+            frame: 0,
 
             default_font: None,
             default_text_size: 14,
+            text_cache_frames: 60,
+            custom_handlers: Vec::new(),
+        };
+
+        #[cfg(feature = "default-font")]
+        cache.fonts.insert(EMBEDDED_FONT_NAME.into(), FontCache {
+            source: FontSource::Bytes(include_bytes!("../resources/Raleway-Regular.ttf").to_vec()),
+            loaded: false,
+        });
+
+        cache
+    }
+
+    /// Sets how many frames laid out text is kept in the cache without being drawn, before it's
+    /// removed. Defaults to 60.
+    pub fn set_text_cache_frames(&mut self, frames: u64) {
+        self.text_cache_frames = frames;
+    }
+
+    /// Adds a handler that draws custom commands issued by component classes through
+    /// `Renderer::custom`. The handler draws to the component's canvas, which it's given, and
+    /// returns true if it recognized the command. Handlers are tried in the order they were added
+    /// until one recognizes the command.
+    pub fn add_custom_handler<F>(&mut self, handler: F)
+        where F: FnMut(&mut Context, &mut Canvas, &Any) -> Result<bool, GameError> + 'static
+    {
+        self.custom_handlers.push(Box::new(handler));
+    }
+
+    /// Removes all laid out text from the cache.
+    pub fn clear_texts(&mut self) {
+        self.texts.clear();
+    }
+
+    /// Sets the maximum memory in bytes used by canvases. When exceeded, unused canvases in the
+    /// pool are removed, least recently used first. Canvases in use by components are never
+    /// removed, so the limit may still be exceeded by those. Defaults to no limit.
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory_limit = limit;
+        self.enforce_memory_limit();
+    }
+
+    /// Gets the memory in bytes used by canvases, both in use and in the pool.
+    pub fn memory_usage(&self) -> usize {
+        self.data.values().map(|c| canvas_memory(&c.image)).sum::<usize>() +
+            self.pool.iter().map(canvas_memory).sum::<usize>()
+    }
+
+    /// Removes all unused canvases from the pool.
+    pub fn trim(&mut self) {
+        self.pool.clear();
+    }
+
+    /// Removes all resources from the cache that can be loaded again when needed, being unused
+    /// canvases, laid out text, and images. Fonts and canvases in use by components are kept.
+    pub fn clear(&mut self) {
+        self.trim();
+        self.clear_texts();
+        self.clear_images();
+    }
+
+    /// Gets a canvas image of exactly the given size from the pool, or creates a new one.
+    fn take_canvas(&mut self, ctx: &mut Context, size: Vector2<u32>) -> Image {
+        let pooled = self.pool.iter().rposition(|i| i.width() == size.x && i.height() == size.y);
+
+        if let Some(index) = pooled {
+            self.pool.remove(index)
+        } else {
+            Image::new_canvas_image(ctx, CANVAS_FORMAT, size.x, size.y, 1)
+        }
+    }
+
+    fn release_canvas(&mut self, image: Image) {
+        self.pool.push(image);
+        self.enforce_memory_limit();
+    }
+
+    fn enforce_memory_limit(&mut self) {
+        if let Some(limit) = self.memory_limit {
+            let mut usage = self.memory_usage();
+            while usage > limit && !self.pool.is_empty() {
+                usage -= canvas_memory(&self.pool.remove(0));
+            }
         }
     }
 
+    /// Starts a new frame, removing text that hasn't been drawn in a while.
+    fn next_frame(&mut self) {
+        self.frame += 1;
+
+        let frame = self.frame;
+        let text_cache_frames = self.text_cache_frames;
+        self.texts.retain(|_, cache| frame - cache.last_used <= text_cache_frames);
+    }
+
     /// Adds a font to the cache by its path.
-    /// This will not actually load the font until it's used with a specific size.
+    /// This will not actually load the font until it's used.
     pub fn add_font<S: Into<String>, P: Into<PathBuf>>(
         &mut self, name: S, location: P
     ) -> Result<(), Error> {
-        let name = name.into();
+        self.insert_font(name.into(), FontSource::Path(location.into()))
+    }
+
+    /// Adds a font to the cache from the contents of a font file, such as one included in the
+    /// binary with `include_bytes!`.
+    /// This will not actually load the font until it's used.
+    pub fn add_font_bytes<S: Into<String>>(
+        &mut self, name: S, bytes: &[u8]
+    ) -> Result<(), Error> {
+        self.insert_font(name.into(), FontSource::Bytes(bytes.to_vec()))
+    }
 
+    fn insert_font(&mut self, name: String, source: FontSource) -> Result<(), Error> {
         if self.default_font.is_none() {
             self.default_font = Some(name.clone());
         }
@@ -59,53 +256,337 @@ impl GgezCache {
         }
 
         self.fonts.insert(name, FontCache {
-            path: location.into(),
-            sizes: MetroHashMap::default(),
+            source,
+            loaded: false,
         });
 
         Ok(())
     }
+
+    /// Loads an image through ggez's filesystem by its path, if it isn't already in the cache.
+    /// Images are otherwise loaded when first rendered, which may cause a hitch.
+    pub fn preload_image(&mut self, ctx: &mut Context, path: &str) -> Result<(), Error> {
+        load_image(ctx, self, path)?;
+        Ok(())
+    }
+
+    /// Removes an image from the cache, returning true if it was cached. The image will be
+    /// loaded again if it's rendered after this.
+    pub fn remove_image(&mut self, path: &str) -> bool {
+        self.images.remove(path).is_some()
+    }
+
+    /// Removes all images from the cache.
+    pub fn clear_images(&mut self) {
+        self.images.clear();
+    }
+
+    /// Adds the regions of a texture atlas, a single image holding many smaller images, so image
+    /// attributes can refer to them as `atlas:name` and they're all drawn from the one image.
+    /// Regions are given in pixels, and replace earlier regions with the same name.
+    pub fn add_atlas(&mut self, image_path: &str, regions: Vec<(String, MRect)>) {
+        for (name, rect) in regions {
+            self.atlas_regions.insert(name, AtlasRegion { image: image_path.into(), rect });
+        }
+    }
+
+    /// Loads a texture atlas from a TOML sheet through ggez's filesystem, see `add_atlas`. The
+    /// sheet gives the path of the image, and every region in pixels as `[x, y, width, height]`:
+    ///
+    /// ```toml
+    /// image = "/ui/atlas.png"
+    ///
+    /// [regions]
+    /// icon_sword = [0, 0, 32, 32]
+    /// icon_shield = [32, 0, 32, 32]
+    /// ```
+    pub fn load_atlas(&mut self, ctx: &mut Context, sheet_path: &str) -> Result<(), Error> {
+        let sheet_error = |error: String| Error::Resource {
+            resource: Some(sheet_path.into()),
+            error,
+        };
+
+        let mut text = String::new();
+        ctx.fs.open(sheet_path)
+            .and_then(|mut file| file.read_to_string(&mut text).map_err(GameError::from))
+            .map_err(|e| sheet_error(format!("{}", e)))?;
+        let sheet: toml::Value = text.parse()
+            .map_err(|e| sheet_error(format!("{}", e)))?;
+
+        let image = sheet.get("image").and_then(|v| v.as_str())
+            .ok_or_else(|| sheet_error("The sheet must have an \"image\" path".into()))?;
+        let table = sheet.get("regions").and_then(|v| v.as_table())
+            .ok_or_else(|| sheet_error("The sheet must have a \"regions\" table".into()))?;
+
+        let mut regions = Vec::new();
+        for (name, value) in table {
+            let values = value.as_array()
+                .filter(|values| values.len() == 4)
+                .and_then(|values| values.iter()
+                    .map(|v| v.as_integer().map(|v| v as f32).or(v.as_float().map(|v| v as f32)))
+                    .collect::<Option<Vec<f32>>>()
+                )
+                .ok_or_else(|| sheet_error(
+                    format!("Region \"{}\" must be [x, y, width, height]", name)
+                ))?;
+            let rect = MRect::new(
+                Point2::new(values[0], values[1]), Vector2::new(values[2], values[3]),
+            );
+            regions.push((name.clone(), rect));
+        }
+
+        self.add_atlas(image, regions);
+        Ok(())
+    }
 }
 
 /// A markedly renderer for ggez, intended to be constructed every frame on-demand.
+///
+/// The UI is drawn to a canvas given by the game, usually the one it draws its frame with, which
+/// the game finishes as usual afterwards. Component caches are drawn through canvases of their
+/// own, which are finished before the UI's canvas ever draws them.
+///
+/// On high DPI displays, when the canvas' screen coordinates are smaller than the window's pixel
+/// size, caches and text are rendered at the display's resolution rather than the screen
+/// coordinates, so they stay sharp.
+///
+/// Components are clipped with the canvases' scissor rects, also when rendering directly to the
+/// game's canvas, after which its own scissor rect is put back.
 pub struct GgezRenderer<'a> {
     ctx: &'a mut Context,
     cache: &'a mut GgezCache,
+    /// The canvas the UI is rendered to.
+    target: &'a mut Canvas,
     target_coordinates: Rect,
+    /// The size in pixels of what the target draws to.
+    target_pixel_size: Vector2<f32>,
+    /// The scissor rect the game had set on the target.
+    target_scissor: Rect,
+    /// The canvas of the component currently being rendered to, if not the target.
+    current: Option<(ComponentId, Canvas)>,
+    clip_stack: Vec<(ComponentId, MRect)>,
+    direct: Option<ComponentId>,
+    scale: f32,
+    batch: Option<TriangleBatch>,
+}
+
+/// Triangles of the same color rendered to the same component, drawn together as one mesh.
+struct TriangleBatch {
+    id: ComponentId,
+    color: Color,
+    vertices: Vec<[f32; 2]>,
 }
 
 impl<'a> GgezRenderer<'a> {
-    pub fn new(ctx: &'a mut Context, cache: &'a mut GgezCache) -> Self {
-        let target_coordinates = graphics::get_screen_coordinates(ctx);
+    /// Creates a renderer that renders to a canvas drawing to the window, such as the one made
+    /// with `Canvas::from_frame`, covering the canvas' screen coordinates.
+    pub fn new(ctx: &'a mut Context, cache: &'a mut GgezCache, canvas: &'a mut Canvas) -> Self {
+        let (drawable_width, drawable_height) = ctx.gfx.drawable_size();
+        let target_coordinates = canvas.screen_coordinates()
+            .unwrap_or(Rect::new(0.0, 0.0, drawable_width, drawable_height));
+
+        // The number of pixels per UI unit, which is more than one on high DPI displays
+        let scale = if target_coordinates.w > 0.0 {
+            (drawable_width / target_coordinates.w).max(1.0)
+        } else {
+            1.0
+        };
+
+        let target_pixel_size = Vector2::new(drawable_width, drawable_height);
+        GgezRenderer::with_target(ctx, cache, canvas, target_coordinates, target_pixel_size, scale)
+    }
+
+    /// Creates a renderer that renders to a canvas drawing to an image instead of the window,
+    /// with a UI of a target size in UI units covering the whole image. The canvas' screen
+    /// coordinates are set to match. See `render_to_canvas`.
+    pub fn new_for_canvas(
+        ctx: &'a mut Context, cache: &'a mut GgezCache, canvas: &'a mut Canvas, image: &Image,
+        target_size: Vector2<f32>,
+    ) -> Self {
+        let target_coordinates = Rect::new(0.0, 0.0, target_size.x, target_size.y);
+        canvas.set_screen_coordinates(target_coordinates);
+
+        let target_pixel_size = Vector2::new(image.width() as f32, image.height() as f32);
+        let scale = if target_size.x > 0.0 { target_pixel_size.x / target_size.x } else { 1.0 };
+        GgezRenderer::with_target(ctx, cache, canvas, target_coordinates, target_pixel_size, scale)
+    }
+
+    fn with_target(
+        ctx: &'a mut Context, cache: &'a mut GgezCache, target: &'a mut Canvas,
+        target_coordinates: Rect, target_pixel_size: Vector2<f32>, scale: f32,
+    ) -> Self {
+        cache.next_frame();
+        let target_scissor = target.scissor_rect();
+
         GgezRenderer {
             ctx,
             cache,
+            target,
             target_coordinates,
+            target_pixel_size,
+            target_scissor,
+            current: None,
+            clip_stack: Vec::new(),
+            direct: None,
+            scale,
+            batch: None,
+        }
+    }
+
+    /// Gets the number of pixels rendered per UI unit.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Gets the size in pixels fonts should be rendered at for a text size in UI units.
+    fn pixel_text_size(&self, text_size: Option<i32>) -> u32 {
+        let text_size = text_size.map(|v| v as f32)
+            .unwrap_or(self.cache.default_text_size as f32);
+        (text_size * self.scale).round() as u32
+    }
+
+    fn current_clip(&self, id: ComponentId) -> Option<MRect> {
+        self.clip_stack.iter().rev()
+            .filter(|v| v.0 == id)
+            .next()
+            .map(|v| v.1)
+    }
+
+    /// Draws the triangles batched so far, this has to be done before anything else is drawn or
+    /// any caches are changed so everything is drawn in order.
+    fn flush_batch(&mut self) -> Result<(), Error> {
+        if let Some(batch) = self.batch.take() {
+            // ggez errors on meshes without any triangles
+            if batch.vertices.is_empty() {
+                return Ok(())
+            }
+
+            self.set_target(batch.id)?;
+            if !self.apply_clip(batch.id)? {
+                return Ok(())
+            }
+
+            let mesh = Mesh::from_triangles(self.ctx, &batch.vertices, color_convert(batch.color))
+                .map_err(egtm)?;
+            current_canvas(self.target, &mut self.current).draw(&mesh, DrawParam::new());
+        }
+
+        Ok(())
+    }
+
+    /// Starts rendering to a component, clipped to its current clip. Returns false if the clip
+    /// leaves nothing visible, in which case drawing can be skipped.
+    fn render_to_component(&mut self, id: ComponentId) -> Result<bool, Error> {
+        self.flush_batch()?;
+        self.set_target(id)?;
+        self.apply_clip(id)
+    }
+
+    /// Sets the scissor rect of the canvas being rendered to, to the component's current clip.
+    fn apply_clip(&mut self, id: ComponentId) -> Result<bool, Error> {
+        let clip = match self.current_clip(id) {
+            Some(clip) => clip,
+            None => {
+                self.reset_clip()?;
+                return Ok(true)
+            },
+        };
+
+        // Scissor rects are in pixels of the image rendered to, while clips are in the canvas'
+        // screen coordinates, which are offset on the target
+        let (origin, scale, pixel_size) = match self.current {
+            Some((current_id, _)) => {
+                let cached = self.cache.data.get(&current_id).unwrap();
+                let pixel_size = Vector2::new(
+                    cached.image.width() as f32, cached.image.height() as f32,
+                );
+                (Point2::new(0.0, 0.0), cached.scale, pixel_size)
+            },
+            None => (
+                Point2::new(self.target_coordinates.x, self.target_coordinates.y),
+                self.scale, self.target_pixel_size,
+            ),
+        };
+        let start = (clip.position - origin) * scale;
+        let end = (clip.position + clip.size - origin) * scale;
+        let start = Vector2::new(start.x.floor().max(0.0), start.y.floor().max(0.0));
+        let end = Vector2::new(end.x.ceil().min(pixel_size.x), end.y.ceil().min(pixel_size.y));
+
+        // ggez errors on empty scissor rects, but then there's nothing to draw anyways
+        if end.x - start.x < 1.0 || end.y - start.y < 1.0 {
+            return Ok(false)
+        }
+
+        current_canvas(self.target, &mut self.current)
+            .set_scissor_rect(Rect::new(start.x, start.y, end.x - start.x, end.y - start.y))
+            .map_err(egtm)?;
+        Ok(true)
+    }
+
+    /// Removes any clip from the canvas being rendered to, putting back the game's own scissor
+    /// rect on the target.
+    fn reset_clip(&mut self) -> Result<(), Error> {
+        match self.current {
+            Some((_, ref mut canvas)) => canvas.set_default_scissor_rect(),
+            None => self.target.set_scissor_rect(self.target_scissor).map_err(egtm)?,
+        }
+
+        Ok(())
+    }
+
+    fn set_target(&mut self, id: ComponentId) -> Result<(), Error> {
+        // In direct mode we render to the target instead
+        if self.direct == Some(id) {
+            return self.bind_target()
+        }
+
+        if self.current.as_ref().map(|v| v.0) == Some(id) {
+            return Ok(())
         }
+
+        self.open_canvas(id, None)
+    }
+
+    /// Starts rendering to a component's canvas image, optionally clearing it first.
+    fn open_canvas(
+        &mut self, id: ComponentId, clear: Option<graphics::Color>,
+    ) -> Result<(), Error> {
+        self.bind_target()?;
+
+        let cached = self.cache.data.get(&id).unwrap();
+        let canvas_size = cached.canvas_size();
+        let mut canvas = Canvas::from_image(self.ctx, cached.image.clone(), clear);
+        canvas.set_screen_coordinates(Rect::new(0.0, 0.0, canvas_size.x, canvas_size.y));
+        self.current = Some((id, canvas));
+
+        Ok(())
     }
 
-    fn render_to_component(&mut self, id: ComponentId) -> Result<(), Error> {
-        let canvas = self.cache.data.get(&id).unwrap();
-        graphics::set_canvas(self.ctx, Some(canvas));
-        graphics::set_screen_coordinates(self.ctx, Rect::new(
-            0.0, 0.0,
-            canvas.get_image().width() as f32, canvas.get_image().height() as f32,
-        )).map_err(egtm)?;
-        graphics::apply_transformations(self.ctx).map_err(egtm)?;
+    /// Switches to rendering to the target, finishing the canvas of the component rendered to
+    /// before, so its draws are submitted before anything draws its cache.
+    fn bind_target(&mut self) -> Result<(), Error> {
+        if let Some((_, canvas)) = self.current.take() {
+            canvas.finish(self.ctx).map_err(egtm)?;
+        }
 
         Ok(())
     }
 }
 
 impl<'a> Renderer for GgezRenderer<'a> {
-    fn render_cache_to_target(&mut self, id: ComponentId) -> Result<(), Error> {
-        graphics::set_canvas(self.ctx, None);
-        graphics::set_screen_coordinates(self.ctx, self.target_coordinates).map_err(egtm)?;
-        graphics::apply_transformations(self.ctx).map_err(egtm)?;
+    fn render_cache_to_target(
+        &mut self, id: ComponentId, transform: RootTransform,
+    ) -> Result<(), Error> {
+        self.flush_batch()?;
+        self.bind_target()?;
 
-        let canvas = self.cache.data.get(&id).unwrap();
-        graphics::set_color(self.ctx, (255, 255, 255, 255).into()).map_err(egtm)?;
-        graphics::draw(self.ctx, canvas, Point2::new(0.0, 0.0), 0.0).map_err(egtm)?;
+        let cached = self.cache.data.get(&id).unwrap();
+        let scale = transform.scale / cached.scale;
+        self.target.draw(&cached.image, DrawParam::new()
+            .src(cached.source())
+            .dest([transform.offset.x, transform.offset.y])
+            .scale([scale, scale])
+        );
 
         Ok(())
     }
@@ -113,111 +594,570 @@ impl<'a> Renderer for GgezRenderer<'a> {
     fn create_resize_cache(
         &mut self, id: ComponentId, size: Vector2<u32>
     ) -> Result<bool, Error> {
-        // If we have a cached canvas and it's of the right size, we only have to clear
-        if let Some(canvas) = self.cache.data.get(&id) {
-            if canvas.get_image().width() == size.x &&
-                canvas.get_image().height() == size.y {
+        self.flush_batch()?;
+
+        let scale = self.scale;
+        let canvas_size = bucket_size(Vector2::new(
+            (size.x as f32 * scale).ceil() as u32, (size.y as f32 * scale).ceil() as u32,
+        ));
+
+        // If we have a cached canvas and it's of the right size, we only have to clear, if it's
+        // still in the same size bucket we can keep using it but it has to be rendered again
+        if let Some(cached) = self.cache.data.get_mut(&id) {
+            if cached.size == size && cached.scale == scale {
                 return Ok(false)
             }
+
+            if cached.scale == scale &&
+                cached.image.width() == canvas_size.x &&
+                cached.image.height() == canvas_size.y {
+                cached.size = size;
+                return Ok(true)
+            }
         }
 
-        // We don't have what we need so create a new canvas
-        let canvas = Canvas::new(self.ctx, size.x, size.y, NumSamples::One).map_err(egtm)?;
-        self.cache.data.insert(id, canvas);
+        // We don't have what we need so swap the canvas out for one from the pool
+        self.bind_target()?;
+        if let Some(cached) = self.cache.data.remove(&id) {
+            self.cache.release_canvas(cached.image);
+        }
+        let image = self.cache.take_canvas(self.ctx, canvas_size);
+        self.cache.data.insert(id, ComponentCanvas {
+            image,
+            size,
+            scale,
+        });
+        self.cache.enforce_memory_limit();
 
         Ok(true)
     }
 
     fn clear_cache(&mut self, id: ComponentId) -> Result<(), Error> {
-        let canvas = self.cache.data.get(&id).unwrap();
-        graphics::set_canvas(self.ctx, Some(canvas));
-        graphics::set_background_color(self.ctx, (255, 255, 255, 0).into());
-        graphics::clear(self.ctx);
+        self.flush_batch()?;
+        self.open_canvas(id, Some(graphics::Color::new(1.0, 1.0, 1.0, 0.0)))
+    }
 
+    fn remove_cache(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.flush_batch()?;
+        self.bind_target()?;
+
+        if let Some(cached) = self.cache.data.remove(&id) {
+            self.cache.release_canvas(cached.image);
+        }
         Ok(())
     }
 
+    fn read_cache_pixels(&mut self, id: ComponentId) -> Result<PixelBuffer, Error> {
+        self.flush_batch()?;
+        self.bind_target()?;
+
+        let cached = self.cache.data.get(&id).unwrap();
+        let image = &cached.image;
+        let data = image.to_pixels(self.ctx).map_err(egtm)?;
+
+        // The canvas may be larger than the component, so only copy the part that's in use
+        let size = Vector2::new(
+            ((cached.size.x as f32 * cached.scale).ceil() as u32).min(image.width()),
+            ((cached.size.y as f32 * cached.scale).ceil() as u32).min(image.height()),
+        );
+        let row_length = image.width() as usize * 4;
+        let used_length = size.x as usize * 4;
+        let mut pixels = Vec::with_capacity(used_length * size.y as usize);
+        for row in data.chunks(row_length).take(size.y as usize) {
+            pixels.extend_from_slice(&row[..used_length]);
+        }
+
+        PixelBuffer::new(size, pixels)
+    }
+
     fn render_cache(
         &mut self, id: ComponentId,
-        source_id: ComponentId, position: Point2<f32>
+        source_id: ComponentId, position: Point2<f32>, alpha: f32, blend_mode: BlendMode,
+    ) -> Result<(), Error> {
+        if !self.render_to_component(id)? {
+            return Ok(())
+        }
+
+        let source = self.cache.data.get(&source_id).unwrap();
+
+        // The blend mode is part of the canvas' state, so it's put back after drawing
+        let canvas = current_canvas(self.target, &mut self.current);
+        let previous_blend_mode = canvas.blend_mode();
+        canvas.set_blend_mode(match blend_mode {
+            BlendMode::Alpha => graphics::BlendMode::ALPHA,
+            BlendMode::Add => graphics::BlendMode::ADD,
+            BlendMode::Multiply => graphics::BlendMode::MULTIPLY,
+        });
+        canvas.draw(&source.image, DrawParam::new()
+            .src(source.source())
+            .dest([position.x.round(), position.y.round()])
+            .scale([1.0 / source.scale, 1.0 / source.scale])
+            .color(graphics::Color::new(1.0, 1.0, 1.0, alpha))
+        );
+        canvas.set_blend_mode(previous_blend_mode);
+
+        Ok(())
+    }
+
+    fn composite_with_effect(
+        &mut self, id: ComponentId,
+        source_id: ComponentId, position: Point2<f32>, alpha: f32, effect: &Effect,
     ) -> Result<(), Error> {
-        self.render_to_component(id)?;
+        match effect.name.as_str() {
+            "blur" => {
+                // Approximate a blur by drawing the cache several times in a ring around the
+                // position, each partially transparent
+                let radius = effect.value(0, 2.0);
+                let samples = 8;
+                for i in 0..samples {
+                    let angle = (i as f32 / samples as f32) * ::std::f32::consts::PI * 2.0;
+                    let offset = Vector2::new(angle.cos(), angle.sin()) * radius;
+                    self.render_cache(
+                        id, source_id, position + offset, alpha / (samples as f32 * 0.5),
+                        BlendMode::Alpha,
+                    )?;
+                }
+                Ok(())
+            },
+            // Anything we don't support we ignore
+            _ => self.render_cache(id, source_id, position, alpha, BlendMode::Alpha),
+        }
+    }
+
+    fn begin_direct(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.flush_batch()?;
+        self.direct = Some(id);
+        Ok(())
+    }
+
+    fn end_direct(&mut self, _id: ComponentId) -> Result<(), Error> {
+        self.flush_batch()?;
+        self.direct = None;
 
-        let source_canvas = self.cache.data.get(&source_id).unwrap();
-        graphics::set_color(self.ctx, (255, 255, 255, 255).into()).map_err(egtm)?;
-        graphics::draw(self.ctx, source_canvas, Point2::new(
-            position.x.round(),
-            position.y.round(),
-        ), 0.0).map_err(egtm)?;
+        // Clips in direct mode were applied to the target, which the game keeps drawing to
+        self.target.set_scissor_rect(self.target_scissor).map_err(egtm)?;
+        Ok(())
+    }
+
+    fn push_clip(&mut self, id: ComponentId, rect: MRect) -> Result<(), Error> {
+        // Batched triangles are clipped when they're drawn, so they have to be drawn first
+        self.flush_batch()?;
+
+        // Nested clips can never show more than the clip they're nested in
+        let rect = if let Some(current) = self.current_clip(id) {
+            current.intersection(&rect)
+                .unwrap_or(MRect::new(rect.position, Vector2::new(0.0, 0.0)))
+        } else {
+            rect
+        };
 
+        self.clip_stack.push((id, rect));
         Ok(())
     }
 
+    fn pop_clip(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.flush_batch()?;
+
+        match self.clip_stack.pop() {
+            Some((clip_id, _)) if clip_id == id => Ok(()),
+            _ => Err("Clip popped without a matching push for the component".into()),
+        }
+    }
+
     fn text(
         &mut self, id: ComponentId,
         text: &String, text_font: Option<&String>, text_size: Option<i32>,
         position: Point2<f32>, size: Vector2<f32>, color: Color,
     ) -> Result<(), Error> {
-        self.render_to_component(id)?;
+        if !self.render_to_component(id)? {
+            return Ok(())
+        }
 
-        // Try to find the font cache, use the default, or error if we can't find it
-        let requested_font_name = text_font.or(self.cache.default_font.as_ref())
-            .ok_or(Error::Resource {
-                resource: None,
-                error: "Could not fall back to default font, no fonts are loaded".into()
-            })?;
-        let font_cache = self.cache.fonts.get_mut(requested_font_name)
-            .ok_or_else(|| Error::Resource {
-                resource: Some(requested_font_name.clone()),
-                error: "Font is not in cache".into()
-            })?;
+        // Text is rendered at the pixel size and scaled back down to UI units
+        let scale = self.scale;
+        let pixel_size = self.pixel_text_size(text_size);
+        let text = load_text(self.ctx, self.cache, text, text_font, pixel_size)?;
+
+        let x_offset = (size.x - text.size.x / scale) * 0.5;
+        let y_offset = (size.y - text.size.y / scale) * 0.5;
+        current_canvas(self.target, &mut self.current).draw(&text.text, DrawParam::new()
+            .dest([(position.x + x_offset).round(), (position.y + y_offset).round()])
+            .scale([1.0 / scale, 1.0 / scale])
+            .color(color_convert(color))
+        );
+
+        Ok(())
+    }
+
+    fn measure_text(
+        &mut self, text: &String, text_font: Option<&String>, text_size: Option<i32>,
+    ) -> Result<Vector2<f32>, Error> {
+        let scale = self.scale;
+        let pixel_size = self.pixel_text_size(text_size);
+        let text = load_text(self.ctx, self.cache, text, text_font, pixel_size)?;
+        Ok(text.size / scale)
+    }
 
-        // Find the cached size for this font, or generate a cache for that
-        let text_size = text_size.map(|v| v as u32).unwrap_or(self.cache.default_text_size);
-        if !font_cache.sizes.contains_key(&text_size) {
-            let font = Font::new(self.ctx, &font_cache.path, text_size).map_err(egtm)?;
-            font_cache.sizes.insert(text_size, font);
+    fn image(
+        &mut self, id: ComponentId,
+        image: &String, source: MRect, destination: MRect, color: Color,
+    ) -> Result<(), Error> {
+        if !self.render_to_component(id)? {
+            return Ok(())
         }
-        let font = font_cache.sizes.get(&text_size).unwrap();
 
-        let text = Text::new(self.ctx, text, font).map_err(egtm)?;
+        let (image, region) = load_image_region(self.ctx, self.cache, image)?;
+        let image_size = Vector2::new(image.width() as f32, image.height() as f32);
+
+        // The source is relative to the region, while ggez wants it relative to the image
+        let src = MRect::new(
+            Point2::new(
+                (region.position.x + source.position.x * region.size.x) / image_size.x,
+                (region.position.y + source.position.y * region.size.y) / image_size.y,
+            ),
+            Vector2::new(
+                source.size.x * region.size.x / image_size.x,
+                source.size.y * region.size.y / image_size.y,
+            ),
+        );
 
-        let x_offset = (size.x - text.width() as f32) * 0.5;
-        let y_offset = (size.y - text.height() as f32) * 0.5;
-        graphics::set_color(self.ctx, color_convert(color)).map_err(egtm)?;
-        graphics::draw(self.ctx, &text, Point2::new(
-            (position.x + x_offset).round(),
-            (position.y + y_offset).round(),
-        ), 0.0).map_err(egtm)?;
+        current_canvas(self.target, &mut self.current).draw(image, DrawParam::new()
+            .src(Rect::new(src.position.x, src.position.y, src.size.x, src.size.y))
+            .dest([destination.position.x.round(), destination.position.y.round()])
+            .scale([
+                destination.size.x / (src.size.x * image_size.x),
+                destination.size.y / (src.size.y * image_size.y),
+            ])
+            .color(color_convert(color))
+        );
 
         Ok(())
     }
 
+    fn image_size(&mut self, image: &String) -> Result<Vector2<u32>, Error> {
+        let (_, region) = load_image_region(self.ctx, self.cache, image)?;
+        Ok(Vector2::new(region.size.x as u32, region.size.y as u32))
+    }
+
     fn vertices(
         &mut self, id: ComponentId,
         vertices: &[Point2<f32>], indices: &[u16], color: Color,
     ) -> Result<(), Error> {
-        self.render_to_component(id)?;
-
-        graphics::set_color(self.ctx, color_convert(color)).map_err(egtm)?;
+        // Every separate mesh is a draw call, so we instead keep adding to the same mesh until we
+        // have to draw something else
+        let batched = self.batch.as_ref()
+            .map(|batch| batch.id == id && batch.color == color)
+            .unwrap_or(false);
+        if !batched {
+            self.flush_batch()?;
+            self.batch = Some(TriangleBatch {
+                id,
+                color,
+                vertices: Vec::new(),
+            });
+        }
 
-        // Convert the vertices+indices to triangles and then a mesh
-        let mut flattened_vertices = Vec::new();
+        // Convert the vertices+indices to triangles to add to the mesh
+        let batch = self.batch.as_mut().unwrap();
         for index in indices {
-            flattened_vertices.push(vertices[*index as usize]);
+            let vertex = vertices[*index as usize];
+            batch.vertices.push([vertex.x, vertex.y]);
+        }
+
+        Ok(())
+    }
+
+    fn ellipse(
+        &mut self, id: ComponentId, center: Point2<f32>, radii: Vector2<f32>, color: Color,
+    ) -> Result<(), Error> {
+        if !self.render_to_component(id)? {
+            return Ok(())
+        }
+
+        let mesh = Mesh::new_ellipse(
+            self.ctx, DrawMode::fill(), [center.x, center.y], radii.x, radii.y, 0.1,
+            color_convert(color),
+        ).map_err(egtm)?;
+        current_canvas(self.target, &mut self.current).draw(&mesh, DrawParam::new());
+
+        Ok(())
+    }
+
+    fn line(
+        &mut self, id: ComponentId, points: &[Point2<f32>], width: f32, color: Color,
+    ) -> Result<(), Error> {
+        // ggez errors on lines without any segments
+        if points.len() < 2 {
+            return Ok(())
+        }
+
+        if !self.render_to_component(id)? {
+            return Ok(())
+        }
+
+        let points: Vec<[f32; 2]> = points.iter().map(|p| [p.x, p.y]).collect();
+        let mesh = Mesh::new_line(self.ctx, &points, width, color_convert(color))
+            .map_err(egtm)?;
+        current_canvas(self.target, &mut self.current).draw(&mesh, DrawParam::new());
+
+        Ok(())
+    }
+
+    fn custom(&mut self, id: ComponentId, command: &Any) -> Result<(), Error> {
+        if !self.render_to_component(id)? {
+            return Ok(())
         }
-        let mesh = Mesh::from_triangles(self.ctx, &flattened_vertices).map_err(egtm)?;
 
-        graphics::draw(self.ctx, &mesh, Point2::new(0.0, 0.0), 0.0).map_err(egtm)?;
+        let canvas = current_canvas(self.target, &mut self.current);
+        for handler in &mut self.cache.custom_handlers {
+            if (**handler)(self.ctx, canvas, command).map_err(egtm)? {
+                break
+            }
+        }
 
         Ok(())
     }
 }
 
+impl<'a> Drop for GgezRenderer<'a> {
+    fn drop(&mut self) {
+        // Errors can't be returned from here, but anything drawn successfully before will still
+        // be there
+        let _ = self.flush_batch();
+        let _ = self.bind_target();
+        let _ = self.target.set_scissor_rect(self.target_scissor);
+    }
+}
+
+/// Gets the canvas being rendered to, either a component's or the target.
+fn current_canvas<'c>(
+    target: &'c mut Canvas, current: &'c mut Option<(ComponentId, Canvas)>,
+) -> &'c mut Canvas {
+    match *current {
+        Some((_, ref mut canvas)) => canvas,
+        None => target,
+    }
+}
+
+/// Renders a UI into an image instead of the window, so it can be drawn onto an in-world
+/// surface such as a computer screen or a billboard, like any other image. The image has to be
+/// created with `Image::new_canvas_image`, it's cleared first, and the UI's target size in UI
+/// units covers the whole image, so an image with more pixels than that renders the UI sharper.
+///
+/// Input for the UI has to be given in UI units, so cursor positions on the surface have to be
+/// converted to positions within the UI's target size by the game.
+///
+/// This is entirely up to the backend, markedly's core renders to whatever target the renderer
+/// draws to and needs no hook for it. The UI is drawn through a canvas of its own, so the game's
+/// canvases and their screen coordinates are untouched, even if rendering the UI fails.
+pub fn render_to_canvas(
+    ctx: &mut Context, cache: &mut GgezCache, ui: &mut Ui, image: &Image,
+) -> Result<(), Error> {
+    let mut canvas = Canvas::from_image(
+        ctx, image.clone(), Some(graphics::Color::new(1.0, 1.0, 1.0, 0.0)),
+    );
+    let result = {
+        let mut renderer = GgezRenderer::new_for_canvas(
+            ctx, cache, &mut canvas, image, ui.target_size(),
+        );
+        render::render(&mut renderer, ui)
+    };
+
+    // Whatever was rendered before a failure is still submitted, like it is for the window
+    canvas.finish(ctx).map_err(egtm)?;
+    result
+}
+
+/// Rounds a canvas size up to a multiple of 64 pixels, so canvases can be reused for components
+/// of a similar size.
+fn bucket_size(size: Vector2<u32>) -> Vector2<u32> {
+    let round = |v: u32| ((v + 63) / 64).max(1) * 64;
+    Vector2::new(round(size.x), round(size.y))
+}
+
+fn canvas_memory(image: &Image) -> usize {
+    image.width() as usize * image.height() as usize * 4
+}
+
+/// Finds the image in the cache by its path, loading it if it wasn't loaded yet.
+fn load_image<'c>(
+    ctx: &mut Context, cache: &'c mut GgezCache, path: &str,
+) -> Result<&'c Image, Error> {
+    if !cache.images.contains_key(path) {
+        let image = Image::from_path(ctx, path)
+            .map_err(|e| Error::Resource {
+                resource: Some(path.into()),
+                error: format!("{}", e),
+            })?;
+        cache.images.insert(path.into(), image);
+    }
+
+    Ok(cache.images.get(path).unwrap())
+}
+
+/// Finds the image for an image identifier, either a path or an atlas region, and the part of
+/// it in pixels that should be used.
+fn load_image_region<'c>(
+    ctx: &mut Context, cache: &'c mut GgezCache, image: &str,
+) -> Result<(&'c Image, MRect), Error> {
+    if image.starts_with(ATLAS_PREFIX) {
+        let (path, rect) = {
+            let region = cache.atlas_regions.get(&image[ATLAS_PREFIX.len()..])
+                .ok_or_else(|| Error::Resource {
+                    resource: Some(image.into()),
+                    error: "No atlas region with this name has been added".into(),
+                })?;
+            (region.image.clone(), region.rect)
+        };
+        Ok((load_image(ctx, cache, &path)?, rect))
+    } else {
+        let image = load_image(ctx, cache, image)?;
+        let size = Vector2::new(image.width() as f32, image.height() as f32);
+        Ok((image, MRect::new(Point2::new(0.0, 0.0), size)))
+    }
+}
+
+/// Finds the laid out text in the cache, laying it out and measuring it if it wasn't yet.
+fn load_text<'c>(
+    ctx: &mut Context, cache: &'c mut GgezCache,
+    text: &String, text_font: Option<&String>, text_size: u32,
+) -> Result<&'c TextCache, Error> {
+    let font_name = load_font(ctx, cache, text_font)?;
+    let key = (text.clone(), font_name, text_size);
+
+    if !cache.texts.contains_key(&key) {
+        let laid_out = Text::new(
+            TextFragment::new(text.as_str())
+                .font(key.1.as_str())
+                .scale(PxScale::from(text_size as f32))
+        );
+        let size = laid_out.measure(ctx).map_err(egtm)?;
+        cache.texts.insert(key.clone(), TextCache {
+            text: laid_out,
+            size: Vector2::new(size.x, size.y),
+            last_used: 0,
+        });
+    }
+
+    let frame = cache.frame;
+    let text_cache = cache.texts.get_mut(&key).unwrap();
+    text_cache.last_used = frame;
+    Ok(text_cache)
+}
+
+/// Finds the font in the cache, using the default if not specified, and adds it to ggez if it
+/// wasn't added yet. Returns the name ggez knows the font by.
+fn load_font(
+    ctx: &mut Context, cache: &mut GgezCache, text_font: Option<&String>,
+) -> Result<String, Error> {
+    // Try to find the font cache, use the default, or error if we can't find it
+    let requested_font_name = text_font.map(|v| v.as_str())
+        .or(cache.default_font.as_ref().map(|v| v.as_str()))
+        .or(embedded_font_name())
+        .ok_or(Error::Resource {
+            resource: None,
+            error: "Could not fall back to default font, no fonts are loaded".into()
+        })?;
+    let font_cache = cache.fonts.get_mut(requested_font_name)
+        .ok_or_else(|| Error::Resource {
+            resource: Some(requested_font_name.into()),
+            error: "Font is not in cache".into()
+        })?;
+
+    // ggez renders fonts at any size, so every font only has to be added once
+    if !font_cache.loaded {
+        let font = match font_cache.source {
+            FontSource::Path(ref path) => FontData::from_path(ctx, path),
+            FontSource::Bytes(ref bytes) => FontData::from_vec(bytes.clone()),
+        }.map_err(egtm)?;
+        ctx.gfx.add_font(requested_font_name, font);
+        font_cache.loaded = true;
+    }
+
+    Ok(requested_font_name.into())
+}
+
+#[cfg(feature = "default-font")]
+fn embedded_font_name() -> Option<&'static str> {
+    Some(EMBEDDED_FONT_NAME)
+}
+
+#[cfg(not(feature = "default-font"))]
+fn embedded_font_name() -> Option<&'static str> {
+    None
+}
+
+/// The operating system's clipboard, to be given to `Input::set_clipboard` so text inputs can
+/// copy and paste text to and from other applications.
+pub struct GgezClipboard {
+    context: ClipboardContext,
+}
+
+impl GgezClipboard {
+    pub fn new() -> Result<Self, Error> {
+        let context = ClipboardContext::new()
+            .map_err(|e| format!("Could not access the clipboard: {}", e))?;
+
+        Ok(GgezClipboard {
+            context,
+        })
+    }
+}
+
+impl Clipboard for GgezClipboard {
+    fn text(&mut self) -> Option<String> {
+        self.context.get_contents().ok()
+    }
+
+    fn set_text(&mut self, text: &str) {
+        // Failing to copy isn't worth interrupting the game for
+        let _ = self.context.set_contents(text.into());
+    }
+}
+
 fn color_convert(color: Color) -> ::ggez::graphics::Color {
     ::ggez::graphics::Color::new(color.red, color.green, color.blue, color.alpha)
 }
 
+/// Converts ggez keyboard modifier flags to markedly modifiers.
+pub fn modifiers_convert(keymods: KeyMods) -> Modifiers {
+    Modifiers {
+        shift: keymods.contains(KeyMods::SHIFT),
+        ctrl: keymods.contains(KeyMods::CTRL),
+        alt: keymods.contains(KeyMods::ALT),
+    }
+}
+
+/// Converts a ggez keycode to the lowercase key name markedly uses for shortcuts, which follows
+/// SDL's key names.
+pub fn keycode_convert(keycode: KeyCode) -> String {
+    match keycode {
+        KeyCode::Back => "backspace".into(),
+        KeyCode::NumpadEnter => "keypad enter".into(),
+        _ => {
+            // Most keys are named the same, apart from these prefixes
+            let name = format!("{:?}", keycode).to_lowercase();
+            if name.starts_with("key") {
+                name["key".len()..].into()
+            } else if name.starts_with("numpad") {
+                format!("keypad {}", &name["numpad".len()..])
+            } else {
+                name
+            }
+        },
+    }
+}
+
+/// Converts a ggez mouse button to a markedly mouse button.
+pub fn mouse_button_convert(button: GMouseButton) -> MouseButton {
+    match button {
+        GMouseButton::Left => MouseButton::Left,
+        GMouseButton::Right => MouseButton::Right,
+        GMouseButton::Middle => MouseButton::Middle,
+        GMouseButton::Other(button) => MouseButton::Other(button.min(255) as u8),
+    }
+}
+
 /// Converts a ggez error to a markedly error.
 pub fn egtm(e: GameError) -> Error {
     Error::Generic { error: Box::new(e) }
@@ -225,5 +1165,5 @@ pub fn egtm(e: GameError) -> Error {
 
 /// Converts a markedly error to a ggez error.
 pub fn emtg(e: Error) -> GameError {
-    GameError::UnknownError(format!("{:#?}", e))
+    GameError::CustomError(format!("{:#?}", e))
 }
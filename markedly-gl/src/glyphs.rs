@@ -0,0 +1,161 @@
+use ab_glyph::{Font, FontArc, GlyphId, PxScale, ScaleFont, point};
+use metrohash::{MetroHashMap};
+use nalgebra::{Point2, Vector2};
+use glow::{self, HasContext, PixelUnpackData};
+
+use markedly::{Error};
+
+const ATLAS_SIZE: u32 = 1024;
+
+/// A rasterized glyph stored in the atlas.
+#[derive(Clone, Copy)]
+pub struct GlyphEntry {
+    /// The top-left of the glyph relative to the pen position on the baseline.
+    pub offset: Vector2<f32>,
+    pub size: Vector2<f32>,
+    pub uv_min: Point2<f32>,
+    pub uv_max: Point2<f32>,
+}
+
+/// A single texture that glyphs are rasterized into as they're used, packed into rows.
+pub struct GlyphAtlas {
+    texture: glow::Texture,
+    entries: MetroHashMap<(String, u32, GlyphId), Option<GlyphEntry>>,
+    cursor: Vector2<u32>,
+    row_height: u32,
+    full: bool,
+}
+
+impl GlyphAtlas {
+    pub fn new(gl: &glow::Context) -> Result<Self, Error> {
+        let texture = ::create_texture(gl, Vector2::new(ATLAS_SIZE, ATLAS_SIZE))?;
+
+        Ok(GlyphAtlas {
+            texture,
+            entries: MetroHashMap::default(),
+            cursor: Vector2::new(0, 0),
+            row_height: 0,
+            full: false,
+        })
+    }
+
+    pub fn texture(&self) -> glow::Texture {
+        self.texture
+    }
+
+    pub fn destroy(&self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_texture(self.texture);
+        }
+    }
+
+    /// Starts over with an empty atlas if it ran out of space last frame.
+    pub fn reset_if_full(&mut self) {
+        if self.full {
+            self.entries.clear();
+            self.cursor = Vector2::new(0, 0);
+            self.row_height = 0;
+            self.full = false;
+        }
+    }
+
+    /// Finds the glyph in the atlas, rasterizing it if it isn't in there yet. Returns None if
+    /// the glyph has nothing to draw, or if there's no space left in the atlas.
+    pub fn glyph(
+        &mut self, gl: &glow::Context, font_name: &String, font: &FontArc, size: u32, id: GlyphId,
+    ) -> Option<GlyphEntry> {
+        let key = (font_name.clone(), size, id);
+        if let Some(entry) = self.entries.get(&key) {
+            return *entry
+        }
+
+        let glyph = id.with_scale_and_position(PxScale::from(size as f32), point(0.0, 0.0));
+        let outlined = match font.outline_glyph(glyph) {
+            Some(outlined) => outlined,
+            None => {
+                self.entries.insert(key, None);
+                return None
+            },
+        };
+        let bounds = outlined.px_bounds();
+        let width = bounds.width().ceil() as u32;
+        let height = bounds.height().ceil() as u32;
+
+        // Find a spot for the glyph, moving to the next row if this one's full, leaving a pixel
+        // of padding so glyphs don't bleed into each other when sampled
+        if self.cursor.x + width + 1 > ATLAS_SIZE {
+            self.cursor = Vector2::new(0, self.cursor.y + self.row_height + 1);
+            self.row_height = 0;
+        }
+        if width + 1 > ATLAS_SIZE || self.cursor.y + height + 1 > ATLAS_SIZE {
+            self.full = true;
+            return None
+        }
+
+        let mut pixels = vec![0u8; (width * height * 4) as usize];
+        outlined.draw(|x, y, coverage| {
+            let index = ((y * width + x) * 4) as usize;
+            pixels[index..index+4].copy_from_slice(&[255, 255, 255, (coverage * 255.0) as u8]);
+        });
+        unsafe {
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            gl.tex_sub_image_2d(
+                glow::TEXTURE_2D, 0,
+                self.cursor.x as i32, self.cursor.y as i32, width as i32, height as i32,
+                glow::RGBA, glow::UNSIGNED_BYTE, PixelUnpackData::Slice(&pixels),
+            );
+        }
+
+        let atlas_size = ATLAS_SIZE as f32;
+        let entry = GlyphEntry {
+            offset: Vector2::new(bounds.min.x, bounds.min.y),
+            size: Vector2::new(width as f32, height as f32),
+            uv_min: Point2::new(
+                self.cursor.x as f32 / atlas_size, self.cursor.y as f32 / atlas_size,
+            ),
+            uv_max: Point2::new(
+                (self.cursor.x + width) as f32 / atlas_size,
+                (self.cursor.y + height) as f32 / atlas_size,
+            ),
+        };
+
+        self.cursor.x += width + 1;
+        self.row_height = self.row_height.max(height);
+        self.entries.insert(key, Some(entry));
+
+        Some(entry)
+    }
+}
+
+/// Lays out a single line of text, returning the glyphs with their pen positions relative to
+/// the start of the baseline, and the total size of the line.
+pub fn layout(font: &FontArc, size: u32, text: &str) -> (Vec<(GlyphId, f32)>, Vector2<f32>) {
+    let scaled = font.as_scaled(PxScale::from(size as f32));
+
+    let mut glyphs = Vec::new();
+    let mut x = 0.0;
+    let mut last = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(last) = last {
+            x += scaled.kern(last, id);
+        }
+
+        glyphs.push((id, x));
+        x += scaled.h_advance(id);
+        last = Some(id);
+    }
+
+    (glyphs, Vector2::new(x, scaled.ascent() - scaled.descent()))
+}
+
+/// Returns true if the font has a glyph for every character in the text.
+pub fn covers(font: &FontArc, text: &str) -> bool {
+    text.chars().all(|c| font.glyph_id(c).0 != 0)
+}
+
+/// Returns the distance from the top of a line to its baseline.
+pub fn ascent(font: &FontArc, size: u32) -> f32 {
+    font.as_scaled(PxScale::from(size as f32)).ascent()
+}
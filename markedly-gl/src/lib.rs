@@ -0,0 +1,656 @@
+extern crate ab_glyph;
+extern crate glow;
+extern crate nalgebra;
+extern crate markedly;
+extern crate metrohash;
+
+mod glyphs;
+
+use ab_glyph::{FontArc};
+use glow::{HasContext};
+use nalgebra::{Point2, Vector2};
+use metrohash::{MetroHashMap};
+
+use markedly::render::{Renderer, Rect as MRect, BlendMode, PixelBuffer, RootTransform};
+use markedly::template::{Color};
+use markedly::{Error, ComponentId};
+
+use glyphs::{GlyphAtlas};
+
+/// Position, texture coordinates, and color.
+const VERTEX_FLOATS: usize = 2 + 2 + 4;
+
+const VERTEX_SHADER: &str = "#version 330 core
+layout(location = 0) in vec2 in_position;
+layout(location = 1) in vec2 in_uv;
+layout(location = 2) in vec4 in_color;
+out vec2 v_uv;
+out vec4 v_color;
+void main() {
+    gl_Position = vec4(in_position, 0.0, 1.0);
+    v_uv = in_uv;
+    v_color = in_color;
+}
+";
+
+const FRAGMENT_SHADER: &str = "#version 330 core
+uniform sampler2D u_texture;
+in vec2 v_uv;
+in vec4 v_color;
+out vec4 out_color;
+void main() {
+    out_color = texture(u_texture, v_uv) * v_color;
+}
+";
+
+struct CacheTexture {
+    texture: glow::Texture,
+    framebuffer: glow::Framebuffer,
+    size: Vector2<u32>,
+}
+
+/// A persistent resource cache for the GL markedly renderer, holding the framebuffer-object
+/// component caches, the shader program, fonts, and the glyph atlas text is rendered from.
+///
+/// GL resources aren't freed automatically, call `destroy` before dropping the GL context.
+pub struct GlCache {
+    data: MetroHashMap<ComponentId, CacheTexture>,
+    fonts: MetroHashMap<String, FontArc>,
+    glyphs: GlyphAtlas,
+
+    program: glow::Program,
+    vertex_array: glow::VertexArray,
+    vertex_buffer: glow::Buffer,
+    white: glow::Texture,
+
+    default_font: Option<String>,
+    default_text_size: u32,
+}
+
+impl GlCache {
+    /// Creates the cache's GL resources, requires a context supporting GLSL 3.30.
+    pub fn new(gl: &glow::Context) -> Result<Self, Error> {
+        unsafe {
+            let program = create_program(gl)?;
+
+            let vertex_array = gl.create_vertex_array()?;
+            let vertex_buffer = gl.create_buffer()?;
+            gl.bind_vertex_array(Some(vertex_array));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertex_buffer));
+            let stride = (VERTEX_FLOATS * 4) as i32;
+            gl.enable_vertex_attrib_array(0);
+            gl.vertex_attrib_pointer_f32(0, 2, glow::FLOAT, false, stride, 0);
+            gl.enable_vertex_attrib_array(1);
+            gl.vertex_attrib_pointer_f32(1, 2, glow::FLOAT, false, stride, 8);
+            gl.enable_vertex_attrib_array(2);
+            gl.vertex_attrib_pointer_f32(2, 4, glow::FLOAT, false, stride, 16);
+            gl.bind_vertex_array(None);
+
+            // Solid color geometry is drawn with a single white pixel as texture, so everything
+            // can share one program
+            let white = create_texture(gl, Vector2::new(1, 1))?;
+            gl.tex_sub_image_2d(
+                glow::TEXTURE_2D, 0, 0, 0, 1, 1, glow::RGBA, glow::UNSIGNED_BYTE,
+                glow::PixelUnpackData::Slice(&[255, 255, 255, 255]),
+            );
+
+            Ok(GlCache {
+                data: MetroHashMap::default(),
+                fonts: MetroHashMap::default(),
+                glyphs: GlyphAtlas::new(gl)?,
+
+                program,
+                vertex_array,
+                vertex_buffer,
+                white,
+
+                default_font: None,
+                default_text_size: 14,
+            })
+        }
+    }
+
+    /// Adds a TrueType or OpenType font to the cache from its data.
+    pub fn add_font<S: Into<String>>(&mut self, name: S, data: Vec<u8>) -> Result<(), Error> {
+        let name = name.into();
+
+        if self.fonts.contains_key(&name) {
+            return Err(Error::Resource {
+                resource: Some(name),
+                error: "Font already added to cache".into(),
+            })
+        }
+
+        let font = FontArc::try_from_vec(data).map_err(|e| Error::Resource {
+            resource: Some(name.clone()),
+            error: e.to_string(),
+        })?;
+
+        if self.default_font.is_none() {
+            self.default_font = Some(name.clone());
+        }
+
+        self.fonts.insert(name, font);
+
+        Ok(())
+    }
+
+    /// Frees all GL resources held by the cache.
+    pub fn destroy(self, gl: &glow::Context) {
+        unsafe {
+            for (_, cache) in self.data {
+                gl.delete_framebuffer(cache.framebuffer);
+                gl.delete_texture(cache.texture);
+            }
+            self.glyphs.destroy(gl);
+            gl.delete_program(self.program);
+            gl.delete_vertex_array(self.vertex_array);
+            gl.delete_buffer(self.vertex_buffer);
+            gl.delete_texture(self.white);
+        }
+    }
+}
+
+/// Where a draw operation renders to.
+#[derive(Clone, Copy)]
+enum Target {
+    Screen,
+    Component(ComponentId),
+}
+
+/// A vertex in pixel coordinates of the framebuffer being rendered to, with texture coordinates
+/// in GL's bottom-left origin convention.
+struct Vertex {
+    position: Point2<f32>,
+    uv: Point2<f32>,
+    color: Color,
+}
+
+/// A markedly renderer for raw GL, intended to be constructed every frame on-demand.
+///
+/// The renderer changes the bound framebuffer, program, vertex array, texture, viewport,
+/// blending, and scissor state. Call `finish` after rendering to rebind the target framebuffer
+/// and disable scissoring, any other state the engine relies on should be restored by it.
+pub struct GlRenderer<'a> {
+    gl: &'a glow::Context,
+    cache: &'a mut GlCache,
+    target: Option<glow::Framebuffer>,
+    target_size: Vector2<u32>,
+    clip_stack: Vec<(ComponentId, MRect)>,
+    direct: Option<ComponentId>,
+}
+
+impl<'a> GlRenderer<'a> {
+    /// Creates a renderer for a target framebuffer, or the default framebuffer if None.
+    pub fn new(
+        gl: &'a glow::Context, cache: &'a mut GlCache,
+        target: Option<glow::Framebuffer>, target_size: Vector2<u32>,
+    ) -> Self {
+        cache.glyphs.reset_if_full();
+
+        unsafe {
+            gl.use_program(Some(cache.program));
+            gl.bind_vertex_array(Some(cache.vertex_array));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(cache.vertex_buffer));
+            gl.active_texture(glow::TEXTURE0);
+            let location = gl.get_uniform_location(cache.program, "u_texture");
+            gl.uniform_1_i32(location.as_ref(), 0);
+
+            gl.enable(glow::BLEND);
+            gl.blend_func_separate(
+                glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA, glow::ONE, glow::ONE_MINUS_SRC_ALPHA,
+            );
+        }
+
+        GlRenderer {
+            gl,
+            cache,
+            target,
+            target_size,
+            clip_stack: Vec::new(),
+            direct: None,
+        }
+    }
+
+    /// Rebinds the target framebuffer and disables scissoring.
+    pub fn finish(self) {
+        unsafe {
+            self.gl.disable(glow::SCISSOR_TEST);
+            self.gl.bind_framebuffer(glow::FRAMEBUFFER, self.target);
+            self.gl.bind_vertex_array(None);
+        }
+    }
+
+    fn current_clip(&self, id: ComponentId) -> Option<MRect> {
+        self.clip_stack.iter().rev()
+            .filter(|v| v.0 == id)
+            .next()
+            .map(|v| v.1)
+    }
+
+    fn component_target(&self, id: ComponentId) -> Target {
+        // In direct mode we render to the target instead
+        if self.direct == Some(id) {
+            Target::Screen
+        } else {
+            Target::Component(id)
+        }
+    }
+
+    fn draw(
+        &mut self, target: Target, clip: Option<MRect>,
+        texture: glow::Texture, vertices: &[Vertex],
+    ) -> Result<(), Error> {
+        let (framebuffer, size) = match target {
+            Target::Screen => (self.target, self.target_size),
+            Target::Component(id) => {
+                let cache = self.cache.data.get(&id)
+                    .ok_or("Component cache not found for rendering")?;
+                (Some(cache.framebuffer), cache.size)
+            },
+        };
+
+        // Clip using a scissor rect limited to the framebuffer, if nothing's visible we're done
+        let mut scissor = MRect::new(
+            Point2::new(0.0, 0.0), Vector2::new(size.x as f32, size.y as f32),
+        );
+        if let Some(clip) = clip {
+            scissor = match scissor.intersection(&clip) {
+                Some(scissor) => scissor,
+                None => return Ok(()),
+            };
+        }
+        let scissor_start = Point2::new(
+            scissor.position.x.floor() as i32, scissor.position.y.floor() as i32,
+        );
+        let scissor_end = scissor.end();
+        let scissor_end = Point2::new(
+            (scissor_end.x.ceil() as i32).min(size.x as i32),
+            (scissor_end.y.ceil() as i32).min(size.y as i32),
+        );
+        if scissor_end.x <= scissor_start.x || scissor_end.y <= scissor_start.y {
+            return Ok(())
+        }
+
+        // Convert the vertices from pixel coordinates to normalized device coordinates
+        let mut data: Vec<u8> = Vec::with_capacity(vertices.len() * VERTEX_FLOATS * 4);
+        for vertex in vertices {
+            let values = [
+                vertex.position.x / size.x as f32 * 2.0 - 1.0,
+                1.0 - vertex.position.y / size.y as f32 * 2.0,
+                vertex.uv.x, vertex.uv.y,
+                vertex.color.red, vertex.color.green, vertex.color.blue, vertex.color.alpha,
+            ];
+            for value in &values {
+                data.extend_from_slice(&value.to_bits().to_ne_bytes());
+            }
+        }
+
+        unsafe {
+            let gl = self.gl;
+            gl.bind_framebuffer(glow::FRAMEBUFFER, framebuffer);
+            gl.viewport(0, 0, size.x as i32, size.y as i32);
+
+            // GL's scissor origin is at the bottom-left
+            gl.enable(glow::SCISSOR_TEST);
+            gl.scissor(
+                scissor_start.x, size.y as i32 - scissor_end.y,
+                scissor_end.x - scissor_start.x, scissor_end.y - scissor_start.y,
+            );
+
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, &data, glow::STREAM_DRAW);
+            gl.draw_arrays(glow::TRIANGLES, 0, vertices.len() as i32);
+        }
+
+        Ok(())
+    }
+
+    fn draw_cache(
+        &mut self, target: Target, clip: Option<MRect>,
+        source_id: ComponentId, position: Point2<f32>, scale: f32, alpha: f32,
+        blend_mode: BlendMode,
+    ) -> Result<(), Error> {
+        let (texture, source_size) = {
+            let source = self.cache.data.get(&source_id)
+                .ok_or("Source component cache not found for rendering")?;
+            (source.texture, source.size)
+        };
+        let size = Vector2::new(source_size.x as f32, source_size.y as f32) * scale;
+        let position = Point2::new(position.x.round(), position.y.round());
+        let color = Color::new(1.0, 1.0, 1.0, alpha);
+
+        // Caches are rendered to with the top at the end of the texture, so flip vertically
+        let vertices = quad(
+            MRect::new(position, size),
+            Point2::new(0.0, 1.0), Point2::new(1.0, 0.0), color,
+        );
+
+        if blend_mode == BlendMode::Alpha {
+            return self.draw(target, clip, texture, &vertices)
+        }
+
+        let color_func = match blend_mode {
+            BlendMode::Add => (glow::SRC_ALPHA, glow::ONE),
+            _ => (glow::DST_COLOR, glow::ONE_MINUS_SRC_ALPHA),
+        };
+        unsafe {
+            self.gl.blend_func_separate(
+                color_func.0, color_func.1, glow::ONE, glow::ONE_MINUS_SRC_ALPHA,
+            );
+        }
+        let result = self.draw(target, clip, texture, &vertices);
+        unsafe {
+            self.gl.blend_func_separate(
+                glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA, glow::ONE, glow::ONE_MINUS_SRC_ALPHA,
+            );
+        }
+        result
+    }
+}
+
+impl<'a> Renderer for GlRenderer<'a> {
+    fn render_cache_to_target(
+        &mut self, id: ComponentId, transform: RootTransform,
+    ) -> Result<(), Error> {
+        let position = Point2::from_coordinates(transform.offset);
+        self.draw_cache(
+            Target::Screen, None, id, position, transform.scale, 1.0, BlendMode::Alpha,
+        )
+    }
+
+    fn create_resize_cache(
+        &mut self, id: ComponentId, size: Vector2<u32>
+    ) -> Result<bool, Error> {
+        // If we have a cached texture and it's of the right size, we only have to clear
+        if let Some(cache) = self.cache.data.get(&id) {
+            if cache.size == size {
+                return Ok(false)
+            }
+        }
+
+        // We don't have what we need so create a new framebuffer, replacing the old one
+        unsafe {
+            let gl = self.gl;
+            if let Some(old) = self.cache.data.remove(&id) {
+                gl.delete_framebuffer(old.framebuffer);
+                gl.delete_texture(old.texture);
+            }
+
+            let texture = create_texture(gl, size)?;
+            let framebuffer = gl.create_framebuffer()?;
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+            gl.framebuffer_texture_2d(
+                glow::FRAMEBUFFER, glow::COLOR_ATTACHMENT0, glow::TEXTURE_2D, Some(texture), 0,
+            );
+            if gl.check_framebuffer_status(glow::FRAMEBUFFER) != glow::FRAMEBUFFER_COMPLETE {
+                gl.delete_framebuffer(framebuffer);
+                gl.delete_texture(texture);
+                return Err("Component cache framebuffer is incomplete".into())
+            }
+
+            self.cache.data.insert(id, CacheTexture {
+                texture,
+                framebuffer,
+                size,
+            });
+        }
+
+        Ok(true)
+    }
+
+    fn clear_cache(&mut self, id: ComponentId) -> Result<(), Error> {
+        let framebuffer = self.cache.data.get(&id)
+            .ok_or("Component cache not found for clearing")?.framebuffer;
+
+        unsafe {
+            let gl = self.gl;
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+            gl.disable(glow::SCISSOR_TEST);
+            gl.clear_color(1.0, 1.0, 1.0, 0.0);
+            gl.clear(glow::COLOR_BUFFER_BIT);
+        }
+
+        Ok(())
+    }
+
+    fn remove_cache(&mut self, id: ComponentId) -> Result<(), Error> {
+        if let Some(cache) = self.cache.data.remove(&id) {
+            unsafe {
+                self.gl.delete_framebuffer(cache.framebuffer);
+                self.gl.delete_texture(cache.texture);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_cache_pixels(&mut self, id: ComponentId) -> Result<PixelBuffer, Error> {
+        let (framebuffer, size) = {
+            let cache = self.cache.data.get(&id)
+                .ok_or("Component cache not found for reading")?;
+            (cache.framebuffer, Vector2::new(cache.size.x.max(1), cache.size.y.max(1)))
+        };
+
+        let row_bytes = (size.x * 4) as usize;
+        let mut flipped = vec![0u8; row_bytes * size.y as usize];
+        unsafe {
+            let gl = self.gl;
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+            gl.pixel_store_i32(glow::PACK_ALIGNMENT, 1);
+            gl.read_pixels(
+                0, 0, size.x as i32, size.y as i32, glow::RGBA, glow::UNSIGNED_BYTE,
+                glow::PixelPackData::Slice(&mut flipped),
+            );
+        }
+
+        // GL reads rows from the bottom up, but caches are rendered with the top at the end
+        let data = flipped.chunks(row_bytes).rev()
+            .flat_map(|row| row.iter().cloned())
+            .collect();
+
+        PixelBuffer::new(size, data)
+    }
+
+    fn render_cache(
+        &mut self, id: ComponentId,
+        source_id: ComponentId, position: Point2<f32>, alpha: f32, blend_mode: BlendMode,
+    ) -> Result<(), Error> {
+        let target = self.component_target(id);
+        let clip = self.current_clip(id);
+        self.draw_cache(target, clip, source_id, position, 1.0, alpha, blend_mode)
+    }
+
+    fn begin_direct(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.direct = Some(id);
+        Ok(())
+    }
+
+    fn end_direct(&mut self, _id: ComponentId) -> Result<(), Error> {
+        self.direct = None;
+        Ok(())
+    }
+
+    fn push_clip(&mut self, id: ComponentId, rect: MRect) -> Result<(), Error> {
+        // Nested clips can never show more than the clip they're nested in
+        let rect = if let Some(current) = self.current_clip(id) {
+            current.intersection(&rect)
+                .unwrap_or(MRect::new(rect.position, Vector2::new(0.0, 0.0)))
+        } else {
+            rect
+        };
+
+        self.clip_stack.push((id, rect));
+        Ok(())
+    }
+
+    fn pop_clip(&mut self, id: ComponentId) -> Result<(), Error> {
+        match self.clip_stack.pop() {
+            Some((clip_id, _)) if clip_id == id => Ok(()),
+            _ => Err("Clip popped without a matching push for the component".into()),
+        }
+    }
+
+    fn text(
+        &mut self, id: ComponentId,
+        text: &String, text_font: Option<&String>, text_size: Option<i32>,
+        position: Point2<f32>, size: Vector2<f32>, color: Color,
+    ) -> Result<(), Error> {
+        let target = self.component_target(id);
+        let clip = self.current_clip(id);
+
+        let (font_name, font) = find_font(self.cache, text_font)?;
+        let text_size = text_size.map(|v| v as u32).unwrap_or(self.cache.default_text_size);
+        let (layout, text_area) = glyphs::layout(&font, text_size, text);
+
+        // Center the line in the area, the glyph positions are relative to the baseline
+        let origin = Point2::new(
+            (position.x + (size.x - text_area.x) * 0.5).round(),
+            (position.y + (size.y - text_area.y) * 0.5 + glyphs::ascent(&font, text_size))
+                .round(),
+        );
+
+        let mut vertices = Vec::new();
+        for (glyph_id, x) in layout {
+            let entry = match self.cache.glyphs.glyph(
+                self.gl, &font_name, &font, text_size, glyph_id,
+            ) {
+                Some(entry) => entry,
+                None => continue,
+            };
+
+            let glyph_position = Point2::new(origin.x + x, origin.y) + entry.offset;
+            vertices.extend(quad(
+                MRect::new(Point2::new(glyph_position.x.round(), glyph_position.y), entry.size),
+                entry.uv_min, entry.uv_max, color,
+            ));
+        }
+
+        if vertices.is_empty() {
+            return Ok(())
+        }
+
+        let texture = self.cache.glyphs.texture();
+        self.draw(target, clip, texture, &vertices)
+    }
+
+    fn measure_text(
+        &mut self, text: &String, text_font: Option<&String>, text_size: Option<i32>,
+    ) -> Result<Vector2<f32>, Error> {
+        let (_, font) = find_font(self.cache, text_font)?;
+        let text_size = text_size.map(|v| v as u32).unwrap_or(self.cache.default_text_size);
+        Ok(glyphs::layout(&font, text_size, text).1)
+    }
+
+    fn has_glyphs(
+        &mut self, text: &String, text_font: Option<&String>,
+    ) -> Result<bool, Error> {
+        let (_, font) = find_font(self.cache, text_font)?;
+        Ok(glyphs::covers(&font, text))
+    }
+
+    fn vertices(
+        &mut self, id: ComponentId,
+        vertices: &[Point2<f32>], indices: &[u16], color: Color,
+    ) -> Result<(), Error> {
+        let target = self.component_target(id);
+        let clip = self.current_clip(id);
+
+        // Convert the vertices+indices to triangles
+        let flattened_vertices: Vec<_> = indices.iter()
+            .map(|index| Vertex {
+                position: vertices[*index as usize],
+                uv: Point2::new(0.0, 0.0),
+                color,
+            })
+            .collect();
+
+        let texture = self.cache.white;
+        self.draw(target, clip, texture, &flattened_vertices)
+    }
+}
+
+/// Finds the font in the cache, using the default if not specified.
+fn find_font(
+    cache: &GlCache, text_font: Option<&String>,
+) -> Result<(String, FontArc), Error> {
+    // Try to find the font, use the default, or error if we can't find it
+    let requested_font_name = text_font.or(cache.default_font.as_ref())
+        .ok_or(Error::Resource {
+            resource: None,
+            error: "Could not fall back to default font, no fonts are loaded".into()
+        })?;
+    let font = cache.fonts.get(requested_font_name)
+        .ok_or_else(|| Error::Resource {
+            resource: Some(requested_font_name.clone()),
+            error: "Font is not in cache".into()
+        })?;
+
+    Ok((requested_font_name.clone(), font.clone()))
+}
+
+fn quad(area: MRect, uv_min: Point2<f32>, uv_max: Point2<f32>, color: Color) -> Vec<Vertex> {
+    let end = area.end();
+    let corners = [
+        (area.position, uv_min),
+        (Point2::new(end.x, area.position.y), Point2::new(uv_max.x, uv_min.y)),
+        (end, uv_max),
+        (Point2::new(area.position.x, end.y), Point2::new(uv_min.x, uv_max.y)),
+    ];
+
+    [0, 1, 2, 0, 2, 3].iter()
+        .map(|i| Vertex { position: corners[*i].0, uv: corners[*i].1, color })
+        .collect()
+}
+
+/// Creates an empty RGBA texture with linear filtering, leaving it bound.
+fn create_texture(gl: &glow::Context, size: Vector2<u32>) -> Result<glow::Texture, Error> {
+    unsafe {
+        let texture = gl.create_texture()?;
+        gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+        gl.tex_image_2d(
+            glow::TEXTURE_2D, 0, glow::RGBA8 as i32,
+            size.x.max(1) as i32, size.y.max(1) as i32, 0,
+            glow::RGBA, glow::UNSIGNED_BYTE, None,
+        );
+        gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MIN_FILTER, glow::LINEAR as i32);
+        gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAG_FILTER, glow::LINEAR as i32);
+        gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE as i32);
+        gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE as i32);
+        Ok(texture)
+    }
+}
+
+unsafe fn create_program(gl: &glow::Context) -> Result<glow::Program, Error> {
+    let program = gl.create_program()?;
+
+    let sources = [
+        (glow::VERTEX_SHADER, VERTEX_SHADER),
+        (glow::FRAGMENT_SHADER, FRAGMENT_SHADER),
+    ];
+    let mut shaders = Vec::new();
+    for &(shader_type, source) in &sources {
+        let shader = gl.create_shader(shader_type)?;
+        gl.shader_source(shader, source);
+        gl.compile_shader(shader);
+        if !gl.get_shader_compile_status(shader) {
+            return Err(format!("Shader failed to compile: {}", gl.get_shader_info_log(shader))
+                .into())
+        }
+        gl.attach_shader(program, shader);
+        shaders.push(shader);
+    }
+
+    gl.link_program(program);
+    if !gl.get_program_link_status(program) {
+        return Err(format!("Program failed to link: {}", gl.get_program_info_log(program))
+            .into())
+    }
+
+    for shader in shaders {
+        gl.detach_shader(program, shader);
+        gl.delete_shader(shader);
+    }
+
+    Ok(program)
+}
@@ -0,0 +1,171 @@
+use std::io::{self, Write};
+
+use crossterm::{QueueableCommand};
+use crossterm::cursor::{MoveTo};
+use crossterm::style::{self, Print, SetForegroundColor, SetBackgroundColor, ResetColor};
+use nalgebra::{Vector2};
+
+use markedly::render::{BlendMode};
+use markedly::template::{Color};
+
+/// A single character cell in a grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub character: char,
+    pub foreground: Color,
+    pub background: Color,
+}
+
+impl Cell {
+    /// An empty cell with a transparent background.
+    pub fn empty() -> Self {
+        Cell {
+            character: ' ',
+            foreground: Color::new(1.0, 1.0, 1.0, 1.0),
+            background: Color::new(0.0, 0.0, 0.0, 0.0),
+        }
+    }
+
+    /// Blends this cell over another cell.
+    pub fn blend_over(&self, other: &Cell, alpha: f32) -> Cell {
+        let background_alpha = self.background.alpha * alpha;
+
+        // An opaque enough background hides the character beneath it
+        let (character, foreground) = if self.character != ' ' {
+            let mut foreground = self.foreground;
+            foreground.alpha *= alpha;
+            (self.character, foreground)
+        } else if background_alpha >= 0.5 {
+            (' ', other.foreground)
+        } else {
+            (other.character, other.foreground)
+        };
+
+        Cell {
+            character,
+            foreground,
+            background: blend(self.background, background_alpha, other.background),
+        }
+    }
+
+    /// Combines this cell with another cell using a blend mode. Added and multiplied backgrounds
+    /// tint the cell beneath rather than hiding its character.
+    pub fn blend_with(&self, other: &Cell, alpha: f32, blend_mode: BlendMode) -> Cell {
+        if blend_mode == BlendMode::Alpha {
+            return self.blend_over(other, alpha)
+        }
+
+        let background_alpha = self.background.alpha * alpha;
+        let channel = |over: f32, under: f32| match blend_mode {
+            BlendMode::Add => (under + over * background_alpha).min(1.0),
+            _ => under * (1.0 - background_alpha + over * background_alpha),
+        };
+        let under = other.background;
+        let background = Color::new(
+            channel(self.background.red, under.red),
+            channel(self.background.green, under.green),
+            channel(self.background.blue, under.blue),
+            under.alpha,
+        );
+
+        let mut cell = if self.character != ' ' {
+            self.blend_over(other, alpha)
+        } else {
+            other.clone()
+        };
+        cell.background = background;
+        cell
+    }
+}
+
+/// A grid of character cells, used both for component caches and as the target that's drawn to
+/// the terminal.
+#[derive(Debug, Clone)]
+pub struct Grid {
+    size: Vector2<u32>,
+    cells: Vec<Cell>,
+}
+
+impl Grid {
+    /// Creates a new grid with a size in cells.
+    pub fn new(size: Vector2<u32>) -> Self {
+        Grid {
+            size,
+            cells: vec![Cell::empty(); (size.x * size.y) as usize],
+        }
+    }
+
+    pub fn size(&self) -> Vector2<u32> {
+        self.size
+    }
+
+    pub fn cell(&self, x: i32, y: i32) -> Option<&Cell> {
+        self.index(x, y).map(|i| &self.cells[i])
+    }
+
+    pub fn cell_mut(&mut self, x: i32, y: i32) -> Option<&mut Cell> {
+        self.index(x, y).map(move |i| &mut self.cells[i])
+    }
+
+    /// Resets all cells to empty.
+    pub fn clear(&mut self) {
+        for cell in &mut self.cells {
+            *cell = Cell::empty();
+        }
+    }
+
+    /// Queues drawing the grid to a terminal at its top-left, the writer still has to be flushed.
+    pub fn draw<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for y in 0..self.size.y {
+            writer.queue(MoveTo(0, y as u16))?;
+            for x in 0..self.size.x {
+                let cell = &self.cells[(y * self.size.x + x) as usize];
+
+                if cell.background.alpha > 0.0 {
+                    writer.queue(SetBackgroundColor(color_convert(cell.background)))?;
+                } else {
+                    writer.queue(SetBackgroundColor(style::Color::Reset))?;
+                }
+                writer.queue(SetForegroundColor(color_convert(cell.foreground)))?;
+                writer.queue(Print(cell.character))?;
+            }
+        }
+        writer.queue(ResetColor)?;
+
+        Ok(())
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x as u32 >= self.size.x || y as u32 >= self.size.y {
+            return None
+        }
+
+        Some((y as u32 * self.size.x + x as u32) as usize)
+    }
+}
+
+/// Blends a color with an alpha over another color.
+pub fn blend(color: Color, alpha: f32, under: Color) -> Color {
+    let out_alpha = alpha + under.alpha * (1.0 - alpha);
+    if out_alpha <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0, 0.0)
+    }
+
+    let channel = |over: f32, under_channel: f32| {
+        (over * alpha + under_channel * under.alpha * (1.0 - alpha)) / out_alpha
+    };
+    Color::new(
+        channel(color.red, under.red),
+        channel(color.green, under.green),
+        channel(color.blue, under.blue),
+        out_alpha,
+    )
+}
+
+fn color_convert(color: Color) -> style::Color {
+    style::Color::Rgb {
+        r: (color.red * 255.0) as u8,
+        g: (color.green * 255.0) as u8,
+        b: (color.blue * 255.0) as u8,
+    }
+}
@@ -0,0 +1,347 @@
+extern crate crossterm;
+extern crate nalgebra;
+extern crate markedly;
+extern crate metrohash;
+
+mod grid;
+
+pub use grid::{Grid, Cell};
+
+use std::io;
+
+use nalgebra::{Point2, Vector2};
+use metrohash::{MetroHashMap, MetroHashSet};
+
+use markedly::render::{Renderer, Rect, BlendMode, TextEffects, RootTransform};
+use markedly::template::{Color};
+use markedly::{Error, ComponentId};
+
+/// A persistent resource cache for the terminal markedly renderer.
+///
+/// Markedly lays out in units the same as pixels on other backends, the cell size decides how
+/// many units a single character cell takes up.
+pub struct TerminalCache {
+    data: MetroHashMap<ComponentId, Grid>,
+    cell_size: Vector2<f32>,
+}
+
+impl TerminalCache {
+    pub fn new(cell_size: Vector2<f32>) -> Self {
+        TerminalCache {
+            data: MetroHashMap::default(),
+            cell_size,
+        }
+    }
+
+    pub fn cell_size(&self) -> Vector2<f32> {
+        self.cell_size
+    }
+
+    /// Returns the size in units a UI should be laid out in to fill a grid.
+    pub fn units_for(&self, grid: &Grid) -> Vector2<f32> {
+        Vector2::new(
+            grid.size().x as f32 * self.cell_size.x,
+            grid.size().y as f32 * self.cell_size.y,
+        )
+    }
+}
+
+/// A markedly renderer for terminals, rendering to a character grid that can then be drawn to
+/// the terminal. Intended to be constructed every frame on-demand.
+pub struct TerminalRenderer<'a> {
+    cache: &'a mut TerminalCache,
+    target: &'a mut Grid,
+    clip_stack: Vec<(ComponentId, Rect)>,
+    direct: Option<ComponentId>,
+}
+
+impl<'a> TerminalRenderer<'a> {
+    pub fn new(cache: &'a mut TerminalCache, target: &'a mut Grid) -> Self {
+        TerminalRenderer {
+            cache,
+            target,
+            clip_stack: Vec::new(),
+            direct: None,
+        }
+    }
+
+    fn current_clip(&self, id: ComponentId) -> Option<Rect> {
+        self.clip_stack.iter().rev()
+            .filter(|v| v.0 == id)
+            .next()
+            .map(|v| v.1)
+    }
+
+    fn grid(&mut self, id: ComponentId) -> Result<&mut Grid, Error> {
+        // In direct mode we render to the target instead
+        if self.direct == Some(id) {
+            return Ok(&mut *self.target)
+        }
+
+        self.cache.data.get_mut(&id).ok_or("Component cache not found for rendering".into())
+    }
+
+    /// Returns the cell a position in units falls in.
+    fn to_cell(&self, position: Point2<f32>) -> Point2<i32> {
+        Point2::new(
+            (position.x / self.cache.cell_size.x).round() as i32,
+            (position.y / self.cache.cell_size.y).round() as i32,
+        )
+    }
+
+    /// Returns the center of a cell in units.
+    fn cell_center(&self, cell: Point2<i32>) -> Point2<f32> {
+        Point2::new(
+            (cell.x as f32 + 0.5) * self.cache.cell_size.x,
+            (cell.y as f32 + 0.5) * self.cache.cell_size.y,
+        )
+    }
+}
+
+impl<'a> Renderer for TerminalRenderer<'a> {
+    fn render_cache_to_target(
+        &mut self, id: ComponentId, transform: RootTransform,
+    ) -> Result<(), Error> {
+        // Cells can't be scaled, so only the offset is applied
+        let offset = Point2::new(
+            (transform.offset.x / self.cache.cell_size.x).round() as i32,
+            (transform.offset.y / self.cache.cell_size.y).round() as i32,
+        );
+        let source = self.cache.data.get(&id).ok_or("Component cache not found for rendering")?;
+        composite(source, self.target, offset, None, 1.0, BlendMode::Alpha);
+        Ok(())
+    }
+
+    fn create_resize_cache(
+        &mut self, id: ComponentId, size: Vector2<u32>
+    ) -> Result<bool, Error> {
+        let cells = Vector2::new(
+            (size.x as f32 / self.cache.cell_size.x).ceil() as u32,
+            (size.y as f32 / self.cache.cell_size.y).ceil() as u32,
+        );
+
+        // If we have a cached grid and it's of the right size, we only have to clear
+        if let Some(grid) = self.cache.data.get(&id) {
+            if grid.size() == cells {
+                return Ok(false)
+            }
+        }
+
+        self.cache.data.insert(id, Grid::new(cells));
+
+        Ok(true)
+    }
+
+    fn clear_cache(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.cache.data.get_mut(&id).ok_or("Component cache not found for clearing")?.clear();
+        Ok(())
+    }
+
+    fn remove_cache(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.cache.data.remove(&id);
+        Ok(())
+    }
+
+    fn render_cache(
+        &mut self, id: ComponentId,
+        source_id: ComponentId, position: Point2<f32>, alpha: f32, blend_mode: BlendMode,
+    ) -> Result<(), Error> {
+        let offset = self.to_cell(position);
+        let clip = self.current_clip(id).map(|clip| (clip, self.cache.cell_size));
+
+        // Take the source out so we can borrow the target mutably, it can't be the same grid
+        let source = self.cache.data.remove(&source_id)
+            .ok_or("Source component cache not found for rendering")?;
+        let result = self.grid(id).map(|target| {
+            composite(&source, target, offset, clip, alpha, blend_mode)
+        });
+        self.cache.data.insert(source_id, source);
+
+        result
+    }
+
+    fn begin_direct(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.direct = Some(id);
+        Ok(())
+    }
+
+    fn end_direct(&mut self, _id: ComponentId) -> Result<(), Error> {
+        self.direct = None;
+        Ok(())
+    }
+
+    fn push_clip(&mut self, id: ComponentId, rect: Rect) -> Result<(), Error> {
+        // Nested clips can never show more than the clip they're nested in
+        let rect = if let Some(current) = self.current_clip(id) {
+            current.intersection(&rect)
+                .unwrap_or(Rect::new(rect.position, Vector2::new(0.0, 0.0)))
+        } else {
+            rect
+        };
+
+        self.clip_stack.push((id, rect));
+        Ok(())
+    }
+
+    fn pop_clip(&mut self, id: ComponentId) -> Result<(), Error> {
+        match self.clip_stack.pop() {
+            Some((clip_id, _)) if clip_id == id => Ok(()),
+            _ => Err("Clip popped without a matching push for the component".into()),
+        }
+    }
+
+    fn text(
+        &mut self, id: ComponentId,
+        text: &String, _text_font: Option<&String>, _text_size: Option<i32>,
+        position: Point2<f32>, size: Vector2<f32>, color: Color,
+    ) -> Result<(), Error> {
+        // Every character takes up exactly one cell, fonts and sizes don't exist in a terminal
+        let cell_size = self.cache.cell_size;
+        let text_size = Vector2::new(text.chars().count() as f32 * cell_size.x, cell_size.y);
+        let start = self.to_cell(Point2::new(
+            position.x + (size.x - text_size.x) * 0.5,
+            position.y + (size.y - text_size.y) * 0.5,
+        ));
+        let clip = self.current_clip(id);
+
+        let cells: Vec<_> = text.chars().enumerate()
+            .map(|(i, c)| (Point2::new(start.x + i as i32, start.y), c))
+            .filter(|&(cell, _)| clip.map(|c| c.contains(self.cell_center(cell))).unwrap_or(true))
+            .collect();
+
+        let target = self.grid(id)?;
+        for (cell, character) in cells {
+            if let Some(cell) = target.cell_mut(cell.x, cell.y) {
+                cell.character = character;
+                cell.foreground = color;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn measure_text(
+        &mut self, text: &String, _text_font: Option<&String>, _text_size: Option<i32>,
+    ) -> Result<Vector2<f32>, Error> {
+        let cell_size = self.cache.cell_size;
+        Ok(Vector2::new(text.chars().count() as f32 * cell_size.x, cell_size.y))
+    }
+
+    fn text_effects(
+        &mut self, _id: ComponentId,
+        _text: &String, _text_font: Option<&String>, _text_size: Option<i32>,
+        _position: Point2<f32>, _size: Vector2<f32>, _effects: &TextEffects,
+    ) -> Result<(), Error> {
+        // Offset copies of the text would overwrite neighbouring cells, so effects are skipped
+        Ok(())
+    }
+
+    fn vertices(
+        &mut self, id: ComponentId,
+        vertices: &[Point2<f32>], indices: &[u16], color: Color,
+    ) -> Result<(), Error> {
+        let clip = self.current_clip(id);
+
+        // Fill every cell that has its center inside of a triangle, each cell only once so
+        // triangles sharing an edge don't blend twice
+        let mut cells = MetroHashSet::default();
+        for triangle in indices.chunks(3) {
+            if triangle.len() != 3 {
+                continue
+            }
+            let points = [
+                vertices[triangle[0] as usize],
+                vertices[triangle[1] as usize],
+                vertices[triangle[2] as usize],
+            ];
+
+            let min = self.to_cell(Point2::new(
+                points[0].x.min(points[1].x).min(points[2].x),
+                points[0].y.min(points[1].y).min(points[2].y),
+            ));
+            let max = self.to_cell(Point2::new(
+                points[0].x.max(points[1].x).max(points[2].x),
+                points[0].y.max(points[1].y).max(points[2].y),
+            ));
+
+            for y in (min.y - 1)..(max.y + 1) {
+                for x in (min.x - 1)..(max.x + 1) {
+                    let cell = Point2::new(x, y);
+                    let center = self.cell_center(cell);
+
+                    if !in_triangle(center, &points) {
+                        continue
+                    }
+                    if let Some(clip) = clip {
+                        if !clip.contains(center) {
+                            continue
+                        }
+                    }
+
+                    cells.insert((cell.x, cell.y));
+                }
+            }
+        }
+
+        let target = self.grid(id)?;
+        for (x, y) in cells {
+            if let Some(cell) = target.cell_mut(x, y) {
+                cell.background = grid::blend(color, color.alpha, cell.background);
+
+                // A filled cell hides the character beneath it
+                if color.alpha >= 0.5 {
+                    cell.character = ' ';
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Blends a source grid over a target grid at a cell offset, only including cells with their
+/// centers in the clip.
+fn composite(
+    source: &Grid, target: &mut Grid, offset: Point2<i32>,
+    clip: Option<(Rect, Vector2<f32>)>, alpha: f32, blend_mode: BlendMode,
+) {
+    for y in 0..source.size().y as i32 {
+        for x in 0..source.size().x as i32 {
+            let target_cell = Point2::new(x + offset.x, y + offset.y);
+
+            if let Some((clip, cell_size)) = clip {
+                let center = Point2::new(
+                    (target_cell.x as f32 + 0.5) * cell_size.x,
+                    (target_cell.y as f32 + 0.5) * cell_size.y,
+                );
+                if !clip.contains(center) {
+                    continue
+                }
+            }
+
+            let source_cell = source.cell(x, y).unwrap();
+            if let Some(cell) = target.cell_mut(target_cell.x, target_cell.y) {
+                *cell = source_cell.blend_with(cell, alpha, blend_mode);
+            }
+        }
+    }
+}
+
+fn in_triangle(point: Point2<f32>, triangle: &[Point2<f32>; 3]) -> bool {
+    let sign = |a: Point2<f32>, b: Point2<f32>, c: Point2<f32>| {
+        (a.x - c.x) * (b.y - c.y) - (b.x - c.x) * (a.y - c.y)
+    };
+
+    let d1 = sign(point, triangle[0], triangle[1]);
+    let d2 = sign(point, triangle[1], triangle[2]);
+    let d3 = sign(point, triangle[2], triangle[0]);
+
+    let has_negative = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let has_positive = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+    !(has_negative && has_positive)
+}
+
+/// Converts an IO error from drawing to the terminal to a markedly error.
+pub fn eitm(e: io::Error) -> Error {
+    Error::Generic { error: Box::new(e) }
+}
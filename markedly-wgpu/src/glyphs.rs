@@ -0,0 +1,175 @@
+use ab_glyph::{Font, FontArc, GlyphId, PxScale, ScaleFont, point};
+use metrohash::{MetroHashMap};
+use nalgebra::{Point2, Vector2};
+use wgpu::{self, Device, Queue, Texture, BindGroup, BindGroupLayout, Sampler};
+
+const ATLAS_SIZE: u32 = 1024;
+
+/// A rasterized glyph stored in the atlas.
+#[derive(Clone, Copy)]
+pub struct GlyphEntry {
+    /// The top-left of the glyph relative to the pen position on the baseline.
+    pub offset: Vector2<f32>,
+    pub size: Vector2<f32>,
+    pub uv_min: Point2<f32>,
+    pub uv_max: Point2<f32>,
+}
+
+/// A single texture that glyphs are rasterized into as they're used, packed into rows.
+pub struct GlyphAtlas {
+    texture: Texture,
+    bind_group: BindGroup,
+    entries: MetroHashMap<(String, u32, GlyphId), Option<GlyphEntry>>,
+    cursor: Vector2<u32>,
+    row_height: u32,
+    full: bool,
+}
+
+impl GlyphAtlas {
+    pub fn new(device: &Device, layout: &BindGroupLayout, sampler: &Sampler) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("markedly glyph atlas"),
+            size: wgpu::Extent3d {
+                width: ATLAS_SIZE, height: ATLAS_SIZE, depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&Default::default());
+        let bind_group = ::create_bind_group(device, layout, &view, sampler);
+
+        GlyphAtlas {
+            texture,
+            bind_group,
+            entries: MetroHashMap::default(),
+            cursor: Vector2::new(0, 0),
+            row_height: 0,
+            full: false,
+        }
+    }
+
+    pub fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+
+    /// Starts over with an empty atlas if it ran out of space last frame. This can't be done
+    /// while a frame is being recorded, as earlier text in the frame still refers to the glyphs.
+    pub fn reset_if_full(&mut self) {
+        if self.full {
+            self.entries.clear();
+            self.cursor = Vector2::new(0, 0);
+            self.row_height = 0;
+            self.full = false;
+        }
+    }
+
+    /// Finds the glyph in the atlas, rasterizing it if it isn't in there yet. Returns None if
+    /// the glyph has nothing to draw, or if there's no space left in the atlas.
+    pub fn glyph(
+        &mut self, queue: &Queue, font_name: &String, font: &FontArc, size: u32, id: GlyphId,
+    ) -> Option<GlyphEntry> {
+        let key = (font_name.clone(), size, id);
+        if let Some(entry) = self.entries.get(&key) {
+            return *entry
+        }
+
+        let glyph = id.with_scale_and_position(PxScale::from(size as f32), point(0.0, 0.0));
+        let outlined = match font.outline_glyph(glyph) {
+            Some(outlined) => outlined,
+            None => {
+                self.entries.insert(key, None);
+                return None
+            },
+        };
+        let bounds = outlined.px_bounds();
+        let width = bounds.width().ceil() as u32;
+        let height = bounds.height().ceil() as u32;
+
+        // Find a spot for the glyph, moving to the next row if this one's full, leaving a pixel
+        // of padding so glyphs don't bleed into each other when sampled
+        if self.cursor.x + width + 1 > ATLAS_SIZE {
+            self.cursor = Vector2::new(0, self.cursor.y + self.row_height + 1);
+            self.row_height = 0;
+        }
+        if width + 1 > ATLAS_SIZE || self.cursor.y + height + 1 > ATLAS_SIZE {
+            self.full = true;
+            return None
+        }
+
+        let mut pixels = vec![0u8; (width * height * 4) as usize];
+        outlined.draw(|x, y, coverage| {
+            let index = ((y * width + x) * 4) as usize;
+            pixels[index..index+4].copy_from_slice(&[255, 255, 255, (coverage * 255.0) as u8]);
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: self.cursor.x, y: self.cursor.y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &pixels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(width * 4),
+                rows_per_image: None,
+            },
+            wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+        );
+
+        let atlas_size = ATLAS_SIZE as f32;
+        let entry = GlyphEntry {
+            offset: Vector2::new(bounds.min.x, bounds.min.y),
+            size: Vector2::new(width as f32, height as f32),
+            uv_min: Point2::new(
+                self.cursor.x as f32 / atlas_size, self.cursor.y as f32 / atlas_size,
+            ),
+            uv_max: Point2::new(
+                (self.cursor.x + width) as f32 / atlas_size,
+                (self.cursor.y + height) as f32 / atlas_size,
+            ),
+        };
+
+        self.cursor.x += width + 1;
+        self.row_height = self.row_height.max(height);
+        self.entries.insert(key, Some(entry));
+
+        Some(entry)
+    }
+}
+
+/// Lays out a single line of text, returning the glyphs with their pen positions relative to
+/// the start of the baseline, and the total size of the line.
+pub fn layout(font: &FontArc, size: u32, text: &str) -> (Vec<(GlyphId, f32)>, Vector2<f32>) {
+    let scaled = font.as_scaled(PxScale::from(size as f32));
+
+    let mut glyphs = Vec::new();
+    let mut x = 0.0;
+    let mut last = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(last) = last {
+            x += scaled.kern(last, id);
+        }
+
+        glyphs.push((id, x));
+        x += scaled.h_advance(id);
+        last = Some(id);
+    }
+
+    (glyphs, Vector2::new(x, scaled.ascent() - scaled.descent()))
+}
+
+/// Returns true if the font has a glyph for every character in the text.
+pub fn covers(font: &FontArc, text: &str) -> bool {
+    text.chars().all(|c| font.glyph_id(c).0 != 0)
+}
+
+/// Returns the distance from the top of a line to its baseline.
+pub fn ascent(font: &FontArc, size: u32) -> f32 {
+    font.as_scaled(PxScale::from(size as f32)).ascent()
+}
@@ -0,0 +1,728 @@
+extern crate ab_glyph;
+extern crate bytemuck;
+extern crate nalgebra;
+extern crate markedly;
+extern crate metrohash;
+extern crate wgpu;
+
+mod glyphs;
+
+use std::mem;
+use std::sync::mpsc;
+
+use ab_glyph::{FontArc};
+use nalgebra::{Point2, Vector2};
+use metrohash::{MetroHashMap};
+use wgpu::util::{DeviceExt};
+use wgpu::{
+    Device, Queue, Texture, TextureView, TextureFormat, BindGroup, BindGroupLayout, Sampler,
+    ShaderModule, PipelineLayout, RenderPipeline, CommandEncoder,
+};
+
+use markedly::render::{Renderer, Rect as MRect, BlendMode, PixelBuffer, RootTransform};
+use markedly::template::{Color};
+use markedly::{Error, ComponentId};
+
+use glyphs::{GlyphAtlas};
+
+/// The format used for component caches.
+const CACHE_FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+
+/// Position, texture coordinates, and color.
+const VERTEX_FLOATS: usize = 2 + 2 + 4;
+
+const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 3] = [
+    wgpu::VertexAttribute { format: wgpu::VertexFormat::Float32x2, offset: 0, shader_location: 0 },
+    wgpu::VertexAttribute { format: wgpu::VertexFormat::Float32x2, offset: 8, shader_location: 1 },
+    wgpu::VertexAttribute { format: wgpu::VertexFormat::Float32x4, offset: 16, shader_location: 2 },
+];
+
+struct CacheTexture {
+    texture: Texture,
+    view: TextureView,
+    bind_group: BindGroup,
+    size: Vector2<u32>,
+}
+
+/// A persistent resource cache for the wgpu markedly renderer, holding the component caches,
+/// pipelines, fonts, and the glyph atlas text is rendered from.
+pub struct WgpuCache {
+    data: MetroHashMap<ComponentId, CacheTexture>,
+    fonts: MetroHashMap<String, FontArc>,
+    glyphs: GlyphAtlas,
+
+    shader: ShaderModule,
+    bind_group_layout: BindGroupLayout,
+    pipeline_layout: PipelineLayout,
+    pipelines: MetroHashMap<(TextureFormat, BlendMode), RenderPipeline>,
+    sampler: Sampler,
+    white: BindGroup,
+
+    default_font: Option<String>,
+    default_text_size: u32,
+}
+
+impl WgpuCache {
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("markedly shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                label: Some("markedly bind group layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            }
+        );
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("markedly pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("markedly sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            .. Default::default()
+        });
+
+        // Solid color geometry is drawn with a single white pixel as texture, so everything can
+        // share one pipeline
+        let white_texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("markedly white"),
+                size: wgpu::Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: CACHE_FORMAT,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &[255, 255, 255, 255],
+        );
+        let white = create_bind_group(
+            device, &bind_group_layout, &white_texture.create_view(&Default::default()), &sampler,
+        );
+
+        let glyphs = GlyphAtlas::new(device, &bind_group_layout, &sampler);
+
+        WgpuCache {
+            data: MetroHashMap::default(),
+            fonts: MetroHashMap::default(),
+            glyphs,
+
+            shader,
+            bind_group_layout,
+            pipeline_layout,
+            pipelines: MetroHashMap::default(),
+            sampler,
+            white,
+
+            default_font: None,
+            default_text_size: 14,
+        }
+    }
+
+    /// Adds a TrueType or OpenType font to the cache from its data.
+    pub fn add_font<S: Into<String>>(&mut self, name: S, data: Vec<u8>) -> Result<(), Error> {
+        let name = name.into();
+
+        if self.fonts.contains_key(&name) {
+            return Err(Error::Resource {
+                resource: Some(name),
+                error: "Font already added to cache".into(),
+            })
+        }
+
+        let font = FontArc::try_from_vec(data).map_err(|e| Error::Resource {
+            resource: Some(name.clone()),
+            error: e.to_string(),
+        })?;
+
+        if self.default_font.is_none() {
+            self.default_font = Some(name.clone());
+        }
+
+        self.fonts.insert(name, font);
+
+        Ok(())
+    }
+
+    fn ensure_pipelines(&mut self, device: &Device, format: TextureFormat) {
+        for blend_mode in &[BlendMode::Alpha, BlendMode::Add, BlendMode::Multiply] {
+            self.ensure_pipeline(device, format, *blend_mode);
+        }
+    }
+
+    fn ensure_pipeline(&mut self, device: &Device, format: TextureFormat, blend_mode: BlendMode) {
+        if self.pipelines.contains_key(&(format, blend_mode)) {
+            return
+        }
+
+        let blend = match blend_mode {
+            BlendMode::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+            BlendMode::Add => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent::OVER,
+            },
+            BlendMode::Multiply => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Dst,
+                    dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent::OVER,
+            },
+        };
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("markedly pipeline"),
+            layout: Some(&self.pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &self.shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: (VERTEX_FLOATS * 4) as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &VERTEX_ATTRIBUTES,
+                }],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &self.shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(blend),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+        self.pipelines.insert((format, blend_mode), pipeline);
+    }
+}
+
+/// Where a draw operation reads its texture from.
+#[derive(Clone, Copy)]
+enum Source {
+    White,
+    Glyphs,
+    Cache(ComponentId),
+}
+
+/// Where a draw operation renders to.
+#[derive(Clone, Copy)]
+enum Target {
+    Screen,
+    Component(ComponentId),
+}
+
+/// A vertex in pixel coordinates of the texture being rendered to.
+struct Vertex {
+    position: Point2<f32>,
+    uv: Point2<f32>,
+    color: Color,
+}
+
+/// A markedly renderer for wgpu, intended to be constructed every frame on-demand.
+///
+/// Rendering operations are recorded into a command encoder, call `finish` to submit them to
+/// the queue. The target should use a non-sRGB format, as markedly colors are already in sRGB.
+pub struct WgpuRenderer<'a> {
+    device: &'a Device,
+    queue: &'a Queue,
+    cache: &'a mut WgpuCache,
+    target: &'a TextureView,
+    target_format: TextureFormat,
+    target_size: Vector2<u32>,
+    encoder: CommandEncoder,
+    clip_stack: Vec<(ComponentId, MRect)>,
+    direct: Option<ComponentId>,
+}
+
+impl<'a> WgpuRenderer<'a> {
+    pub fn new(
+        device: &'a Device, queue: &'a Queue, cache: &'a mut WgpuCache,
+        target: &'a TextureView, target_format: TextureFormat, target_size: Vector2<u32>,
+    ) -> Self {
+        cache.ensure_pipelines(device, CACHE_FORMAT);
+        cache.ensure_pipelines(device, target_format);
+        cache.glyphs.reset_if_full();
+
+        let encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("markedly encoder"),
+        });
+
+        WgpuRenderer {
+            device,
+            queue,
+            cache,
+            target,
+            target_format,
+            target_size,
+            encoder,
+            clip_stack: Vec::new(),
+            direct: None,
+        }
+    }
+
+    /// Submits the recorded rendering operations to the queue.
+    pub fn finish(self) {
+        self.queue.submit(Some(self.encoder.finish()));
+    }
+
+    fn current_clip(&self, id: ComponentId) -> Option<MRect> {
+        self.clip_stack.iter().rev()
+            .filter(|v| v.0 == id)
+            .next()
+            .map(|v| v.1)
+    }
+
+    fn component_target(&self, id: ComponentId) -> Target {
+        // In direct mode we render to the target instead
+        if self.direct == Some(id) {
+            Target::Screen
+        } else {
+            Target::Component(id)
+        }
+    }
+
+    fn draw(
+        &mut self, target: Target, clip: Option<MRect>, source: Source, vertices: &[Vertex],
+        blend_mode: BlendMode,
+    ) -> Result<(), Error> {
+        let (view, format, size) = match target {
+            Target::Screen => (self.target, self.target_format, self.target_size),
+            Target::Component(id) => {
+                let cache = self.cache.data.get(&id)
+                    .ok_or("Component cache not found for rendering")?;
+                (&cache.view, CACHE_FORMAT, cache.size)
+            },
+        };
+        let bind_group = match source {
+            Source::White => &self.cache.white,
+            Source::Glyphs => self.cache.glyphs.bind_group(),
+            Source::Cache(source_id) => &self.cache.data.get(&source_id)
+                .ok_or("Source component cache not found for rendering")?.bind_group,
+        };
+
+        // Clip using a scissor rect limited to the texture, if nothing's visible we're done
+        let mut scissor = MRect::new(
+            Point2::new(0.0, 0.0), Vector2::new(size.x as f32, size.y as f32),
+        );
+        if let Some(clip) = clip {
+            scissor = match scissor.intersection(&clip) {
+                Some(scissor) => scissor,
+                None => return Ok(()),
+            };
+        }
+        let scissor_start = Point2::new(
+            scissor.position.x.floor() as u32, scissor.position.y.floor() as u32,
+        );
+        let scissor_end = scissor.end();
+        let scissor_end = Point2::new(
+            (scissor_end.x.ceil() as u32).min(size.x), (scissor_end.y.ceil() as u32).min(size.y),
+        );
+        if scissor_end.x <= scissor_start.x || scissor_end.y <= scissor_start.y {
+            return Ok(())
+        }
+
+        // Convert the vertices from pixel coordinates to normalized device coordinates
+        let mut data: Vec<f32> = Vec::with_capacity(vertices.len() * VERTEX_FLOATS);
+        for vertex in vertices {
+            data.push(vertex.position.x / size.x as f32 * 2.0 - 1.0);
+            data.push(1.0 - vertex.position.y / size.y as f32 * 2.0);
+            data.push(vertex.uv.x);
+            data.push(vertex.uv.y);
+            data.push(vertex.color.red);
+            data.push(vertex.color.green);
+            data.push(vertex.color.blue);
+            data.push(vertex.color.alpha);
+        }
+        let buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("markedly vertices"),
+            contents: bytemuck::cast_slice(&data[..]),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let mut pass = self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("markedly pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(self.cache.pipelines.get(&(format, blend_mode)).unwrap());
+        pass.set_bind_group(0, bind_group, &[]);
+        pass.set_vertex_buffer(0, buffer.slice(..));
+        pass.set_scissor_rect(
+            scissor_start.x, scissor_start.y,
+            scissor_end.x - scissor_start.x, scissor_end.y - scissor_start.y,
+        );
+        pass.draw(0..vertices.len() as u32, 0..1);
+
+        Ok(())
+    }
+
+    fn draw_cache(
+        &mut self, target: Target, clip: Option<MRect>,
+        source_id: ComponentId, position: Point2<f32>, scale: f32, alpha: f32,
+        blend_mode: BlendMode,
+    ) -> Result<(), Error> {
+        let source_size = self.cache.data.get(&source_id)
+            .ok_or("Source component cache not found for rendering")?.size;
+        let size = Vector2::new(source_size.x as f32, source_size.y as f32) * scale;
+        let position = Point2::new(position.x.round(), position.y.round());
+        let color = Color::new(1.0, 1.0, 1.0, alpha);
+
+        let vertices = quad(
+            MRect::new(position, size),
+            Point2::new(0.0, 0.0), Point2::new(1.0, 1.0), color,
+        );
+        self.draw(target, clip, Source::Cache(source_id), &vertices, blend_mode)
+    }
+}
+
+impl<'a> Renderer for WgpuRenderer<'a> {
+    fn render_cache_to_target(
+        &mut self, id: ComponentId, transform: RootTransform,
+    ) -> Result<(), Error> {
+        let position = Point2::from_coordinates(transform.offset);
+        self.draw_cache(
+            Target::Screen, None, id, position, transform.scale, 1.0, BlendMode::Alpha,
+        )
+    }
+
+    fn create_resize_cache(
+        &mut self, id: ComponentId, size: Vector2<u32>
+    ) -> Result<bool, Error> {
+        // If we have a cached texture and it's of the right size, we only have to clear
+        if let Some(cache) = self.cache.data.get(&id) {
+            if cache.size == size {
+                return Ok(false)
+            }
+        }
+
+        // We don't have what we need so create a new texture, wgpu doesn't allow empty textures
+        let texture = create_cache_texture(self.device, size);
+        let view = texture.create_view(&Default::default());
+        let bind_group = create_bind_group(
+            self.device, &self.cache.bind_group_layout, &view, &self.cache.sampler,
+        );
+        self.cache.data.insert(id, CacheTexture {
+            texture,
+            view,
+            bind_group,
+            size,
+        });
+
+        Ok(true)
+    }
+
+    fn clear_cache(&mut self, id: ComponentId) -> Result<(), Error> {
+        let cache = self.cache.data.get(&id).ok_or("Component cache not found for clearing")?;
+        self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("markedly clear"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &cache.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        Ok(())
+    }
+
+    fn remove_cache(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.cache.data.remove(&id);
+        Ok(())
+    }
+
+    fn read_cache_pixels(&mut self, id: ComponentId) -> Result<PixelBuffer, Error> {
+        let cache = self.cache.data.get(&id).ok_or("Component cache not found for reading")?;
+        let size = Vector2::new(cache.size.x.max(1), cache.size.y.max(1));
+
+        // Rows copied out of a texture have to be aligned, so we remove the padding afterwards
+        let row_bytes = size.x * 4;
+        let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_row_bytes = (row_bytes + alignment - 1) / alignment * alignment;
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("markedly read back"),
+            size: (padded_row_bytes * size.y) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        self.encoder.copy_texture_to_buffer(
+            cache.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d { width: size.x, height: size.y, depth_or_array_layers: 1 },
+        );
+
+        // Everything recorded so far has to be executed before we can read the result back
+        let encoder = mem::replace(
+            &mut self.encoder,
+            self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("markedly encoder"),
+            }),
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.recv()
+            .map_err(|_| Error::from("Cache read back was never completed"))?
+            .map_err(|e| Error::Generic { error: Box::new(e) })?;
+
+        let mut data = Vec::with_capacity((row_bytes * size.y) as usize);
+        {
+            let mapped = slice.get_mapped_range();
+            for row in 0..size.y {
+                let start = (row * padded_row_bytes) as usize;
+                data.extend_from_slice(&mapped[start..start + row_bytes as usize]);
+            }
+        }
+        buffer.unmap();
+
+        PixelBuffer::new(size, data)
+    }
+
+    fn render_cache(
+        &mut self, id: ComponentId,
+        source_id: ComponentId, position: Point2<f32>, alpha: f32, blend_mode: BlendMode,
+    ) -> Result<(), Error> {
+        let target = self.component_target(id);
+        let clip = self.current_clip(id);
+        self.draw_cache(target, clip, source_id, position, 1.0, alpha, blend_mode)
+    }
+
+    fn begin_direct(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.direct = Some(id);
+        Ok(())
+    }
+
+    fn end_direct(&mut self, _id: ComponentId) -> Result<(), Error> {
+        self.direct = None;
+        Ok(())
+    }
+
+    fn push_clip(&mut self, id: ComponentId, rect: MRect) -> Result<(), Error> {
+        // Nested clips can never show more than the clip they're nested in
+        let rect = if let Some(current) = self.current_clip(id) {
+            current.intersection(&rect)
+                .unwrap_or(MRect::new(rect.position, Vector2::new(0.0, 0.0)))
+        } else {
+            rect
+        };
+
+        self.clip_stack.push((id, rect));
+        Ok(())
+    }
+
+    fn pop_clip(&mut self, id: ComponentId) -> Result<(), Error> {
+        match self.clip_stack.pop() {
+            Some((clip_id, _)) if clip_id == id => Ok(()),
+            _ => Err("Clip popped without a matching push for the component".into()),
+        }
+    }
+
+    fn text(
+        &mut self, id: ComponentId,
+        text: &String, text_font: Option<&String>, text_size: Option<i32>,
+        position: Point2<f32>, size: Vector2<f32>, color: Color,
+    ) -> Result<(), Error> {
+        let target = self.component_target(id);
+        let clip = self.current_clip(id);
+
+        let vertices = {
+            let (font_name, font) = find_font(self.cache, text_font)?;
+            let text_size = text_size.map(|v| v as u32).unwrap_or(self.cache.default_text_size);
+            let (layout, text_area) = glyphs::layout(&font, text_size, text);
+
+            // Center the line in the area, the glyph positions are relative to the baseline
+            let origin = Point2::new(
+                (position.x + (size.x - text_area.x) * 0.5).round(),
+                (position.y + (size.y - text_area.y) * 0.5 + glyphs::ascent(&font, text_size))
+                    .round(),
+            );
+
+            let mut vertices = Vec::new();
+            for (glyph_id, x) in layout {
+                let entry = match self.cache.glyphs.glyph(
+                    self.queue, &font_name, &font, text_size, glyph_id,
+                ) {
+                    Some(entry) => entry,
+                    None => continue,
+                };
+
+                let glyph_position = Point2::new(origin.x + x, origin.y) + entry.offset;
+                vertices.extend(quad(
+                    MRect::new(Point2::new(glyph_position.x.round(), glyph_position.y), entry.size),
+                    entry.uv_min, entry.uv_max, color,
+                ));
+            }
+            vertices
+        };
+
+        if vertices.is_empty() {
+            return Ok(())
+        }
+
+        self.draw(target, clip, Source::Glyphs, &vertices, BlendMode::Alpha)
+    }
+
+    fn measure_text(
+        &mut self, text: &String, text_font: Option<&String>, text_size: Option<i32>,
+    ) -> Result<Vector2<f32>, Error> {
+        let (_, font) = find_font(self.cache, text_font)?;
+        let text_size = text_size.map(|v| v as u32).unwrap_or(self.cache.default_text_size);
+        Ok(glyphs::layout(&font, text_size, text).1)
+    }
+
+    fn has_glyphs(
+        &mut self, text: &String, text_font: Option<&String>,
+    ) -> Result<bool, Error> {
+        let (_, font) = find_font(self.cache, text_font)?;
+        Ok(glyphs::covers(&font, text))
+    }
+
+    fn vertices(
+        &mut self, id: ComponentId,
+        vertices: &[Point2<f32>], indices: &[u16], color: Color,
+    ) -> Result<(), Error> {
+        let target = self.component_target(id);
+        let clip = self.current_clip(id);
+
+        // Convert the vertices+indices to triangles
+        let flattened_vertices: Vec<_> = indices.iter()
+            .map(|index| Vertex {
+                position: vertices[*index as usize],
+                uv: Point2::new(0.0, 0.0),
+                color,
+            })
+            .collect();
+
+        self.draw(target, clip, Source::White, &flattened_vertices, BlendMode::Alpha)
+    }
+}
+
+/// Finds the font in the cache, using the default if not specified.
+fn find_font(
+    cache: &WgpuCache, text_font: Option<&String>,
+) -> Result<(String, FontArc), Error> {
+    // Try to find the font, use the default, or error if we can't find it
+    let requested_font_name = text_font.or(cache.default_font.as_ref())
+        .ok_or(Error::Resource {
+            resource: None,
+            error: "Could not fall back to default font, no fonts are loaded".into()
+        })?;
+    let font = cache.fonts.get(requested_font_name)
+        .ok_or_else(|| Error::Resource {
+            resource: Some(requested_font_name.clone()),
+            error: "Font is not in cache".into()
+        })?;
+
+    Ok((requested_font_name.clone(), font.clone()))
+}
+
+fn quad(area: MRect, uv_min: Point2<f32>, uv_max: Point2<f32>, color: Color) -> Vec<Vertex> {
+    let end = area.end();
+    let corners = [
+        (area.position, uv_min),
+        (Point2::new(end.x, area.position.y), Point2::new(uv_max.x, uv_min.y)),
+        (end, uv_max),
+        (Point2::new(area.position.x, end.y), Point2::new(uv_min.x, uv_max.y)),
+    ];
+
+    [0, 1, 2, 0, 2, 3].iter()
+        .map(|i| Vertex { position: corners[*i].0, uv: corners[*i].1, color })
+        .collect()
+}
+
+fn create_cache_texture(device: &Device, size: Vector2<u32>) -> Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("markedly cache"),
+        size: wgpu::Extent3d {
+            width: size.x.max(1), height: size.y.max(1), depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: CACHE_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING |
+            wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
+fn create_bind_group(
+    device: &Device, layout: &BindGroupLayout, view: &TextureView, sampler: &Sampler,
+) -> BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("markedly bind group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    })
+}
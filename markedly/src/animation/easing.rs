@@ -0,0 +1,272 @@
+//! Easing functions, mapping linear progress from 0.0 to 1.0 to eased progress. These can be
+//! used by component classes directly so their motion is consistent with animated attributes.
+
+use std::f32::consts::{PI};
+
+use scripting::{ScriptRuntime};
+use template::{TemplateValue};
+use {Error};
+
+/// How an animation progresses over time, changing its speed at the start and end.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    /// Progresses at a constant speed.
+    Linear,
+    /// Starts slow and speeds up, the same as `CubicIn`.
+    EaseIn,
+    /// Starts fast and slows down, the same as `CubicOut`.
+    EaseOut,
+    /// Starts slow, speeds up, and slows down again, the same as `CubicInOut`.
+    EaseInOut,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    /// Winds up with a growing wobble before moving to the end.
+    ElasticIn,
+    /// Overshoots the end and wobbles around it before settling.
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    /// Bounces against the end like a dropped ball.
+    BounceOut,
+    BounceInOut,
+    /// A cubic bezier curve from (0, 0) to (1, 1) with two control points, the same as in CSS.
+    CubicBezier(f32, f32, f32, f32),
+}
+
+impl Easing {
+    /// Loads an easing from either its name, such as `"ease-out"` or `"bounce-out"`, or a tuple
+    /// of `"cubic-bezier"` followed by the four control point coordinates.
+    pub fn from_value(value: &TemplateValue, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        if let TemplateValue::Tuple(ref vec) = *value {
+            return Self::from_bezier_value(vec, runtime)
+        }
+
+        match value.as_string(runtime)?.as_str() {
+            "linear" => Ok(Easing::Linear),
+            "ease-in" => Ok(Easing::EaseIn),
+            "ease-out" => Ok(Easing::EaseOut),
+            "ease-in-out" => Ok(Easing::EaseInOut),
+            "quad-in" => Ok(Easing::QuadIn),
+            "quad-out" => Ok(Easing::QuadOut),
+            "quad-in-out" => Ok(Easing::QuadInOut),
+            "cubic-in" => Ok(Easing::CubicIn),
+            "cubic-out" => Ok(Easing::CubicOut),
+            "cubic-in-out" => Ok(Easing::CubicInOut),
+            "elastic-in" => Ok(Easing::ElasticIn),
+            "elastic-out" => Ok(Easing::ElasticOut),
+            "elastic-in-out" => Ok(Easing::ElasticInOut),
+            "bounce-in" => Ok(Easing::BounceIn),
+            "bounce-out" => Ok(Easing::BounceOut),
+            "bounce-in-out" => Ok(Easing::BounceInOut),
+            name => Err(format!("Unknown easing \"{}\"", name).into()),
+        }
+    }
+
+    fn from_bezier_value(vec: &[TemplateValue], runtime: &ScriptRuntime) -> Result<Self, Error> {
+        if vec.len() != 5 {
+            return Err("Tuple is incorrect size".into())
+        }
+        if vec[0].as_string(runtime)? != "cubic-bezier" {
+            return Err("Value 1: Only \"cubic-bezier\" easings can be given as a tuple".into())
+        }
+
+        let mut points = [0.0; 4];
+        for i in 0..4 {
+            points[i] = vec[i + 1].as_float(runtime)
+                .map_err(|e| Error::new_value(&format!("Value {}", i + 2), e))?;
+        }
+
+        if points[0] < 0.0 || points[0] > 1.0 || points[2] < 0.0 || points[2] > 1.0 {
+            return Err("The x coordinates of the control points must be from 0.0 to 1.0".into())
+        }
+
+        Ok(Easing::CubicBezier(points[0], points[1], points[2], points[3]))
+    }
+
+    /// Maps linear progress from 0.0 to 1.0 to eased progress. Some easings, such as elastic,
+    /// go outside of 0.0 to 1.0 in between.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.max(0.0).min(1.0);
+        match *self {
+            Easing::Linear => linear(t),
+            Easing::QuadIn => quad_in(t),
+            Easing::QuadOut => quad_out(t),
+            Easing::QuadInOut => quad_in_out(t),
+            Easing::EaseIn | Easing::CubicIn => cubic_in(t),
+            Easing::EaseOut | Easing::CubicOut => cubic_out(t),
+            Easing::EaseInOut | Easing::CubicInOut => cubic_in_out(t),
+            Easing::ElasticIn => elastic_in(t),
+            Easing::ElasticOut => elastic_out(t),
+            Easing::ElasticInOut => elastic_in_out(t),
+            Easing::BounceIn => bounce_in(t),
+            Easing::BounceOut => bounce_out(t),
+            Easing::BounceInOut => bounce_in_out(t),
+            Easing::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t),
+        }
+    }
+}
+
+pub fn linear(t: f32) -> f32 {
+    t
+}
+
+pub fn quad_in(t: f32) -> f32 {
+    t * t
+}
+
+pub fn quad_out(t: f32) -> f32 {
+    1.0 - (1.0 - t) * (1.0 - t)
+}
+
+pub fn quad_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        2.0 * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(2) * 0.5
+    }
+}
+
+pub fn cubic_in(t: f32) -> f32 {
+    t * t * t
+}
+
+pub fn cubic_out(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+pub fn cubic_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) * 0.5
+    }
+}
+
+pub fn elastic_in(t: f32) -> f32 {
+    if t <= 0.0 || t >= 1.0 {
+        return t
+    }
+
+    -(2.0f32).powf(10.0 * t - 10.0) * ((t * 10.0 - 10.75) * (2.0 * PI / 3.0)).sin()
+}
+
+pub fn elastic_out(t: f32) -> f32 {
+    if t <= 0.0 || t >= 1.0 {
+        return t
+    }
+
+    (2.0f32).powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+}
+
+pub fn elastic_in_out(t: f32) -> f32 {
+    if t <= 0.0 || t >= 1.0 {
+        return t
+    }
+
+    let wave = ((20.0 * t - 11.125) * (2.0 * PI / 4.5)).sin();
+    if t < 0.5 {
+        -(2.0f32).powf(20.0 * t - 10.0) * wave * 0.5
+    } else {
+        (2.0f32).powf(-20.0 * t + 10.0) * wave * 0.5 + 1.0
+    }
+}
+
+pub fn bounce_in(t: f32) -> f32 {
+    1.0 - bounce_out(1.0 - t)
+}
+
+pub fn bounce_out(t: f32) -> f32 {
+    let n = 7.5625;
+    let d = 2.75;
+
+    if t < 1.0 / d {
+        n * t * t
+    } else if t < 2.0 / d {
+        let t = t - 1.5 / d;
+        n * t * t + 0.75
+    } else if t < 2.5 / d {
+        let t = t - 2.25 / d;
+        n * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / d;
+        n * t * t + 0.984375
+    }
+}
+
+pub fn bounce_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        (1.0 - bounce_out(1.0 - 2.0 * t)) * 0.5
+    } else {
+        (1.0 + bounce_out(2.0 * t - 1.0)) * 0.5
+    }
+}
+
+/// Evaluates a cubic bezier curve from (0, 0) to (1, 1) with control points (x1, y1) and
+/// (x2, y2), finding the y coordinate at x coordinate `t`.
+pub fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, t: f32) -> f32 {
+    let bezier = |a: f32, b: f32, s: f32| {
+        3.0 * a * s * (1.0 - s) * (1.0 - s) + 3.0 * b * s * s * (1.0 - s) + s * s * s
+    };
+    let bezier_slope = |a: f32, b: f32, s: f32| {
+        3.0 * a * (1.0 - s) * (1.0 - s) + 6.0 * (b - a) * s * (1.0 - s) + 3.0 * (1.0 - b) * s * s
+    };
+
+    // Find the curve position at the x coordinate using Newton's method, falling back to
+    // bisection where the slope is too flat for it to converge
+    let mut s = t;
+    for _ in 0..8 {
+        let error = bezier(x1, x2, s) - t;
+        if error.abs() < 1e-5 {
+            return bezier(y1, y2, s)
+        }
+
+        let slope = bezier_slope(x1, x2, s);
+        if slope.abs() < 1e-6 {
+            break
+        }
+        s -= error / slope;
+    }
+
+    let (mut low, mut high) = (0.0, 1.0);
+    s = t;
+    for _ in 0..32 {
+        let x = bezier(x1, x2, s);
+        if (x - t).abs() < 1e-5 {
+            break
+        }
+
+        if x < t { low = s; } else { high = s; }
+        s = (low + high) * 0.5;
+    }
+
+    bezier(y1, y2, s)
+}
+
+#[cfg(test)]
+mod test {
+    use animation::easing::{self, Easing};
+
+    #[test]
+    fn it_starts_and_ends_at_the_edges() {
+        let easings = [
+            Easing::Linear, Easing::QuadInOut, Easing::CubicInOut, Easing::ElasticOut,
+            Easing::BounceOut, Easing::BounceInOut, Easing::CubicBezier(0.25, 0.1, 0.25, 1.0),
+        ];
+        for easing in &easings {
+            assert!(easing.apply(0.0).abs() < 0.001, "{:?} doesn't start at 0", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 0.001, "{:?} doesn't end at 1", easing);
+        }
+    }
+
+    #[test]
+    fn it_matches_linear_with_linear_bezier() {
+        for i in 0..11 {
+            let t = i as f32 / 10.0;
+            assert!((easing::cubic_bezier(0.0, 0.0, 1.0, 1.0, t) - t).abs() < 0.001);
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use animation::{Easing, interpolate};
+use scripting::{ScriptRuntime};
+use template::{AnimationTemplate, Attributes, TemplateValue};
+use {Error, Symbol};
+
+/// A keyframe animation played on a component, set with the `animation` attribute, such as
+/// `("pulse", 1.0, "loop", "ease-in-out")`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    /// The name of the animation in the style.
+    pub name: String,
+    /// The duration of a single play-through in seconds.
+    pub duration: f32,
+    pub repeat: AnimationRepeat,
+    /// The easing applied to every play-through.
+    pub easing: Easing,
+}
+
+impl Animation {
+    pub fn from_value(value: &TemplateValue, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        let vec = value.as_vec()?;
+
+        if vec.len() < 2 || vec.len() > 4 {
+            return Err("Tuple is incorrect size".into())
+        }
+
+        let name = vec[0].as_string(runtime)
+            .map_err(|e| Error::new_value("Value 1", e))?;
+        let duration = vec[1].as_float(runtime)
+            .map_err(|e| Error::new_value("Value 2", e))?;
+        let repeat = if vec.len() >= 3 {
+            AnimationRepeat::from_value(&vec[2], runtime)
+                .map_err(|e| Error::new_value("Value 3", e))?
+        } else {
+            AnimationRepeat::Once
+        };
+        let easing = if vec.len() == 4 {
+            Easing::from_value(&vec[3], runtime)
+                .map_err(|e| Error::new_value("Value 4", e))?
+        } else {
+            Easing::Linear
+        };
+
+        if duration <= 0.0 {
+            return Err("Value 2: Duration must be positive".into())
+        }
+
+        Ok(Animation {
+            name,
+            duration,
+            repeat,
+            easing,
+        })
+    }
+}
+
+/// How an animation repeats after reaching its end.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnimationRepeat {
+    /// Plays once, keeping the attributes of the last keyframe after it's done.
+    Once,
+    /// Starts over from the beginning.
+    Loop,
+    /// Plays backwards to the beginning, then forwards again.
+    Alternate,
+}
+
+impl AnimationRepeat {
+    pub fn from_value(value: &TemplateValue, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        match value.as_string(runtime)?.as_str() {
+            "once" => Ok(AnimationRepeat::Once),
+            "loop" => Ok(AnimationRepeat::Loop),
+            "alternate" => Ok(AnimationRepeat::Alternate),
+            _ => Err("Value must be either \"once\", \"loop\", or \"alternate\"".into())
+        }
+    }
+}
+
+/// An animation being played on a component.
+pub(crate) struct AnimationState {
+    animation: Animation,
+    /// The values of every animated attribute at their keyframe positions, sorted by position.
+    tracks: Vec<(Symbol, Vec<(f32, TemplateValue)>)>,
+    elapsed: f32,
+}
+
+impl AnimationState {
+    /// Starts an animation, evaluating the values of all keyframes.
+    pub fn new(
+        animation: Animation, template: &AnimationTemplate, runtime: &ScriptRuntime,
+    ) -> Result<Self, Error> {
+        let mut tracks: Vec<(Symbol, Vec<(f32, TemplateValue)>)> = Vec::new();
+
+        for keyframe in &template.keyframes {
+            for attribute in &keyframe.attributes {
+                if !attribute.check_conditional(runtime)? {
+                    continue
+                }
+
+                let value = match attribute.value {
+                    TemplateValue::ScriptValue(ref script) =>
+                        runtime.eval_template_value(script)?,
+                    ref value => value.clone(),
+                };
+
+                if let Some(index) = tracks.iter().position(|t| t.0 == attribute.key) {
+                    tracks[index].1.push((keyframe.position, value));
+                } else {
+                    tracks.push((attribute.key.clone(), vec![(keyframe.position, value)]));
+                }
+            }
+        }
+
+        for track in &mut tracks {
+            track.1.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        }
+
+        Ok(AnimationState {
+            animation,
+            tracks,
+            elapsed: 0.0,
+        })
+    }
+
+    pub fn animation(&self) -> &Animation {
+        &self.animation
+    }
+
+    /// Returns true if the animation still needs to be progressed every frame.
+    pub fn is_playing(&self) -> bool {
+        self.animation.repeat != AnimationRepeat::Once || self.elapsed < self.animation.duration
+    }
+
+    pub fn advance(&mut self, delta: f32) {
+        self.elapsed += delta;
+
+        if self.animation.repeat == AnimationRepeat::Once {
+            self.elapsed = self.elapsed.min(self.animation.duration);
+        }
+    }
+
+    /// Sets the current values of all animated attributes in the attributes.
+    pub fn apply(&self, attributes: &mut Attributes) {
+        let progress = self.progress();
+
+        for &(ref key, ref keyframes) in &self.tracks {
+            attributes.set(key.clone(), value_at(keyframes, progress));
+        }
+    }
+
+    fn progress(&self) -> f32 {
+        let t = self.elapsed / self.animation.duration;
+        let t = match self.animation.repeat {
+            AnimationRepeat::Once => t.min(1.0),
+            AnimationRepeat::Loop => t.fract(),
+            AnimationRepeat::Alternate => {
+                let t = t % 2.0;
+                if t > 1.0 { 2.0 - t } else { t }
+            },
+        };
+
+        self.animation.easing.apply(t)
+    }
+}
+
+fn value_at(keyframes: &[(f32, TemplateValue)], progress: f32) -> TemplateValue {
+    // Before the first and after the last keyframe, the value stays the same
+    let first = &keyframes[0];
+    if progress <= first.0 {
+        return first.1.clone()
+    }
+
+    for window in keyframes.windows(2) {
+        let (start, end) = (&window[0], &window[1]);
+        if progress <= end.0 {
+            let length = end.0 - start.0;
+            let t = if length > 0.0 { (progress - start.0) / length } else { 1.0 };
+            return interpolate(&start.1, &end.1, t)
+        }
+    }
+
+    keyframes[keyframes.len() - 1].1.clone()
+}
+
+#[cfg(test)]
+mod test {
+    use {Ui, Context};
+
+    #[test]
+    fn it_plays_keyframe_animations() {
+        let context = Context::for_test();
+        let (mut ui, tree) = Ui::for_test(
+            "container { animation: (\"fade\", 2.0), on-animation-end: \"faded\" }\n",
+            "@animation fade { 0%: { opacity: 1.0 }, 100%: { opacity: 0.0 } }\n",
+            &context,
+        );
+        assert_eq!(ui.get(tree.root()).unwrap().attributes().opacity, 1.0);
+
+        ui.update(1.0, &context).unwrap();
+        assert_eq!(ui.get(tree.root()).unwrap().attributes().opacity, 0.5);
+        assert_eq!(tree.event_sink().next(), None);
+
+        // Animations played once keep their last keyframe's attributes
+        ui.update(5.0, &context).unwrap();
+        assert_eq!(ui.get(tree.root()).unwrap().attributes().opacity, 0.0);
+        assert_eq!(tree.event_sink().next(), Some("faded".into()));
+
+        ui.update(1.0, &context).unwrap();
+        assert_eq!(tree.event_sink().next(), None);
+    }
+}
@@ -0,0 +1,12 @@
+//! Animating attributes and other values over time.
+
+pub mod easing;
+mod keyframes;
+mod transition;
+
+pub use self::easing::{Easing};
+pub use self::keyframes::{Animation, AnimationRepeat};
+pub use self::transition::{Transition, interpolate};
+
+pub(crate) use self::keyframes::{AnimationState};
+pub(crate) use self::transition::{AttributeTransitions};
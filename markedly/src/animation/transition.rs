@@ -0,0 +1,217 @@
+use std::collections::{HashMap};
+
+use animation::{Easing};
+use scripting::{ScriptRuntime};
+use template::{Attributes, TemplateValue};
+use {Error};
+
+/// Smoothly changes an attribute over time when its resolved value changes, rather than
+/// snapping to the new value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    pub attribute: String,
+    /// The duration in seconds.
+    pub duration: f32,
+    pub easing: Easing,
+}
+
+impl Transition {
+    /// Loads transitions from either a single tuple, such as `("color", 0.2, "ease-out")`, or a
+    /// tuple of those tuples. The easing is optional, and defaults to linear.
+    pub fn from_value(
+        value: &TemplateValue, runtime: &ScriptRuntime
+    ) -> Result<Vec<Self>, Error> {
+        let vec = value.as_vec()?;
+
+        if let Some(&TemplateValue::Tuple(_)) = vec.first() {
+            let mut transitions = Vec::new();
+            for (i, value) in vec.iter().enumerate() {
+                transitions.push(
+                    Self::from_value_individual(value, runtime)
+                        .map_err(|e| Error::new_value(&format!("Value {}", i + 1), e))?
+                );
+            }
+            Ok(transitions)
+        } else {
+            Ok(vec![Self::from_value_individual(value, runtime)?])
+        }
+    }
+
+    fn from_value_individual(
+        value: &TemplateValue, runtime: &ScriptRuntime
+    ) -> Result<Self, Error> {
+        let vec = value.as_vec()?;
+
+        if vec.len() != 2 && vec.len() != 3 {
+            return Err("Tuple is incorrect size".into())
+        }
+
+        let attribute = vec[0].as_string(runtime)
+            .map_err(|e| Error::new_value("Value 1", e))?;
+        let duration = vec[1].as_float(runtime)
+            .map_err(|e| Error::new_value("Value 2", e))?;
+        let easing = if vec.len() == 3 {
+            Easing::from_value(&vec[2], runtime)
+                .map_err(|e| Error::new_value("Value 3", e))?
+        } else {
+            Easing::Linear
+        };
+
+        if duration < 0.0 {
+            return Err("Value 2: Duration can not be negative".into())
+        }
+
+        Ok(Transition {
+            attribute,
+            duration,
+            easing,
+        })
+    }
+}
+
+/// Interpolates between two values, `t` being the progress from 0.0 to 1.0. Numbers, and tuples
+/// of numbers such as colors and coordinates, are interpolated, any other values switch over
+/// at the end.
+pub fn interpolate(from: &TemplateValue, to: &TemplateValue, t: f32) -> TemplateValue {
+    let lerp = |a: f32, b: f32| a + (b - a) * t;
+
+    match (from, to) {
+        (&TemplateValue::Integer(a), &TemplateValue::Integer(b)) =>
+            TemplateValue::Integer(lerp(a as f32, b as f32).round() as i32),
+        (&TemplateValue::Percentage(a), &TemplateValue::Percentage(b)) =>
+            TemplateValue::Percentage(lerp(a as f32, b as f32).round() as i32),
+        (&TemplateValue::Float(a), &TemplateValue::Float(b)) =>
+            TemplateValue::Float(lerp(a, b)),
+        (&TemplateValue::Integer(a), &TemplateValue::Float(b)) =>
+            TemplateValue::Float(lerp(a as f32, b)),
+        (&TemplateValue::Float(a), &TemplateValue::Integer(b)) =>
+            TemplateValue::Float(lerp(a, b as f32)),
+        (&TemplateValue::Tuple(ref a), &TemplateValue::Tuple(ref b)) if a.len() == b.len() =>
+            TemplateValue::Tuple(
+                a.iter().zip(b.iter()).map(|(a, b)| interpolate(a, b, t)).collect()
+            ),
+        _ => if t < 1.0 { from.clone() } else { to.clone() },
+    }
+}
+
+/// A transition of an attribute in progress.
+#[derive(Debug, Clone)]
+pub(crate) struct TransitionState {
+    from: TemplateValue,
+    to: TemplateValue,
+    elapsed: f32,
+    duration: f32,
+    easing: Easing,
+}
+
+impl TransitionState {
+    pub fn new(from: TemplateValue, to: TemplateValue, transition: &Transition) -> Self {
+        TransitionState {
+            from,
+            to,
+            elapsed: 0.0,
+            duration: transition.duration,
+            easing: transition.easing,
+        }
+    }
+
+    pub fn advance(&mut self, delta: f32) {
+        self.elapsed += delta;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Gets the current value of the attribute.
+    pub fn value(&self) -> TemplateValue {
+        let t = if self.duration > 0.0 { self.elapsed / self.duration } else { 1.0 };
+        interpolate(&self.from, &self.to, self.easing.apply(t))
+    }
+}
+
+/// Keeps track of the transitions of a component's attributes.
+pub(crate) struct AttributeTransitions {
+    transitions: Vec<Transition>,
+    /// The last resolved values of transitioned attributes, with scripts evaluated.
+    targets: HashMap<String, TemplateValue>,
+    active: HashMap<String, TransitionState>,
+}
+
+impl AttributeTransitions {
+    pub fn new() -> Self {
+        AttributeTransitions {
+            transitions: Vec::new(),
+            targets: HashMap::new(),
+            active: HashMap::new(),
+        }
+    }
+
+    /// Starts transitions for attributes that changed since the last resolve. Values of
+    /// transitioned attributes are evaluated and stored back into the attributes, so scripts
+    /// aren't evaluated again while transitioning.
+    pub fn update(
+        &mut self, attributes: &mut Attributes, runtime: &ScriptRuntime,
+    ) -> Result<(), Error> {
+        self.transitions = attributes.attribute(
+            "transition", |v| Transition::from_value(v, runtime), Vec::new(),
+        )?;
+
+        for transition in &self.transitions {
+            let key = &transition.attribute;
+            let target = match attributes.get(key) {
+                Some(&TemplateValue::ScriptValue(ref script)) =>
+                    Some(runtime.eval_template_value(script)?),
+                Some(value) => Some(value.clone()),
+                None => None,
+            };
+
+            if let Some(target) = target {
+                if let Some(previous) = self.targets.get(key) {
+                    if *previous != target {
+                        // Start from wherever the attribute currently is, which may be halfway
+                        // through another transition
+                        let from = self.active.get(key)
+                            .map(|state| state.value())
+                            .unwrap_or_else(|| previous.clone());
+                        self.active.insert(
+                            key.clone(), TransitionState::new(from, target.clone(), transition),
+                        );
+                    }
+                }
+
+                attributes.set(key.clone(), target.clone());
+                self.targets.insert(key.clone(), target);
+            } else {
+                self.targets.remove(key);
+                self.active.remove(key);
+            }
+        }
+
+        // Attributes that aren't transitioned anymore should stop immediately
+        let transitions = &self.transitions;
+        self.targets.retain(|key, _| transitions.iter().any(|t| t.attribute == *key));
+        self.active.retain(|key, _| transitions.iter().any(|t| t.attribute == *key));
+
+        Ok(())
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.active.is_empty()
+    }
+
+    /// Progresses all active transitions, removing the ones that finished.
+    pub fn advance(&mut self, delta: f32) {
+        for state in self.active.values_mut() {
+            state.advance(delta);
+        }
+        self.active.retain(|_, state| !state.is_finished());
+    }
+
+    /// Sets the current values of all active transitions in the attributes.
+    pub fn apply(&self, attributes: &mut Attributes) {
+        for (key, state) in &self.active {
+            attributes.set(key.clone(), state.value());
+        }
+    }
+}
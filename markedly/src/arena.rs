@@ -0,0 +1,164 @@
+//! Storage for the components of a UI, indexed directly by their IDs.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use {Component};
+
+/// Counts the UIs created so far, to give every UI's components IDs of their own.
+static NEXT_UI: AtomicUsize = AtomicUsize::new(0);
+
+/// An ID pointing to a component in a UI.
+///
+/// The storage of removed components is reused, but their IDs are not. An ID of a removed
+/// component will never point to a component created after it, storage that has been reused so
+/// often its IDs would start repeating is retired instead.
+///
+/// IDs are unique across all UIs, so a renderer backend's cache keyed by them can be shared by
+/// several UIs, such as a HUD and in-world screens, without their components colliding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ComponentId {
+    ui: u32,
+    index: u32,
+    generation: u32,
+}
+
+impl ComponentId {
+    /// An ID that doesn't point to any component, for before the real ID is known.
+    pub(crate) fn placeholder() -> Self {
+        ComponentId { ui: u32::max_value(), index: u32::max_value(), generation: 0 }
+    }
+}
+
+struct Slot {
+    /// Increased every time the component in this slot is removed, so old IDs stop matching.
+    generation: u32,
+    component: Option<Component>,
+}
+
+/// A slab of components. Looking up a component is a direct index rather than a hash lookup, and
+/// components loaded together are stored next to each other in tree order.
+pub(crate) struct Components {
+    /// Which UI these components are in, part of every ID given out.
+    ui: u32,
+    slots: Vec<Slot>,
+    /// Indices of slots that have been emptied and can be reused.
+    free: Vec<u32>,
+}
+
+impl Components {
+    pub fn new() -> Self {
+        Components {
+            ui: NEXT_UI.fetch_add(1, Ordering::Relaxed) as u32,
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    /// Reserves an ID for a component that will be inserted later, so the ID can be known before
+    /// the component has been fully loaded.
+    pub fn reserve(&mut self) -> ComponentId {
+        if let Some(index) = self.free.pop() {
+            let generation = self.slots[index as usize].generation;
+            return ComponentId { ui: self.ui, index, generation }
+        }
+
+        self.slots.push(Slot { generation: 0, component: None });
+        ComponentId { ui: self.ui, index: self.slots.len() as u32 - 1, generation: 0 }
+    }
+
+    /// Inserts a component at an ID previously given out by `reserve`.
+    pub fn insert(&mut self, id: ComponentId, component: Component) {
+        assert_eq!(self.ui, id.ui, "Component ID is from another UI");
+        let slot = &mut self.slots[id.index as usize];
+        assert_eq!(slot.generation, id.generation, "Component ID is no longer reserved");
+        slot.component = Some(component);
+    }
+
+    /// Gives back an ID from `reserve` that no component was inserted at, such as when loading
+    /// the component failed, so its slot can be reused.
+    pub fn release(&mut self, id: ComponentId) {
+        assert_eq!(self.ui, id.ui, "Component ID is from another UI");
+        {
+            let slot = &self.slots[id.index as usize];
+            assert_eq!(slot.generation, id.generation, "Component ID is no longer reserved");
+            assert!(slot.component.is_none(), "Component ID has a component inserted");
+        }
+        self.free.push(id.index);
+    }
+
+    /// Removes a component, freeing its slot for reuse.
+    pub fn remove(&mut self, id: ComponentId) -> Option<Component> {
+        let (component, reusable) = {
+            let slot = match self.slot_mut(id) {
+                Some(slot) => slot,
+                None => return None,
+            };
+
+            let component = slot.component.take();
+
+            // A slot that ran out of generations is never used again, rather than wrapping
+            // around and handing out IDs that were used before
+            let reusable = component.is_some() && slot.generation < u32::max_value();
+            if reusable {
+                slot.generation += 1;
+            }
+            (component, reusable)
+        };
+
+        if reusable {
+            self.free.push(id.index);
+        }
+        component
+    }
+
+    pub fn contains(&self, id: ComponentId) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn get(&self, id: ComponentId) -> Option<&Component> {
+        if id.ui != self.ui {
+            return None
+        }
+
+        match self.slots.get(id.index as usize) {
+            Some(slot) if slot.generation == id.generation => slot.component.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, id: ComponentId) -> Option<&mut Component> {
+        self.slot_mut(id).and_then(|slot| slot.component.as_mut())
+    }
+
+    /// Iterates over all components, in the order they are stored.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item=(ComponentId, &'a Component)> + 'a {
+        let ui = self.ui;
+        self.slots.iter().enumerate().filter_map(move |(index, slot)| {
+            let id = ComponentId { ui, index: index as u32, generation: slot.generation };
+            slot.component.as_ref().map(|component| (id, component))
+        })
+    }
+
+    /// Iterates over all components as mutable, in the order they are stored.
+    pub fn iter_mut<'a>(
+        &'a mut self
+    ) -> impl Iterator<Item=(ComponentId, &'a mut Component)> + 'a {
+        let ui = self.ui;
+        self.slots.iter_mut().enumerate().filter_map(move |(index, slot)| {
+            let id = ComponentId { ui, index: index as u32, generation: slot.generation };
+            slot.component.as_mut().map(|component| (id, component))
+        })
+    }
+
+    /// Gets the slot an ID points to, if it's still the slot's current ID.
+    fn slot_mut(&mut self, id: ComponentId) -> Option<&mut Slot> {
+        if id.ui != self.ui {
+            return None
+        }
+
+        match self.slots.get_mut(id.index as usize) {
+            Some(slot) if slot.generation == id.generation => Some(slot),
+            _ => None,
+        }
+    }
+}
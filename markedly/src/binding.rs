@@ -0,0 +1,164 @@
+use scripting::{ScriptTable, ScriptValue};
+use template::{Template};
+use {ComponentId, Context, Error, Tree, Ui};
+
+/// Keeps the children of a component in sync with a list of items in a model, inserting a tree
+/// from a template for every item.
+///
+/// Items are matched up with the trees inserted for them by a key field, so updating the
+/// binding only inserts trees for new items, removes the trees of items that are gone, moves
+/// trees of items that were reordered, and updates the models of items that changed. Trees of
+/// unchanged items are left alone, keeping their runtime state such as hover and focus.
+pub struct ListBinding {
+    parent: ComponentId,
+    key: String,
+    items: Vec<BoundItem>,
+}
+
+struct BoundItem {
+    key: ScriptValue,
+    model: ScriptTable,
+    tree: Tree,
+}
+
+impl ListBinding {
+    /// Creates a new binding that inserts trees into a parent component, matching items by the
+    /// value of their field with the given key, such as "id".
+    pub fn new(parent: ComponentId, key: &str) -> Self {
+        ListBinding {
+            parent,
+            key: key.into(),
+            items: Vec::new(),
+        }
+    }
+
+    /// Gets the parent component trees are inserted into.
+    pub fn parent(&self) -> ComponentId {
+        self.parent
+    }
+
+    /// Gets the amount of items currently bound.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Gets the tree inserted for the item at an index, such as to read its events.
+    pub fn tree(&self, index: usize) -> Option<&Tree> {
+        self.items.get(index).map(|item| &item.tree)
+    }
+
+    /// Gets the tree inserted for the item with the given key.
+    pub fn tree_for(&self, key: &ScriptValue) -> Option<&Tree> {
+        self.items.iter().find(|item| item.key == *key).map(|item| &item.tree)
+    }
+
+    /// Updates the children of the parent to match a list of items. The items are placed
+    /// together, starting where the first item was placed before, or after any other children
+    /// of the parent if nothing was bound yet.
+    pub fn update(
+        &mut self, ui: &mut Ui, template: &Template, items: &[ScriptTable], context: &Context,
+    ) -> Result<(), Error> {
+        let mut keys = Vec::new();
+        for item in items {
+            let key = item.get(&self.key)
+                .ok_or(format!("List item does not have a \"{}\" field", self.key))?;
+            if keys.contains(&key) {
+                return Err(format!("List items have duplicate key {:?}", key).into())
+            }
+            keys.push(key);
+        }
+
+        // Trees of items that are gone are removed first, so they don't affect where the
+        // remaining items are placed
+        let mut old_items = Vec::new();
+        for item in self.items.drain(..) {
+            if keys.contains(&&item.key) {
+                old_items.push(item);
+            } else if ui.get(item.tree.root()).is_some() {
+                ui.remove_tree(&item.tree)?;
+            }
+        }
+
+        let start = {
+            let parent = ui.get(self.parent)
+                .ok_or(format!("Unable to find component {:?}", self.parent))?;
+            old_items.iter()
+                .filter_map(|item| child_index(&*ui, self.parent, item.tree.root()))
+                .min()
+                .unwrap_or(parent.children().len())
+        };
+
+        for (i, (item, key)) in items.iter().zip(keys).enumerate() {
+            let index = start + i;
+            let existing = old_items.iter().position(|old| old.key == *key);
+
+            let bound = if let Some(existing) = existing {
+                let bound = old_items.swap_remove(existing);
+                if bound.model != *item {
+                    ui.update_model(&bound.tree, item, context)?;
+                }
+                if child_index(ui, self.parent, bound.tree.root()) != Some(index) {
+                    ui.reparent(bound.tree.root(), self.parent, index, context)?;
+                }
+                BoundItem { model: item.clone(), ..bound }
+            } else {
+                let tree = ui.insert_template_at(
+                    template, Some(item), self.parent, Some(index), context,
+                )?;
+                BoundItem { key: key.clone(), model: item.clone(), tree }
+            };
+
+            self.items.push(bound);
+        }
+
+        Ok(())
+    }
+}
+
+fn child_index(ui: &Ui, parent: ComponentId, child: ComponentId) -> Option<usize> {
+    ui.get(parent)?.children().iter().position(|id| *id == child)
+}
+
+#[cfg(test)]
+mod test {
+    use scripting::{ScriptTable, ScriptValue};
+    use template::{Template};
+    use {Ui, Context, ListBinding};
+
+    fn item(id: &str, shown: bool) -> ScriptTable {
+        let mut item = ScriptTable::new();
+        item.set("id", id.to_string());
+        item.set("shown", shown);
+        item
+    }
+
+    #[test]
+    fn it_updates_only_changed_list_items() {
+        let context = Context::for_test();
+        let item_template = Template::from_str("container { visible: ={model.shown} }\n")
+            .unwrap();
+        let (mut ui, tree) = Ui::for_test("container { }\n    container { }\n", "", &context);
+        let mut binding = ListBinding::new(tree.root(), "id");
+
+        let items = vec![item("a", true), item("b", true), item("c", true)];
+        binding.update(&mut ui, &item_template, &items, &context).unwrap();
+        let a = binding.tree(0).unwrap().root();
+        let c = binding.tree(2).unwrap().root();
+        assert_eq!(ui.get(tree.root()).unwrap().children().len(), 4);
+
+        // Removing, reordering, and changing items keeps the trees of items that stay
+        let items = vec![item("c", true), item("d", true), item("a", false)];
+        binding.update(&mut ui, &item_template, &items, &context).unwrap();
+        let d = binding.tree_for(&ScriptValue::String("d".into())).unwrap().root();
+        let children = ui.get(tree.root()).unwrap().children().clone();
+        assert_eq!(&children[1..], &[c, d, a]);
+        assert!(!ui.get(a).unwrap().attributes().visible);
+        assert!(ui.get(c).unwrap().attributes().visible);
+
+        assert!(binding.update(&mut ui, &item_template, &[ScriptTable::new()], &context).is_err());
+    }
+}
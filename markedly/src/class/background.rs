@@ -1,16 +1,20 @@
 use nalgebra::{Point2, Vector2};
-use lyon::math::rect;
-use lyon::tessellation as lt;
 
-use render::{Renderer};
+use render::{self, Renderer, Rect, FillMode, CornerRadii, Sides};
 use scripting::{ScriptRuntime};
-use template::{Attributes, Color};
+use template::{Attributes, Color, TemplateValue};
 use {Error, ComponentAttributes, ComponentId};
 
 pub struct BackgroundAttributes {
     color: Option<Color>,
     color_hovering: Option<Color>,
-    border_radius: f32,
+    image: Option<String>,
+    image_hovering: Option<String>,
+    fill_mode: FillMode,
+    border_radius: CornerRadii,
+    border_width: Sides<f32>,
+    border_color: Option<Sides<Color>>,
+    shape: BackgroundShape,
 }
 
 impl BackgroundAttributes {
@@ -20,7 +24,25 @@ impl BackgroundAttributes {
             color_hovering: attributes.attribute_optional(
                 "color-hovering", |v| v.as_color(runtime)
             )?,
-            border_radius: attributes.attribute("border-radius", |v| v.as_float(runtime), 0.0)?,
+            image: attributes.attribute_optional("image", |v| v.as_string(runtime))?,
+            image_hovering: attributes.attribute_optional(
+                "image-hovering", |v| v.as_string(runtime)
+            )?,
+            fill_mode: attributes.attribute(
+                "fill-mode", |v| FillMode::from_value(v, runtime), FillMode::Stretch,
+            )?,
+            border_radius: attributes.attribute(
+                "border-radius", |v| corner_radii(v, runtime), CornerRadii::new(0.0),
+            )?,
+            border_width: attributes.attribute(
+                "border-width", |v| border_widths(v, runtime), Sides::new(1.0),
+            )?,
+            border_color: attributes.attribute_optional(
+                "border-color", |v| border_colors(v, runtime)
+            )?,
+            shape: attributes.attribute(
+                "shape", |v| BackgroundShape::from_value(v, runtime), BackgroundShape::Rectangle,
+            )?,
         })
     }
 
@@ -29,45 +51,62 @@ impl BackgroundAttributes {
         _attributes: &ComponentAttributes, computed_size: Vector2<f32>, renderer: &mut Renderer,
         hovering: bool,
     ) -> Result<(), Error> {
-        let current_color = if hovering && self.color_hovering.is_some() {
-            self.color_hovering
-        } else {
-            self.color
+        self.render_hover(
+            id, _attributes, computed_size, renderer, if hovering { 1.0 } else { 0.0 },
+        )
+    }
+
+    /// Renders the background, blending between the normal and hovering colors and images by
+    /// how far `hover` is from 0.0 to 1.0.
+    pub fn render_hover(
+        &self, id: ComponentId,
+        _attributes: &ComponentAttributes, computed_size: Vector2<f32>, renderer: &mut Renderer,
+        hover: f32,
+    ) -> Result<(), Error> {
+        // Images go under the color, so a translucent color can tint or highlight them
+        let area = Rect::new(Point2::new(0.0, 0.0), computed_size);
+        if let Some(ref image) = self.image {
+            render::image(
+                renderer, id, image, area, self.fill_mode, Color::new(1.0, 1.0, 1.0, 1.0),
+            )?;
+        }
+        if let Some(ref image_hovering) = self.image_hovering {
+            if hover > 0.0 {
+                render::image(
+                    renderer, id, image_hovering, area, self.fill_mode,
+                    Color::new(1.0, 1.0, 1.0, hover),
+                )?;
+            }
+        }
+
+        let current_color = match (self.color, self.color_hovering) {
+            (Some(color), Some(color_hovering)) => Some(blend(color, color_hovering, hover)),
+            (None, Some(mut color_hovering)) => {
+                color_hovering.alpha *= hover;
+                Some(color_hovering)
+            },
+            (color, None) => color,
         };
 
         if let Some(color) = current_color {
-            if self.border_radius == 0.0 {
-                // Simple rectangle fast path
-                renderer.vertices(id, &[
-                    Point2::new(0.0, 0.0),
-                    Point2::new(0.0, computed_size.y),
-                    Point2::new(computed_size.x, computed_size.y),
-                    Point2::new(computed_size.x, 0.0),
-                ], &[0, 1, 3, 2, 3, 1], color)?;
-            } else {
-                // Generate the rounded rectangle
-                let mut geometry = lt::VertexBuffers::new();
-                let options = lt::FillOptions::tolerance(0.1);
-                lt::basic_shapes::fill_rounded_rectangle(
-                    &rect(0.0, 0.0, computed_size.x, computed_size.y),
-                    &lt::basic_shapes::BorderRadii {
-                        top_left: self.border_radius,
-                        top_right: self.border_radius,
-                        bottom_left: self.border_radius,
-                        bottom_right: self.border_radius,
-                    },
-                    &options,
-                    &mut lt::geometry_builder::simple_builder(&mut geometry),
-                );
-
-                // Send it over to the renderer
-                let vertices: Vec<_> = geometry.vertices.into_iter()
-                    .map(|v| Point2::new(v.position.x, v.position.y)).collect();
-                renderer.vertices(
-                    id,
-                    &vertices,
-                    &geometry.indices,
-                    color,
+            let center = Point2::from_coordinates(computed_size * 0.5);
+            match self.shape {
+                BackgroundShape::Rectangle => render::rounded_rectangle(
+                    renderer, id, area, self.border_radius, color,
+                )?,
+                BackgroundShape::Circle => {
+                    let radius = computed_size.x.min(computed_size.y) * 0.5;
+                    renderer.ellipse(id, center, Vector2::new(radius, radius), color)?
+                },
+                BackgroundShape::Ellipse =>
+                    renderer.ellipse(id, center, computed_size * 0.5, color)?,
+            }
+        }
+
+        if let Some(border_color) = self.border_color {
+            if self.shape == BackgroundShape::Rectangle {
+                render::border(
+                    renderer, id, area, self.border_radius, self.border_width, border_color,
                 )?;
             }
         }
@@ -76,6 +115,98 @@ impl BackgroundAttributes {
     }
 
     pub fn is_capturing_cursor(&self) -> bool {
-        self.color.is_some()
+        self.color.is_some() || self.image.is_some()
+    }
+}
+
+/// Reads a radius for every corner, either the same radius for all of them or a tuple going
+/// clockwise from the top left.
+fn corner_radii(value: &TemplateValue, runtime: &ScriptRuntime) -> Result<CornerRadii, Error> {
+    if let TemplateValue::Tuple(_) = *value {
+        let radii = four(value, |v| v.as_float(runtime))?;
+        Ok(CornerRadii {
+            top_left: radii[0],
+            top_right: radii[1],
+            bottom_right: radii[2],
+            bottom_left: radii[3],
+        })
+    } else {
+        Ok(CornerRadii::new(value.as_float(runtime)?))
+    }
+}
+
+/// Reads a border width for every side, either the same width for all of them or a tuple going
+/// clockwise from the top.
+fn border_widths(value: &TemplateValue, runtime: &ScriptRuntime) -> Result<Sides<f32>, Error> {
+    if let TemplateValue::Tuple(_) = *value {
+        let widths = four(value, |v| v.as_float(runtime))?;
+        Ok(Sides { top: widths[0], right: widths[1], bottom: widths[2], left: widths[3] })
+    } else {
+        Ok(Sides::new(value.as_float(runtime)?))
+    }
+}
+
+/// Reads a border color for every side, either the same color for all of them or a tuple of
+/// colors going clockwise from the top.
+fn border_colors(value: &TemplateValue, runtime: &ScriptRuntime) -> Result<Sides<Color>, Error> {
+    // A single color can be a tuple of channels as well, so it's told apart by what's in it
+    let per_side = match *value {
+        TemplateValue::Tuple(ref values) => match values.first() {
+            Some(&TemplateValue::Tuple(_)) | Some(&TemplateValue::String(_)) => true,
+            _ => false,
+        },
+        _ => false,
+    };
+
+    if per_side {
+        let colors = four(value, |v| v.as_color(runtime))?;
+        Ok(Sides { top: colors[0], right: colors[1], bottom: colors[2], left: colors[3] })
+    } else {
+        Ok(Sides::new(value.as_color(runtime)?))
+    }
+}
+
+/// Reads a tuple of exactly four values.
+fn four<T, F: Fn(&TemplateValue) -> Result<T, Error>>(
+    value: &TemplateValue, read: F,
+) -> Result<Vec<T>, Error> {
+    let values = value.as_vec()?;
+    if values.len() != 4 {
+        return Err("Tuple is incorrect size".into())
+    }
+
+    values.iter().enumerate()
+        .map(|(i, v)| read(v).map_err(|e| Error::new_value(&format!("Value {}", i + 1), e)))
+        .collect()
+}
+
+fn blend(from: Color, to: Color, t: f32) -> Color {
+    Color::new(
+        from.red + (to.red - from.red) * t,
+        from.green + (to.green - from.green) * t,
+        from.blue + (to.blue - from.blue) * t,
+        from.alpha + (to.alpha - from.alpha) * t,
+    )
+}
+
+/// The shape a background is filled with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BackgroundShape {
+    /// Fills the component, with optionally rounded corners.
+    Rectangle,
+    /// The largest circle that fits in the component, centered.
+    Circle,
+    /// An ellipse touching the component's edges.
+    Ellipse,
+}
+
+impl BackgroundShape {
+    pub fn from_value(value: &TemplateValue, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        match value.as_string(runtime)?.as_str() {
+            "rectangle" => Ok(BackgroundShape::Rectangle),
+            "circle" => Ok(BackgroundShape::Circle),
+            "ellipse" => Ok(BackgroundShape::Ellipse),
+            _ => Err("Value must be either \"rectangle\", \"circle\", or \"ellipse\"".into())
+        }
     }
 }
@@ -1,7 +1,9 @@
 use nalgebra::{Point2, Vector2};
 
+use animation::{Transition};
 use class::{ComponentClass, ComponentClassFactory, BackgroundAttributes};
-use render::{Renderer};
+use input::{Modifiers, PressPosition};
+use render::{self, Renderer, RichText, TextStyle, TextEffects};
 use scripting::{ScriptRuntime};
 use template::{Attributes, Color, EventHook};
 use {EventSink, Error, ComponentAttributes, ComponentId};
@@ -11,6 +13,18 @@ pub struct ButtonClass {
     background: BackgroundAttributes,
     attributes: ButtonAttributes,
     hovering: bool,
+    /// How far the background is blended towards the hovering color.
+    hover: f32,
+    hover_transition: Option<Transition>,
+    /// The modifiers the button was pressed down with, while it's held down.
+    held: Option<Modifiers>,
+    /// How long the button has been held down for, in seconds.
+    held_time: f32,
+    /// When the next repeated press should be raised, in seconds since the button was pressed.
+    next_repeat: f32,
+    /// If a press was already raised since the button was pressed down, by repeating or a long
+    /// press, in which case releasing it doesn't raise another press.
+    press_handled: bool,
 }
 
 impl ComponentClassFactory for ButtonClass {
@@ -19,6 +33,12 @@ impl ComponentClassFactory for ButtonClass {
             background: BackgroundAttributes::load(attributes, runtime)?,
             attributes: ButtonAttributes::load(attributes, runtime)?,
             hovering: false,
+            hover: 0.0,
+            hover_transition: load_hover_transition(attributes, runtime)?,
+            held: None,
+            held_time: 0.0,
+            next_repeat: 0.0,
+            press_handled: false,
         })
     }
 }
@@ -29,6 +49,7 @@ impl ComponentClass for ButtonClass {
     ) -> Result<(), Error> {
         self.background = BackgroundAttributes::load(attributes, runtime)?;
         self.attributes = ButtonAttributes::load(attributes, runtime)?;
+        self.hover_transition = load_hover_transition(attributes, runtime)?;
         Ok(())
     }
 
@@ -36,12 +57,25 @@ impl ComponentClass for ButtonClass {
         &self, id: ComponentId,
         attributes: &ComponentAttributes, computed_size: Vector2<f32>, renderer: &mut Renderer,
     ) -> Result<(), Error> {
-        self.background.render(id, attributes, computed_size, renderer, self.hovering)?;
+        let hover = self.hover_transition.as_ref()
+            .map(|t| t.easing.apply(self.hover))
+            .unwrap_or(self.hover);
+        self.background.render_hover(id, attributes, computed_size, renderer, hover)?;
 
         if let Some(ref text) = self.attributes.text {
-            renderer.text(
-                id, &text, self.attributes.text_font.as_ref(), self.attributes.text_size,
-                Point2::new(0.0, 0.0), computed_size, self.attributes.text_color,
+            let style = TextStyle {
+                font: self.attributes.text_font.as_ref(),
+                font_bold: self.attributes.text_font_bold.as_ref(),
+                font_italic: self.attributes.text_font_italic.as_ref(),
+                fallback_fonts: &self.attributes.text_font_fallbacks,
+                size: self.attributes.text_size,
+                color: self.attributes.text_color,
+                effects: self.attributes.text_effects,
+                letter_spacing: self.attributes.letter_spacing,
+                line_height: self.attributes.line_height,
+            };
+            render::rich_text(
+                renderer, id, text, &style, Point2::new(0.0, 0.0), computed_size,
             )?;
         }
 
@@ -52,41 +86,241 @@ impl ComponentClass for ButtonClass {
         true
     }
 
+    fn update(&mut self, delta: f32, event_sink: &mut EventSink) -> bool {
+        self.update_repeat(delta, event_sink);
+
+        let target = if self.hovering { 1.0 } else { 0.0 };
+        if self.hover == target {
+            return false
+        }
+
+        // The hovering color is transitioned to as set for the normal color
+        let step = match self.hover_transition {
+            Some(ref transition) if transition.duration > 0.0 => delta / transition.duration,
+            _ => 1.0,
+        };
+        self.hover = if target > self.hover {
+            (self.hover + step).min(target)
+        } else {
+            (self.hover - step).max(target)
+        };
+
+        true
+    }
+
     fn hover_start_event(&mut self, _event_sink: &mut EventSink) -> bool {
         self.hovering = true;
+        if self.hover_transition.is_none() {
+            self.hover = 1.0;
+        }
         true
     }
 
     fn hover_end_event(&mut self, _event_sink: &mut EventSink) -> bool {
         self.hovering = false;
+        if self.hover_transition.is_none() {
+            self.hover = 0.0;
+        }
         true
     }
 
-    fn pressed_event(&mut self, event_sink: &mut EventSink) {
-        if let Some(ref event) = self.attributes.on_pressed {
+    fn press_start_event(
+        &mut self, _event_sink: &mut EventSink, modifiers: Modifiers,
+        _position: PressPosition, _computed_size: Vector2<f32>,
+    ) -> bool {
+        self.held = Some(modifiers);
+        self.held_time = 0.0;
+        self.next_repeat = self.attributes.repeat_delay.unwrap_or(0.0);
+        self.press_handled = false;
+        false
+    }
+
+    fn press_end_event(&mut self, _event_sink: &mut EventSink) -> bool {
+        self.held = None;
+        false
+    }
+
+    fn long_pressed_event(&mut self, event_sink: &mut EventSink, _modifiers: Modifiers) {
+        if let Some(ref event) = self.attributes.on_long_pressed {
             event_sink.raise(event);
+            self.press_handled = true;
+        }
+    }
+
+    fn pressed_event(
+        &mut self, event_sink: &mut EventSink, modifiers: Modifiers,
+        position: Option<PressPosition>,
+    ) -> bool {
+        // The presses already raised while holding the button down take the place of this one
+        if self.press_handled {
+            self.press_handled = false;
+            return false
+        }
+
+        self.raise_pressed(event_sink, modifiers, position);
+        true
+    }
+}
+
+impl ButtonClass {
+    fn update_repeat(&mut self, delta: f32, event_sink: &mut EventSink) {
+        let modifiers = match (self.held, self.attributes.repeat_delay) {
+            (Some(modifiers), Some(_)) => modifiers,
+            _ => return,
+        };
+
+        // Repeating pauses while the cursor is moved off the button, like scrollbar arrows
+        if !self.hovering {
+            return
+        }
+
+        self.held_time += delta;
+        while self.held_time >= self.next_repeat {
+            // Repeats are raised by time passing rather than by the cursor, so have no position
+            self.raise_pressed(event_sink, modifiers, None);
+            self.press_handled = true;
+            self.next_repeat += self.attributes.repeat_interval;
+        }
+    }
+
+    fn raise_pressed(
+        &self, event_sink: &mut EventSink, modifiers: Modifiers, position: Option<PressPosition>,
+    ) {
+        // Modifier specific hooks take priority over the generic hook, so shift or ctrl clicking
+        // can trigger alternate actions
+        let modifier_hook = if modifiers.shift && self.attributes.on_pressed_shift.is_some() {
+            self.attributes.on_pressed_shift.as_ref()
+        } else if modifiers.ctrl && self.attributes.on_pressed_ctrl.is_some() {
+            self.attributes.on_pressed_ctrl.as_ref()
+        } else if modifiers.alt && self.attributes.on_pressed_alt.is_some() {
+            self.attributes.on_pressed_alt.as_ref()
+        } else {
+            None
+        };
+
+        if let Some(event) = modifier_hook.or(self.attributes.on_pressed.as_ref()) {
+            event_sink.raise_pressed(event, position);
         }
     }
 }
 
+fn load_hover_transition(
+    attributes: &Attributes, runtime: &ScriptRuntime,
+) -> Result<Option<Transition>, Error> {
+    let transitions = attributes.attribute(
+        "transition", |v| Transition::from_value(v, runtime), Vec::new(),
+    )?;
+    Ok(transitions.into_iter().find(|t| t.attribute == "color"))
+}
+
 struct ButtonAttributes {
-    text: Option<String>,
+    text: Option<RichText>,
     text_color: Color,
     text_font: Option<String>,
+    text_font_fallbacks: Vec<String>,
+    text_font_bold: Option<String>,
+    text_font_italic: Option<String>,
     text_size: Option<i32>,
+    text_effects: TextEffects,
+    letter_spacing: f32,
+    line_height: Option<f32>,
     on_pressed: Option<EventHook>,
+    on_pressed_shift: Option<EventHook>,
+    on_pressed_ctrl: Option<EventHook>,
+    on_pressed_alt: Option<EventHook>,
+    on_long_pressed: Option<EventHook>,
+    repeat_delay: Option<f32>,
+    repeat_interval: f32,
 }
 
 impl ButtonAttributes {
     pub fn load(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        // The first font is the text's font, any others are fallbacks for missing glyphs
+        let mut text_fonts = attributes.attribute_optional(
+            "text-font", |v| v.as_strings(runtime)
+        )?.unwrap_or_else(Vec::new);
+        let text_font = if text_fonts.len() != 0 { Some(text_fonts.remove(0)) } else { None };
+
         Ok(ButtonAttributes {
-            text: attributes.attribute_optional("text", |v| v.as_string(runtime))?,
+            text: attributes.attribute_optional(
+                "text", |v| v.as_string(runtime).map(|v| RichText::parse(&v))
+            )?,
             text_color: attributes.attribute(
                 "text-color", |v| v.as_color(runtime), Color::new_u8(0, 0, 0, 255)
             )?,
-            text_font: attributes.attribute_optional("text-font", |v| v.as_string(runtime))?,
+            text_font,
+            text_font_fallbacks: text_fonts,
+            text_font_bold: attributes.attribute_optional(
+                "text-font-bold", |v| v.as_string(runtime)
+            )?,
+            text_font_italic: attributes.attribute_optional(
+                "text-font-italic", |v| v.as_string(runtime)
+            )?,
             text_size: attributes.attribute_optional("text-size", |v| v.as_integer(runtime))?,
+            text_effects: TextEffects::load(attributes, runtime)?,
+            letter_spacing: attributes.attribute(
+                "letter-spacing", |v| v.as_float(runtime), 0.0
+            )?,
+            line_height: attributes.attribute_optional("line-height", |v| v.as_float(runtime))?,
             on_pressed: attributes.attribute_optional("on-pressed", |v| v.as_event_hook(runtime))?,
+            on_pressed_shift: attributes.attribute_optional(
+                "on-pressed-shift", |v| v.as_event_hook(runtime)
+            )?,
+            on_pressed_ctrl: attributes.attribute_optional(
+                "on-pressed-ctrl", |v| v.as_event_hook(runtime)
+            )?,
+            on_pressed_alt: attributes.attribute_optional(
+                "on-pressed-alt", |v| v.as_event_hook(runtime)
+            )?,
+            on_long_pressed: attributes.attribute_optional(
+                "on-long-pressed", |v| v.as_event_hook(runtime)
+            )?,
+            repeat_delay: attributes.attribute_optional(
+                "repeat-delay", |v| v.as_float(runtime)
+            )?,
+            // A minimum interval makes sure a long frame can't raise an endless amount of presses
+            repeat_interval: attributes.attribute(
+                "repeat-interval", |v| v.as_float(runtime), 0.1
+            )?.max(0.01),
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Point2};
+
+    use class::{ButtonClass};
+    use input::{Input, Modifiers};
+    use {Ui, Context};
+
+    #[test]
+    fn it_repeats_presses_while_buttons_are_held() {
+        let mut context = Context::for_test();
+        context.classes.register::<ButtonClass>("button");
+        let (mut ui, tree) = Ui::for_test(
+            "button { on-pressed: \"more\", repeat-delay: 0.5, repeat-interval: 0.25 }\n",
+            "", &context,
+        );
+        let mut input = Input::new();
+        let position = Point2::new(5.0, 5.0);
+
+        input.handle_cursor_moved(position, &mut ui);
+        input.handle_drag_started(position, Modifiers::none(), &mut ui);
+        ui.update(0.25, &context).unwrap();
+        assert_eq!(tree.event_sink().next(), None);
+
+        ui.update(0.25, &context).unwrap();
+        ui.update(0.5, &context).unwrap();
+        let mut presses = 0;
+        while tree.event_sink().next().is_some() {
+            presses += 1;
+        }
+        assert_eq!(presses, 3);
+
+        // Releasing after repeating doesn't count as another press
+        input.handle_drag_ended(position, Modifiers::none(), &mut ui);
+        ui.update(1.0, &context).unwrap();
+        assert_eq!(tree.event_sink().next(), None);
+    }
+}
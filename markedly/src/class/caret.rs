@@ -0,0 +1,223 @@
+use std::cell::{RefCell};
+
+use nalgebra::{Point2, Vector2};
+
+use render::{self, Renderer, Rect};
+use template::{Color};
+use {ComponentId, Error};
+
+/// The caret and selection of text being edited, shared by component classes that edit text so
+/// they all look and behave the same. The caret blinks as the class is updated, and is placed
+/// using the renderer's text measurements from when the text was last rendered.
+///
+/// Positions are in characters from the start of the text.
+pub struct TextCaret {
+    position: usize,
+    /// Where the selection was started, the selection is between this and the caret.
+    anchor: Option<usize>,
+    /// How long the caret has been shown or hidden for, in seconds.
+    blink_time: f32,
+    blink_shown: bool,
+    /// Where every character starts along the text followed by where the text ends, as measured
+    /// when the text was last rendered.
+    offsets: RefCell<Vec<f32>>,
+}
+
+impl TextCaret {
+    pub fn new(position: usize) -> Self {
+        TextCaret {
+            position,
+            anchor: None,
+            blink_time: 0.0,
+            blink_shown: true,
+            offsets: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the start and end of the selection, if anything is selected.
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        let anchor = self.anchor?;
+        if anchor == self.position {
+            None
+        } else {
+            Some((anchor.min(self.position), anchor.max(self.position)))
+        }
+    }
+
+    /// Moves the caret, extending the selection to it or removing the selection.
+    pub fn move_to(&mut self, position: usize, extend_selection: bool) {
+        if extend_selection {
+            if self.anchor.is_none() {
+                self.anchor = Some(self.position);
+            }
+        } else {
+            self.anchor = None;
+        }
+        self.position = position;
+        self.reset_blink();
+    }
+
+    /// Selects from an anchor to a position, placing the caret at the position.
+    pub fn select(&mut self, anchor: usize, position: usize) {
+        self.anchor = Some(anchor);
+        self.position = position;
+        self.reset_blink();
+    }
+
+    /// Removes the selection, leaving the caret where it is.
+    pub fn clear_selection(&mut self) {
+        self.anchor = None;
+    }
+
+    /// Keeps the caret and selection within text of a length in characters, for when the text
+    /// was changed.
+    pub fn clamp(&mut self, length: usize) {
+        self.position = self.position.min(length);
+        self.anchor = self.anchor.map(|anchor| anchor.min(length));
+    }
+
+    /// Shows the caret and starts its blinking over, so it's visible right after it's moved or
+    /// typed at.
+    pub fn reset_blink(&mut self) {
+        self.blink_time = 0.0;
+        self.blink_shown = true;
+    }
+
+    /// Advances the blinking by the time since the last update in seconds, switching between
+    /// shown and hidden every interval. An interval of zero turns blinking off.
+    /// Returns true if the caret was shown or hidden, and should be rendered again.
+    pub fn update(&mut self, delta: f32, interval: f32) -> bool {
+        if interval <= 0.0 {
+            let changed = !self.blink_shown;
+            self.reset_blink();
+            return changed
+        }
+
+        let was_shown = self.blink_shown;
+        self.blink_time += delta;
+        while self.blink_time >= interval {
+            self.blink_time -= interval;
+            self.blink_shown = !self.blink_shown;
+        }
+
+        self.blink_shown != was_shown
+    }
+
+    /// Returns true if the caret is currently shown in its blinking.
+    pub fn is_blink_shown(&self) -> bool {
+        self.blink_shown
+    }
+
+    /// Measures where every character of text starts, to place the caret and selection in it
+    /// and to find which character a position is at, with extra letter spacing between every
+    /// character. Should be called every time the text is rendered. Returns the size of the
+    /// text.
+    pub fn measure(
+        &self, renderer: &mut Renderer, text: &str, font: Option<&String>, size: Option<i32>,
+        letter_spacing: f32,
+    ) -> Result<Vector2<f32>, Error> {
+        let length = text.chars().count();
+        let mut offsets = vec![0.0];
+        let mut measured = String::new();
+        for (i, c) in text.chars().enumerate() {
+            measured.push(c);
+            // The text ends right after its last character, without spacing after it
+            let spacing = if i + 1 == length { i } else { i + 1 };
+            offsets.push(
+                renderer.measure_text(&measured, font, size)?.x + letter_spacing * spacing as f32
+            );
+        }
+
+        let width = offsets[offsets.len() - 1];
+        let height = renderer.measure_text(&text.to_string(), font, size)?.y;
+        *self.offsets.borrow_mut() = offsets;
+
+        Ok(Vector2::new(width, height))
+    }
+
+    /// Gets how far along the text a position starts, as last measured.
+    pub fn offset(&self, position: usize) -> f32 {
+        let offsets = self.offsets.borrow();
+        offsets.get(position).or(offsets.last()).cloned().unwrap_or(0.0)
+    }
+
+    /// Finds the position closest to a distance along the text, as last measured, within text
+    /// of a length in characters.
+    pub fn position_at(&self, x: f32, length: usize) -> usize {
+        let offsets = self.offsets.borrow();
+        let closest = offsets.iter().enumerate()
+            .min_by(|a, b| (a.1 - x).abs().partial_cmp(&(b.1 - x).abs()).unwrap())
+            .map(|(index, _)| index);
+
+        // The text may have changed since it was last measured
+        closest.unwrap_or(self.position).min(length)
+    }
+
+    /// Renders the selection, if there is one, behind text measured with `measure` that starts
+    /// at an origin and is a height tall.
+    pub fn render_selection(
+        &self, id: ComponentId, renderer: &mut Renderer,
+        origin: Point2<f32>, height: f32, color: Color,
+    ) -> Result<(), Error> {
+        if let Some((start, end)) = self.selection() {
+            let start_offset = self.offset(start);
+            let selection = Rect::new(
+                Point2::new(origin.x + start_offset, origin.y),
+                Vector2::new(self.offset(end) - start_offset, height),
+            );
+            render::rectangle(renderer, id, selection, 0.0, color)?;
+        }
+
+        Ok(())
+    }
+
+    /// Renders the caret, if it's currently shown in its blinking, in text measured with
+    /// `measure` that starts at an origin and is a height tall.
+    pub fn render_caret(
+        &self, id: ComponentId, renderer: &mut Renderer,
+        origin: Point2<f32>, height: f32, color: Color,
+    ) -> Result<(), Error> {
+        if !self.blink_shown {
+            return Ok(())
+        }
+
+        let caret = Rect::new(
+            Point2::new(origin.x + self.offset(self.position), origin.y), Vector2::new(1.0, height),
+        );
+        render::rectangle(renderer, id, caret, 0.0, color)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Vector2};
+
+    use class::{TextCaret};
+    use render::{RecordingRenderer};
+
+    #[test]
+    fn it_places_and_blinks_text_carets() {
+        let mut renderer = RecordingRenderer::new();
+        let mut caret = TextCaret::new(0);
+        let size = caret.measure(&mut renderer, "hello", None, None, 0.0).unwrap();
+        assert_eq!(size, Vector2::new(35.0, 14.0));
+
+        caret.move_to(3, true);
+        assert_eq!(caret.selection(), Some((0, 3)));
+        assert_eq!(caret.offset(3), 21.0);
+        assert_eq!(caret.position_at(15.0, 5), 2);
+        assert_eq!(caret.position_at(100.0, 4), 4);
+
+        // Blinking switches every interval, and moving the caret shows it again right away
+        assert!(!caret.update(0.3, 0.5));
+        assert!(caret.update(0.3, 0.5));
+        assert!(!caret.is_blink_shown());
+        caret.move_to(4, false);
+        assert!(caret.is_blink_shown());
+        assert_eq!(caret.selection(), None);
+    }
+}
@@ -0,0 +1,190 @@
+use nalgebra::{Point2, Vector2};
+
+use class::{ComponentClass, ComponentClassFactory, BackgroundAttributes};
+use render::{self, Renderer, Rect};
+use scripting::{ScriptRuntime};
+use template::{Attributes, Color, TemplateValue};
+use {Error, ComponentAttributes, ComponentId};
+
+/// A chart component class, plots a list of numbers within its bounds as a line or as bars,
+/// such as frame times in a debug overlay or resources over time on a stats screen.
+pub struct ChartClass {
+    background: BackgroundAttributes,
+    attributes: ChartAttributes,
+}
+
+impl ComponentClassFactory for ChartClass {
+    fn new(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        Ok(ChartClass {
+            background: BackgroundAttributes::load(attributes, runtime)?,
+            attributes: ChartAttributes::load(attributes, runtime)?,
+        })
+    }
+}
+
+impl ComponentClass for ChartClass {
+    fn update_attributes(
+        &mut self, attributes: &Attributes, runtime: &ScriptRuntime,
+    ) -> Result<(), Error> {
+        self.background = BackgroundAttributes::load(attributes, runtime)?;
+        self.attributes = ChartAttributes::load(attributes, runtime)?;
+        Ok(())
+    }
+
+    fn render(
+        &self, id: ComponentId,
+        attributes: &ComponentAttributes, computed_size: Vector2<f32>, renderer: &mut Renderer,
+    ) -> Result<(), Error> {
+        self.background.render(id, attributes, computed_size, renderer, false)?;
+
+        let (min, max) = self.range();
+        let baseline = self.y(0.0_f32.max(min).min(max), computed_size);
+
+        match self.attributes.chart_type {
+            ChartType::Line => self.render_line(id, computed_size, renderer)?,
+            ChartType::Bar => self.render_bars(id, computed_size, baseline, renderer)?,
+        }
+
+        if let Some(axis_color) = self.attributes.axis_color {
+            let width = self.attributes.axis_width;
+            renderer.line(id, &[
+                Point2::new(0.0, 0.0), Point2::new(0.0, computed_size.y),
+            ], width, axis_color)?;
+            renderer.line(id, &[
+                Point2::new(0.0, baseline), Point2::new(computed_size.x, baseline),
+            ], width, axis_color)?;
+        }
+
+        Ok(())
+    }
+
+    fn is_capturing_cursor(&self) -> bool {
+        self.background.is_capturing_cursor()
+    }
+}
+
+impl ChartClass {
+    fn render_line(
+        &self, id: ComponentId, computed_size: Vector2<f32>, renderer: &mut Renderer,
+    ) -> Result<(), Error> {
+        let values = &self.attributes.values;
+        let points: Vec<_> = if values.len() == 1 {
+            // A single value has nothing to connect to, so it's shown as a flat line
+            let y = self.y(values[0], computed_size);
+            vec![Point2::new(0.0, y), Point2::new(computed_size.x, y)]
+        } else {
+            let step = computed_size.x / (values.len() as f32 - 1.0);
+            values.iter().enumerate()
+                .map(|(i, value)| Point2::new(i as f32 * step, self.y(*value, computed_size)))
+                .collect()
+        };
+
+        if points.len() < 2 {
+            return Ok(())
+        }
+        renderer.line(id, &points, self.attributes.line_width, self.attributes.plot_color)
+    }
+
+    fn render_bars(
+        &self, id: ComponentId, computed_size: Vector2<f32>, baseline: f32,
+        renderer: &mut Renderer,
+    ) -> Result<(), Error> {
+        let values = &self.attributes.values;
+        let spacing = self.attributes.bar_spacing;
+        let count = values.len() as f32;
+        let width = ((computed_size.x - spacing * (count - 1.0)) / count).max(0.0);
+
+        for (i, value) in values.iter().enumerate() {
+            // Bars grow from zero, so negative values hang below it
+            let y = self.y(*value, computed_size);
+            let bar = Rect::new(
+                Point2::new(i as f32 * (width + spacing), y.min(baseline)),
+                Vector2::new(width, (y - baseline).abs()),
+            );
+            render::rectangle(renderer, id, bar, 0.0, self.attributes.plot_color)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the range of values that's plotted, from the minimum and maximum attributes if
+    /// set, otherwise fitting the values and zero.
+    fn range(&self) -> (f32, f32) {
+        let values = &self.attributes.values;
+        let min = self.attributes.min
+            .unwrap_or_else(|| values.iter().cloned().fold(0.0, f32::min));
+        let max = self.attributes.max
+            .unwrap_or_else(|| values.iter().cloned().fold(0.0, f32::max));
+        (min, max)
+    }
+
+    /// Returns the height within the chart a value is plotted at.
+    fn y(&self, value: f32, computed_size: Vector2<f32>) -> f32 {
+        let (min, max) = self.range();
+        if max <= min {
+            return computed_size.y
+        }
+
+        let fraction = ((value - min) / (max - min)).max(0.0).min(1.0);
+        computed_size.y * (1.0 - fraction)
+    }
+}
+
+/// How a chart shows its values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChartType {
+    /// A line through all values, spread out over the full width.
+    Line,
+    /// A bar for every value, side by side.
+    Bar,
+}
+
+impl ChartType {
+    pub fn from_value(value: &TemplateValue, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        match value.as_string(runtime)?.as_str() {
+            "line" => Ok(ChartType::Line),
+            "bar" => Ok(ChartType::Bar),
+            _ => Err("Value must be either \"line\" or \"bar\"".into())
+        }
+    }
+}
+
+struct ChartAttributes {
+    values: Vec<f32>,
+    chart_type: ChartType,
+    min: Option<f32>,
+    max: Option<f32>,
+    plot_color: Color,
+    line_width: f32,
+    bar_spacing: f32,
+    axis_color: Option<Color>,
+    axis_width: f32,
+}
+
+impl ChartAttributes {
+    pub fn load(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        Ok(ChartAttributes {
+            values: attributes.attribute("values", |v| v.as_floats(runtime), Vec::new())?,
+            chart_type: attributes.attribute(
+                "chart-type", |v| ChartType::from_value(v, runtime), ChartType::Line,
+            )?,
+            min: attributes.attribute_optional("min", |v| v.as_float(runtime))?,
+            max: attributes.attribute_optional("max", |v| v.as_float(runtime))?,
+            plot_color: attributes.attribute(
+                "plot-color", |v| v.as_color(runtime), Color::new(1.0, 1.0, 1.0, 1.0)
+            )?,
+            line_width: attributes.attribute(
+                "line-width", |v| v.as_float(runtime), 2.0
+            )?,
+            bar_spacing: attributes.attribute(
+                "bar-spacing", |v| v.as_float(runtime), 2.0
+            )?.max(0.0),
+            axis_color: attributes.attribute_optional(
+                "axis-color", |v| v.as_color(runtime)
+            )?,
+            axis_width: attributes.attribute(
+                "axis-width", |v| v.as_float(runtime), 1.0
+            )?,
+        })
+    }
+}
@@ -1,15 +1,16 @@
-use std::collections::{HashMap};
+use std::collections::{HashMap, HashSet};
 
-use nalgebra::{Vector2};
+use nalgebra::{Point2, Vector2};
 
-use render::{Renderer};
+use input::{Modifiers, PressPosition, Clipboard};
+use render::{Renderer, Effect, Rect};
 use scripting::{ScriptRuntime};
 use template::{ComponentTemplate, Attributes};
-use {EventSink, ComponentAttributes, Error, ComponentId};
+use {EventSink, ComponentAttributes, Error, ComponentId, ComponentState};
 
 /// The class of a component, defines specific appearance and functionality in response to user
 /// input.
-pub trait ComponentClass {
+pub trait ComponentClass: MaybeSend {
     fn update_attributes(
         &mut self, attributes: &Attributes, runtime: &ScriptRuntime,
     ) -> Result<(), Error>;
@@ -21,6 +22,33 @@ pub trait ComponentClass {
         renderer: &mut Renderer,
     ) -> Result<(), Error>;
 
+    /// Returns the post-effect that should be applied when compositing this component, if any.
+    /// This takes priority over the `effect` attribute.
+    fn effect(&self) -> Option<&Effect> { None }
+
+    /// Adjusts the size computed from this component's attributes, for classes that change their
+    /// own size, such as to collapse. Called every time the component is laid out.
+    fn size(&self, size: Vector2<f32>) -> Vector2<f32> { size }
+
+    /// Positions a child that doesn't have a position set, for classes that arrange their
+    /// children themselves, given the child's index and how many children there are, the
+    /// child's size, and this component's size. Returns None to place the child in the flow.
+    fn child_position(
+        &self, _index: usize, _count: usize, _child_size: Vector2<f32>, _size: Vector2<f32>,
+    ) -> Option<Point2<f32>> { None }
+
+    /// Gives a child an area of its own within this component of a size, for classes that
+    /// divide themselves between their children, given the child's index and how many children
+    /// there are. The child's size and position are relative to the area instead of to the
+    /// whole component. Returns None to lay the child out in the whole component.
+    fn child_area(
+        &self, _index: usize, _count: usize, _size: Vector2<f32>,
+    ) -> Option<Rect> { None }
+
+    /// Called when children are added to or removed from this component, with how many children
+    /// it has now, for classes that need to know outside of laying them out.
+    fn children_changed(&mut self, _count: usize) {}
+
     /// Returns if this component class captures cursor events or not. Does not affect children.
     fn is_capturing_cursor(&self) -> bool { false }
 
@@ -32,26 +60,124 @@ pub trait ComponentClass {
     /// Returns if the component should be marked for render update.
     fn hover_end_event(&mut self, _event_sink: &mut EventSink) -> bool { false }
 
-    /// Called when the component is clicked or tapped.
-    fn pressed_event(&mut self, _event_sink: &mut EventSink) {}
+    /// Called when the component is clicked or tapped, with the modifier keys held down at the
+    /// time and where it was pressed. The position is None if it wasn't pressed by the cursor
+    /// or a touch, such as by a keyboard shortcut.
+    /// Returns false if the press was ignored, such as when a long press took its place, so the
+    /// component's pressed sound isn't played.
+    fn pressed_event(
+        &mut self, _event_sink: &mut EventSink, _modifiers: Modifiers,
+        _position: Option<PressPosition>,
+    ) -> bool { true }
+
+    /// Called when the component is pressed down and held in place for a while, such as to open
+    /// a context menu on touch screens. The press still ends as usual afterwards.
+    fn long_pressed_event(&mut self, _event_sink: &mut EventSink, _modifiers: Modifiers) {}
+
+    /// Called when the cursor or a touch is pressed down on this component, before it's
+    /// released. The component is pressed until `press_end_event` is called.
+    /// Returns if the component should be marked for render update.
+    fn press_start_event(
+        &mut self, _event_sink: &mut EventSink, _modifiers: Modifiers,
+        _position: PressPosition, _computed_size: Vector2<f32>,
+    ) -> bool { false }
+
+    /// Called when the cursor or touch pressing this component moves, even if it isn't over the
+    /// component anymore, such as to drag parts of the component.
+    /// Returns if the component should be marked for render update.
+    fn press_moved_event(
+        &mut self, _event_sink: &mut EventSink,
+        _position: PressPosition, _computed_size: Vector2<f32>,
+    ) -> bool { false }
+
+    /// Called when the cursor or touch that pressed down on this component is released, even if
+    /// it isn't over the component anymore. Called before `pressed_event`.
+    /// Returns if the component should be marked for render update.
+    fn press_end_event(&mut self, _event_sink: &mut EventSink) -> bool { false }
+
+    /// Returns if pressing this component gives it focus, so it receives keys and typed text
+    /// instead of them triggering shortcuts.
+    fn is_focusable(&self) -> bool { false }
+
+    /// Called when this component gains or loses focus.
+    /// Returns if the component should be marked for render update.
+    fn focus_changed_event(&mut self, _event_sink: &mut EventSink, _focused: bool) -> bool {
+        false
+    }
+
+    /// Called when a key is pressed down or repeats while this component has focus, with the
+    /// clipboard to copy text to and paste text from.
+    /// Returns if the component should be marked for render update.
+    fn key_down_event(
+        &mut self, _event_sink: &mut EventSink, _key: &str, _modifiers: Modifiers,
+        _clipboard: &mut Clipboard,
+    ) -> bool { false }
+
+    /// Called when text is typed while this component has focus.
+    /// Returns if the component should be marked for render update.
+    fn text_event(&mut self, _event_sink: &mut EventSink, _text: &str) -> bool { false }
+
+    /// Called every frame with the time since the last frame in seconds, for classes that
+    /// animate themselves or raise events over time.
+    /// Returns if the component should be marked for render update.
+    fn update(&mut self, _delta: f32, _event_sink: &mut EventSink) -> bool { false }
+
+    /// Stores runtime state that should survive the UI being built again, such as a scroll
+    /// offset, in the given state.
+    fn save_state(&self, _state: &mut ComponentState) {}
+
+    /// Restores runtime state previously stored by `save_state`.
+    /// Returns if the component should be marked for render update.
+    fn restore_state(&mut self, _state: &ComponentState) -> bool { false }
 }
 
+/// Requires component classes to be `Send` when the `send` feature is enabled, so the UI they're
+/// in can be `Send` as well.
+#[cfg(feature = "send")]
+pub trait MaybeSend: Send {}
+#[cfg(feature = "send")]
+impl<T: Send + ?Sized> MaybeSend for T {}
+
+/// Requires component classes to be `Send` when the `send` feature is enabled, so the UI they're
+/// in can be `Send` as well.
+#[cfg(not(feature = "send"))]
+pub trait MaybeSend {}
+#[cfg(not(feature = "send"))]
+impl<T: ?Sized> MaybeSend for T {}
 
 /// A registry of component class factories.
 pub struct ComponentClasses {
     factories: HashMap<String, Box<
         Fn(&Attributes, &ScriptRuntime) -> Result<Box<ComponentClass>, Error>
     >>,
+    inherited: HashSet<String>,
 }
 
 impl ComponentClasses {
-    /// Creates a new registry.
+    /// Creates a new registry, with the text attributes of the built-in classes inherited.
     pub fn new() -> Self {
+        let inherited = [
+            "text-color", "text-font", "text-font-bold", "text-font-italic", "text-size",
+            "text-outline", "text-shadow", "letter-spacing", "line-height",
+        ].iter().map(|key| key.to_string()).collect();
+
         ComponentClasses {
             factories: HashMap::new(),
+            inherited,
         }
     }
 
+    /// Marks an attribute as inherited, so setting it on a component applies it to all of the
+    /// component's descendants that don't set it themselves.
+    pub fn register_inherited(&mut self, attribute: &str) {
+        self.inherited.insert(attribute.into());
+    }
+
+    /// Returns true if an attribute is inherited by the descendants of components it's set on.
+    pub fn is_inherited(&self, attribute: &str) -> bool {
+        self.inherited.contains(attribute)
+    }
+
     /// Registers a component class by name.
     pub fn register<F: ComponentClassFactory>(
         &mut self, class: &str
@@ -62,12 +188,17 @@ impl ComponentClasses {
         }));
     }
 
+    /// Returns true if a component class is registered by this name.
+    pub fn is_registered(&self, class: &str) -> bool {
+        self.factories.contains_key(class)
+    }
+
     /// Creates a new boxed instance of the component class requested in the template.
     pub fn create(
         &self, template: &ComponentTemplate, attributes: &Attributes, runtime: &ScriptRuntime,
     ) -> Result<Box<ComponentClass>, Error> {
         let component_class = self.factories
-            .get(&template.class)
+            .get(template.class.as_str())
             .ok_or(format!("Component class \"{}\" was not registered", template.class))?
             (attributes, runtime)?;
 
@@ -0,0 +1,226 @@
+use std::cell::{Cell};
+
+use nalgebra::{Point2, Vector2};
+
+use class::{ComponentClass, ComponentClassFactory, BackgroundAttributes};
+use input::{Modifiers, PressPosition};
+use render::{Renderer, Rect};
+use scripting::{ScriptRuntime};
+use template::{Attributes, EventHook};
+use {EventSink, Error, ComponentAttributes, ComponentId, ComponentState};
+
+/// A collapsible component class, a header with a body below it that's shown or hidden by
+/// pressing the header. The first child is the header and the second the body. While collapsed
+/// the component shrinks down to the header, so components after it in the flow move up.
+pub struct CollapsibleClass {
+    background: BackgroundAttributes,
+    attributes: CollapsibleAttributes,
+    expanded: bool,
+    /// How far the body is shown, from 0.0 collapsed to 1.0 expanded, moving towards `expanded`
+    /// over the expand duration.
+    progress: f32,
+    /// The height the component is expanded to, as last laid out.
+    expanded_height: Cell<f32>,
+}
+
+impl ComponentClassFactory for CollapsibleClass {
+    fn new(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        let collapsible_attributes = CollapsibleAttributes::load(attributes, runtime)?;
+        let expanded = collapsible_attributes.expanded.unwrap_or(true);
+        Ok(CollapsibleClass {
+            background: BackgroundAttributes::load(attributes, runtime)?,
+            attributes: collapsible_attributes,
+            expanded,
+            progress: if expanded { 1.0 } else { 0.0 },
+            expanded_height: Cell::new(0.0),
+        })
+    }
+}
+
+impl ComponentClass for CollapsibleClass {
+    fn update_attributes(
+        &mut self, attributes: &Attributes, runtime: &ScriptRuntime,
+    ) -> Result<(), Error> {
+        self.background = BackgroundAttributes::load(attributes, runtime)?;
+        self.attributes = CollapsibleAttributes::load(attributes, runtime)?;
+
+        // Expanding or collapsing from the model takes over from the header being pressed
+        if let Some(expanded) = self.attributes.expanded {
+            self.expanded = expanded;
+        }
+
+        Ok(())
+    }
+
+    fn render(
+        &self, id: ComponentId,
+        attributes: &ComponentAttributes, computed_size: Vector2<f32>, renderer: &mut Renderer,
+    ) -> Result<(), Error> {
+        self.background.render(id, attributes, computed_size, renderer, false)
+    }
+
+    fn size(&self, size: Vector2<f32>) -> Vector2<f32> {
+        self.expanded_height.set(size.y);
+
+        let header_height = self.attributes.header_height.min(size.y);
+        let body_height = (size.y - header_height) * self.progress;
+        Vector2::new(size.x, header_height + body_height)
+    }
+
+    fn child_area(&self, index: usize, _count: usize, size: Vector2<f32>) -> Option<Rect> {
+        let header_height = self.attributes.header_height.min(size.y);
+        match index {
+            0 => Some(Rect::new(Point2::new(0.0, 0.0), Vector2::new(size.x, header_height))),
+            // The body keeps its full size while it's collapsing, and is cut off by the
+            // component instead, so it doesn't have to be laid out again every frame
+            1 => Some(Rect::new(
+                Point2::new(0.0, header_height),
+                Vector2::new(size.x, (self.expanded_height.get() - header_height).max(0.0)),
+            )),
+            _ => None,
+        }
+    }
+
+    fn is_capturing_cursor(&self) -> bool {
+        true
+    }
+
+    fn pressed_event(
+        &mut self, event_sink: &mut EventSink, _modifiers: Modifiers,
+        position: Option<PressPosition>,
+    ) -> bool {
+        // Only the header toggles the body, presses anywhere on the body are ignored
+        let on_header = position
+            .map(|position| position.local.y <= self.attributes.header_height)
+            .unwrap_or(true);
+        if !on_header {
+            return false
+        }
+
+        self.expanded = !self.expanded;
+        let event = if self.expanded {
+            &self.attributes.on_expanded
+        } else {
+            &self.attributes.on_collapsed
+        };
+        if let Some(ref event) = *event {
+            event_sink.raise(event);
+        }
+        true
+    }
+
+    fn update(&mut self, delta: f32, _event_sink: &mut EventSink) -> bool {
+        let target = if self.expanded { 1.0 } else { 0.0 };
+        if self.progress == target {
+            return false
+        }
+
+        let duration = self.attributes.expand_duration;
+        self.progress = if duration <= 0.0 {
+            target
+        } else if target > self.progress {
+            (self.progress + delta / duration).min(target)
+        } else {
+            (self.progress - delta / duration).max(target)
+        };
+
+        true
+    }
+
+    fn save_state(&self, state: &mut ComponentState) {
+        state.set("expanded", self.expanded);
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> bool {
+        if let Some(expanded) = state.get("expanded").and_then(|v| v.as_bool()) {
+            self.expanded = expanded;
+            self.progress = if expanded { 1.0 } else { 0.0 };
+            true
+        } else {
+            false
+        }
+    }
+}
+
+struct CollapsibleAttributes {
+    expanded: Option<bool>,
+    header_height: f32,
+    expand_duration: f32,
+    on_expanded: Option<EventHook>,
+    on_collapsed: Option<EventHook>,
+}
+
+impl CollapsibleAttributes {
+    pub fn load(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        Ok(CollapsibleAttributes {
+            expanded: attributes.attribute_optional(
+                "expanded", |v| v.as_bool(runtime)
+            )?,
+            header_height: attributes.attribute(
+                "header-height", |v| v.as_float(runtime), 24.0
+            )?.max(0.0),
+            expand_duration: attributes.attribute(
+                "expand-duration", |v| v.as_float(runtime), 0.0
+            )?,
+            on_expanded: attributes.attribute_optional(
+                "on-expanded", |v| v.as_event_hook(runtime)
+            )?,
+            on_collapsed: attributes.attribute_optional(
+                "on-collapsed", |v| v.as_event_hook(runtime)
+            )?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Point2, Vector2};
+
+    use class::{CollapsibleClass};
+    use input::{Input, Modifiers};
+    use template::{Template, Style};
+    use {Ui, Context};
+
+    #[test]
+    fn it_collapses_collapsibles_from_their_header() {
+        let mut context = Context::for_test();
+        context.classes.register::<CollapsibleClass>("collapsible");
+        let template = Template::from_str(&[
+            "container",
+            "    collapsible { size: (100, 100), header-height: 20.0, expand-duration: 0.5, \
+                on-collapsed: \"collapsed\", on-expanded: \"expanded\" }",
+            "        container",
+            "        container",
+            "    container { size: (100, 10) }",
+            "",
+        ].join("\n")).unwrap();
+        let style = Style::from_str("").unwrap();
+        let (mut ui, tree) = Ui::new(
+            &template, None, style, Vector2::new(100.0, 200.0), &context,
+        ).unwrap();
+        let mut input = Input::new();
+        let children = ui.get(tree.root()).unwrap().children().clone();
+        let body = ui.get(children[0]).unwrap().children()[1];
+        assert_eq!(ui.explain_layout(body).unwrap().component().position, Point2::new(0.0, 20.0));
+
+        // Pressing the body doesn't do anything, pressing the header starts collapsing
+        input.handle_drag_started(Point2::new(50.0, 50.0), Modifiers::none(), &mut ui);
+        input.handle_drag_ended(Point2::new(50.0, 50.0), Modifiers::none(), &mut ui);
+        assert_eq!(tree.event_sink().next(), None);
+        input.handle_drag_started(Point2::new(50.0, 10.0), Modifiers::none(), &mut ui);
+        input.handle_drag_ended(Point2::new(50.0, 10.0), Modifiers::none(), &mut ui);
+        assert_eq!(tree.event_sink().next(), Some("collapsed".into()));
+
+        // The component shrinks towards its header, moving the components after it up
+        ui.update(0.25, &context).unwrap();
+        let next = ui.explain_layout(children[1]).unwrap();
+        assert_eq!(next.component().position, Point2::new(0.0, 60.0));
+        ui.update(0.5, &context).unwrap();
+        let next = ui.explain_layout(children[1]).unwrap();
+        assert_eq!(next.component().position, Point2::new(0.0, 20.0));
+
+        input.handle_drag_started(Point2::new(50.0, 10.0), Modifiers::none(), &mut ui);
+        input.handle_drag_ended(Point2::new(50.0, 10.0), Modifiers::none(), &mut ui);
+        assert_eq!(tree.event_sink().next(), Some("expanded".into()));
+    }
+}
@@ -0,0 +1,114 @@
+use nalgebra::{Point2, Vector2};
+
+use class::{ComponentClass, ComponentClassFactory, BackgroundAttributes};
+use render::{self, Renderer};
+use scripting::{ScriptRuntime};
+use template::{Attributes, Color};
+use {Error, ComponentAttributes, ComponentId};
+
+/// A gauge component class, an arc around the center that's filled by how far a value is
+/// between a minimum and maximum, such as a speedometer, a cooldown, or a stamina ring.
+pub struct GaugeClass {
+    background: BackgroundAttributes,
+    attributes: GaugeAttributes,
+}
+
+impl ComponentClassFactory for GaugeClass {
+    fn new(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        Ok(GaugeClass {
+            background: BackgroundAttributes::load(attributes, runtime)?,
+            attributes: GaugeAttributes::load(attributes, runtime)?,
+        })
+    }
+}
+
+impl ComponentClass for GaugeClass {
+    fn update_attributes(
+        &mut self, attributes: &Attributes, runtime: &ScriptRuntime,
+    ) -> Result<(), Error> {
+        self.background = BackgroundAttributes::load(attributes, runtime)?;
+        self.attributes = GaugeAttributes::load(attributes, runtime)?;
+        Ok(())
+    }
+
+    fn render(
+        &self, id: ComponentId,
+        attributes: &ComponentAttributes, computed_size: Vector2<f32>, renderer: &mut Renderer,
+    ) -> Result<(), Error> {
+        self.background.render(id, attributes, computed_size, renderer, false)?;
+
+        // The arc touches the edges of the component, growing inwards by its thickness
+        let center = Point2::from_coordinates(computed_size * 0.5);
+        let outer = computed_size.x.min(computed_size.y) * 0.5;
+        let inner = (outer - self.attributes.thickness).max(0.0);
+        let start = self.attributes.start_angle.to_radians();
+        let end = self.attributes.end_angle.to_radians();
+
+        if let Some(track_color) = self.attributes.track_color {
+            let (vertices, indices) = render::ring_geometry(center, inner, outer, start, end);
+            renderer.vertices(id, &vertices, &indices, track_color)?;
+        }
+
+        let fraction = self.fraction();
+        if fraction > 0.0 {
+            let (vertices, indices) = render::ring_geometry(
+                center, inner, outer, start, start + (end - start) * fraction,
+            );
+            renderer.vertices(id, &vertices, &indices, self.attributes.fill_color)?;
+        }
+
+        Ok(())
+    }
+
+    fn is_capturing_cursor(&self) -> bool {
+        self.background.is_capturing_cursor()
+    }
+}
+
+impl GaugeClass {
+    /// Returns how far the value is between the minimum and maximum, from 0.0 to 1.0.
+    fn fraction(&self) -> f32 {
+        let range = self.attributes.max - self.attributes.min;
+        if range <= 0.0 {
+            return 0.0
+        }
+
+        ((self.attributes.value - self.attributes.min) / range).max(0.0).min(1.0)
+    }
+}
+
+struct GaugeAttributes {
+    value: f32,
+    min: f32,
+    max: f32,
+    start_angle: f32,
+    end_angle: f32,
+    thickness: f32,
+    fill_color: Color,
+    track_color: Option<Color>,
+}
+
+impl GaugeAttributes {
+    pub fn load(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        Ok(GaugeAttributes {
+            value: attributes.attribute("value", |v| v.as_float(runtime), 0.0)?,
+            min: attributes.attribute("min", |v| v.as_float(runtime), 0.0)?,
+            max: attributes.attribute("max", |v| v.as_float(runtime), 1.0)?,
+            start_angle: attributes.attribute(
+                "start-angle", |v| v.as_float(runtime), -90.0
+            )?,
+            end_angle: attributes.attribute(
+                "end-angle", |v| v.as_float(runtime), 270.0
+            )?,
+            thickness: attributes.attribute(
+                "thickness", |v| v.as_float(runtime), 8.0
+            )?.max(0.0),
+            fill_color: attributes.attribute(
+                "fill-color", |v| v.as_color(runtime), Color::new(1.0, 1.0, 1.0, 1.0)
+            )?,
+            track_color: attributes.attribute_optional(
+                "track-color", |v| v.as_color(runtime)
+            )?,
+        })
+    }
+}
@@ -0,0 +1,55 @@
+use nalgebra::{Point2, Vector2};
+
+use class::{ComponentClass, ComponentClassFactory};
+use render::{self, Renderer, Rect, FillMode};
+use scripting::{ScriptRuntime};
+use template::{Attributes, Color};
+use {Error, ComponentAttributes, ComponentId};
+
+/// An image component class, displays an image resolved by the renderer's image cache.
+pub struct ImageClass {
+    image: Option<String>,
+    fill_mode: FillMode,
+    color: Color,
+}
+
+impl ComponentClassFactory for ImageClass {
+    fn new(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        let mut class = ImageClass {
+            image: None,
+            fill_mode: FillMode::Stretch,
+            color: Color::new(1.0, 1.0, 1.0, 1.0),
+        };
+        class.update_attributes(attributes, runtime)?;
+        Ok(class)
+    }
+}
+
+impl ComponentClass for ImageClass {
+    fn update_attributes(
+        &mut self, attributes: &Attributes, runtime: &ScriptRuntime,
+    ) -> Result<(), Error> {
+        self.image = attributes.attribute_optional("image", |v| v.as_string(runtime))?;
+        self.fill_mode = attributes.attribute(
+            "fill-mode", |v| FillMode::from_value(v, runtime), FillMode::Stretch,
+        )?;
+        self.color = attributes.attribute(
+            "image-color", |v| v.as_color(runtime), Color::new(1.0, 1.0, 1.0, 1.0),
+        )?;
+        Ok(())
+    }
+
+    fn render(
+        &self, id: ComponentId,
+        _attributes: &ComponentAttributes, computed_size: Vector2<f32>, renderer: &mut Renderer,
+    ) -> Result<(), Error> {
+        if let Some(ref image) = self.image {
+            render::image(
+                renderer, id, image,
+                Rect::new(Point2::new(0.0, 0.0), computed_size), self.fill_mode, self.color,
+            )?;
+        }
+
+        Ok(())
+    }
+}
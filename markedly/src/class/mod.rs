@@ -1,11 +1,33 @@
 //! Component classes that define functionality and appearance.
 
 mod background;
+mod caret;
+mod collapsible;
 mod container;
 mod classes;
 mod button;
+mod chart;
+mod gauge;
+mod image;
+mod radial_menu;
+mod scrollbar;
+mod scroll_view;
+mod split_pane;
+mod text_input;
+mod vector_image;
 
-pub use self::background::{BackgroundAttributes};
+pub use self::background::{BackgroundAttributes, BackgroundShape};
+pub use self::caret::{TextCaret};
+pub use self::collapsible::{CollapsibleClass};
 pub use self::container::{ContainerClass};
-pub use self::classes::{ComponentClass, ComponentClasses, ComponentClassFactory};
+pub use self::classes::{ComponentClass, ComponentClasses, ComponentClassFactory, MaybeSend};
 pub use self::button::{ButtonClass};
+pub use self::chart::{ChartClass, ChartType};
+pub use self::gauge::{GaugeClass};
+pub use self::image::{ImageClass};
+pub use self::radial_menu::{RadialMenuClass};
+pub use self::scrollbar::{ScrollbarClass, Orientation};
+pub use self::scroll_view::{ScrollViewClass};
+pub use self::split_pane::{SplitPaneClass};
+pub use self::text_input::{TextInputClass, InputType};
+pub use self::vector_image::{VectorImageClass};
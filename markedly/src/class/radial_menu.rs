@@ -0,0 +1,220 @@
+use std::f32::consts::{PI};
+
+use nalgebra::{Point2, Vector2};
+
+use class::{ComponentClass, ComponentClassFactory, BackgroundAttributes};
+use input::{Modifiers, PressPosition};
+use render::{self, Renderer};
+use scripting::{ScriptRuntime};
+use template::{Attributes, Color, EventHook};
+use {EventSink, Error, ComponentAttributes, ComponentId};
+
+/// A radial menu component class, arranges its children in a circle around its center. Pressing
+/// the menu and releasing it selects the item in the direction of the cursor from the center,
+/// so items can be picked with a flick rather than by hitting them exactly.
+pub struct RadialMenuClass {
+    background: BackgroundAttributes,
+    attributes: RadialMenuAttributes,
+    /// The amount of items, kept up to date as children are added and removed.
+    count: usize,
+    /// The item in the direction of the cursor, while the menu is pressed.
+    highlighted: Option<usize>,
+}
+
+impl ComponentClassFactory for RadialMenuClass {
+    fn new(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        Ok(RadialMenuClass {
+            background: BackgroundAttributes::load(attributes, runtime)?,
+            attributes: RadialMenuAttributes::load(attributes, runtime)?,
+            count: 0,
+            highlighted: None,
+        })
+    }
+}
+
+impl ComponentClass for RadialMenuClass {
+    fn update_attributes(
+        &mut self, attributes: &Attributes, runtime: &ScriptRuntime,
+    ) -> Result<(), Error> {
+        self.background = BackgroundAttributes::load(attributes, runtime)?;
+        self.attributes = RadialMenuAttributes::load(attributes, runtime)?;
+        Ok(())
+    }
+
+    fn render(
+        &self, id: ComponentId,
+        attributes: &ComponentAttributes, computed_size: Vector2<f32>, renderer: &mut Renderer,
+    ) -> Result<(), Error> {
+        self.background.render(id, attributes, computed_size, renderer, false)?;
+
+        if let (Some(index), Some(color)) = (self.highlighted, self.attributes.highlight_color) {
+            let count = self.count.max(1);
+            let step = 2.0 * PI / count as f32;
+            let middle = self.start_angle() + step * index as f32;
+            let (vertices, indices) = render::ring_geometry(
+                Point2::from_coordinates(computed_size * 0.5),
+                self.attributes.dead_zone, computed_size.x.min(computed_size.y) * 0.5,
+                middle - step * 0.5, middle + step * 0.5,
+            );
+            renderer.vertices(id, &vertices, &indices, color)?;
+        }
+
+        Ok(())
+    }
+
+    fn child_position(
+        &self, index: usize, count: usize, child_size: Vector2<f32>, size: Vector2<f32>,
+    ) -> Option<Point2<f32>> {
+        // By default the items are placed as far out as they fit
+        let radius = self.attributes.radius.unwrap_or_else(|| {
+            size.x.min(size.y) * 0.5 - child_size.x.max(child_size.y) * 0.5
+        });
+        let angle = self.start_angle() + 2.0 * PI * index as f32 / count as f32;
+        let center = size * 0.5 + Vector2::new(angle.cos(), angle.sin()) * radius;
+
+        Some(Point2::from_coordinates(center - child_size * 0.5))
+    }
+
+    fn children_changed(&mut self, count: usize) {
+        self.count = count;
+    }
+
+    fn is_capturing_cursor(&self) -> bool {
+        true
+    }
+
+    fn press_start_event(
+        &mut self, _event_sink: &mut EventSink, _modifiers: Modifiers,
+        position: PressPosition, computed_size: Vector2<f32>,
+    ) -> bool {
+        self.highlight(position, computed_size)
+    }
+
+    fn press_moved_event(
+        &mut self, _event_sink: &mut EventSink,
+        position: PressPosition, computed_size: Vector2<f32>,
+    ) -> bool {
+        self.highlight(position, computed_size)
+    }
+
+    fn press_end_event(&mut self, event_sink: &mut EventSink) -> bool {
+        let highlighted = match self.highlighted.take() {
+            Some(highlighted) => highlighted,
+            None => return false,
+        };
+
+        if let Some(ref event) = self.attributes.on_selected {
+            event_sink.raise_value(event, highlighted as f32);
+        }
+
+        true
+    }
+}
+
+impl RadialMenuClass {
+    fn start_angle(&self) -> f32 {
+        self.attributes.start_angle.to_radians()
+    }
+
+    /// Finds the item in the direction of a position from the center, or None if the position
+    /// is within the dead zone.
+    fn item_at(&self, position: Point2<f32>, computed_size: Vector2<f32>) -> Option<usize> {
+        let count = self.count;
+        let offset = position.coords - computed_size * 0.5;
+        if count == 0 || offset.norm() < self.attributes.dead_zone {
+            return None
+        }
+
+        // Every item covers the angles halfway to its neighbours on either side
+        let step = 2.0 * PI / count as f32;
+        let angle = offset.y.atan2(offset.x) - self.start_angle() + step * 0.5;
+        let angle = ((angle % (2.0 * PI)) + 2.0 * PI) % (2.0 * PI);
+        Some((angle / step) as usize % count)
+    }
+
+    /// Highlights the item in the direction of a press.
+    /// Returns if the component should be marked for render update.
+    fn highlight(&mut self, position: PressPosition, computed_size: Vector2<f32>) -> bool {
+        let highlighted = self.item_at(position.local, computed_size);
+        if highlighted == self.highlighted {
+            return false
+        }
+
+        self.highlighted = highlighted;
+        true
+    }
+}
+
+struct RadialMenuAttributes {
+    radius: Option<f32>,
+    start_angle: f32,
+    dead_zone: f32,
+    highlight_color: Option<Color>,
+    on_selected: Option<EventHook>,
+}
+
+impl RadialMenuAttributes {
+    pub fn load(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        Ok(RadialMenuAttributes {
+            radius: attributes.attribute_optional("radius", |v| v.as_float(runtime))?,
+            start_angle: attributes.attribute(
+                "start-angle", |v| v.as_float(runtime), -90.0
+            )?,
+            dead_zone: attributes.attribute(
+                "dead-zone", |v| v.as_float(runtime), 0.0
+            )?,
+            highlight_color: attributes.attribute_optional(
+                "highlight-color", |v| v.as_color(runtime)
+            )?,
+            on_selected: attributes.attribute_optional(
+                "on-selected", |v| v.as_event_hook(runtime)
+            )?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Point2, Vector2};
+
+    use class::{RadialMenuClass};
+    use input::{Input, Modifiers};
+    use template::{Template, Style};
+    use {Ui, Context};
+
+    #[test]
+    fn it_arranges_and_selects_radial_menu_items() {
+        let mut context = Context::for_test();
+        context.classes.register::<RadialMenuClass>("radial-menu");
+        let template = Template::from_str(&[
+            "radial-menu { dead-zone: 10.0, on-selected: \"selected\" }",
+            "    container { size: (20, 20) }",
+            "    container { size: (20, 20) }",
+            "    container { size: (20, 20) }",
+            "    container { size: (20, 20) }",
+            "",
+        ].join("\n")).unwrap();
+        let style = Style::from_str("").unwrap();
+        let (mut ui, tree) = Ui::new(
+            &template, None, style, Vector2::new(100.0, 100.0), &context,
+        ).unwrap();
+        let mut input = Input::new();
+
+        // The first item is at the top, the rest follow clockwise
+        let items = ui.get(tree.root()).unwrap().children().clone();
+        let top = ui.explain_layout(items[0]).unwrap().component().position;
+        let right = ui.explain_layout(items[1]).unwrap().component().position;
+        assert!((top - Point2::new(40.0, 0.0)).norm() < 0.001);
+        assert!((right - Point2::new(80.0, 40.0)).norm() < 0.001);
+
+        // Releasing picks the item in the direction of the cursor, but not in the dead zone
+        input.handle_drag_started(Point2::new(52.0, 50.0), Modifiers::none(), &mut ui);
+        input.handle_cursor_moved(Point2::new(60.0, 75.0), &mut ui);
+        input.handle_drag_ended(Point2::new(60.0, 75.0), Modifiers::none(), &mut ui);
+        assert_eq!(tree.event_sink().next_event().unwrap().value, Some(2.0));
+
+        input.handle_drag_started(Point2::new(52.0, 50.0), Modifiers::none(), &mut ui);
+        input.handle_drag_ended(Point2::new(52.0, 50.0), Modifiers::none(), &mut ui);
+        assert_eq!(tree.event_sink().next_event(), None);
+    }
+}
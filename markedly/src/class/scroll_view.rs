@@ -0,0 +1,283 @@
+use nalgebra::{Point2, Vector2};
+
+use class::{ComponentClass, ComponentClassFactory, BackgroundAttributes};
+use input::{Modifiers, PressPosition};
+use render::{Renderer, Rect};
+use scripting::{ScriptRuntime};
+use template::{Attributes, Coordinates, Coordinate};
+use {EventSink, Error, ComponentAttributes, ComponentId, ComponentState};
+
+/// How quickly content past an edge springs back, as the fraction it moves back per second.
+const BOUNCE_RATE: f32 = 12.0;
+/// How quickly flung content slows down once it's past an edge.
+const BOUNCE_FRICTION: f32 = 20.0;
+/// The speed below which flung content comes to a stop, in units per second.
+const STOP_SPEED: f32 = 5.0;
+
+/// A scroll view component class, lays its children out in content that can be larger than the
+/// view, and scrolls the content by dragging it like on a touch screen. Content that's flung
+/// keeps moving after it's released, slowing down over time, and bounces back when it's
+/// dragged or flung past its edges.
+pub struct ScrollViewClass {
+    background: BackgroundAttributes,
+    attributes: ScrollViewAttributes,
+    /// How far the content is scrolled, can be past the edges while bouncing.
+    offset: Vector2<f32>,
+    /// How fast the content is scrolling, in units per second.
+    velocity: Vector2<f32>,
+    /// The size of the view when it was last pressed, which limits how far it can scroll.
+    view_size: Vector2<f32>,
+    /// Where the cursor was last dragged to, and how far the content was scrolled by dragging
+    /// since the last frame, while it's being dragged.
+    drag: Option<(Point2<f32>, Vector2<f32>)>,
+}
+
+impl ComponentClassFactory for ScrollViewClass {
+    fn new(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        Ok(ScrollViewClass {
+            background: BackgroundAttributes::load(attributes, runtime)?,
+            attributes: ScrollViewAttributes::load(attributes, runtime)?,
+            offset: Vector2::new(0.0, 0.0),
+            velocity: Vector2::new(0.0, 0.0),
+            view_size: Vector2::new(0.0, 0.0),
+            drag: None,
+        })
+    }
+}
+
+impl ComponentClass for ScrollViewClass {
+    fn update_attributes(
+        &mut self, attributes: &Attributes, runtime: &ScriptRuntime,
+    ) -> Result<(), Error> {
+        self.background = BackgroundAttributes::load(attributes, runtime)?;
+        self.attributes = ScrollViewAttributes::load(attributes, runtime)?;
+        Ok(())
+    }
+
+    fn render(
+        &self, id: ComponentId,
+        attributes: &ComponentAttributes, computed_size: Vector2<f32>, renderer: &mut Renderer,
+    ) -> Result<(), Error> {
+        self.background.render(id, attributes, computed_size, renderer, false)
+    }
+
+    fn child_area(&self, _index: usize, _count: usize, size: Vector2<f32>) -> Option<Rect> {
+        Some(Rect::new(
+            Point2::new(-self.offset.x, -self.offset.y),
+            self.attributes.content_size.to_vector(size),
+        ))
+    }
+
+    fn is_capturing_cursor(&self) -> bool {
+        true
+    }
+
+    fn press_start_event(
+        &mut self, _event_sink: &mut EventSink, _modifiers: Modifiers,
+        position: PressPosition, computed_size: Vector2<f32>,
+    ) -> bool {
+        // Pressing catches content that's still moving
+        self.view_size = computed_size;
+        self.velocity = Vector2::new(0.0, 0.0);
+        self.drag = Some((position.local, Vector2::new(0.0, 0.0)));
+        false
+    }
+
+    fn press_moved_event(
+        &mut self, _event_sink: &mut EventSink,
+        position: PressPosition, _computed_size: Vector2<f32>,
+    ) -> bool {
+        let (last, mut moved) = match self.drag {
+            Some(drag) => drag,
+            None => return false,
+        };
+
+        // The content follows the cursor, but only half as far while it's past an edge, and
+        // doesn't move in directions it already fits in
+        let mut offset = self.offset;
+        let max = self.max_offset();
+        for axis in 0..2 {
+            if max[axis] <= 0.0 {
+                continue
+            }
+
+            let delta = last[axis] - position.local[axis];
+            moved[axis] += delta;
+            let past_edge = offset[axis] < 0.0 || offset[axis] > max[axis];
+            offset[axis] += if past_edge { delta * 0.5 } else { delta };
+
+            let overscroll = self.attributes.overscroll;
+            offset[axis] = offset[axis].max(-overscroll).min(max[axis] + overscroll);
+        }
+
+        self.drag = Some((position.local, moved));
+        self.scroll_to(offset)
+    }
+
+    fn press_end_event(&mut self, _event_sink: &mut EventSink) -> bool {
+        self.drag = None;
+        false
+    }
+
+    fn update(&mut self, delta: f32, _event_sink: &mut EventSink) -> bool {
+        if delta <= 0.0 {
+            return false
+        }
+
+        // While dragging, the velocity follows how fast the content is being dragged, so it can
+        // be flung when released
+        if let Some((last, moved)) = self.drag {
+            self.velocity = self.velocity * 0.2 + (moved / delta) * 0.8;
+            self.drag = Some((last, Vector2::new(0.0, 0.0)));
+            return false
+        }
+
+        let mut offset = self.offset;
+        let max = self.max_offset();
+        for axis in 0..2 {
+            let max = max[axis].max(0.0);
+            let edge = offset[axis].max(0.0).min(max);
+            let friction = if offset[axis] != edge {
+                BOUNCE_FRICTION
+            } else {
+                self.attributes.scroll_friction
+            };
+
+            offset[axis] += self.velocity[axis] * delta;
+            self.velocity[axis] *= (-friction * delta).exp();
+            if self.velocity[axis].abs() < STOP_SPEED {
+                self.velocity[axis] = 0.0;
+            }
+
+            // Content past an edge springs back towards it, and can't go further than the
+            // overscroll
+            let edge = offset[axis].max(0.0).min(max);
+            if offset[axis] != edge {
+                let overscroll = self.attributes.overscroll;
+                let mut past = (offset[axis] - edge).max(-overscroll).min(overscroll);
+                if past.abs() >= overscroll {
+                    self.velocity[axis] = 0.0;
+                }
+                past *= (-BOUNCE_RATE * delta).exp();
+
+                if past.abs() < 0.5 && self.velocity[axis] == 0.0 {
+                    past = 0.0;
+                }
+                offset[axis] = edge + past;
+            }
+        }
+
+        self.scroll_to(offset)
+    }
+
+    fn save_state(&self, state: &mut ComponentState) {
+        state.set("offset-x", self.offset.x);
+        state.set("offset-y", self.offset.y);
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> bool {
+        let x = state.get("offset-x").and_then(|v| v.as_float());
+        let y = state.get("offset-y").and_then(|v| v.as_float());
+        if let (Some(x), Some(y)) = (x, y) {
+            self.velocity = Vector2::new(0.0, 0.0);
+            self.offset = Vector2::new(x, y);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl ScrollViewClass {
+    /// Returns how far the content can be scrolled in each direction before it's past an edge.
+    fn max_offset(&self) -> Vector2<f32> {
+        self.attributes.content_size.to_vector(self.view_size) - self.view_size
+    }
+
+    /// Moves the content.
+    /// Returns if the component should be marked for render update.
+    fn scroll_to(&mut self, offset: Vector2<f32>) -> bool {
+        if offset == self.offset {
+            return false
+        }
+
+        self.offset = offset;
+        true
+    }
+}
+
+struct ScrollViewAttributes {
+    content_size: Coordinates,
+    scroll_friction: f32,
+    overscroll: f32,
+}
+
+impl ScrollViewAttributes {
+    pub fn load(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        Ok(ScrollViewAttributes {
+            content_size: attributes.attribute(
+                "content-size", |v| v.as_coordinates(runtime), Coordinates::new(
+                    Coordinate::RelativeToParent(1.0), Coordinate::RelativeToParent(1.0),
+                ),
+            )?,
+            scroll_friction: attributes.attribute(
+                "scroll-friction", |v| v.as_float(runtime), 3.0
+            )?.max(0.0),
+            overscroll: attributes.attribute(
+                "overscroll", |v| v.as_float(runtime), 40.0
+            )?.max(0.0),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Point2};
+
+    use class::{ScrollViewClass};
+    use input::{Input, Modifiers};
+    use {Ui, Context};
+
+    #[test]
+    fn it_flings_and_bounces_scroll_views() {
+        let mut context = Context::for_test();
+        context.classes.register::<ScrollViewClass>("scroll-view");
+        let (mut ui, tree) = Ui::for_test(&[
+            "scroll-view { content-size: (100%, 200), scroll-friction: 3.0, overscroll: 40.0 }",
+            "    container { size: (100%, 20) }",
+            "",
+        ].join("\n"), "", &context);
+        let mut input = Input::new();
+        let content = ui.get(tree.root()).unwrap().children()[0];
+        let position = |ui: &Ui| ui.explain_layout(content).unwrap().component().position;
+
+        // Dragging past the top edge moves the content only half as far, and it springs back
+        // once released
+        input.handle_drag_started(Point2::new(50.0, 10.0), Modifiers::none(), &mut ui);
+        input.handle_cursor_moved(Point2::new(50.0, 30.0), &mut ui);
+        assert_eq!(position(&ui), Point2::new(0.0, 20.0));
+        input.handle_cursor_moved(Point2::new(50.0, 40.0), &mut ui);
+        assert_eq!(position(&ui), Point2::new(0.0, 25.0));
+        input.handle_drag_ended(Point2::new(50.0, 40.0), Modifiers::none(), &mut ui);
+        for _ in 0..10 {
+            ui.update(0.1, &context).unwrap();
+        }
+        assert_eq!(position(&ui), Point2::new(0.0, 0.0));
+
+        // Flinging the content keeps it moving after it's released, until friction stops it
+        input.handle_drag_started(Point2::new(50.0, 40.0), Modifiers::none(), &mut ui);
+        input.handle_cursor_moved(Point2::new(50.0, 10.0), &mut ui);
+        ui.update(0.1, &context).unwrap();
+        input.handle_drag_ended(Point2::new(50.0, 10.0), Modifiers::none(), &mut ui);
+        assert_eq!(position(&ui), Point2::new(0.0, -30.0));
+        ui.update(0.1, &context).unwrap();
+        assert!(position(&ui).y < -50.0);
+        for _ in 0..50 {
+            ui.update(0.1, &context).unwrap();
+        }
+        let stopped = position(&ui);
+        assert!(stopped.y < -100.0 && stopped.y >= -150.0);
+        ui.update(0.1, &context).unwrap();
+        assert_eq!(position(&ui), stopped);
+    }
+}
@@ -0,0 +1,258 @@
+use nalgebra::{Point2, Vector2};
+
+use class::{ComponentClass, ComponentClassFactory, BackgroundAttributes};
+use input::{Modifiers, PressPosition};
+use render::{self, Renderer, Rect};
+use scripting::{ScriptRuntime};
+use template::{Attributes, Color, EventHook, TemplateValue};
+use {EventSink, Error, ComponentAttributes, ComponentId, ComponentState};
+
+/// A scrollbar component class, a track with a thumb that can be dragged along it, or paged by
+/// clicking the track, to pick a position from 0.0 at the start to 1.0 at the end.
+pub struct ScrollbarClass {
+    background: BackgroundAttributes,
+    attributes: ScrollbarAttributes,
+    /// The scrolled position, from 0.0 at the start to 1.0 at the end.
+    position: f32,
+    /// How far along the thumb it was grabbed, while it's being dragged.
+    grab_offset: Option<f32>,
+}
+
+impl ComponentClassFactory for ScrollbarClass {
+    fn new(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        let scrollbar_attributes = ScrollbarAttributes::load(attributes, runtime)?;
+        Ok(ScrollbarClass {
+            background: BackgroundAttributes::load(attributes, runtime)?,
+            position: scrollbar_attributes.value.unwrap_or(0.0),
+            attributes: scrollbar_attributes,
+            grab_offset: None,
+        })
+    }
+}
+
+impl ComponentClass for ScrollbarClass {
+    fn update_attributes(
+        &mut self, attributes: &Attributes, runtime: &ScriptRuntime,
+    ) -> Result<(), Error> {
+        self.background = BackgroundAttributes::load(attributes, runtime)?;
+        self.attributes = ScrollbarAttributes::load(attributes, runtime)?;
+
+        // A position set from the model takes over from the one the scrollbar was dragged to
+        if let Some(value) = self.attributes.value {
+            self.position = value;
+        }
+
+        Ok(())
+    }
+
+    fn render(
+        &self, id: ComponentId,
+        attributes: &ComponentAttributes, computed_size: Vector2<f32>, renderer: &mut Renderer,
+    ) -> Result<(), Error> {
+        self.background.render(id, attributes, computed_size, renderer, false)?;
+
+        let (start, length) = self.thumb(computed_size);
+        let thumb = match self.attributes.orientation {
+            Orientation::Horizontal =>
+                Rect::new(Point2::new(start, 0.0), Vector2::new(length, computed_size.y)),
+            Orientation::Vertical =>
+                Rect::new(Point2::new(0.0, start), Vector2::new(computed_size.x, length)),
+        };
+        render::rectangle(
+            renderer, id, thumb, self.attributes.thumb_radius, self.attributes.thumb_color,
+        )?;
+
+        Ok(())
+    }
+
+    fn is_capturing_cursor(&self) -> bool {
+        true
+    }
+
+    fn press_start_event(
+        &mut self, event_sink: &mut EventSink, _modifiers: Modifiers,
+        position: PressPosition, computed_size: Vector2<f32>,
+    ) -> bool {
+        let (start, length) = self.thumb(computed_size);
+        let pressed = self.attributes.orientation.along(position.local.coords);
+
+        if pressed >= start && pressed <= start + length {
+            self.grab_offset = Some(pressed - start);
+            return false
+        }
+
+        // Clicking the track pages towards the click, by as much as the thumb covers
+        let page_size = self.attributes.page_size;
+        let page = if page_size < 1.0 { page_size / (1.0 - page_size) } else { 0.0 };
+        let direction = if pressed < start { -1.0 } else { 1.0 };
+        let target = self.position + page * direction;
+        self.scroll_to(target, event_sink)
+    }
+
+    fn press_moved_event(
+        &mut self, event_sink: &mut EventSink,
+        position: PressPosition, computed_size: Vector2<f32>,
+    ) -> bool {
+        let grab_offset = match self.grab_offset {
+            Some(grab_offset) => grab_offset,
+            None => return false,
+        };
+
+        let (_, length) = self.thumb(computed_size);
+        let range = self.attributes.orientation.along(computed_size) - length;
+        if range <= 0.0 {
+            return false
+        }
+
+        let pressed = self.attributes.orientation.along(position.local.coords);
+        self.scroll_to((pressed - grab_offset) / range, event_sink)
+    }
+
+    fn press_end_event(&mut self, _event_sink: &mut EventSink) -> bool {
+        self.grab_offset = None;
+        false
+    }
+
+    fn save_state(&self, state: &mut ComponentState) {
+        state.set("position", self.position);
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> bool {
+        if let Some(position) = state.get("position").and_then(|v| v.as_float()) {
+            self.position = position;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl ScrollbarClass {
+    /// Returns where the thumb starts along the track, and how long it is.
+    fn thumb(&self, computed_size: Vector2<f32>) -> (f32, f32) {
+        let track = self.attributes.orientation.along(computed_size);
+
+        // The thumb covers as much of the track as the page covers of the content
+        let length = (track * self.attributes.page_size)
+            .max(self.attributes.thumb_min_length)
+            .min(track);
+
+        ((track - length) * self.position, length)
+    }
+
+    /// Moves the scrolled position, raising the scrolled event if it changed.
+    /// Returns if the component should be marked for render update.
+    fn scroll_to(&mut self, position: f32, event_sink: &mut EventSink) -> bool {
+        let position = position.max(0.0).min(1.0);
+        if position == self.position {
+            return false
+        }
+
+        self.position = position;
+        if let Some(ref event) = self.attributes.on_scrolled {
+            event_sink.raise_value(event, position);
+        }
+
+        true
+    }
+}
+
+struct ScrollbarAttributes {
+    value: Option<f32>,
+    orientation: Orientation,
+    page_size: f32,
+    thumb_color: Color,
+    thumb_radius: f32,
+    thumb_min_length: f32,
+    on_scrolled: Option<EventHook>,
+}
+
+impl ScrollbarAttributes {
+    pub fn load(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        Ok(ScrollbarAttributes {
+            value: attributes.attribute_optional(
+                "value", |v| v.as_float(runtime)
+            )?.map(|v| v.max(0.0).min(1.0)),
+            orientation: attributes.attribute(
+                "orientation", |v| Orientation::from_value(v, runtime), Orientation::Vertical,
+            )?,
+            page_size: attributes.attribute(
+                "page-size", |v| v.as_float(runtime), 0.1
+            )?.max(0.0).min(1.0),
+            thumb_color: attributes.attribute(
+                "thumb-color", |v| v.as_color(runtime), Color::new_u8(128, 128, 128, 255)
+            )?,
+            thumb_radius: attributes.attribute(
+                "thumb-radius", |v| v.as_float(runtime), 0.0
+            )?,
+            thumb_min_length: attributes.attribute(
+                "thumb-min-length", |v| v.as_float(runtime), 10.0
+            )?,
+            on_scrolled: attributes.attribute_optional(
+                "on-scrolled", |v| v.as_event_hook(runtime)
+            )?,
+        })
+    }
+}
+
+/// The direction a component is laid out along.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Orientation {
+    Horizontal,
+    Vertical,
+}
+
+impl Orientation {
+    pub fn from_value(value: &TemplateValue, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        match value.as_string(runtime)?.as_str() {
+            "horizontal" => Ok(Orientation::Horizontal),
+            "vertical" => Ok(Orientation::Vertical),
+            _ => Err("Value must be either \"horizontal\" or \"vertical\"".into())
+        }
+    }
+
+    /// Returns the component of a vector along this direction.
+    pub fn along(self, vector: Vector2<f32>) -> f32 {
+        match self {
+            Orientation::Horizontal => vector.x,
+            Orientation::Vertical => vector.y,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Point2, Vector2};
+
+    use class::{ScrollbarClass};
+    use input::{Input, Modifiers};
+    use template::{Template, Style};
+    use {Ui, Context};
+
+    #[test]
+    fn it_drags_and_pages_scrollbars() {
+        let mut context = Context::for_test();
+        context.classes.register::<ScrollbarClass>("scrollbar");
+        let template = Template::from_str(
+            "scrollbar { page-size: 0.25, on-scrolled: \"scrolled\" }\n"
+        ).unwrap();
+        let style = Style::from_str("").unwrap();
+        let (mut ui, tree) = Ui::new(
+            &template, None, style, Vector2::new(10.0, 100.0), &context,
+        ).unwrap();
+        let mut input = Input::new();
+
+        // The thumb is a quarter of the track, grabbing it and dragging it halfway down the rest
+        input.handle_drag_started(Point2::new(5.0, 10.0), Modifiers::none(), &mut ui);
+        input.handle_cursor_moved(Point2::new(5.0, 47.5), &mut ui);
+        input.handle_drag_ended(Point2::new(5.0, 47.5), Modifiers::none(), &mut ui);
+        assert_eq!(tree.event_sink().next_event().unwrap().value, Some(0.5));
+        assert_eq!(tree.event_sink().next_event(), None);
+
+        // Clicking the track below the thumb pages down, as far as the thumb covers
+        input.handle_drag_started(Point2::new(5.0, 90.0), Modifiers::none(), &mut ui);
+        input.handle_drag_ended(Point2::new(5.0, 90.0), Modifiers::none(), &mut ui);
+        let value = tree.event_sink().next_event().unwrap().value.unwrap();
+        assert!((value - 0.5 - 1.0 / 3.0).abs() < 0.0001);
+    }
+}
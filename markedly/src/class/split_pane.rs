@@ -0,0 +1,298 @@
+use nalgebra::{Point2, Vector2};
+
+use class::{ComponentClass, ComponentClassFactory, BackgroundAttributes, Orientation};
+use input::{Modifiers, PressPosition};
+use render::{self, Renderer, Rect};
+use scripting::{ScriptRuntime};
+use template::{Attributes, Color, EventHook};
+use {EventSink, Error, ComponentAttributes, ComponentId, ComponentState};
+
+/// A split pane component class, divides itself between its first two children with a divider
+/// between them that can be dragged to change how much of the pane each of them gets. Any
+/// further children are laid out over the whole pane as usual.
+pub struct SplitPaneClass {
+    background: BackgroundAttributes,
+    attributes: SplitPaneAttributes,
+    /// How much of the space besides the divider goes to the first child, from 0.0 to 1.0.
+    split: f32,
+    /// How far along the divider it was grabbed, while it's being dragged.
+    grab_offset: Option<f32>,
+}
+
+impl ComponentClassFactory for SplitPaneClass {
+    fn new(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        let split_pane_attributes = SplitPaneAttributes::load(attributes, runtime)?;
+        Ok(SplitPaneClass {
+            background: BackgroundAttributes::load(attributes, runtime)?,
+            split: split_pane_attributes.clamp(split_pane_attributes.split.unwrap_or(0.5)),
+            attributes: split_pane_attributes,
+            grab_offset: None,
+        })
+    }
+}
+
+impl ComponentClass for SplitPaneClass {
+    fn update_attributes(
+        &mut self, attributes: &Attributes, runtime: &ScriptRuntime,
+    ) -> Result<(), Error> {
+        let previous_split = self.attributes.split;
+        self.background = BackgroundAttributes::load(attributes, runtime)?;
+        self.attributes = SplitPaneAttributes::load(attributes, runtime)?;
+
+        // A split set from the model takes over from the one the divider was dragged to, but
+        // only when it changed, so resolving the pane again for another reason keeps the drag
+        if self.attributes.split != previous_split {
+            if let Some(split) = self.attributes.split {
+                self.split = split;
+            }
+        }
+        self.split = self.attributes.clamp(self.split);
+
+        Ok(())
+    }
+
+    fn render(
+        &self, id: ComponentId,
+        attributes: &ComponentAttributes, computed_size: Vector2<f32>, renderer: &mut Renderer,
+    ) -> Result<(), Error> {
+        self.background.render(id, attributes, computed_size, renderer, false)?;
+        render::rectangle(
+            renderer, id, self.divider(computed_size), 0.0, self.attributes.divider_color,
+        )
+    }
+
+    fn child_area(&self, index: usize, _count: usize, size: Vector2<f32>) -> Option<Rect> {
+        let divider = self.divider(size);
+        match (index, self.attributes.orientation) {
+            (0, Orientation::Horizontal) =>
+                Some(Rect::new(Point2::new(0.0, 0.0), Vector2::new(divider.position.x, size.y))),
+            (0, Orientation::Vertical) =>
+                Some(Rect::new(Point2::new(0.0, 0.0), Vector2::new(size.x, divider.position.y))),
+            (1, Orientation::Horizontal) => {
+                let start = divider.position.x + divider.size.x;
+                Some(Rect::new(Point2::new(start, 0.0), Vector2::new(size.x - start, size.y)))
+            },
+            (1, Orientation::Vertical) => {
+                let start = divider.position.y + divider.size.y;
+                Some(Rect::new(Point2::new(0.0, start), Vector2::new(size.x, size.y - start)))
+            },
+            _ => None,
+        }
+    }
+
+    fn is_capturing_cursor(&self) -> bool {
+        true
+    }
+
+    fn press_start_event(
+        &mut self, _event_sink: &mut EventSink, _modifiers: Modifiers,
+        position: PressPosition, computed_size: Vector2<f32>,
+    ) -> bool {
+        // Only the divider can be dragged, the rest of the pane belongs to the children
+        let divider = self.divider(computed_size);
+        let orientation = self.attributes.orientation;
+        let start = orientation.along(divider.position.coords);
+        let pressed = orientation.along(position.local.coords);
+
+        if pressed >= start && pressed <= start + orientation.along(divider.size) {
+            self.grab_offset = Some(pressed - start);
+        }
+
+        false
+    }
+
+    fn press_moved_event(
+        &mut self, event_sink: &mut EventSink,
+        position: PressPosition, computed_size: Vector2<f32>,
+    ) -> bool {
+        let grab_offset = match self.grab_offset {
+            Some(grab_offset) => grab_offset,
+            None => return false,
+        };
+
+        let range = self.range(computed_size);
+        if range <= 0.0 {
+            return false
+        }
+
+        let pressed = self.attributes.orientation.along(position.local.coords);
+        self.split_to((pressed - grab_offset) / range, event_sink)
+    }
+
+    fn press_end_event(&mut self, _event_sink: &mut EventSink) -> bool {
+        self.grab_offset = None;
+        false
+    }
+
+    fn save_state(&self, state: &mut ComponentState) {
+        state.set("split", self.split);
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> bool {
+        if let Some(split) = state.get("split").and_then(|v| v.as_float()) {
+            self.split = self.attributes.clamp(split);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl SplitPaneClass {
+    /// Returns the length of the space besides the divider, which is split between the
+    /// children.
+    fn range(&self, computed_size: Vector2<f32>) -> f32 {
+        let length = self.attributes.orientation.along(computed_size);
+        (length - self.attributes.divider_size).max(0.0)
+    }
+
+    /// Returns the bounds of the divider.
+    fn divider(&self, computed_size: Vector2<f32>) -> Rect {
+        let start = self.range(computed_size) * self.split;
+        let divider_size = self.attributes.divider_size;
+        match self.attributes.orientation {
+            Orientation::Horizontal => Rect::new(
+                Point2::new(start, 0.0), Vector2::new(divider_size, computed_size.y),
+            ),
+            Orientation::Vertical => Rect::new(
+                Point2::new(0.0, start), Vector2::new(computed_size.x, divider_size),
+            ),
+        }
+    }
+
+    /// Moves the divider, raising the split changed event if it changed.
+    /// Returns if the component should be marked for render update.
+    fn split_to(&mut self, split: f32, event_sink: &mut EventSink) -> bool {
+        let split = self.attributes.clamp(split);
+        if split == self.split {
+            return false
+        }
+
+        self.split = split;
+        if let Some(ref event) = self.attributes.on_split_changed {
+            event_sink.raise_value(event, split);
+        }
+
+        true
+    }
+}
+
+struct SplitPaneAttributes {
+    split: Option<f32>,
+    orientation: Orientation,
+    min_split: f32,
+    max_split: f32,
+    divider_size: f32,
+    divider_color: Color,
+    on_split_changed: Option<EventHook>,
+}
+
+impl SplitPaneAttributes {
+    pub fn load(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        Ok(SplitPaneAttributes {
+            split: attributes.attribute_optional(
+                "split", |v| v.as_float(runtime)
+            )?,
+            orientation: attributes.attribute(
+                "orientation", |v| Orientation::from_value(v, runtime), Orientation::Horizontal,
+            )?,
+            min_split: attributes.attribute(
+                "min-split", |v| v.as_float(runtime), 0.0
+            )?.max(0.0).min(1.0),
+            max_split: attributes.attribute(
+                "max-split", |v| v.as_float(runtime), 1.0
+            )?.max(0.0).min(1.0),
+            divider_size: attributes.attribute(
+                "divider-size", |v| v.as_float(runtime), 4.0
+            )?.max(0.0),
+            divider_color: attributes.attribute(
+                "divider-color", |v| v.as_color(runtime), Color::new_u8(128, 128, 128, 255)
+            )?,
+            on_split_changed: attributes.attribute_optional(
+                "on-split-changed", |v| v.as_event_hook(runtime)
+            )?,
+        })
+    }
+
+    /// Keeps a split within the minimum and maximum.
+    fn clamp(&self, split: f32) -> f32 {
+        split.max(self.min_split).min(self.max_split)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Point2, Vector2};
+
+    use class::{SplitPaneClass};
+    use input::{Input, Modifiers};
+    use template::{Template, Style, TemplateValue};
+    use {Ui, Context};
+
+    #[test]
+    fn it_drags_split_pane_dividers() {
+        let mut context = Context::for_test();
+        context.classes.register::<SplitPaneClass>("split-pane");
+        let template = Template::from_str(&[
+            "split-pane { divider-size: 4.0, on-split-changed: \"split\" }",
+            "    container",
+            "    container { size: (100%, 20), position: (0, 0), docking: (\"start\", \"end\") }",
+            "",
+        ].join("\n")).unwrap();
+        let style = Style::from_str("").unwrap();
+        let (mut ui, tree) = Ui::new(
+            &template, None, style, Vector2::new(104.0, 50.0), &context,
+        ).unwrap();
+        let mut input = Input::new();
+
+        // The children are laid out in the areas on either side of the divider
+        let panes = ui.get(tree.root()).unwrap().children().clone();
+        let first = ui.explain_layout(panes[0]).unwrap();
+        assert_eq!(first.component().size, Vector2::new(50.0, 50.0));
+        let second = ui.explain_layout(panes[1]).unwrap();
+        assert_eq!(second.component().position, Point2::new(54.0, 30.0));
+        assert_eq!(second.component().size, Vector2::new(50.0, 20.0));
+
+        // Dragging the divider moves the split, but pressing elsewhere doesn't
+        input.handle_drag_started(Point2::new(52.0, 10.0), Modifiers::none(), &mut ui);
+        input.handle_cursor_moved(Point2::new(77.0, 10.0), &mut ui);
+        input.handle_drag_ended(Point2::new(77.0, 10.0), Modifiers::none(), &mut ui);
+        assert_eq!(tree.event_sink().next_event().unwrap().value, Some(0.75));
+        let second = ui.explain_layout(panes[1]).unwrap();
+        assert_eq!(second.component().position, Point2::new(79.0, 30.0));
+        assert_eq!(second.component().size, Vector2::new(25.0, 20.0));
+
+        input.handle_drag_started(Point2::new(20.0, 10.0), Modifiers::none(), &mut ui);
+        input.handle_cursor_moved(Point2::new(30.0, 10.0), &mut ui);
+        input.handle_drag_ended(Point2::new(30.0, 10.0), Modifiers::none(), &mut ui);
+        assert_eq!(tree.event_sink().next_event(), None);
+    }
+
+    #[test]
+    fn it_keeps_dragged_splits_until_the_split_attribute_changes() {
+        let mut context = Context::for_test();
+        context.classes.register::<SplitPaneClass>("split-pane");
+        let template = Template::from_str(
+            "split-pane { divider-size: 4.0, split: 0.5 }\n    container\n    container\n"
+        ).unwrap();
+        let style = Style::from_str("").unwrap();
+        let (mut ui, tree) = Ui::new(
+            &template, None, style, Vector2::new(104.0, 50.0), &context,
+        ).unwrap();
+        let mut input = Input::new();
+        let first = ui.get(tree.root()).unwrap().children()[0];
+        let width = |ui: &Ui| ui.explain_layout(first).unwrap().component().size.x;
+
+        input.handle_drag_started(Point2::new(52.0, 10.0), Modifiers::none(), &mut ui);
+        input.handle_cursor_moved(Point2::new(77.0, 10.0), &mut ui);
+        input.handle_drag_ended(Point2::new(77.0, 10.0), Modifiers::none(), &mut ui);
+        assert_eq!(width(&ui), 75.0);
+
+        // Resolving the pane again with the same split doesn't undo the drag
+        ui.set_style(Style::from_str("").unwrap(), &context).unwrap();
+        assert_eq!(width(&ui), 75.0);
+
+        ui.set_attribute(tree.root(), "split", TemplateValue::Float(0.25), &context).unwrap();
+        assert_eq!(width(&ui), 25.0);
+    }
+}
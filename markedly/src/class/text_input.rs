@@ -0,0 +1,562 @@
+use nalgebra::{Point2, Vector2};
+
+use class::{ComponentClass, ComponentClassFactory, BackgroundAttributes, TextCaret};
+use input::{Modifiers, PressPosition, Clipboard};
+use render::{self, Renderer, RichText, TextSpan, TextStyle, TextEffects};
+use scripting::{ScriptRuntime};
+use template::{Attributes, Color, EventHook, TemplateValue};
+use {EventSink, Error, ComponentAttributes, ComponentId, ComponentState};
+
+/// A text input component class, a single line of text that can be typed into while it has
+/// focus. Text can be selected by dragging over it or by holding shift, and cut, copied, and
+/// pasted through the clipboard given to `Input`.
+///
+/// Inputs can be limited to numbers or alphanumeric characters and to a maximum length, typed
+/// text that doesn't fit is rejected.
+pub struct TextInputClass {
+    background: BackgroundAttributes,
+    attributes: TextInputAttributes,
+    text: String,
+    caret: TextCaret,
+    focused: bool,
+    /// If the cursor is pressed down and dragging out a selection.
+    selecting: bool,
+}
+
+impl ComponentClassFactory for TextInputClass {
+    fn new(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        let text_input_attributes = TextInputAttributes::load(attributes, runtime)?;
+        let text = text_input_attributes.text.clone().unwrap_or_else(String::new);
+        Ok(TextInputClass {
+            background: BackgroundAttributes::load(attributes, runtime)?,
+            attributes: text_input_attributes,
+            caret: TextCaret::new(text.chars().count()),
+            text,
+            focused: false,
+            selecting: false,
+        })
+    }
+}
+
+impl ComponentClass for TextInputClass {
+    fn update_attributes(
+        &mut self, attributes: &Attributes, runtime: &ScriptRuntime,
+    ) -> Result<(), Error> {
+        self.background = BackgroundAttributes::load(attributes, runtime)?;
+        let text_input_attributes = TextInputAttributes::load(attributes, runtime)?;
+
+        // Text set from the model takes over from what was typed, but only if it changed, so
+        // resolving the attributes again for other reasons doesn't undo typing
+        if text_input_attributes.text != self.attributes.text {
+            if let Some(ref text) = text_input_attributes.text {
+                self.text = text.clone();
+                self.caret.clamp(self.text.chars().count());
+                self.caret.clear_selection();
+            }
+        }
+        self.attributes = text_input_attributes;
+
+        Ok(())
+    }
+
+    fn render(
+        &self, id: ComponentId,
+        attributes: &ComponentAttributes, computed_size: Vector2<f32>, renderer: &mut Renderer,
+    ) -> Result<(), Error> {
+        self.background.render(id, attributes, computed_size, renderer, false)?;
+
+        let font = self.attributes.text_font.as_ref();
+        let size = self.attributes.text_size;
+        let padding = self.attributes.text_padding;
+
+        let text = self.display_text();
+
+        // Measuring the text also lets presses be mapped to characters later
+        let letter_spacing = self.attributes.letter_spacing;
+        let text_size = self.caret.measure(renderer, &text, font, size, letter_spacing)?;
+        let line_height = text_size.y * self.attributes.line_height.unwrap_or(1.0);
+        let origin = Point2::new(padding, (computed_size.y - line_height) * 0.5);
+
+        self.caret.render_selection(
+            id, renderer, origin, line_height, self.attributes.selection_color,
+        )?;
+
+        if !text.is_empty() {
+            // Typed text is never markup, so it's rendered as a single span
+            let text = RichText {
+                spans: vec![TextSpan {
+                    text, bold: false, italic: false, color: None, font: None, size: None,
+                }],
+            };
+            let style = TextStyle {
+                font,
+                font_bold: None,
+                font_italic: None,
+                fallback_fonts: &[],
+                size,
+                color: self.attributes.text_color,
+                effects: self.attributes.text_effects,
+                letter_spacing,
+                line_height: self.attributes.line_height,
+            };
+
+            // Text is centered in the area it's given, so it's given exactly its own width
+            render::rich_text(
+                renderer, id, &text, &style,
+                Point2::new(padding, 0.0), Vector2::new(text_size.x, computed_size.y),
+            )?;
+        }
+
+        if self.focused {
+            let color = self.attributes.caret_color.unwrap_or(self.attributes.text_color);
+            self.caret.render_caret(id, renderer, origin, line_height, color)?;
+        }
+
+        Ok(())
+    }
+
+    fn is_capturing_cursor(&self) -> bool {
+        true
+    }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn focus_changed_event(&mut self, _event_sink: &mut EventSink, focused: bool) -> bool {
+        self.focused = focused;
+        if focused {
+            self.caret.reset_blink();
+        } else {
+            self.caret.clear_selection();
+            self.selecting = false;
+        }
+        true
+    }
+
+    fn press_start_event(
+        &mut self, _event_sink: &mut EventSink, modifiers: Modifiers,
+        position: PressPosition, _computed_size: Vector2<f32>,
+    ) -> bool {
+        let index = self.index_at(position.local.x);
+
+        // Shift clicking extends the selection to where was clicked
+        if modifiers.shift {
+            self.caret.move_to(index, true);
+        } else {
+            self.caret.select(index, index);
+        }
+        self.selecting = true;
+
+        true
+    }
+
+    fn press_moved_event(
+        &mut self, _event_sink: &mut EventSink,
+        position: PressPosition, _computed_size: Vector2<f32>,
+    ) -> bool {
+        if !self.selecting {
+            return false
+        }
+
+        let index = self.index_at(position.local.x);
+        if index == self.caret.position() {
+            return false
+        }
+
+        self.caret.move_to(index, true);
+        true
+    }
+
+    fn press_end_event(&mut self, _event_sink: &mut EventSink) -> bool {
+        self.selecting = false;
+        false
+    }
+
+    fn key_down_event(
+        &mut self, event_sink: &mut EventSink, key: &str, modifiers: Modifiers,
+        clipboard: &mut Clipboard,
+    ) -> bool {
+        let length = self.text.chars().count();
+        let caret = self.caret.position();
+
+        match key {
+            "left" => {
+                let target = match self.caret.selection() {
+                    Some((start, _)) if !modifiers.shift => start,
+                    _ => caret.saturating_sub(1),
+                };
+                self.caret.move_to(target, modifiers.shift);
+                true
+            },
+            "right" => {
+                let target = match self.caret.selection() {
+                    Some((_, end)) if !modifiers.shift => end,
+                    _ => (caret + 1).min(length),
+                };
+                self.caret.move_to(target, modifiers.shift);
+                true
+            },
+            "home" => {
+                self.caret.move_to(0, modifiers.shift);
+                true
+            },
+            "end" => {
+                self.caret.move_to(length, modifiers.shift);
+                true
+            },
+            "backspace" => {
+                let (start, end) = match self.caret.selection() {
+                    Some(selection) => selection,
+                    None if caret > 0 => (caret - 1, caret),
+                    None => return false,
+                };
+                self.replace(start, end, "", event_sink);
+                true
+            },
+            "delete" => {
+                let (start, end) = match self.caret.selection() {
+                    Some(selection) => selection,
+                    None if caret < length => (caret, caret + 1),
+                    None => return false,
+                };
+                self.replace(start, end, "", event_sink);
+                true
+            },
+            "a" if modifiers.ctrl => {
+                self.caret.select(0, length);
+                true
+            },
+            // Passwords can't be copied out of the input
+            "c" if modifiers.ctrl && !self.attributes.password => {
+                if let Some(text) = self.selected_text() {
+                    clipboard.set_text(&text);
+                }
+                false
+            },
+            "x" if modifiers.ctrl && !self.attributes.password => {
+                if let Some((start, end)) = self.caret.selection() {
+                    clipboard.set_text(&self.selected_text().unwrap());
+                    self.replace(start, end, "", event_sink);
+                    true
+                } else {
+                    false
+                }
+            },
+            "v" if modifiers.ctrl => {
+                // Pasted text stays on a single line
+                let text = clipboard.text().map(|text| single_line(&text)).unwrap_or_default();
+                if text.is_empty() {
+                    return false
+                }
+                self.insert(&text, event_sink)
+            },
+            "return" | "keypad enter" => {
+                if let Some(ref event) = self.attributes.on_submitted {
+                    event_sink.raise_text(event, &self.text);
+                }
+                false
+            },
+            _ => false,
+        }
+    }
+
+    fn text_event(&mut self, event_sink: &mut EventSink, text: &str) -> bool {
+        let text = single_line(text);
+        if text.is_empty() {
+            return false
+        }
+
+        self.insert(&text, event_sink)
+    }
+
+    fn update(&mut self, delta: f32, _event_sink: &mut EventSink) -> bool {
+        self.focused && self.caret.update(delta, self.attributes.caret_blink)
+    }
+
+    fn save_state(&self, state: &mut ComponentState) {
+        state.set("text", self.text.clone());
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> bool {
+        if let Some(text) = state.get("text").and_then(|v| v.as_string()) {
+            self.text = text.clone();
+            self.caret = TextCaret::new(self.text.chars().count());
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl TextInputClass {
+    fn selected_text(&self) -> Option<String> {
+        self.caret.selection().map(|(start, end)| {
+            self.text.chars().skip(start).take(end - start).collect()
+        })
+    }
+
+    /// Inserts text at the caret, replacing the selection if there is one.
+    /// Rejects the text and raises the validation failed event instead if the input wouldn't
+    /// be valid with it.
+    /// Returns if the component should be marked for render update.
+    fn insert(&mut self, text: &str, event_sink: &mut EventSink) -> bool {
+        let caret = self.caret.position();
+        let (start, end) = self.caret.selection().unwrap_or((caret, caret));
+
+        let mut new_text = self.text.clone();
+        new_text.replace_range(byte_index(&self.text, start)..byte_index(&self.text, end), text);
+        let too_long = self.attributes.max_length
+            .map(|max_length| new_text.chars().count() > max_length)
+            .unwrap_or(false);
+        if too_long || !self.attributes.input_type.is_valid(&new_text) {
+            if let Some(ref event) = self.attributes.on_validation_failed {
+                event_sink.raise_text(event, text);
+            }
+            return false
+        }
+
+        self.replace(start, end, text, event_sink);
+        true
+    }
+
+    /// Returns the text as it's shown, which hides passwords.
+    fn display_text(&self) -> String {
+        if self.attributes.password {
+            self.text.chars().map(|_| '\u{2022}').collect()
+        } else {
+            self.text.clone()
+        }
+    }
+
+    /// Replaces the characters between start and end with text, placing the caret after it, and
+    /// raises the changed event.
+    fn replace(&mut self, start: usize, end: usize, text: &str, event_sink: &mut EventSink) {
+        let start_byte = byte_index(&self.text, start);
+        let end_byte = byte_index(&self.text, end);
+        self.text.replace_range(start_byte..end_byte, text);
+        self.caret.move_to(start + text.chars().count(), false);
+
+        if let Some(ref event) = self.attributes.on_changed {
+            event_sink.raise_text(event, &self.text);
+        }
+    }
+
+    /// Finds the character boundary closest to a position along the input.
+    fn index_at(&self, x: f32) -> usize {
+        self.caret.position_at(x - self.attributes.text_padding, self.text.chars().count())
+    }
+}
+
+fn byte_index(text: &str, index: usize) -> usize {
+    text.char_indices().nth(index).map(|(i, _)| i).unwrap_or(text.len())
+}
+
+fn single_line(text: &str) -> String {
+    text.chars().filter(|c| !c.is_control()).collect()
+}
+
+struct TextInputAttributes {
+    text: Option<String>,
+    text_color: Color,
+    text_font: Option<String>,
+    text_size: Option<i32>,
+    text_effects: TextEffects,
+    letter_spacing: f32,
+    line_height: Option<f32>,
+    text_padding: f32,
+    selection_color: Color,
+    caret_color: Option<Color>,
+    caret_blink: f32,
+    input_type: InputType,
+    max_length: Option<usize>,
+    password: bool,
+    on_changed: Option<EventHook>,
+    on_submitted: Option<EventHook>,
+    on_validation_failed: Option<EventHook>,
+}
+
+impl TextInputAttributes {
+    pub fn load(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        Ok(TextInputAttributes {
+            text: attributes.attribute_optional("text", |v| v.as_string(runtime))?,
+            text_color: attributes.attribute(
+                "text-color", |v| v.as_color(runtime), Color::new_u8(0, 0, 0, 255)
+            )?,
+            // Text inputs only render in a single font, fallback fonts are ignored
+            text_font: attributes.attribute_optional(
+                "text-font", |v| v.as_strings(runtime)
+            )?.and_then(|fonts| fonts.into_iter().next()),
+            text_size: attributes.attribute_optional("text-size", |v| v.as_integer(runtime))?,
+            text_effects: TextEffects::load(attributes, runtime)?,
+            letter_spacing: attributes.attribute(
+                "letter-spacing", |v| v.as_float(runtime), 0.0
+            )?,
+            line_height: attributes.attribute_optional("line-height", |v| v.as_float(runtime))?,
+            text_padding: attributes.attribute(
+                "text-padding", |v| v.as_float(runtime), 4.0
+            )?,
+            selection_color: attributes.attribute(
+                "selection-color", |v| v.as_color(runtime), Color::new_u8(51, 153, 255, 128)
+            )?,
+            caret_color: attributes.attribute_optional(
+                "caret-color", |v| v.as_color(runtime)
+            )?,
+            caret_blink: attributes.attribute(
+                "caret-blink", |v| v.as_float(runtime), 0.5
+            )?,
+            input_type: attributes.attribute(
+                "input-type", |v| InputType::from_value(v, runtime), InputType::Text,
+            )?,
+            max_length: attributes.attribute_optional(
+                "max-length", |v| v.as_integer(runtime)
+            )?.map(|v| v.max(0) as usize),
+            password: attributes.attribute("password", |v| v.as_bool(runtime), false)?,
+            on_changed: attributes.attribute_optional("on-changed", |v| v.as_event_hook(runtime))?,
+            on_submitted: attributes.attribute_optional(
+                "on-submitted", |v| v.as_event_hook(runtime)
+            )?,
+            on_validation_failed: attributes.attribute_optional(
+                "on-validation-failed", |v| v.as_event_hook(runtime)
+            )?,
+        })
+    }
+}
+
+/// The characters a text input accepts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputType {
+    Text,
+    /// Whole numbers, optionally negative.
+    Integer,
+    /// Numbers with an optional fraction, optionally negative.
+    Float,
+    /// Only letters and digits.
+    Alphanumeric,
+}
+
+impl InputType {
+    pub fn from_value(value: &TemplateValue, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        match value.as_string(runtime)?.as_str() {
+            "text" => Ok(InputType::Text),
+            "integer" => Ok(InputType::Integer),
+            "float" => Ok(InputType::Float),
+            "alphanumeric" => Ok(InputType::Alphanumeric),
+            _ => Err(
+                "Value must be either \"text\", \"integer\", \"float\", or \"alphanumeric\""
+                    .into()
+            )
+        }
+    }
+
+    /// Returns if text is valid for this input type. Numbers that are still being typed, such as
+    /// just a minus sign, are valid.
+    pub fn is_valid(self, text: &str) -> bool {
+        let digits = if text.starts_with('-') { &text[1..] } else { text };
+        match self {
+            InputType::Text => true,
+            InputType::Integer => digits.chars().all(|c| c.is_ascii_digit()),
+            InputType::Float =>
+                digits.chars().all(|c| c.is_ascii_digit() || c == '.') &&
+                    digits.matches('.').count() <= 1,
+            InputType::Alphanumeric => text.chars().all(|c| c.is_alphanumeric()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Point2};
+
+    use class::{TextInputClass};
+    use input::{Input, Modifiers, RawEvent};
+    use render::{self, RecordingRenderer, RenderCommand};
+    use scripting::{ScriptValue};
+    use {Ui, Context};
+
+    #[test]
+    fn it_selects_copies_and_pastes_in_text_inputs() {
+        let mut context = Context::for_test();
+        context.classes.register::<TextInputClass>("text-input");
+        let (mut ui, tree) = Ui::for_test("text-input { on-changed: \"changed\" }\n", "", &context);
+        let mut input = Input::new();
+        let mut renderer = RecordingRenderer::new();
+        let shift = Modifiers { shift: true, .. Modifiers::none() };
+        let ctrl = Modifiers { ctrl: true, .. Modifiers::none() };
+        fn key(input: &mut Input, ui: &mut Ui, key: &str, modifiers: Modifiers) {
+            input.handle_event(RawEvent::Key {
+                key: key.into(), pressed: true, repeat: false, modifiers,
+            }, ui);
+        }
+        let last_text = || {
+            let mut text = None;
+            while let Some(event) = tree.event_sink().next_event() {
+                assert_eq!(event.name, "changed");
+                text = event.payload;
+            }
+            text
+        };
+
+        // Keys only go to the input once it has focus
+        assert!(!input.handle_event(RawEvent::Text { text: "a".into() }, &mut ui));
+        input.handle_drag_started(Point2::new(5.0, 5.0), Modifiers::none(), &mut ui);
+        input.handle_drag_ended(Point2::new(5.0, 5.0), Modifiers::none(), &mut ui);
+        assert_eq!(ui.focused(), Some(tree.root()));
+        assert!(input.handle_event(RawEvent::Text { text: "hello".into() }, &mut ui));
+        assert_eq!(last_text(), Some(ScriptValue::String("hello".into())));
+
+        // Selecting with shift, then pasting it at the end
+        key(&mut input, &mut ui, "left", shift);
+        key(&mut input, &mut ui, "left", shift);
+        key(&mut input, &mut ui, "c", ctrl);
+        key(&mut input, &mut ui, "end", Modifiers::none());
+        key(&mut input, &mut ui, "v", ctrl);
+        assert_eq!(last_text(), Some(ScriptValue::String("hellolo".into())));
+
+        // Selecting by dragging over the rendered characters, which are 7 wide after the padding
+        render::render(&mut renderer, &mut ui).unwrap();
+        input.handle_drag_started(Point2::new(11.0, 5.0), Modifiers::none(), &mut ui);
+        input.handle_cursor_moved(Point2::new(25.0, 5.0), &mut ui);
+        input.handle_drag_ended(Point2::new(25.0, 5.0), Modifiers::none(), &mut ui);
+        key(&mut input, &mut ui, "x", ctrl);
+        assert_eq!(last_text(), Some(ScriptValue::String("hlolo".into())));
+        assert_eq!(input.clipboard().text(), Some("el".into()));
+        key(&mut input, &mut ui, "v", ctrl);
+        assert_eq!(last_text(), Some(ScriptValue::String("hellolo".into())));
+    }
+
+    #[test]
+    fn it_rejects_invalid_text_input() {
+        let mut context = Context::for_test();
+        context.classes.register::<TextInputClass>("text-input");
+        let (mut ui, tree) = Ui::for_test(
+            "text-input { input-type: \"integer\", max-length: 4, password: true, \
+            on-changed: \"changed\", on-validation-failed: \"invalid\" }\n",
+            "", &context,
+        );
+        let mut input = Input::new();
+        input.handle_drag_started(Point2::new(5.0, 5.0), Modifiers::none(), &mut ui);
+        input.handle_drag_ended(Point2::new(5.0, 5.0), Modifiers::none(), &mut ui);
+
+        for text in &["12", "a", "345"] {
+            input.handle_event(RawEvent::Text { text: text.to_string() }, &mut ui);
+        }
+        let mut events = Vec::new();
+        while let Some(event) = tree.event_sink().next_event() {
+            events.push((event.name, event.payload));
+        }
+        assert_eq!(events, vec![
+            ("changed".to_string(), Some(ScriptValue::String("12".into()))),
+            ("invalid".to_string(), Some(ScriptValue::String("a".into()))),
+            ("invalid".to_string(), Some(ScriptValue::String("345".into()))),
+        ]);
+
+        // Passwords are rendered hidden
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+        assert!(renderer.commands(tree.root()).unwrap().iter().any(|c| match *c {
+            RenderCommand::Text { ref text, .. } => text == "\u{2022}\u{2022}",
+            _ => false,
+        }));
+    }
+}
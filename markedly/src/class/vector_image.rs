@@ -0,0 +1,108 @@
+use nalgebra::{Point2, Vector2};
+
+use class::{ComponentClass, ComponentClassFactory};
+use render::path::{Path};
+use render::{Renderer, Rect};
+use scripting::{ScriptRuntime};
+use template::{Attributes, Color, TemplateValue};
+use {Error, ComponentAttributes, ComponentId};
+
+/// A vector image component class, displays a shape from SVG path data. The shape is tessellated
+/// at the size the component is rendered at, so it stays crisp at any size or UI scale.
+pub struct VectorImageClass {
+    path: Option<String>,
+    view_box: Option<Rect>,
+    fill_color: Option<Color>,
+    stroke_color: Option<Color>,
+    stroke_width: f32,
+}
+
+impl ComponentClassFactory for VectorImageClass {
+    fn new(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        let mut class = VectorImageClass {
+            path: None,
+            view_box: None,
+            fill_color: None,
+            stroke_color: None,
+            stroke_width: 1.0,
+        };
+        class.update_attributes(attributes, runtime)?;
+        Ok(class)
+    }
+}
+
+impl ComponentClass for VectorImageClass {
+    fn update_attributes(
+        &mut self, attributes: &Attributes, runtime: &ScriptRuntime,
+    ) -> Result<(), Error> {
+        // The path is parsed here as well, so mistakes in it are reported with the attribute
+        self.path = attributes.attribute_optional("path", |v| {
+            let data = v.as_string(runtime)?;
+            let unit = Rect::new(Point2::new(0.0, 0.0), Vector2::new(1.0, 1.0));
+            Path::from_svg(&data, unit, unit)?;
+            Ok(data)
+        })?;
+        self.view_box = attributes.attribute_optional("view-box", |v| view_box(v, runtime))?;
+        self.fill_color = attributes.attribute_optional("fill-color", |v| v.as_color(runtime))?;
+        self.stroke_color = attributes.attribute_optional(
+            "stroke-color", |v| v.as_color(runtime)
+        )?;
+        self.stroke_width = attributes.attribute(
+            "stroke-width", |v| v.as_float(runtime), 1.0
+        )?;
+        Ok(())
+    }
+
+    fn render(
+        &self, id: ComponentId,
+        _attributes: &ComponentAttributes, computed_size: Vector2<f32>, renderer: &mut Renderer,
+    ) -> Result<(), Error> {
+        let data = match self.path {
+            Some(ref data) => data,
+            None => return Ok(()),
+        };
+
+        let view_box = self.view_box
+            .unwrap_or_else(|| Rect::new(Point2::new(0.0, 0.0), computed_size));
+        if view_box.size.x <= 0.0 || view_box.size.y <= 0.0 {
+            return Ok(())
+        }
+
+        // The view box is scaled to fit the component while keeping its aspect ratio, centered
+        let scale = (computed_size.x / view_box.size.x).min(computed_size.y / view_box.size.y);
+        let size = view_box.size * scale;
+        let area = Rect::new(Point2::from_coordinates((computed_size - size) * 0.5), size);
+        let path = Path::from_svg(data, view_box, area)?;
+
+        // Without any colors the shape is filled white, like an image without a tint
+        let fill_color = match (self.fill_color, self.stroke_color) {
+            (None, None) => Some(Color::new(1.0, 1.0, 1.0, 1.0)),
+            (fill_color, _) => fill_color,
+        };
+        if let Some(fill_color) = fill_color {
+            path.fill(renderer, id, fill_color)?;
+        }
+        if let Some(stroke_color) = self.stroke_color {
+            path.stroke(renderer, id, self.stroke_width * scale, stroke_color)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn view_box(value: &TemplateValue, runtime: &ScriptRuntime) -> Result<Rect, Error> {
+    let values = value.as_vec()?;
+    if values.len() != 4 {
+        return Err("Tuple is incorrect size".into())
+    }
+
+    let mut numbers = Vec::new();
+    for (i, value) in values.iter().enumerate() {
+        numbers.push(
+            value.as_float(runtime)
+                .map_err(|e| Error::new_value(&format!("Value {}", i + 1), e))?
+        );
+    }
+
+    Ok(Rect::new(Point2::new(numbers[0], numbers[1]), Vector2::new(numbers[2], numbers[3])))
+}
@@ -0,0 +1,69 @@
+use std::collections::{VecDeque};
+
+use shared::{Shared, lock};
+use template::{Template, TemplateValue};
+use {ComponentId, EventSink};
+
+/// A queue of changes to make to a UI, which can be filled while the UI is borrowed, such as from
+/// component classes and event handlers. The changes are made when `Ui::apply_commands` is
+/// called, typically once per frame.
+///
+/// Every handle to the same UI's queue shares it, so it can be cloned freely.
+#[derive(Clone)]
+pub struct UiCommands {
+    commands: Shared<VecDeque<UiCommand>>,
+}
+
+impl UiCommands {
+    pub(crate) fn new() -> Self {
+        UiCommands {
+            commands: Default::default(),
+        }
+    }
+
+    /// Queues a template to be inserted as a child of a component, at an index of the parent's
+    /// children or as the last child. The inserted components become part of the parent's tree,
+    /// using its model and raising events on its event sink.
+    pub fn insert_template(
+        &self, template: Template, parent: ComponentId, index: Option<usize>,
+    ) {
+        self.push(UiCommand::InsertTemplate { template, parent, index });
+    }
+
+    /// Queues a component and its children to be removed.
+    pub fn remove_component(&self, id: ComponentId) {
+        self.push(UiCommand::RemoveComponent { id });
+    }
+
+    /// Queues an attribute to be set on a component, see `Ui::set_attribute`.
+    pub fn set_attribute(&self, id: ComponentId, key: &str, value: TemplateValue) {
+        self.push(UiCommand::SetAttribute { id, key: key.into(), value });
+    }
+
+    /// Queues a component to be focused.
+    pub fn focus(&self, id: ComponentId) {
+        self.push(UiCommand::Focus { id });
+    }
+
+    /// Queues a command.
+    pub fn push(&self, command: UiCommand) {
+        lock(&self.commands).push_back(command);
+    }
+
+    /// Takes all queued commands, in the order they were queued.
+    pub(crate) fn take(&self) -> Vec<UiCommand> {
+        lock(&self.commands).drain(..).collect()
+    }
+}
+
+/// A change to make to a UI, queued in `UiCommands`.
+#[derive(Clone, Debug)]
+pub enum UiCommand {
+    InsertTemplate { template: Template, parent: ComponentId, index: Option<usize> },
+    RemoveComponent { id: ComponentId },
+    SetAttribute { id: ComponentId, key: String, value: TemplateValue },
+    Focus { id: ComponentId },
+    /// Runs a script statement from an event hook, with the model of the tree the event sink
+    /// belongs to.
+    RunScript { script: String, event_sink: EventSink },
+}
@@ -1,15 +1,25 @@
+use std::collections::{HashMap};
+use std::sync::{Arc};
+
 use nalgebra::{Point2, Vector2};
 
-use class::{ComponentClass};
-use render::{Renderer};
+use animation::{AttributeTransitions, Animation, AnimationState};
+use class::{ComponentClass, ComponentClasses};
+use input::{Modifiers, Shortcut, PressPosition, Clipboard};
+use render::{self, Renderer, Rect, RenderCommand, Effect, BlendMode};
 use scripting::{ScriptRuntime};
-use template::{ComponentTemplate, Style, TemplateValue, Attributes, Coordinates};
-use {ComponentId, Error, Context, EventSink};
+use template::{
+    ComponentTemplate, Style, TemplateValue, Attributes, Coordinates, Color, EventHook,
+    InheritedAttributes, resolve_value,
+};
+use {ComponentId, Error, Context, EventSink, ComponentState, Warning, Symbol};
 
 /// A component generated from a template, active in a UI.
 pub struct Component {
     class: Box<ComponentClass>,
-    style_class: Option<String>,
+    /// The style classes, starting with the one from the template, which pick the style entries
+    /// the attributes are resolved with.
+    style_classes: Vec<Symbol>,
 
     event_sink: EventSink,
     needs_rendering: bool,
@@ -17,25 +27,57 @@ pub struct Component {
     children: Vec<ComponentId>,
     attributes: ComponentAttributes,
 
-    template: ComponentTemplate,
+    template: Arc<ComponentTemplate>,
+    /// Attributes set at runtime, which take priority over the template and style.
+    overrides: HashMap<Symbol, TemplateValue>,
+    /// The attributes inherited from the parent, kept so the attributes can be resolved again.
+    inherited: InheritedAttributes,
+    /// The last resolved attributes, kept so transitioning values can be applied over them.
+    resolved: Attributes,
+    transitions: AttributeTransitions,
+    animation: Option<AnimationState>,
+
+    /// The size and commands last recorded for the retained render mode.
+    retained: Option<(Vector2<f32>, Vec<RenderCommand>)>,
 }
 
 impl Component {
     pub(crate) fn from_template(
-        template: &ComponentTemplate,
+        template: &Arc<ComponentTemplate>,
         event_sink: EventSink,
         style: &Style,
+        inherited: InheritedAttributes,
         context: &Context,
     ) -> Result<Self, Error> {
         let runtime = &context.runtime;
-        let attributes = Attributes::resolve(template, style, context)?;
+        let style_classes: Vec<Symbol> = template.style_class.iter().cloned().collect();
+        let mut attributes = Attributes::resolve(
+            template, &style_classes, style, &inherited, context,
+        )?;
+        let mut transitions = AttributeTransitions::new();
+        transitions.update(&mut attributes, runtime)?;
+        let animation = load_animation(None, &attributes, style, runtime)?;
 
         let class = context.classes.create(template, &attributes, runtime)?;
         let component_attributes = ComponentAttributes::load(&attributes, runtime)?;
 
-        Ok(Component {
+        // Anything that wasn't read by now isn't used by the component or its class, which is
+        // most likely a typo, unless it's there to be inherited
+        for key in attributes.unused_keys() {
+            if context.classes.is_inherited(&key) {
+                continue
+            }
+
+            context.warnings.push(Warning::UnknownAttribute {
+                component: template.class.to_string(),
+                source: attributes.source(&key).cloned(),
+                key: key.to_string(),
+            });
+        }
+
+        let mut component = Component {
             class,
-            style_class: template.style_class.clone(),
+            style_classes,
 
             event_sink,
             needs_rendering: true,
@@ -43,17 +85,101 @@ impl Component {
             children: Vec::new(),
             attributes: component_attributes,
 
-            // This seems very expensive to store, we should look at alternative solutions
+            // Templates are shared, so this only keeps the template alive for when the
+            // attributes need to be resolved again
             template: template.clone(),
-        })
+            overrides: HashMap::new(),
+            inherited,
+            resolved: attributes,
+            transitions,
+            animation,
+
+            retained: None,
+        };
+
+        // The animation's starting values need to be applied before the first render
+        if component.animation.is_some() {
+            component.load_attributes(runtime)?;
+        }
+
+        Ok(component)
+    }
+
+    /// Creates a new component from the same template, with the same style classes and
+    /// attributes set at runtime, but without any children or runtime state of its class.
+    pub(crate) fn duplicate(
+        &self, event_sink: EventSink, style: &Style, inherited: InheritedAttributes,
+        context: &Context,
+    ) -> Result<Self, Error> {
+        let mut component = Component::from_template(
+            &self.template, event_sink, style, inherited, context,
+        )?;
+
+        if component.style_classes != self.style_classes || !self.overrides.is_empty() {
+            component.style_classes = self.style_classes.clone();
+            component.overrides = self.overrides.clone();
+            component.update_attributes(style, context)?;
+        }
+
+        Ok(component)
     }
 
     pub fn class(&self) -> &ComponentClass {
         self.class.as_ref()
     }
 
-    pub fn style_class(&self) -> Option<&String> {
-        self.style_class.as_ref()
+    pub fn style_classes(&self) -> &[Symbol] {
+        &self.style_classes
+    }
+
+    pub fn has_style_class(&self, style_class: &str) -> bool {
+        self.style_classes.iter().any(|class| class == style_class)
+    }
+
+    /// Changes the style classes, which are applied the next time the attributes are updated.
+    pub(crate) fn set_style_classes(&mut self, style_classes: Vec<Symbol>) {
+        self.style_classes = style_classes;
+    }
+
+    /// Gets the name of the class this component was created with in the template.
+    pub fn class_name(&self) -> &Symbol {
+        &self.template.class
+    }
+
+    /// Gets the template this component was created from.
+    pub(crate) fn template(&self) -> &Arc<ComponentTemplate> {
+        &self.template
+    }
+
+    /// Replaces the template the component was created from, such as when its markup was
+    /// reloaded, which is used the next time the attributes are updated. Style classes set at
+    /// runtime are kept, unless the template's style class changed.
+    pub(crate) fn set_template(&mut self, template: Arc<ComponentTemplate>) {
+        if template.style_class != self.template.style_class {
+            self.style_classes = template.style_class.iter().cloned().collect();
+        }
+        self.template = template;
+    }
+
+    /// Gets the amount of attribute values kept for this component, both resolved and set at
+    /// runtime.
+    pub(crate) fn attribute_count(&self) -> usize {
+        self.resolved.len() + self.overrides.len()
+    }
+
+    /// Gets the name set with the component's `name` attribute, if any, used to tell components
+    /// apart while debugging.
+    pub fn name(&self) -> Option<&str> {
+        self.attributes.name.as_ref().map(|name| name.as_str())
+    }
+
+    /// Describes the component for debugging output, its class followed by its name if it has
+    /// one, such as `button "save"`.
+    pub fn label(&self) -> String {
+        match self.name() {
+            Some(name) => format!("{} \"{}\"", self.class_name(), name),
+            None => self.class_name().to_string(),
+        }
     }
 
     pub fn needs_rendering(&self) -> bool {
@@ -64,18 +190,118 @@ impl Component {
         self.needs_rendering = false;
     }
 
+    pub(crate) fn mark_needs_rendering(&mut self) {
+        self.needs_rendering = true;
+    }
+
+    pub(crate) fn retained_size(&self) -> Option<Vector2<f32>> {
+        self.retained.as_ref().map(|v| v.0)
+    }
+
+    pub(crate) fn retained_commands(&self) -> Option<&Vec<RenderCommand>> {
+        self.retained.as_ref().map(|v| &v.1)
+    }
+
+    pub(crate) fn set_retained(&mut self, size: Vector2<f32>, commands: Vec<RenderCommand>) {
+        self.retained = Some((size, commands));
+    }
+
     pub fn children(&self) -> &Vec<ComponentId> {
         &self.children
     }
 
+    /// Computes the size of the component from the size of its parent, letting the class adjust
+    /// the size set in its attributes.
+    pub(crate) fn compute_size(&self, parent_size: Vector2<f32>) -> Vector2<f32> {
+        self.class.size(self.attributes.compute_size(parent_size))
+    }
+
+    /// Lays the component out as the next child in its parent's flow, returning its position
+    /// relative to the parent and its size.
+    pub(crate) fn compute_layout(&self, parent_flow: &mut ComponentFlow) -> Rect {
+        let (index, arranged_area) = parent_flow.next_child();
+        let area = arranged_area
+            .unwrap_or_else(|| Rect::new(Point2::new(0.0, 0.0), parent_flow.limits));
+        let parent_size = area.size;
+        let size = self.compute_size(parent_size);
+        let attributes = &self.attributes;
+
+        let position = if let Some(position) = attributes.position {
+            let position = area.position + position.to_point(parent_size).coords;
+
+            // If we have a position, we need to use that
+            let x = match attributes.docking.0 {
+                Docking::Start =>
+                    position.x,
+                Docking::Middle =>
+                    position.x + (parent_size.x - size.x)*0.5,
+                Docking::End =>
+                    position.x + parent_size.x - size.x,
+            };
+            let y = match attributes.docking.1 {
+                Docking::Start =>
+                    position.y,
+                Docking::Middle =>
+                    position.y + (parent_size.y - size.y)*0.5,
+                Docking::End =>
+                    position.y + parent_size.y - size.y,
+            };
+
+            Point2::new(x, y)
+        } else if attributes.collapsed {
+            // Collapsed components don't take up any space in the flow
+            area.position
+        } else if arranged_area.is_some() {
+            // Components in an area of their own don't share it with any siblings to flow with
+            area.position + Vector2::new(attributes.margin, attributes.margin)
+        } else {
+            // If we don't have a position, we need to automatically calculate it
+            parent_flow.position(index, size, attributes.margin)
+        };
+
+        Rect::new(position, size)
+    }
+
+    /// Gets the area of this component of a size that the child at an index is laid out in,
+    /// which is all of it unless the class divides it between its children.
+    pub(crate) fn child_area(&self, index: usize, size: Vector2<f32>) -> Rect {
+        self.class.child_area(index, self.children.len(), size)
+            .unwrap_or_else(|| Rect::new(Point2::new(0.0, 0.0), size))
+    }
+
+    pub(crate) fn set_children(&mut self, children: Vec<ComponentId>) {
+        self.children = children;
+        self.class.children_changed(self.children.len());
+        self.needs_rendering = true;
+    }
+
     pub(crate) fn add_child(&mut self, id: ComponentId) {
         self.children.push(id);
+        self.class.children_changed(self.children.len());
+    }
+
+    pub(crate) fn insert_child(&mut self, index: usize, id: ComponentId) {
+        self.children.insert(index, id);
+        self.class.children_changed(self.children.len());
+    }
+
+    /// Removes a child, returns true if it was a child of this component.
+    pub(crate) fn remove_child(&mut self, id: ComponentId) -> bool {
+        let length = self.children.len();
+        self.children.retain(|child| *child != id);
+        self.class.children_changed(self.children.len());
+        length != self.children.len()
     }
 
     pub fn attributes(&self) -> &ComponentAttributes {
         &self.attributes
     }
 
+    /// Gets the post-effect to apply when compositing this component, if any.
+    pub fn effect(&self) -> Option<&Effect> {
+        self.class.effect().or(self.attributes.effect.as_ref())
+    }
+
     pub(crate) fn render(
         &self, id: ComponentId, computed_size: Vector2<f32>, renderer: &mut Renderer,
     ) -> Result<(), Error> {
@@ -90,28 +316,220 @@ impl Component {
         self.needs_rendering |= self.class.hover_end_event(&mut self.event_sink);
     }
 
-    pub(crate) fn raise_pressed_event(&mut self) {
-        self.class.pressed_event(&mut self.event_sink);
+    /// Returns false if the component ignored the press.
+    pub(crate) fn raise_pressed_event(
+        &mut self, modifiers: Modifiers, position: Option<PressPosition>,
+    ) -> bool {
+        self.class.pressed_event(&mut self.event_sink, modifiers, position)
+    }
+
+    pub(crate) fn raise_long_pressed_event(&mut self, modifiers: Modifiers) {
+        self.class.long_pressed_event(&mut self.event_sink, modifiers);
+    }
+
+    pub(crate) fn raise_press_start_event(
+        &mut self, modifiers: Modifiers, position: PressPosition, computed_size: Vector2<f32>,
+    ) {
+        self.needs_rendering |= self.class.press_start_event(
+            &mut self.event_sink, modifiers, position, computed_size,
+        );
+    }
+
+    pub(crate) fn raise_press_moved_event(
+        &mut self, position: PressPosition, computed_size: Vector2<f32>,
+    ) {
+        self.needs_rendering |= self.class.press_moved_event(
+            &mut self.event_sink, position, computed_size,
+        );
+    }
+
+    pub(crate) fn raise_press_end_event(&mut self) {
+        self.needs_rendering |= self.class.press_end_event(&mut self.event_sink);
+    }
+
+    pub(crate) fn raise_focus_changed_event(&mut self, focused: bool) {
+        self.needs_rendering |= self.class.focus_changed_event(&mut self.event_sink, focused);
+    }
+
+    pub(crate) fn raise_key_down_event(
+        &mut self, key: &str, modifiers: Modifiers, clipboard: &mut Clipboard,
+    ) {
+        self.needs_rendering |= self.class.key_down_event(
+            &mut self.event_sink, key, modifiers, clipboard,
+        );
+    }
+
+    pub(crate) fn raise_text_event(&mut self, text: &str) {
+        self.needs_rendering |= self.class.text_event(&mut self.event_sink, text);
+    }
+
+    pub(crate) fn event_sink(&self) -> &EventSink {
+        &self.event_sink
+    }
+
+    pub(crate) fn save_state(&self, state: &mut ComponentState) {
+        self.class.save_state(state);
+    }
+
+    pub(crate) fn restore_state(&mut self, state: &ComponentState) {
+        self.needs_rendering |= self.class.restore_state(state);
+    }
+
+    /// Overrides an attribute, taking priority over the template and style, and reloads all
+    /// attributes. If the value is rejected, the previous override is put back.
+    pub(crate) fn set_attribute(
+        &mut self, key: Symbol, value: TemplateValue, style: &Style, context: &Context,
+    ) -> Result<(), Error> {
+        let previous = self.overrides.insert(key.clone(), value);
+
+        let result = self.update_attributes(style, context);
+        if result.is_err() {
+            // Keeping a rejected value would make every later update of the component fail
+            match previous {
+                Some(previous) => self.overrides.insert(key, previous),
+                None => self.overrides.remove(&key),
+            };
+            self.update_attributes(style, context)?;
+        }
+
+        result
+    }
+
+    /// Gets the attributes this component's children inherit from it.
+    pub(crate) fn inherited_for_children(&self, classes: &ComponentClasses) -> InheritedAttributes {
+        self.resolved.inherited(classes)
+    }
+
+    pub(crate) fn inherited(&self) -> &InheritedAttributes {
+        &self.inherited
+    }
+
+    /// Sets the attributes inherited from the parent, which are applied the next time the
+    /// attributes are updated.
+    pub(crate) fn set_inherited(&mut self, inherited: InheritedAttributes) {
+        self.inherited = inherited;
     }
 
     pub(crate) fn update_attributes(
         &mut self, style: &Style, context: &Context
     ) -> Result<(), Error> {
         let runtime = &context.runtime;
-        let attributes = Attributes::resolve(&self.template, style, context)?;
-        self.class.update_attributes(&attributes, runtime)?;
+        let mut attributes = Attributes::resolve(
+            &self.template, &self.style_classes, style, &self.inherited, context,
+        )?;
+        for (key, value) in &self.overrides {
+            attributes.set_override(key.clone(), resolve_value(value, style, context)?);
+        }
+        self.transitions.update(&mut attributes, runtime)?;
+        self.animation = load_animation(self.animation.take(), &attributes, style, runtime)?;
+        self.resolved = attributes;
+
+        self.load_attributes(runtime)
+    }
+
+    /// Progresses the component by a frame, `delta` being the time since the last frame in
+    /// seconds.
+    pub(crate) fn update(&mut self, delta: f32) {
+        self.needs_rendering |= self.class.update(delta, &mut self.event_sink);
+    }
+
+    /// Returns true if attributes are transitioning or animating, and need to be progressed every
+    /// frame.
+    pub(crate) fn is_animating(&self) -> bool {
+        self.transitions.is_active() ||
+            self.animation.as_ref().map(|a| a.is_playing()).unwrap_or(false)
+    }
+
+    /// Progresses the transitions and animation of attributes, reloading the attributes with
+    /// their new values. Raises the `on-animation-end` event if the animation finished.
+    pub(crate) fn advance_animations(
+        &mut self, delta: f32, runtime: &ScriptRuntime,
+    ) -> Result<(), Error> {
+        self.transitions.advance(delta);
+        let mut finished = false;
+        if let Some(ref mut animation) = self.animation {
+            let was_playing = animation.is_playing();
+            animation.advance(delta);
+            finished = was_playing && !animation.is_playing();
+        }
+
+        self.load_attributes(runtime)?;
+
+        if finished {
+            if let Some(ref event) = self.attributes.on_animation_end {
+                self.event_sink.raise(event);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Loads the resolved attributes into the class and the component, with any transitioning
+    /// and animated values applied over them.
+    fn load_attributes(&mut self, runtime: &ScriptRuntime) -> Result<(), Error> {
+        let animated;
+        let attributes = if self.transitions.is_active() || self.animation.is_some() {
+            let mut attributes = self.resolved.clone();
+            self.transitions.apply(&mut attributes);
+            if let Some(ref animation) = self.animation {
+                animation.apply(&mut attributes);
+            }
+            animated = attributes;
+            &animated
+        } else {
+            &self.resolved
+        };
+
+        self.class.update_attributes(attributes, runtime)?;
+        self.attributes = ComponentAttributes::load(attributes, runtime)?;
         self.needs_rendering = true;
 
         Ok(())
     }
 }
 
+/// Starts the animation set in the attributes, or keeps playing the previous one if it didn't
+/// change.
+fn load_animation(
+    previous: Option<AnimationState>, attributes: &Attributes, style: &Style,
+    runtime: &ScriptRuntime,
+) -> Result<Option<AnimationState>, Error> {
+    let animation = attributes.attribute_optional(
+        "animation", |v| Animation::from_value(v, runtime),
+    )?;
+    let animation = match animation {
+        Some(animation) => animation,
+        None => return Ok(None),
+    };
+
+    if let Some(previous) = previous {
+        if *previous.animation() == animation {
+            return Ok(Some(previous))
+        }
+    }
+
+    let template = style.animation(&animation.name)
+        .ok_or_else(|| format!("Unable to find animation \"{}\" in style", animation.name))?;
+    Ok(Some(AnimationState::new(animation, template, runtime)?))
+}
+
 /// Core attributes all components share.
 pub struct ComponentAttributes {
+    pub name: Option<String>,
     pub position: Option<Coordinates>,
     pub size: Option<Coordinates>,
     pub docking: (Docking, Docking),
     pub margin: f32,
+    pub shortcut: Option<Shortcut>,
+    pub shadow: Option<Shadow>,
+    pub opacity: f32,
+    pub effect: Option<Effect>,
+    pub blend_mode: BlendMode,
+    pub visible: bool,
+    pub collapsed: bool,
+    pub on_animation_end: Option<EventHook>,
+    pub sound_hover: Option<String>,
+    pub sound_pressed: Option<String>,
 }
 
 impl ComponentAttributes {
@@ -119,6 +537,9 @@ impl ComponentAttributes {
         attributes: &Attributes, runtime: &ScriptRuntime
     ) -> Result<Self, Error> {
         Ok(ComponentAttributes {
+            name: attributes.attribute_optional(
+                "name", |v| v.as_string(runtime),
+            )?,
             position: attributes.attribute_optional(
                 "position", |v| v.as_coordinates(runtime),
             )?,
@@ -131,50 +552,54 @@ impl ComponentAttributes {
             margin: attributes.attribute(
                 "margin", |v| v.as_float(runtime), 0.0,
             )?,
+            shortcut: attributes.attribute_optional(
+                "shortcut", |v| Shortcut::from_value(v, runtime),
+            )?,
+            shadow: attributes.attribute_optional(
+                "shadow", |v| Shadow::from_value(v, runtime),
+            )?,
+            opacity: attributes.attribute(
+                "opacity", |v| v.as_opacity(runtime), 1.0,
+            )?,
+            effect: attributes.attribute_optional(
+                "effect", |v| Effect::from_value(v, runtime),
+            )?,
+            blend_mode: attributes.attribute(
+                "blend-mode", |v| BlendMode::from_value(v, runtime), BlendMode::Alpha,
+            )?,
+            visible: attributes.attribute(
+                "visible", |v| v.as_bool(runtime), true,
+            )?,
+            collapsed: attributes.attribute(
+                "collapsed", |v| v.as_bool(runtime), false,
+            )?,
+            on_animation_end: attributes.attribute_optional(
+                "on-animation-end", |v| v.as_event_hook(runtime),
+            )?,
+            sound_hover: attributes.attribute_optional(
+                "sound-hover", |v| v.as_string(runtime),
+            )?,
+            sound_pressed: attributes.attribute_optional(
+                "sound-pressed", |v| v.as_string(runtime),
+            )?,
         })
     }
 
+    /// Returns if the component should be rendered and receive input, collapsed components are
+    /// never visible.
+    pub fn is_visible(&self) -> bool {
+        self.visible && !self.collapsed
+    }
+
     pub(crate) fn compute_size(&self, parent_size: Vector2<f32>) -> Vector2<f32> {
         self.size
             .map(|v| v.to_vector(parent_size))
             .unwrap_or(parent_size)
     }
-
-    pub(crate) fn compute_position(
-        &self, parent_size: Vector2<f32>, parent_flow: &mut ComponentFlow
-    ) -> Point2<f32> {
-        let size = self.compute_size(parent_size);
-
-        if let Some(position) = self.position {
-            let position = position.to_point(parent_size);
-
-            // If we have a position, we need to use that
-            let x = match self.docking.0 {
-                Docking::Start =>
-                    position.x,
-                Docking::Middle =>
-                    position.x + (parent_size.x - size.x)*0.5,
-                Docking::End =>
-                    position.x + parent_size.x - size.x,
-            };
-            let y = match self.docking.1 {
-                Docking::Start =>
-                    position.y,
-                Docking::Middle =>
-                    position.y + (parent_size.y - size.y)*0.5,
-                Docking::End =>
-                    position.y + parent_size.y - size.y,
-            };
-
-            Point2::new(x, y)
-        } else {
-            // If we don't have a position, we need to automatically calculate it
-            parent_flow.position(size, self.margin)
-        }
-    }
 }
 
-#[derive(Copy, Clone)]
+/// What side of the parent a component's position is relative to.
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Docking {
     Start, Middle, End,
 }
@@ -207,24 +632,130 @@ impl Docking {
     }
 }
 
-pub struct ComponentFlow {
+/// A drop shadow rendered behind a component.
+#[derive(Copy, Clone)]
+pub struct Shadow {
+    pub offset: Vector2<f32>,
+    pub blur: f32,
+    pub color: Color,
+}
+
+impl Shadow {
+    pub fn from_value(
+        value: &TemplateValue, runtime: &ScriptRuntime
+    ) -> Result<Self, Error> {
+        let vec = value.as_vec()?;
+
+        if vec.len() != 4 {
+            return Err("Tuple is incorrect size".into())
+        }
+
+        let offset_x = vec[0].as_float(runtime)
+            .map_err(|e| Error::new_value("Value 1", e))?;
+        let offset_y = vec[1].as_float(runtime)
+            .map_err(|e| Error::new_value("Value 2", e))?;
+        let blur = vec[2].as_float(runtime)
+            .map_err(|e| Error::new_value("Value 3", e))?;
+        let color = vec[3].as_color(runtime)
+            .map_err(|e| Error::new_value("Value 4", e))?;
+
+        if blur < 0.0 {
+            return Err("Value 3: Blur can not be negative".into())
+        }
+
+        Ok(Shadow {
+            offset: Vector2::new(offset_x, offset_y),
+            blur,
+            color,
+        })
+    }
+
+    /// Renders the shadow of a component at the given area to the parent's cache, faded by the
+    /// component's opacity.
+    pub(crate) fn render(
+        &self, parent_id: ComponentId, area: Rect, opacity: f32, renderer: &mut Renderer,
+    ) -> Result<(), Error> {
+        let area = Rect::new(area.position + self.offset, area.size);
+        let mut color = self.color;
+        color.alpha *= opacity;
+
+        if self.blur == 0.0 {
+            return render::rectangle(renderer, parent_id, area, 0.0, color)
+        }
+
+        // Blur is approximated by layering translucent rectangles, going from the outer edge of
+        // the blur inwards, so the alpha accumulates towards the center
+        let layers = (self.blur / 2.0).ceil().max(1.0) as i32;
+        color.alpha /= layers as f32;
+
+        for layer in 0..layers {
+            let expand = self.blur * 0.5 - self.blur * (layer as f32 / layers as f32);
+            let layer_area = Rect::new(
+                area.position - Vector2::new(expand, expand),
+                area.size + Vector2::new(expand, expand) * 2.0,
+            );
+
+            if layer_area.size.x > 0.0 && layer_area.size.y > 0.0 {
+                let radius = expand.max(0.0).min(layer_area.size.x.min(layer_area.size.y) * 0.5);
+                render::rectangle(renderer, parent_id, layer_area, radius, color)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct ComponentFlow<'a> {
     limits: Vector2<f32>,
     pointer: Point2<f32>,
     pointer_margin: f32,
     next_line: f32,
+    /// The class of the parent, which can arrange the children itself, with how many children
+    /// there are and how many have been laid out so far.
+    class: Option<&'a ComponentClass>,
+    count: usize,
+    index: usize,
 }
 
-impl ComponentFlow {
+impl<'a> ComponentFlow<'a> {
     pub fn new(limits: Vector2<f32>) -> Self {
         ComponentFlow {
             limits,
             pointer: Point2::new(0.0, 0.0),
             pointer_margin: 0.0,
             next_line: 0.0,
+            class: None,
+            count: 0,
+            index: 0,
         }
     }
 
-    pub fn position(&mut self, size: Vector2<f32>, margin: f32) -> Point2<f32> {
+    /// Creates a flow for the children of a component, letting its class arrange them.
+    pub fn for_children(component: &'a Component, limits: Vector2<f32>) -> Self {
+        let mut flow = ComponentFlow::new(limits);
+        flow.class = Some(component.class());
+        flow.count = component.children().len();
+        flow
+    }
+
+    /// Moves on to the next child, returning its index and the area the parent's class gives it,
+    /// if any.
+    fn next_child(&mut self) -> (usize, Option<Rect>) {
+        let index = self.index;
+        self.index += 1;
+        let area = self.class
+            .and_then(|class| class.child_area(index, self.count, self.limits));
+        (index, area)
+    }
+
+    fn position(&mut self, index: usize, size: Vector2<f32>, margin: f32) -> Point2<f32> {
+        // The parent's class gets to arrange its children before they're flowed
+        let arranged = self.class
+            .and_then(|class| class.child_position(index, self.count, size, self.limits));
+        if let Some(position) = arranged {
+            return position
+        }
+
         // TODO: This function is a perfect unit testing candidate
         // TODO: Vertical margin is incorrect right now, instead of correctly overlapping line
         //  margins, it just uses the current component's margin on top. This needs to be changed
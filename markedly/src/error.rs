@@ -1,9 +1,17 @@
 use std::error::{Error as RError};
 
+use template::{SourceLocation};
+
 /// A markedly error.
 #[derive(Debug)]
 pub enum Error {
-    Attribute { component: String, line: usize, field: String, inner: Box<Error> },
+    /// An error in an attribute of a component. The location is where the component is in its
+    /// template, the source is where the attribute's value was set, which may be a style. The
+    /// name is the component's `name` attribute, if it has one.
+    Attribute {
+        component: String, name: Option<String>, location: SourceLocation, field: String,
+        source: Option<SourceLocation>, inner: Box<Error>,
+    },
     Value { error: String, inner: Option<Box<Error>> },
     Script { error: String },
     /// An error relating a resource that has to be loaded in.
@@ -1,34 +1,109 @@
 use std::collections::{VecDeque};
-use std::rc::{Rc};
-use std::cell::{RefCell};
 
+use std::fmt::{self, Debug, Formatter};
+
+use input::{PressPosition};
+use scripting::{ScriptValue};
+use shared::{Shared, lock, same};
 use template::{EventHook};
+use {UiCommands, UiCommand};
 
 /// Data for interacting with an active UI component tree inserted through a template.
 #[derive(Clone)]
 pub struct EventSink {
-    events: Rc<RefCell<VecDeque<String>>>,
+    events: Shared<VecDeque<Event>>,
+    commands: UiCommands,
+}
+
+/// An event raised by a component, with the details of what raised it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    /// The name of the event, as set in the component's attribute.
+    pub name: String,
+    /// Where the component was pressed, if the event was raised by pressing it.
+    pub position: Option<PressPosition>,
+    /// The value the component changed to, if the event was raised by a value changing, such as
+    /// a scrollbar's position.
+    pub value: Option<f32>,
+    /// The data given to `raise` by a script if the event was raised by a script, or the text of
+    /// a component if the event was raised by its text changing.
+    pub payload: Option<ScriptValue>,
 }
 
 impl EventSink {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(commands: UiCommands) -> Self {
         EventSink {
             events: Default::default(),
+            commands,
         }
     }
 
-    /// Retrieves the next event raised by a component, or returns None.
+    /// Gets the queue of changes to make to the UI this tree is in.
+    pub fn commands(&self) -> &UiCommands {
+        &self.commands
+    }
+
+    /// Retrieves the name of the next event raised by a component, or returns None.
     pub fn next(&self) -> Option<String> {
-        self.events.borrow_mut().pop_front()
+        self.next_event().map(|event| event.name)
+    }
+
+    /// Retrieves the next event raised by a component with its details, or returns None.
+    pub fn next_event(&self) -> Option<Event> {
+        lock(&self.events).pop_front()
     }
 
     /// Raises an event.
     pub fn raise(&self, event: &EventHook) {
+        self.raise_with(event, None, None, None);
+    }
+
+    /// Raises an event caused by a component being pressed, at a position if it was pressed by
+    /// the cursor or a touch.
+    pub fn raise_pressed(&self, event: &EventHook, position: Option<PressPosition>) {
+        self.raise_with(event, position, None, None);
+    }
+
+    /// Raises an event caused by a value of a component changing.
+    pub fn raise_value(&self, event: &EventHook, value: f32) {
+        self.raise_with(event, None, Some(value), None);
+    }
+
+    /// Raises an event caused by the text of a component changing, with the text as payload.
+    pub fn raise_text(&self, event: &EventHook, text: &str) {
+        self.raise_with(event, None, None, Some(ScriptValue::String(text.into())));
+    }
+
+    /// Raises an event from a script, with the data the script gave.
+    pub(crate) fn raise_script_event(&self, name: String, payload: Option<ScriptValue>) {
+        lock(&self.events).push_back(Event { name, position: None, value: None, payload });
+    }
+
+    /// Returns true if both are handles to the events of the same tree.
+    pub(crate) fn is_same(&self, other: &EventSink) -> bool {
+        same(&self.events, &other.events)
+    }
+
+    fn raise_with(
+        &self, event: &EventHook,
+        position: Option<PressPosition>, value: Option<f32>, payload: Option<ScriptValue>,
+    ) {
         match *event {
-            EventHook::Direct(ref value) =>
-                self.events.borrow_mut().push_back(value.clone()),
-            EventHook::Script(ref _script) =>
-                unimplemented!(),
+            EventHook::Direct(ref name) => lock(&self.events).push_back(Event {
+                name: name.clone(), position, value, payload,
+            }),
+            // Scripts need the scripting runtime and the tree's model, so they're run when the
+            // UI's commands are applied
+            EventHook::Script(ref script) => self.commands.push(UiCommand::RunScript {
+                script: script.clone(),
+                event_sink: self.clone(),
+            }),
         }
     }
 }
+
+impl Debug for EventSink {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("EventSink").finish()
+    }
+}
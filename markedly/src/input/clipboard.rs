@@ -0,0 +1,31 @@
+/// Access to a clipboard for copying and pasting text, such as the operating system's
+/// clipboard, supplied by the game or backend through `Input::set_clipboard`.
+pub trait Clipboard {
+    /// Gets the text on the clipboard, if there is any.
+    fn text(&mut self) -> Option<String>;
+
+    /// Puts text on the clipboard.
+    fn set_text(&mut self, text: &str);
+}
+
+/// A clipboard that only exists inside of the game, used when no other clipboard is supplied.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryClipboard {
+    text: Option<String>,
+}
+
+impl MemoryClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Clipboard for MemoryClipboard {
+    fn text(&mut self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn set_text(&mut self, text: &str) {
+        self.text = Some(text.into());
+    }
+}
@@ -0,0 +1,74 @@
+use nalgebra::{Point2, Vector2};
+
+use input::{Modifiers};
+use {ComponentId};
+
+/// An input event in a form independent of the windowing library it came from, to be given to
+/// `Input::handle_event`. Positions are on the target, in the same units as the UI's target
+/// size, and are converted to positions in the UI by inverting its root transform.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RawEvent {
+    /// The cursor moved to a new position.
+    CursorMoved { position: Point2<f32> },
+    /// A cursor button was pressed down or released.
+    Button {
+        position: Point2<f32>, button: MouseButton, pressed: bool, modifiers: Modifiers,
+    },
+    /// A key was pressed down or released. Key names are expected in lowercase, such as `s`,
+    /// `f1`, or `escape`. Repeated presses from a key being held down should set `repeat`.
+    Key { key: String, pressed: bool, repeat: bool, modifiers: Modifiers },
+    /// Text was typed, after the keyboard layout and any input method were applied.
+    Text { text: String },
+    /// The scroll wheel or a touchpad scrolled, with the cursor at a position.
+    Scroll { position: Point2<f32>, delta: Vector2<f32> },
+    /// A finger touched, moved over, or left the screen. The ID tells apart separate fingers.
+    Touch { id: u64, position: Point2<f32>, phase: TouchPhase },
+}
+
+/// A button on a mouse or other pointing device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Other(u8),
+}
+
+/// Where a component was pressed, given to the component and the events it raises.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PressPosition {
+    /// The position in the same units as the UI's target size.
+    pub absolute: Point2<f32>,
+    /// The position relative to the top left of the pressed component.
+    pub local: Point2<f32>,
+}
+
+/// A sound to play because a component was interacted with, from the component's `sound-hover`
+/// or `sound-pressed` attribute. Retrieved with `Input::next_sound`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundEvent {
+    /// The sound, as set in the component's attribute.
+    pub sound: String,
+    /// The component that was interacted with.
+    pub component: ComponentId,
+    pub trigger: SoundTrigger,
+}
+
+/// What interaction with a component made it play a sound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundTrigger {
+    /// The cursor started hovering over the component.
+    Hover,
+    /// The component was pressed, by the cursor, a touch, or its shortcut.
+    Pressed,
+}
+
+/// What stage a touch is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TouchPhase {
+    Started,
+    Moved,
+    Ended,
+    /// The touch was interrupted, such as by the system, and shouldn't be acted on.
+    Cancelled,
+}
@@ -1,12 +1,52 @@
 //! Systems for handling user input.
 
-use nalgebra::{Point2, Vector2};
+mod clipboard;
+mod event;
+mod modifiers;
+mod shortcuts;
 
+pub use self::clipboard::{Clipboard, MemoryClipboard};
+pub use self::event::{
+    RawEvent, MouseButton, TouchPhase, PressPosition, SoundEvent, SoundTrigger,
+};
+pub use self::modifiers::{Modifiers};
+pub use self::shortcuts::{Shortcut, Shortcuts};
+
+use std::collections::{VecDeque};
+
+use nalgebra::{self, Point2};
+
+use render::{Rect};
 use {Ui, ComponentId, ComponentFlow};
 
 /// Handles user input, raising events on components and storing current input information.
 pub struct Input {
     hovering_over: Option<ComponentId>,
+    /// Where the cursor was last moved to or pressed.
+    cursor_position: Option<Point2<f32>>,
+    /// The press on a component by the cursor or touch, until it's released.
+    pressing: Option<Press>,
+    /// The touch that's acting as the cursor, other touches are ignored while it's down.
+    touch: Option<u64>,
+    long_press_duration: f32,
+    long_press_threshold: f32,
+    clipboard: Box<Clipboard>,
+    /// Sounds components asked to be played, until they're retrieved.
+    sounds: VecDeque<SoundEvent>,
+}
+
+/// A press on a component that hasn't been released yet.
+struct Press {
+    component: ComponentId,
+    /// Where the component was when it was pressed, which the press's positions are local to.
+    bounds: Rect,
+    position: Point2<f32>,
+    modifiers: Modifiers,
+    /// How long the press has been held down for, in seconds.
+    time: f32,
+    /// If the press can still become a long press, which it can't anymore once it moved too far
+    /// or already became one.
+    long_press_pending: bool,
 }
 
 impl Input {
@@ -14,6 +54,96 @@ impl Input {
     pub fn new() -> Self {
         Input {
             hovering_over: None,
+            cursor_position: None,
+            pressing: None,
+            touch: None,
+            long_press_duration: 0.5,
+            long_press_threshold: 10.0,
+            clipboard: Box::new(MemoryClipboard::new()),
+            sounds: VecDeque::new(),
+        }
+    }
+
+    /// Sets the clipboard focused components copy text to and paste text from, such as the
+    /// operating system's clipboard. Without one, text can only be copied and pasted inside of
+    /// the game.
+    pub fn set_clipboard(&mut self, clipboard: Box<Clipboard>) {
+        self.clipboard = clipboard;
+    }
+
+    /// Gets the clipboard focused components copy text to and paste text from, so the game's
+    /// own copy and paste shortcuts share it with the UI's.
+    pub fn clipboard(&mut self) -> &mut Clipboard {
+        &mut *self.clipboard
+    }
+
+    /// Sets how long a press has to be held down in seconds, and how far it can move while
+    /// held, before it's a long press. Defaults to 0.5 seconds and 10.0 units.
+    pub fn set_long_press(&mut self, duration: f32, threshold: f32) {
+        self.long_press_duration = duration;
+        self.long_press_threshold = threshold;
+    }
+
+    /// Retrieves the next sound a component asked to be played by being hovered over or pressed,
+    /// or returns None. The game decides how to play it, such as by looking up the sound by
+    /// name in its own audio assets.
+    pub fn next_sound(&mut self) -> Option<SoundEvent> {
+        self.sounds.pop_front()
+    }
+
+    /// Progresses held presses by a frame, `delta` being the time since the last frame in
+    /// seconds, raising long press events. This should be called every frame.
+    pub fn update(&mut self, delta: f32, ui: &mut Ui) {
+        let press = match self.pressing {
+            Some(ref mut press) => press,
+            None => return,
+        };
+        if !press.long_press_pending {
+            return
+        }
+
+        press.time += delta;
+        if press.time >= self.long_press_duration {
+            press.long_press_pending = false;
+
+            // The pressed component may have been removed from the UI since then
+            if let Some(component) = ui.get_mut(press.component) {
+                component.raise_long_pressed_event(press.modifiers);
+            }
+        }
+    }
+
+    /// Handles an input event, calling the specific handler for it. This lets integrations
+    /// translate their windowing library's events to `RawEvent` rather than calling every
+    /// handler themselves.
+    /// Returns true if the event was used by the UI, and shouldn't also be handled by the game.
+    pub fn handle_event(&mut self, event: RawEvent, ui: &mut Ui) -> bool {
+        match event {
+            RawEvent::CursorMoved { position } => {
+                self.handle_cursor_moved(position, ui);
+                self.is_cursor_over_ui()
+            },
+            RawEvent::Button { position, button: MouseButton::Left, pressed, modifiers } => {
+                if pressed {
+                    self.handle_drag_started(position, modifiers, ui);
+                } else {
+                    self.handle_drag_ended(position, modifiers, ui);
+                }
+                find_in_layers(ui.root_transform().invert(position), ui).is_some()
+            },
+            RawEvent::Button { position, .. } |
+            RawEvent::Scroll { position, .. } =>
+                find_in_layers(ui.root_transform().invert(position), ui).is_some(),
+            RawEvent::Key { ref key, pressed: true, repeat: false, modifiers } =>
+                self.handle_key_down(key, modifiers, ui),
+            // Held down keys repeat in text inputs, but shouldn't trigger shortcuts again
+            RawEvent::Key { ref key, pressed: true, repeat: true, modifiers } =>
+                self.handle_focused_key_down(key, modifiers, ui),
+            RawEvent::Key { .. } => false,
+            RawEvent::Text { ref text } =>
+                self.handle_text(text, ui),
+            RawEvent::Touch { id, position, phase } =>
+                self.handle_touch(id, position, phase, ui),
         }
     }
 
@@ -22,63 +152,245 @@ impl Input {
         self.hovering_over.is_some()
     }
 
+    /// Gets where in the UI the cursor was last moved to or pressed, if it was at all, such as to
+    /// open a context menu at.
+    pub fn cursor_position(&self) -> Option<Point2<f32>> {
+        self.cursor_position
+    }
+
     /// Handles cursor movement.
     pub fn handle_cursor_moved(
         &mut self, position: Point2<f32>, ui: &mut Ui,
     ) {
-        let mut flow = ComponentFlow::new(ui.target_size());
-        let new_hovering = find_at_position(
-            position, ui, ui.root_id(), Point2::new(0.0, 0.0), ui.target_size(), &mut flow,
-        );
+        let position = ui.root_transform().invert(position);
+        self.cursor_position = Some(position);
+        let new_hovering = find_in_layers(position, ui).map(|(id, _)| id);
+
+        if let Some(ref mut press) = self.pressing {
+            // Moving too far while pressing means the press is part of a drag instead
+            if nalgebra::distance(&press.position, &position) > self.long_press_threshold {
+                press.long_press_pending = false;
+            }
+
+            // The pressed component may have been removed from the UI since then
+            if let Some(component) = ui.get_mut(press.component) {
+                component.raise_press_moved_event(
+                    press_position(position, press.bounds), press.bounds.size,
+                );
+            }
+        }
 
         if let Some(new_hovering) = new_hovering {
             // If the thing we're hovering over is a new thing, we need to notify it
             if self.hovering_over.map(|v| v != new_hovering).unwrap_or(true) {
                 ui.get_mut(new_hovering).unwrap()
                     .raise_hover_start_event();
+                self.queue_sound(new_hovering, SoundTrigger::Hover, ui);
             }
         }
 
         if let Some(hovering_over) = self.hovering_over {
             // If the thing we're hovering over is a new thing, we need to notify the old one
+            // The old one may have been removed from the UI since then
             if new_hovering.map(|v| v != hovering_over).unwrap_or(true) {
-                ui.get_mut(hovering_over).unwrap()
-                    .raise_hover_end_event();
+                if let Some(component) = ui.get_mut(hovering_over) {
+                    component.raise_hover_end_event();
+                }
             }
         }
 
         self.hovering_over = new_hovering;
+        ui.set_hovered(new_hovering);
     }
 
     /// Handles the start of a cursor or touch drag.
     pub fn handle_drag_started(
-        &mut self, _position: Point2<f32>, _ui: &mut Ui,
+        &mut self, position: Point2<f32>, modifiers: Modifiers, ui: &mut Ui,
     ) {
+        let position = ui.root_transform().invert(position);
+        self.cursor_position = Some(position);
+        self.pressing = find_in_layers(position, ui).map(|(component, bounds)| Press {
+            component,
+            bounds,
+            position,
+            modifiers,
+            time: 0.0,
+            long_press_pending: true,
+        });
+
+        // Pressing a component that takes focus, such as a text input, gives it focus, pressing
+        // anywhere else takes focus away from it again
+        let pressed_focusable = self.pressing.as_ref()
+            .map(|press| press.component)
+            .filter(|id| ui.get(*id).unwrap().class().is_focusable());
+        let has_focusable = focused_focusable(ui).is_some();
+        if pressed_focusable.is_some() || has_focusable {
+            ui.set_focused(pressed_focusable).unwrap();
+        }
+
+        if let Some(ref press) = self.pressing {
+            let component = ui.get_mut(press.component).unwrap();
+            component.raise_press_start_event(
+                modifiers, press_position(position, press.bounds), press.bounds.size,
+            );
+        }
     }
 
     /// Handles the end of a cursor or touch drag.
     pub fn handle_drag_ended(
-        &mut self, position: Point2<f32>, ui: &mut Ui,
+        &mut self, position: Point2<f32>, modifiers: Modifiers, ui: &mut Ui,
     ) {
-        let mut flow = ComponentFlow::new(ui.target_size());
-        if let Some(component_id) = find_at_position(
-            position, ui, ui.root_id(), Point2::new(0.0, 0.0), ui.target_size(), &mut flow,
-        ) {
+        let position = ui.root_transform().invert(position);
+        // The pressed component may have been removed from the UI since then
+        let pressed = self.pressing.take().and_then(|press| ui.get_mut(press.component));
+        if let Some(component) = pressed {
+            component.raise_press_end_event();
+        }
+
+        if let Some((component_id, bounds)) = find_in_layers(position, ui) {
             let component = ui.get_mut(component_id).unwrap();
-            component.raise_pressed_event();
+            let position = Some(press_position(position, bounds));
+            if component.raise_pressed_event(modifiers, position) {
+                self.queue_sound(component_id, SoundTrigger::Pressed, ui);
+            }
+        }
+    }
+
+    /// Handles a key being pressed down. If a component that takes focus has focus, such as a
+    /// text input, it receives the key. Otherwise this raises the pressed event of the component
+    /// that has a matching shortcut, even if it isn't hovered over.
+    /// Key names are expected in lowercase, such as `s`, `f1`, or `escape`.
+    /// Returns true if the key was used by a focused component or a shortcut was triggered.
+    pub fn handle_key_down(
+        &mut self, key: &str, modifiers: Modifiers, ui: &mut Ui,
+    ) -> bool {
+        if self.handle_focused_key_down(key, modifiers, ui) {
+            return true
+        }
+
+        let shortcut = Shortcut::new(key, modifiers);
+        if let Some(component_id) = ui.shortcuts().find(&shortcut) {
+            let component = ui.get_mut(component_id).unwrap();
+            if component.raise_pressed_event(modifiers, None) {
+                self.queue_sound(component_id, SoundTrigger::Pressed, ui);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Handles text being typed, giving it to the focused component if it takes focus.
+    /// Returns true if a component received the text.
+    pub fn handle_text(&mut self, text: &str, ui: &mut Ui) -> bool {
+        if let Some(id) = focused_focusable(ui) {
+            ui.get_mut(id).unwrap().raise_text_event(text);
+            true
+        } else {
+            false
         }
     }
+
+    /// Queues the sound a component plays when interacted with in a way, if it has one.
+    fn queue_sound(&mut self, component: ComponentId, trigger: SoundTrigger, ui: &Ui) {
+        let attributes = ui.get(component).unwrap().attributes();
+        let sound = match trigger {
+            SoundTrigger::Hover => &attributes.sound_hover,
+            SoundTrigger::Pressed => &attributes.sound_pressed,
+        };
+
+        if let Some(ref sound) = *sound {
+            self.sounds.push_back(SoundEvent { sound: sound.clone(), component, trigger });
+        }
+    }
+
+    fn handle_focused_key_down(&mut self, key: &str, modifiers: Modifiers, ui: &mut Ui) -> bool {
+        if let Some(id) = focused_focusable(ui) {
+            ui.get_mut(id).unwrap().raise_key_down_event(key, modifiers, &mut *self.clipboard);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Handles touches by having the first finger act as the cursor.
+    fn handle_touch(
+        &mut self, id: u64, position: Point2<f32>, phase: TouchPhase, ui: &mut Ui,
+    ) -> bool {
+        if self.touch.map(|touch| touch != id).unwrap_or(phase != TouchPhase::Started) {
+            return false
+        }
+
+        // Touch screens don't have a cursor that can hover without pressing, so the cursor is
+        // moved onto the touch every time
+        self.handle_cursor_moved(position, ui);
+        let used = self.is_cursor_over_ui();
+
+        match phase {
+            TouchPhase::Started => {
+                self.touch = Some(id);
+                self.handle_drag_started(position, Modifiers::none(), ui);
+            },
+            TouchPhase::Moved => {},
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touch = None;
+                if phase == TouchPhase::Ended {
+                    self.handle_drag_ended(position, Modifiers::none(), ui);
+                }
+
+                // The finger left the screen, so nothing is hovered over anymore
+                if let Some(component) = self.hovering_over.and_then(|id| ui.get_mut(id)) {
+                    component.raise_hover_end_event();
+                }
+                self.hovering_over = None;
+                ui.set_hovered(None);
+            },
+        }
+
+        used
+    }
+}
+
+/// Finds the focused component, if it takes focus itself and isn't just focused by a command.
+fn focused_focusable(ui: &Ui) -> Option<ComponentId> {
+    ui.focused().filter(|id| ui.get(*id).map(|c| c.class().is_focusable()).unwrap_or(false))
+}
+
+fn press_position(position: Point2<f32>, bounds: Rect) -> PressPosition {
+    PressPosition {
+        absolute: position,
+        local: Point2::from_coordinates(position - bounds.position),
+    }
+}
+
+/// Finds the component at a position, checking the layers rendered on top first, returning it
+/// with its computed bounds.
+fn find_in_layers(position: Point2<f32>, ui: &Ui) -> Option<(ComponentId, Rect)> {
+    for layer_id in ui.visible_layers().iter().rev() {
+        let mut flow = ComponentFlow::new(ui.target_size());
+        let found = find_at_position(position, ui, *layer_id, Point2::new(0.0, 0.0), &mut flow);
+
+        if found.is_some() {
+            return found
+        }
+    }
+
+    None
 }
 
 fn find_at_position(
     position: Point2<f32>, ui: &Ui, id: ComponentId,
-    computed_parent_position: Point2<f32>, parent_size: Vector2<f32>,
-    parent_flow: &mut ComponentFlow,
-) -> Option<ComponentId> {
+    computed_parent_position: Point2<f32>, parent_flow: &mut ComponentFlow,
+) -> Option<(ComponentId, Rect)> {
     let component = ui.get(id).unwrap();
-    let computed_position = computed_parent_position +
-        component.attributes().compute_position(parent_size, parent_flow).coords;
-    let computed_size = component.attributes().compute_size(parent_size);
+    let rect = component.compute_layout(parent_flow);
+    let computed_position = computed_parent_position + rect.position.coords;
+    let computed_size = rect.size;
+
+    // Invisible components and their children can't be interacted with
+    if !component.attributes().is_visible() {
+        return None
+    }
 
     // If the position isn't over us, it also won't be over any children, so just return none
     if position.x < computed_position.x ||
@@ -91,7 +403,7 @@ fn find_at_position(
     // If this component doesn't capture input, we still need to check children, but we can't
     // return this one.
     let mut found_id = if component.class().is_capturing_cursor() {
-        Some(id)
+        Some((id, Rect::new(computed_position, computed_size)))
     } else {
         None
     };
@@ -99,14 +411,131 @@ fn find_at_position(
     // Go through all children, if any of them find a hit, replace the ID we found, we want to find
     // the last one that matches because it's the one rendered on top. The function will
     // recursively find the deepest matching child like this.
-    let mut flow = ComponentFlow::new(computed_size);
+    let mut flow = ComponentFlow::for_children(component, computed_size);
     for child_id in component.children() {
-        if let Some(id) = find_at_position(
-            position, ui, *child_id, computed_position, computed_size, &mut flow,
+        if let Some(found) = find_at_position(
+            position, ui, *child_id, computed_position, &mut flow,
         ) {
-            found_id = Some(id);
+            found_id = Some(found);
         }
     }
 
     found_id
 }
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Point2};
+
+    use class::{ButtonClass};
+    use input::{Input, Modifiers, PressPosition, SoundTrigger};
+    use {Ui, Context};
+
+    #[test]
+    fn it_raises_long_presses_instead_of_presses() {
+        let mut context = Context::for_test();
+        context.classes.register::<ButtonClass>("button");
+        let (mut ui, tree) = Ui::for_test(
+            "button { on-pressed: \"pressed\", on-long-pressed: \"menu\" }\n", "", &context,
+        );
+        let mut input = Input::new();
+        let position = Point2::new(5.0, 5.0);
+
+        input.handle_drag_started(position, Modifiers::none(), &mut ui);
+        input.update(1.0, &mut ui);
+        input.handle_drag_ended(position, Modifiers::none(), &mut ui);
+        assert_eq!(tree.event_sink().next(), Some("menu".into()));
+        assert_eq!(tree.event_sink().next(), None);
+
+        // Moving while held makes it a drag instead, which is still pressed on release
+        input.handle_drag_started(position, Modifiers::none(), &mut ui);
+        input.handle_cursor_moved(Point2::new(50.0, 5.0), &mut ui);
+        input.update(1.0, &mut ui);
+        input.handle_drag_ended(Point2::new(50.0, 5.0), Modifiers::none(), &mut ui);
+        assert_eq!(tree.event_sink().next(), Some("pressed".into()));
+        assert_eq!(tree.event_sink().next(), None);
+    }
+
+    #[test]
+    fn it_raises_presses_with_their_position() {
+        let mut context = Context::for_test();
+        context.classes.register::<ButtonClass>("button");
+        let (mut ui, tree) = Ui::for_test(
+            "container\n    button { position: (20.0, 10.0), size: (30.0, 20.0), \
+            on-pressed: \"pressed\" }\n",
+            "", &context,
+        );
+        let mut input = Input::new();
+        let position = Point2::new(25.0, 15.0);
+
+        input.handle_drag_started(position, Modifiers::none(), &mut ui);
+        input.handle_drag_ended(position, Modifiers::none(), &mut ui);
+        let event = tree.event_sink().next_event().unwrap();
+        assert_eq!(event.name, "pressed");
+        assert_eq!(event.position, Some(PressPosition {
+            absolute: position,
+            local: Point2::new(5.0, 5.0),
+        }));
+    }
+
+    #[test]
+    fn it_queues_sounds_of_hovered_and_pressed_components() {
+        let mut context = Context::for_test();
+        context.classes.register::<ButtonClass>("button");
+        let (mut ui, tree) = Ui::for_test(&[
+            "container",
+            "    button { size: (30, 20), shortcut: \"enter\", \
+                sound-hover: \"tick\", sound-pressed: \"click\" }",
+            "    button { size: (30, 20) }",
+            "",
+        ].join("\n"), "", &context);
+        let mut input = Input::new();
+        let button = ui.get(tree.root()).unwrap().children()[0];
+
+        input.handle_cursor_moved(Point2::new(10.0, 10.0), &mut ui);
+        input.handle_cursor_moved(Point2::new(15.0, 10.0), &mut ui);
+        input.handle_drag_started(Point2::new(15.0, 10.0), Modifiers::none(), &mut ui);
+        input.handle_drag_ended(Point2::new(15.0, 10.0), Modifiers::none(), &mut ui);
+        input.handle_key_down("enter", Modifiers::none(), &mut ui);
+        let mut sounds = Vec::new();
+        while let Some(sound) = input.next_sound() {
+            assert_eq!(sound.component, button);
+            sounds.push((sound.sound, sound.trigger));
+        }
+        assert_eq!(sounds, vec![
+            ("tick".to_string(), SoundTrigger::Hover),
+            ("click".to_string(), SoundTrigger::Pressed),
+            ("click".to_string(), SoundTrigger::Pressed),
+        ]);
+
+        // Components without sounds don't queue any
+        input.handle_cursor_moved(Point2::new(40.0, 10.0), &mut ui);
+        input.handle_drag_started(Point2::new(40.0, 10.0), Modifiers::none(), &mut ui);
+        input.handle_drag_ended(Point2::new(40.0, 10.0), Modifiers::none(), &mut ui);
+        assert_eq!(input.next_sound(), None);
+    }
+
+    #[test]
+    fn it_does_not_queue_pressed_sounds_for_long_presses() {
+        let mut context = Context::for_test();
+        context.classes.register::<ButtonClass>("button");
+        let (mut ui, tree) = Ui::for_test(
+            "button { on-pressed: \"pressed\", on-long-pressed: \"menu\", \
+            sound-pressed: \"click\" }\n",
+            "", &context,
+        );
+        let mut input = Input::new();
+        let position = Point2::new(5.0, 5.0);
+
+        input.handle_drag_started(position, Modifiers::none(), &mut ui);
+        input.update(1.0, &mut ui);
+        input.handle_drag_ended(position, Modifiers::none(), &mut ui);
+        assert_eq!(tree.event_sink().next(), Some("menu".into()));
+        assert_eq!(input.next_sound(), None);
+
+        input.handle_drag_started(position, Modifiers::none(), &mut ui);
+        input.handle_drag_ended(position, Modifiers::none(), &mut ui);
+        assert_eq!(tree.event_sink().next(), Some("pressed".into()));
+        assert_eq!(input.next_sound().map(|sound| sound.trigger), Some(SoundTrigger::Pressed));
+    }
+}
@@ -0,0 +1,19 @@
+/// The state of the keyboard modifier keys at the time of an input event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    /// Creates a new modifiers state with no modifier keys held down.
+    pub fn none() -> Self {
+        Modifiers::default()
+    }
+
+    /// Returns true if no modifier keys are held down.
+    pub fn is_none(&self) -> bool {
+        !self.shift && !self.ctrl && !self.alt
+    }
+}
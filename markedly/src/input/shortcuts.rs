@@ -0,0 +1,120 @@
+use metrohash::{MetroHashMap};
+
+use input::{Modifiers};
+use scripting::{ScriptRuntime};
+use template::{TemplateValue};
+use {ComponentId, Error};
+
+/// A key combination that triggers a component, such as `ctrl+s`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Shortcut {
+    /// The lowercase name of the key, such as `s`, `f1`, or `escape`.
+    pub key: String,
+    pub modifiers: Modifiers,
+}
+
+impl Shortcut {
+    /// Creates a new shortcut from a key name and modifiers.
+    pub fn new(key: &str, modifiers: Modifiers) -> Self {
+        Shortcut {
+            key: key.to_lowercase(),
+            modifiers,
+        }
+    }
+
+    /// Parses a shortcut from a string in the format `ctrl+shift+s`.
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        let mut modifiers = Modifiers::none();
+        let mut key = None;
+
+        for part in value.split('+') {
+            let part = part.trim().to_lowercase();
+
+            // Anything after the key is not valid, the key should always be last
+            if key.is_some() {
+                return Err(format!("Key in shortcut \"{}\" must be last", value).into())
+            }
+
+            match part.as_str() {
+                "shift" => modifiers.shift = true,
+                "ctrl" | "control" => modifiers.ctrl = true,
+                "alt" => modifiers.alt = true,
+                "" => return Err(format!("Empty key in shortcut \"{}\"", value).into()),
+                _ => key = Some(part),
+            }
+        }
+
+        let key = key.ok_or_else(|| format!("No key found in shortcut \"{}\"", value))?;
+        Ok(Shortcut {
+            key,
+            modifiers,
+        })
+    }
+
+    pub fn from_value(value: &TemplateValue, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        Self::parse(&value.as_string(runtime)?)
+    }
+}
+
+/// A registry of keyboard shortcuts declared by components in a UI.
+pub struct Shortcuts {
+    shortcuts: MetroHashMap<Shortcut, Vec<ComponentId>>,
+}
+
+impl Shortcuts {
+    /// Creates a new empty registry.
+    pub fn new() -> Self {
+        Shortcuts {
+            shortcuts: MetroHashMap::default(),
+        }
+    }
+
+    /// Registers a shortcut as triggering a component.
+    pub fn register(&mut self, shortcut: Shortcut, id: ComponentId) {
+        self.shortcuts.entry(shortcut).or_insert_with(|| Vec::new()).push(id);
+    }
+
+    /// Removes all shortcuts triggering a component.
+    pub fn unregister(&mut self, id: ComponentId) {
+        for ids in self.shortcuts.values_mut() {
+            ids.retain(|v| *v != id);
+        }
+        self.shortcuts.retain(|_, ids| ids.len() != 0);
+    }
+
+    /// Finds the component a shortcut triggers, if multiple components share a shortcut the most
+    /// recently registered one is returned.
+    pub fn find(&self, shortcut: &Shortcut) -> Option<ComponentId> {
+        self.shortcuts.get(shortcut).and_then(|ids| ids.last().cloned())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use input::{Shortcut, Modifiers};
+
+    #[test]
+    fn it_parses_key_only() {
+        let shortcut = Shortcut::parse("s").unwrap();
+        assert_eq!(shortcut.key, "s");
+        assert!(shortcut.modifiers.is_none());
+    }
+
+    #[test]
+    fn it_parses_modifiers() {
+        let shortcut = Shortcut::parse("Ctrl+Shift+S").unwrap();
+        assert_eq!(shortcut.key, "s");
+        assert_eq!(shortcut.modifiers, Modifiers { shift: true, ctrl: true, alt: false });
+    }
+
+    #[test]
+    fn it_fails_missing_key() {
+        assert!(Shortcut::parse("ctrl+").is_err());
+        assert!(Shortcut::parse("ctrl+alt").is_err());
+    }
+
+    #[test]
+    fn it_fails_key_before_modifier() {
+        assert!(Shortcut::parse("s+ctrl").is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use nalgebra::{Point2, Vector2};
+
+use template::{Coordinates};
+use {Component, ComponentId, Docking};
+
+/// An explanation of how a component's position and size were computed, made with
+/// `Ui::explain_layout` to find out why a component isn't where it's expected to be.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayoutReport {
+    /// How every component was laid out, from the root of the component's layer down to the
+    /// component itself.
+    pub path: Vec<LayoutStep>,
+}
+
+impl LayoutReport {
+    /// Gets how the component itself was laid out.
+    pub fn component(&self) -> &LayoutStep {
+        self.path.last().unwrap()
+    }
+
+    /// Gets the position of the component relative to the target, where it ends up on screen.
+    pub fn absolute_position(&self) -> Point2<f32> {
+        self.path.iter()
+            .fold(Point2::new(0.0, 0.0), |position, step| position + step.position.coords)
+    }
+}
+
+/// How a single component was laid out in its parent.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayoutStep {
+    pub id: ComponentId,
+    /// The component's class followed by its name, as shown in the debug overlay.
+    pub label: String,
+    /// The size of the parent the component was laid out in, the target's size for the root of
+    /// a layer. If the parent's class gave the component an area of its own, this is the size
+    /// of that area instead.
+    pub parent_size: Vector2<f32>,
+    pub size: Vector2<f32>,
+    pub size_source: SizeSource,
+    /// The position relative to the parent.
+    pub position: Point2<f32>,
+    pub position_source: PositionSource,
+    /// Invisible components aren't shown, but still take up space in their parent's flow.
+    pub visible: bool,
+}
+
+impl LayoutStep {
+    pub(crate) fn new(
+        id: ComponentId, component: &Component,
+        parent_size: Vector2<f32>, position: Point2<f32>, position_source: PositionSource,
+    ) -> Self {
+        let attributes = component.attributes();
+        let size_source = match attributes.size {
+            Some(size) => SizeSource::Attribute(size),
+            None => SizeSource::Parent,
+        };
+
+        LayoutStep {
+            id,
+            label: component.label(),
+            parent_size,
+            size: component.compute_size(parent_size),
+            size_source,
+            position,
+            position_source,
+            visible: attributes.is_visible(),
+        }
+    }
+}
+
+/// What a component's size was computed from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SizeSource {
+    /// No size was set, so the component fills its parent.
+    Parent,
+    /// The `size` attribute, in which relative coordinates are a fraction of the parent's width
+    /// and height respectively.
+    Attribute(Coordinates),
+}
+
+/// What a component's position was computed from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PositionSource {
+    /// The root of a layer, which is always at the target's top left.
+    LayerRoot,
+    /// The `position` attribute, relative to the sides of the parent picked by `docking`.
+    Attribute { position: Coordinates, docking: (Docking, Docking) },
+    /// The component is collapsed, so it's left at its parent's top left without taking up space.
+    Collapsed,
+    /// No position was set, so the component was placed in its parent's flow after the siblings
+    /// before it, wrapping to a new line if it didn't fit, or arranged by its parent's class.
+    Flow { margin: f32, preceding_siblings: usize },
+}
@@ -8,20 +8,73 @@ extern crate palette;
 extern crate metrohash;
 extern crate lyon;
 extern crate rlua;
+#[macro_use]
+extern crate lazy_static;
+#[cfg(feature = "serialize")]
+extern crate serde;
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(all(test, feature = "serialize"))]
+extern crate bincode;
+#[cfg(feature = "reload")]
+extern crate notify;
+#[cfg(feature = "log")]
+#[macro_use]
+extern crate log;
+
+// Without the log feature log records are compiled out, the arguments are still type checked so
+// they don't break when the feature is enabled
+#[cfg(not(feature = "log"))]
+macro_rules! trace {
+    ($($arg:tt)*) => { if false { let _ = format!($($arg)*); } }
+}
+#[cfg(not(feature = "log"))]
+macro_rules! warn {
+    ($($arg:tt)*) => { if false { let _ = format!($($arg)*); } }
+}
 
+pub mod animation;
 pub mod class;
 pub mod input;
+#[cfg(feature = "reload")]
+pub mod reload;
 pub mod render;
 pub mod scripting;
 pub mod template;
+pub mod testing;
 
+mod arena;
+mod binding;
+mod commands;
 mod component;
 mod error;
 mod events;
+mod layout;
+mod localization;
+mod notifications;
+mod popups;
+mod shared;
+mod state;
+mod stats;
+mod symbol;
 mod ui;
+mod warnings;
 
 use component::{Component, ComponentAttributes, ComponentFlow};
 
+pub use arena::{ComponentId};
+pub use binding::{ListBinding};
+pub use commands::{UiCommands, UiCommand};
+pub use component::{Docking};
 pub use error::{Error};
-pub use events::{EventSink};
-pub use ui::{Ui, Context, ComponentId, Tree};
+pub use events::{EventSink, Event};
+pub use layout::{LayoutReport, LayoutStep, SizeSource, PositionSource};
+pub use localization::{Localization};
+pub use notifications::{Notifications};
+pub use popups::{Popups, PopupAnchor};
+pub use state::{UiState, ComponentState, StateValue};
+pub use stats::{UiStats};
+pub use symbol::{Symbol};
+pub use ui::{Ui, Context, Tree};
+pub use warnings::{Warnings, Warning};
@@ -0,0 +1,131 @@
+use std::collections::{HashMap};
+
+use template::{TemplateValue};
+
+/// Translated texts for the `@tr("key")` values in templates and styles, in every locale the
+/// game supports.
+///
+/// Texts can contain the arguments given to `@tr` as `{0}`, `{1}`, and so on. Texts with plural
+/// forms pick their form using the first integer argument and the locale's plural rule.
+///
+/// UIs resolve their translations again when they're updated after the locale or texts changed.
+pub struct Localization {
+    locale: String,
+    locales: HashMap<String, LocaleTexts>,
+    /// Increased on every change, so UIs can tell if their texts are outdated.
+    version: u64,
+}
+
+struct LocaleTexts {
+    texts: HashMap<String, Vec<String>>,
+    plural_rule: fn(i64) -> usize,
+}
+
+impl Localization {
+    /// Creates a new localization without any texts, with an active locale.
+    pub fn new(locale: &str) -> Self {
+        Localization {
+            locale: locale.into(),
+            locales: HashMap::new(),
+            version: 0,
+        }
+    }
+
+    /// Gets the active locale.
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Changes the active locale.
+    pub fn set_locale(&mut self, locale: &str) {
+        self.locale = locale.into();
+        self.version += 1;
+    }
+
+    /// Gets a number that changes every time the localization changes.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Adds a text to a locale, replacing the text with the same key if it already existed.
+    pub fn add_text(&mut self, locale: &str, key: &str, text: &str) {
+        self.add_plural_text(locale, key, &[text]);
+    }
+
+    /// Adds a text with plural forms to a locale, in the order the locale's plural rule picks
+    /// them in. For the default rule, that's the singular form followed by the plural form.
+    pub fn add_plural_text(&mut self, locale: &str, key: &str, forms: &[&str]) {
+        let forms = forms.iter().map(|form| form.to_string()).collect();
+        self.locale_texts(locale).texts.insert(key.into(), forms);
+        self.version += 1;
+    }
+
+    /// Sets the rule a locale uses to pick which plural form to use for a count. The default
+    /// rule picks the first form for a count of one, and the second form for any other count.
+    pub fn set_plural_rule(&mut self, locale: &str, rule: fn(i64) -> usize) {
+        self.locale_texts(locale).plural_rule = rule;
+        self.version += 1;
+    }
+
+    /// Finds the text for a key in the active locale and fills in the arguments, returns None
+    /// if the locale doesn't have a text for the key.
+    pub fn translate(&self, key: &str, arguments: &[TemplateValue]) -> Option<String> {
+        let locale = self.locales.get(&self.locale)?;
+        let forms = locale.texts.get(key)?;
+
+        let count = arguments.iter().filter_map(|argument| match *argument {
+            TemplateValue::Integer(value) => Some(value as i64),
+            _ => None,
+        }).next();
+        let form = count.map(|count| (locale.plural_rule)(count)).unwrap_or(0);
+        let mut text = forms.get(form).or(forms.last())?.clone();
+
+        for (i, argument) in arguments.iter().enumerate() {
+            let value = match *argument {
+                TemplateValue::String(ref value) => value.clone(),
+                TemplateValue::Integer(value) => value.to_string(),
+                TemplateValue::Float(value) => value.to_string(),
+                TemplateValue::Percentage(value) => format!("{}%", value),
+                TemplateValue::Bool(value) => value.to_string(),
+                _ => continue,
+            };
+            text = text.replace(&format!("{{{}}}", i), &value);
+        }
+
+        Some(text)
+    }
+
+    fn locale_texts(&mut self, locale: &str) -> &mut LocaleTexts {
+        self.locales.entry(locale.into()).or_insert_with(|| LocaleTexts {
+            texts: HashMap::new(),
+            plural_rule: default_plural_rule,
+        })
+    }
+}
+
+fn default_plural_rule(count: i64) -> usize {
+    if count == 1 { 0 } else { 1 }
+}
+
+#[cfg(test)]
+mod test {
+    use template::{TemplateValue};
+    use {Localization};
+
+    #[test]
+    fn it_translates_with_plural_forms() {
+        let mut localization = Localization::new("en");
+        localization.add_plural_text("en", "items", &["{0} item", "{0} items"]);
+        localization.add_text("nl", "items", "{0} voorwerpen");
+
+        let one = localization.translate("items", &[TemplateValue::Integer(1)]);
+        let many = localization.translate("items", &[TemplateValue::Integer(3)]);
+        assert_eq!(one, Some("1 item".into()));
+        assert_eq!(many, Some("3 items".into()));
+
+        localization.set_locale("nl");
+        let many = localization.translate("items", &[TemplateValue::Integer(3)]);
+        assert_eq!(many, Some("3 voorwerpen".into()));
+        assert_eq!(localization.translate("missing", &[]), None);
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::{VecDeque};
+
+use nalgebra::{Point2};
+
+use scripting::{ScriptTable};
+use template::{Template, TemplateValue};
+use {ComponentId, Context, Error, Tree, Ui};
+
+/// Shows short-lived notifications, such as toasts for achievements or saved games, inserting a
+/// tree from a template into an area component for every notification.
+///
+/// Notifications fade in, stay for a duration, and fade out again before they're removed, or
+/// earlier when they're pressed. When more notifications are pushed than can be shown at once,
+/// the rest wait in a queue until there's room. The area lays them out like any other children,
+/// so it decides where they go on the target, such as a container in the corner of a layer.
+///
+/// The fading is done by setting the `opacity` attribute of the notifications' root components,
+/// overriding any opacity set in their templates.
+pub struct Notifications {
+    area: ComponentId,
+    duration: f32,
+    fade_duration: f32,
+    max_shown: usize,
+    shown: Vec<Shown>,
+    queue: VecDeque<(Template, Option<ScriptTable>)>,
+}
+
+struct Shown {
+    tree: Tree,
+    /// How long the notification has been shown for, in seconds.
+    time: f32,
+    /// How long the notification has been fading out for, once it's leaving.
+    leaving: Option<f32>,
+}
+
+impl Notifications {
+    /// Creates notifications that are shown in an area component. By default notifications are
+    /// shown for 4 seconds, fade in and out over 0.25 seconds, and up to 3 are shown at once.
+    pub fn new(area: ComponentId) -> Self {
+        Notifications {
+            area,
+            duration: 4.0,
+            fade_duration: 0.25,
+            max_shown: 3,
+            shown: Vec::new(),
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// Sets how long notifications are shown for, and how long they take to fade in and out,
+    /// in seconds.
+    pub fn set_duration(&mut self, duration: f32, fade_duration: f32) {
+        self.duration = duration;
+        self.fade_duration = fade_duration;
+    }
+
+    /// Sets how many notifications are shown at once, at least one.
+    pub fn set_max_shown(&mut self, max_shown: usize) {
+        self.max_shown = max_shown.max(1);
+    }
+
+    /// Gets the area component notifications are inserted into.
+    pub fn area(&self) -> ComponentId {
+        self.area
+    }
+
+    /// Gets the amount of notifications currently shown, including those fading out.
+    pub fn shown_len(&self) -> usize {
+        self.shown.len()
+    }
+
+    /// Gets the amount of notifications waiting to be shown.
+    pub fn queued_len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Gets the tree of the shown notification at an index, in the order they were shown, such
+    /// as to read its events.
+    pub fn tree(&self, index: usize) -> Option<&Tree> {
+        self.shown.get(index).map(|shown| &shown.tree)
+    }
+
+    /// Queues a notification made from a template, it's shown as soon as there's room.
+    pub fn push(
+        &mut self, ui: &mut Ui, template: &Template, model: Option<&ScriptTable>,
+        context: &Context,
+    ) -> Result<(), Error> {
+        self.queue.push_back((template.clone(), model.cloned()));
+        self.show_queued(ui, context)
+    }
+
+    /// Starts fading out a shown notification by the root of its tree, such as when a close
+    /// button in it is pressed.
+    pub fn dismiss(&mut self, notification: ComponentId) -> Result<(), Error> {
+        let shown = self.shown.iter_mut().find(|shown| shown.tree.root() == notification)
+            .ok_or(format!("Component {:?} is not a shown notification", notification))?;
+        if shown.leaving.is_none() {
+            shown.leaving = Some(0.0);
+        }
+
+        Ok(())
+    }
+
+    /// Dismisses the notification the target is pressed on, if any. This should be called when
+    /// the cursor or a touch is pressed.
+    /// Returns true if a notification was dismissed.
+    pub fn handle_press(&mut self, ui: &Ui, position: Point2<f32>) -> Result<bool, Error> {
+        for shown in &mut self.shown {
+            let report = ui.explain_layout(shown.tree.root())?;
+            let start = report.absolute_position();
+            let end = start + report.component().size;
+            let pressed = position.x >= start.x && position.y >= start.y &&
+                position.x <= end.x && position.y <= end.y;
+
+            if pressed && shown.leaving.is_none() {
+                shown.leaving = Some(0.0);
+                return Ok(true)
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Progresses the notifications by a frame, `delta` being the time since the last frame in
+    /// seconds, fading them and removing those that are done. This should be called every
+    /// frame.
+    pub fn update(&mut self, delta: f32, ui: &mut Ui, context: &Context) -> Result<(), Error> {
+        let mut index = 0;
+        while index < self.shown.len() {
+            let opacity = {
+                let shown = &mut self.shown[index];
+                shown.time += delta;
+                if let Some(ref mut leaving) = shown.leaving {
+                    *leaving += delta;
+                } else if shown.time >= self.duration {
+                    shown.leaving = Some(shown.time - self.duration);
+                }
+
+                match shown.leaving {
+                    Some(leaving) => 1.0 - fade(leaving, self.fade_duration),
+                    None => fade(shown.time, self.fade_duration),
+                }
+            };
+
+            if opacity <= 0.0 && self.shown[index].leaving.is_some() {
+                let shown = self.shown.remove(index);
+                ui.remove_tree(&shown.tree)?;
+                continue
+            }
+
+            let root = self.shown[index].tree.root();
+            ui.set_attribute(root, "opacity", TemplateValue::Float(opacity), context)?;
+            index += 1;
+        }
+
+        self.show_queued(ui, context)
+    }
+
+    /// Shows queued notifications while there's room for them.
+    fn show_queued(&mut self, ui: &mut Ui, context: &Context) -> Result<(), Error> {
+        while self.shown.len() < self.max_shown {
+            let (template, model) = match self.queue.pop_front() {
+                Some(queued) => queued,
+                None => break,
+            };
+
+            let tree = ui.insert_template_into(&template, model.as_ref(), self.area, context)?;
+            let opacity = if self.fade_duration > 0.0 { 0.0 } else { 1.0 };
+            ui.set_attribute(tree.root(), "opacity", TemplateValue::Float(opacity), context)?;
+            self.shown.push(Shown { tree, time: 0.0, leaving: None });
+        }
+
+        Ok(())
+    }
+}
+
+/// Gets how far along a fade is, from 0.0 to 1.0.
+fn fade(time: f32, duration: f32) -> f32 {
+    if duration <= 0.0 {
+        1.0
+    } else {
+        (time / duration).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Point2, Vector2};
+
+    use template::{Template, Style};
+    use {Ui, Context, Notifications};
+
+    #[test]
+    fn it_fades_notifications_in_and_out_from_a_queue() {
+        let context = Context::for_test();
+        let template = Template::from_str("container\n").unwrap();
+        let notification = Template::from_str("container { size: (50, 10) }\n").unwrap();
+        let style = Style::from_str("").unwrap();
+        let (mut ui, tree) = Ui::new(
+            &template, None, style, Vector2::new(100.0, 100.0), &context,
+        ).unwrap();
+        let mut notifications = Notifications::new(tree.root());
+        notifications.set_duration(2.0, 0.5);
+        notifications.set_max_shown(1);
+
+        notifications.push(&mut ui, &notification, None, &context).unwrap();
+        notifications.push(&mut ui, &notification, None, &context).unwrap();
+        assert_eq!(notifications.shown_len(), 1);
+        assert_eq!(notifications.queued_len(), 1);
+        let first = notifications.tree(0).unwrap().root();
+
+        // The notification fades in, and is removed after fading out again
+        notifications.update(0.25, &mut ui, &context).unwrap();
+        assert_eq!(ui.get(first).unwrap().attributes().opacity, 0.5);
+        notifications.update(2.0, &mut ui, &context).unwrap();
+        assert_eq!(ui.get(first).unwrap().attributes().opacity, 0.5);
+        notifications.update(0.25, &mut ui, &context).unwrap();
+        assert!(ui.get(first).is_none());
+
+        // The queued notification takes its place, and can be dismissed by pressing it
+        assert_eq!(notifications.queued_len(), 0);
+        let second = notifications.tree(0).unwrap().root();
+        notifications.update(1.0, &mut ui, &context).unwrap();
+        assert!(notifications.handle_press(&ui, Point2::new(10.0, 5.0)).unwrap());
+        notifications.update(0.5, &mut ui, &context).unwrap();
+        assert!(ui.get(second).is_none());
+        assert_eq!(notifications.shown_len(), 0);
+    }
+}
@@ -0,0 +1,221 @@
+use nalgebra::{Point2, Vector2};
+
+use render::{Rect};
+use scripting::{ScriptTable};
+use template::{Template, TemplateValue};
+use {ComponentId, Context, Error, Tree, Ui};
+
+/// The layer order popups are added at, so they're above all other layers.
+const POPUP_LAYER_ORDER: i32 = ::std::i32::MAX;
+
+/// Opens templates as popups on top of everything else, such as dropdowns, context menus and
+/// tooltips, placed next to a component or a point such as the cursor.
+///
+/// Popups are moved to stay on the target, flipping to the other side of what they're anchored
+/// to if they don't fit, and are closed when the target is pressed outside of them. Popups
+/// opened later are stacked on top of earlier ones, so a submenu stays open while it's pressed
+/// but the menus above it close.
+///
+/// Every popup is placed in a `container` in a layer of its own, so that class has to be
+/// registered.
+pub struct Popups {
+    /// An empty component the size of the target, every popup gets its own layer of one with the
+    /// popup placed in it.
+    layer_template: Template,
+    popups: Vec<Popup>,
+}
+
+struct Popup {
+    layer: ComponentId,
+    tree: Tree,
+}
+
+/// What a popup is placed next to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PopupAnchor {
+    /// Below a component, or above it if there's no room below, such as for a dropdown.
+    Component(ComponentId),
+    /// At a point on the target, such as the cursor's position for a context menu. The popup
+    /// extends to the bottom right of the point, or to the left and up if there's no room.
+    Point(Point2<f32>),
+}
+
+impl Popups {
+    pub fn new() -> Self {
+        Popups {
+            layer_template: Template::from_str("container\n").unwrap(),
+            popups: Vec::new(),
+        }
+    }
+
+    /// Gets the amount of popups currently open.
+    pub fn len(&self) -> usize {
+        self.popups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.popups.is_empty()
+    }
+
+    /// Gets the tree of the popup at an index, counting from the bottom, such as to read its
+    /// events.
+    pub fn tree(&self, index: usize) -> Option<&Tree> {
+        self.popups.get(index).map(|popup| &popup.tree)
+    }
+
+    /// Opens a template as a popup on top of all other popups, placed at an anchor. The popup's
+    /// template should set its size, as it otherwise fills the entire target.
+    pub fn open(
+        &mut self, ui: &mut Ui,
+        template: &Template, model: Option<&ScriptTable>, anchor: PopupAnchor,
+        context: &Context,
+    ) -> Result<&Tree, Error> {
+        let anchor_rect = match anchor {
+            PopupAnchor::Component(id) => {
+                let report = ui.explain_layout(id)?;
+                Rect::new(report.absolute_position(), report.component().size)
+            },
+            PopupAnchor::Point(point) => Rect::new(point, Vector2::new(0.0, 0.0)),
+        };
+
+        let layer = ui.add_layer(&self.layer_template, None, POPUP_LAYER_ORDER, context)?.root();
+        let tree = match ui.insert_template_into(template, model, layer, context) {
+            Ok(tree) => tree,
+            Err(error) => {
+                ui.remove_layer(layer)?;
+                return Err(error)
+            },
+        };
+
+        // The popup's size is only known once it's in the UI, so it's moved afterwards
+        let size = ui.explain_layout(tree.root())?.component().size;
+        let position = place(anchor, anchor_rect, size, ui.target_size());
+        ui.set_attribute(tree.root(), "position", TemplateValue::Tuple(vec![
+            TemplateValue::Float(position.x), TemplateValue::Float(position.y),
+        ]), context)?;
+
+        self.popups.push(Popup { layer, tree });
+        Ok(&self.popups.last().unwrap().tree)
+    }
+
+    /// Closes a popup by the root of its tree, and every popup opened on top of it.
+    pub fn close(&mut self, ui: &mut Ui, popup: ComponentId) -> Result<(), Error> {
+        let index = self.popups.iter().position(|p| p.tree.root() == popup)
+            .ok_or(format!("Component {:?} is not the root of an open popup", popup))?;
+        self.close_from(ui, index)
+    }
+
+    /// Closes all popups.
+    pub fn close_all(&mut self, ui: &mut Ui) -> Result<(), Error> {
+        self.close_from(ui, 0)
+    }
+
+    /// Closes the popups the target is pressed outside of, starting from the top and stopping at
+    /// the first popup the position is inside of. This should be called when the cursor or a
+    /// touch is pressed, before the press is given to `Input`.
+    /// Returns true if any popup was closed.
+    pub fn handle_press(&mut self, ui: &mut Ui, position: Point2<f32>) -> Result<bool, Error> {
+        let mut keep = self.popups.len();
+        while keep > 0 {
+            let report = ui.explain_layout(self.popups[keep - 1].tree.root())?;
+            let bounds = Rect::new(report.absolute_position(), report.component().size);
+            if contains(bounds, position) {
+                break
+            }
+            keep -= 1;
+        }
+
+        let closed = keep != self.popups.len();
+        self.close_from(ui, keep)?;
+        Ok(closed)
+    }
+
+    fn close_from(&mut self, ui: &mut Ui, index: usize) -> Result<(), Error> {
+        for popup in self.popups.drain(index..).rev() {
+            ui.remove_layer(popup.layer)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Finds where a popup of a size should be placed at an anchor, keeping it on the target.
+fn place(
+    anchor: PopupAnchor, anchor_rect: Rect, size: Vector2<f32>, target_size: Vector2<f32>,
+) -> Point2<f32> {
+    let mut position = match anchor {
+        PopupAnchor::Component(_) =>
+            Point2::new(anchor_rect.position.x, anchor_rect.position.y + anchor_rect.size.y),
+        PopupAnchor::Point(point) => point,
+    };
+
+    // Flip to the other side of the anchor if the popup doesn't fit, but only if it does fit
+    // there, otherwise it's better to just push it back onto the target
+    if position.y + size.y > target_size.y && anchor_rect.position.y - size.y >= 0.0 {
+        position.y = anchor_rect.position.y - size.y;
+    }
+    if let PopupAnchor::Point(point) = anchor {
+        if position.x + size.x > target_size.x && point.x - size.x >= 0.0 {
+            position.x = point.x - size.x;
+        }
+    }
+
+    Point2::new(
+        position.x.min(target_size.x - size.x).max(0.0),
+        position.y.min(target_size.y - size.y).max(0.0),
+    )
+}
+
+fn contains(rect: Rect, position: Point2<f32>) -> bool {
+    position.x >= rect.position.x && position.y >= rect.position.y &&
+        position.x <= rect.position.x + rect.size.x &&
+        position.y <= rect.position.y + rect.size.y
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Point2, Vector2};
+
+    use template::{Template, Style};
+    use {Ui, Context, Popups, PopupAnchor};
+
+    #[test]
+    fn it_places_popups_on_the_target_and_closes_them_outside() {
+        let context = Context::for_test();
+        let template = Template::from_str(&[
+            "container",
+            "    container { size: (20, 10), position: (10, 85) }",
+            "",
+        ].join("\n")).unwrap();
+        let popup_template = Template::from_str("container { size: (30, 20) }\n").unwrap();
+        let style = Style::from_str("").unwrap();
+        let (mut ui, tree) = Ui::new(
+            &template, None, style, Vector2::new(100.0, 100.0), &context,
+        ).unwrap();
+        let mut popups = Popups::new();
+
+        // There's no room below the component, so the popup flips above it
+        let anchor = ui.get(tree.root()).unwrap().children()[0];
+        let dropdown = popups.open(
+            &mut ui, &popup_template, None, PopupAnchor::Component(anchor), &context,
+        ).unwrap().root();
+        let report = ui.explain_layout(dropdown).unwrap();
+        assert_eq!(report.absolute_position(), Point2::new(10.0, 65.0));
+
+        // A popup at the edge of the target is flipped to the other side of the point
+        let menu = popups.open(
+            &mut ui, &popup_template, None, PopupAnchor::Point(Point2::new(90.0, 20.0)),
+            &context,
+        ).unwrap().root();
+        let report = ui.explain_layout(menu).unwrap();
+        assert_eq!(report.absolute_position(), Point2::new(60.0, 20.0));
+
+        // Pressing the lower popup only closes the one above it
+        assert!(popups.handle_press(&mut ui, Point2::new(15.0, 70.0)).unwrap());
+        assert_eq!(popups.len(), 1);
+        assert!(!popups.handle_press(&mut ui, Point2::new(15.0, 70.0)).unwrap());
+        assert!(popups.handle_press(&mut ui, Point2::new(90.0, 5.0)).unwrap());
+        assert!(popups.is_empty());
+        assert!(ui.get(dropdown).is_none());
+    }
+}
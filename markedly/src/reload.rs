@@ -0,0 +1,130 @@
+//! Hot reloading of a UI's markup while it's running, enabled with the `reload` feature.
+
+use std::fs::{File};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration};
+
+use notify::{self, Watcher, RecommendedWatcher, RecursiveMode, DebouncedEvent};
+
+use scripting::{ScriptTable};
+use template::{Template, Style};
+use {Ui, Context, Error, Warning};
+
+/// Watches the template and style files of a UI, and reloads the UI when they change so markup
+/// can be edited while a game is running.
+///
+/// Reloading applies the changed template to the UI's base layer the same way `Ui::reload_tree`
+/// applies one to a tree, so components that are still in the template keep their IDs and
+/// runtime state, such as scroll offsets. Templates inserted into the UI and layers added to it
+/// are kept as well.
+pub struct UiReloader {
+    template_path: PathBuf,
+    style_path: PathBuf,
+    // Kept alive as watching stops when it's dropped
+    _watcher: RecommendedWatcher,
+    events: Receiver<DebouncedEvent>,
+}
+
+impl UiReloader {
+    /// Starts watching a template and style file.
+    pub fn new<T: AsRef<Path>, S: AsRef<Path>>(
+        template_path: T, style_path: S,
+    ) -> Result<Self, Error> {
+        let template_path = template_path.as_ref().to_path_buf();
+        let style_path = style_path.as_ref().to_path_buf();
+
+        // Editors often save by replacing the file, which a watch on the file itself doesn't
+        // survive, so the directories the files are in are watched instead
+        let (sender, events) = channel();
+        let mut watcher = notify::watcher(sender, Duration::from_millis(100))
+            .map_err(|e| Error::Generic { error: Box::new(e) })?;
+        for path in &[&template_path, &style_path] {
+            let directory = path.parent()
+                .filter(|p| *p != Path::new(""))
+                .unwrap_or(Path::new("."));
+            watcher.watch(directory, RecursiveMode::NonRecursive)
+                .map_err(|e| Error::Generic { error: Box::new(e) })?;
+        }
+
+        Ok(UiReloader {
+            template_path,
+            style_path,
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Loads the template and style from their files, to build the UI with initially.
+    pub fn load(&self) -> Result<(Template, Style), Error> {
+        let mut template = Template::from_reader(open(&self.template_path)?)
+            .map_err(|e| parse_error(&self.template_path, e))?;
+        template.set_source_name(self.template_path.to_string_lossy());
+
+        let mut style = Style::from_reader(open(&self.style_path)?)
+            .map_err(|e| parse_error(&self.style_path, e))?;
+        style.set_source_name(self.style_path.to_string_lossy());
+
+        Ok((template, style))
+    }
+
+    /// Checks if the template or style changed since the last call, and if so reloads the UI
+    /// from them. This should be called every frame.
+    /// Returns true if the UI was reloaded.
+    ///
+    /// If the changed markup can't be loaded or built, such as when it's saved halfway through
+    /// an edit, the problem is reported as a warning and the UI is left as it was.
+    pub fn update(
+        &mut self, ui: &mut Ui, model: Option<&ScriptTable>, context: &Context,
+    ) -> bool {
+        if !self.changed() {
+            return false
+        }
+
+        // The markup is first built on its own, so markup that doesn't work can't leave the UI
+        // halfway reloaded
+        let result = self.load().and_then(|(template, style)| {
+            Ui::new(&template, model, style.clone(), ui.target_size(), context)?;
+            let root = ui.root_id();
+            ui.reload_root_with_style(root, &template, style, context)
+        });
+        if let Err(error) = result {
+            context.warnings.push(Warning::ReloadFailed { error: format!("{:?}", error) });
+            return false
+        }
+
+        true
+    }
+
+    /// Drains the events received since the last call, returning if any affect the files.
+    fn changed(&self) -> bool {
+        let mut changed = false;
+        while let Ok(event) = self.events.try_recv() {
+            let path = match event {
+                DebouncedEvent::Create(path) |
+                DebouncedEvent::Write(path) |
+                DebouncedEvent::Rename(_, path) => path,
+                _ => continue,
+            };
+
+            // Watched directories give full paths, while the paths we were given may be relative
+            changed |= path.file_name() == self.template_path.file_name() ||
+                path.file_name() == self.style_path.file_name();
+        }
+        changed
+    }
+}
+
+fn open(path: &Path) -> Result<File, Error> {
+    File::open(path).map_err(|e| Error::Resource {
+        resource: Some(path.to_string_lossy().into_owned()),
+        error: format!("{}", e),
+    })
+}
+
+fn parse_error(path: &Path, error: String) -> Error {
+    Error::Resource {
+        resource: Some(path.to_string_lossy().into_owned()),
+        error,
+    }
+}
@@ -0,0 +1,31 @@
+use scripting::{ScriptRuntime};
+use template::{TemplateValue};
+use {Error};
+
+/// How a component's cache is combined with what's rendered behind it when it's composited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// Covers what's behind it by its alpha.
+    Alpha,
+    /// Adds its colors to what's behind it, brightening it, such as for glows.
+    Add,
+    /// Multiplies what's behind it by its colors, darkening it, such as for tinted overlays.
+    Multiply,
+}
+
+impl BlendMode {
+    pub fn from_value(value: &TemplateValue, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        match value.as_string(runtime)?.as_str() {
+            "alpha" => Ok(BlendMode::Alpha),
+            "add" => Ok(BlendMode::Add),
+            "multiply" => Ok(BlendMode::Multiply),
+            _ => Err("Value must be either \"alpha\", \"add\", or \"multiply\"".into())
+        }
+    }
+}
+
+impl Default for BlendMode {
+    fn default() -> Self {
+        BlendMode::Alpha
+    }
+}
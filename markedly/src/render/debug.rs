@@ -0,0 +1,84 @@
+use nalgebra::{Point2, Vector2};
+
+use render::{Renderer, Rect};
+use template::{Color};
+use {ComponentId, Ui, Error, ComponentFlow};
+
+/// Renders the debug overlay of a layer straight to the target, on top of the layer. Every
+/// visible component gets an outline of its bounds and margin, and is labeled with its class
+/// and name. Like the direct render mode, only the offset of the root transform is applied.
+pub(crate) fn render<R: Renderer>(
+    renderer: &mut R, ui: &Ui, root_id: ComponentId,
+) -> Result<(), Error> {
+    renderer.begin_direct(root_id)?;
+    render_component(
+        renderer, ui, root_id, root_id,
+        Rect::new(Point2::from_coordinates(ui.root_transform().offset), ui.target_size()),
+    )?;
+    renderer.end_direct(root_id)?;
+
+    Ok(())
+}
+
+fn render_component<R: Renderer>(
+    renderer: &mut R, ui: &Ui, target_id: ComponentId, id: ComponentId, rect: Rect,
+) -> Result<(), Error> {
+    let component = ui.get(id).unwrap();
+    let margin = component.attributes().margin;
+
+    if ui.hovered() == Some(id) {
+        renderer.vertices(
+            target_id, &rect_points(rect), &[0, 1, 2, 0, 2, 3], Color::new(1.0, 1.0, 0.0, 0.25),
+        )?;
+    }
+
+    if margin != 0.0 {
+        let margin_rect = Rect::new(
+            rect.position - Vector2::new(margin, margin),
+            rect.size + Vector2::new(margin, margin) * 2.0,
+        );
+        outline(renderer, target_id, margin_rect, Color::new(1.0, 0.5, 0.0, 0.5))?;
+    }
+    outline(renderer, target_id, rect, Color::new(0.0, 1.0, 1.0, 1.0))?;
+
+    let label = component.label();
+    renderer.text(
+        target_id, &label, None, Some(10),
+        rect.position, Vector2::new(rect.size.x, 10.0), Color::new(0.0, 1.0, 1.0, 1.0),
+    )?;
+
+    // Children are labeled after their parent, so their labels are rendered on top
+    let mut flow = ComponentFlow::for_children(component, rect.size);
+    for child_id in component.children() {
+        let child = ui.get(*child_id).unwrap();
+        let child_rect = child.compute_layout(&mut flow);
+        if !child.attributes().is_visible() {
+            continue
+        }
+
+        render_component(
+            renderer, ui, target_id, *child_id,
+            Rect::new(rect.position + child_rect.position.coords, child_rect.size),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn outline<R: Renderer>(
+    renderer: &mut R, target_id: ComponentId, rect: Rect, color: Color,
+) -> Result<(), Error> {
+    let mut points = rect_points(rect).to_vec();
+    points.push(points[0]);
+    renderer.line(target_id, &points, 1.0, color)
+}
+
+fn rect_points(rect: Rect) -> [Point2<f32>; 4] {
+    let end = rect.end();
+    [
+        rect.position,
+        Point2::new(end.x, rect.position.y),
+        end,
+        Point2::new(rect.position.x, end.y),
+    ]
+}
@@ -0,0 +1,206 @@
+use nalgebra::{Point2, Vector2};
+
+use render::{self, Renderer, RenderStats, Rect, BlendMode, TextEffects, RootTransform};
+use template::{Color};
+use {ComponentId, Ui, Error, ComponentFlow};
+
+/// Renders all components in a layer straight to the target, without any caches. Only the
+/// offset of the root transform is applied, as operations can't be scaled.
+pub(crate) fn render<R: Renderer>(
+    renderer: &mut R, ui: &Ui, root_id: ComponentId, stats: &mut RenderStats,
+) -> Result<(), Error> {
+    let position = Point2::from_coordinates(ui.root_transform().offset);
+    renderer.begin_direct(root_id)?;
+    render_component(
+        renderer, ui, root_id, root_id, position, ui.target_size(), 1.0, stats,
+    )?;
+    renderer.end_direct(root_id)?;
+
+    Ok(())
+}
+
+fn render_component<R: Renderer>(
+    renderer: &mut R, ui: &Ui, target_id: ComponentId, id: ComponentId,
+    position: Point2<f32>, size: Vector2<f32>, opacity: f32, stats: &mut RenderStats,
+) -> Result<(), Error> {
+    stats.components_visited += 1;
+    stats.caches_rendered += 1;
+    let component = ui.get(id).unwrap();
+
+    // Let the component's class render itself, transformed to where it is on the target
+    {
+        let mut transformed = TransformedRenderer {
+            inner: renderer,
+            target_id,
+            offset: position.coords,
+            opacity,
+        };
+        let result = component.render(id, size, &mut transformed);
+        render::warn_missing_resource(result, ui.warnings())?;
+    }
+
+    // Opacity is applied to every operation individually, so overlapping children of a
+    // translucent component will show through each other, unlike in cached mode
+    renderer.push_clip(target_id, Rect::new(position, size))?;
+    let mut flow = ComponentFlow::for_children(component, size);
+    for child_id in component.children() {
+        let child = ui.get(*child_id).unwrap();
+        let child_rect = child.compute_layout(&mut flow);
+        if !child.attributes().is_visible() {
+            continue
+        }
+        let child_position = position + child_rect.position.coords;
+        let child_size = child_rect.size;
+        let child_opacity = opacity * child.attributes().opacity;
+
+        if let Some(ref shadow) = child.attributes().shadow {
+            shadow.render(
+                target_id, Rect::new(child_position, child_size), child_opacity, renderer,
+            )?;
+        }
+
+        render_component(
+            renderer, ui, target_id, *child_id, child_position, child_size, child_opacity, stats,
+        )?;
+    }
+    renderer.pop_clip(target_id)?;
+
+    Ok(())
+}
+
+/// Redirects operations to the target, offset to the component's position.
+struct TransformedRenderer<'r, R: Renderer + 'r> {
+    inner: &'r mut R,
+    target_id: ComponentId,
+    offset: Vector2<f32>,
+    opacity: f32,
+}
+
+impl<'r, R: Renderer> TransformedRenderer<'r, R> {
+    fn fade(&self, mut color: Color) -> Color {
+        color.alpha *= self.opacity;
+        color
+    }
+}
+
+impl<'r, R: Renderer> Renderer for TransformedRenderer<'r, R> {
+    fn render_cache_to_target(
+        &mut self, _id: ComponentId, _transform: RootTransform,
+    ) -> Result<(), Error> {
+        Err("Caches can not be used in direct render mode".into())
+    }
+
+    fn create_resize_cache(
+        &mut self, _id: ComponentId, _size: Vector2<u32>
+    ) -> Result<bool, Error> {
+        Err("Caches can not be used in direct render mode".into())
+    }
+
+    fn clear_cache(&mut self, _id: ComponentId) -> Result<(), Error> {
+        Err("Caches can not be used in direct render mode".into())
+    }
+
+    fn remove_cache(&mut self, _id: ComponentId) -> Result<(), Error> {
+        Err("Caches can not be used in direct render mode".into())
+    }
+
+    fn render_cache(
+        &mut self, _id: ComponentId,
+        _source_id: ComponentId, _position: Point2<f32>, _alpha: f32, _blend_mode: BlendMode,
+    ) -> Result<(), Error> {
+        Err("Caches can not be used in direct render mode".into())
+    }
+
+    fn push_clip(&mut self, _id: ComponentId, rect: Rect) -> Result<(), Error> {
+        self.inner.push_clip(self.target_id, Rect::new(rect.position + self.offset, rect.size))
+    }
+
+    fn pop_clip(&mut self, _id: ComponentId) -> Result<(), Error> {
+        self.inner.pop_clip(self.target_id)
+    }
+
+    fn begin_direct(&mut self, _id: ComponentId) -> Result<(), Error> {
+        Err("Already rendering in direct render mode".into())
+    }
+
+    fn end_direct(&mut self, _id: ComponentId) -> Result<(), Error> {
+        Err("Already rendering in direct render mode".into())
+    }
+
+    fn text(
+        &mut self, _id: ComponentId,
+        text: &String, text_font: Option<&String>, text_size: Option<i32>,
+        position: Point2<f32>, size: Vector2<f32>, color: Color,
+    ) -> Result<(), Error> {
+        let color = self.fade(color);
+        self.inner.text(
+            self.target_id, text, text_font, text_size, position + self.offset, size, color,
+        )
+    }
+
+    fn measure_text(
+        &mut self, text: &String, text_font: Option<&String>, text_size: Option<i32>,
+    ) -> Result<Vector2<f32>, Error> {
+        self.inner.measure_text(text, text_font, text_size)
+    }
+
+    fn has_glyphs(
+        &mut self, text: &String, text_font: Option<&String>,
+    ) -> Result<bool, Error> {
+        self.inner.has_glyphs(text, text_font)
+    }
+
+    fn text_effects(
+        &mut self, _id: ComponentId,
+        text: &String, text_font: Option<&String>, text_size: Option<i32>,
+        position: Point2<f32>, size: Vector2<f32>, effects: &TextEffects,
+    ) -> Result<(), Error> {
+        let mut effects = *effects;
+        if let Some(ref mut outline) = effects.outline {
+            outline.color = self.fade(outline.color);
+        }
+        if let Some(ref mut shadow) = effects.shadow {
+            shadow.color = self.fade(shadow.color);
+        }
+        self.inner.text_effects(
+            self.target_id, text, text_font, text_size, position + self.offset, size, &effects,
+        )
+    }
+
+    fn vertices(
+        &mut self, _id: ComponentId,
+        vertices: &[Point2<f32>], indices: &[u16], color: Color,
+    ) -> Result<(), Error> {
+        let vertices: Vec<_> = vertices.iter().map(|v| *v + self.offset).collect();
+        let color = self.fade(color);
+        self.inner.vertices(self.target_id, &vertices, indices, color)
+    }
+
+    fn ellipse(
+        &mut self, _id: ComponentId, center: Point2<f32>, radii: Vector2<f32>, color: Color,
+    ) -> Result<(), Error> {
+        let color = self.fade(color);
+        self.inner.ellipse(self.target_id, center + self.offset, radii, color)
+    }
+
+    fn image(
+        &mut self, _id: ComponentId,
+        image: &String, source: Rect, destination: Rect, color: Color,
+    ) -> Result<(), Error> {
+        let destination = Rect::new(destination.position + self.offset, destination.size);
+        let color = self.fade(color);
+        self.inner.image(self.target_id, image, source, destination, color)
+    }
+
+    fn image_size(&mut self, image: &String) -> Result<Vector2<u32>, Error> {
+        self.inner.image_size(image)
+    }
+
+    fn line(
+        &mut self, _id: ComponentId, points: &[Point2<f32>], width: f32, color: Color,
+    ) -> Result<(), Error> {
+        let points: Vec<_> = points.iter().map(|v| *v + self.offset).collect();
+        let color = self.fade(color);
+        self.inner.line(self.target_id, &points, width, color)
+    }
+}
@@ -0,0 +1,49 @@
+use scripting::{ScriptRuntime};
+use template::{TemplateValue};
+use {Error};
+
+/// A named post-effect applied when a component's cache is composited, such as `"blur"`,
+/// `"grayscale"`, or `"outline"`. What effects are available and what their values mean is up to
+/// the renderer backend, backends may ignore effects they don't support.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Effect {
+    pub name: String,
+    pub values: Vec<f32>,
+}
+
+impl Effect {
+    pub fn new<S: Into<String>>(name: S, values: Vec<f32>) -> Self {
+        Effect {
+            name: name.into(),
+            values,
+        }
+    }
+
+    /// Gets an effect from either just a name, or a tuple of a name followed by float values.
+    pub fn from_value(value: &TemplateValue, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        if let TemplateValue::Tuple(ref values) = *value {
+            if values.len() == 0 {
+                return Err("Tuple is incorrect size".into())
+            }
+
+            let name = values[0].as_string(runtime)
+                .map_err(|e| Error::new_value("Value 1", e))?;
+            let mut effect_values = Vec::new();
+            for (i, value) in values.iter().enumerate().skip(1) {
+                effect_values.push(
+                    value.as_float(runtime)
+                        .map_err(|e| Error::new_value(&format!("Value {}", i + 1), e))?
+                );
+            }
+
+            Ok(Effect::new(name, effect_values))
+        } else {
+            Ok(Effect::new(value.as_string(runtime)?, Vec::new()))
+        }
+    }
+
+    /// Gets a value of the effect, or the default if the value isn't given.
+    pub fn value(&self, index: usize, default: f32) -> f32 {
+        self.values.get(index).cloned().unwrap_or(default)
+    }
+}
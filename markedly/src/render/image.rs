@@ -0,0 +1,106 @@
+use nalgebra::{Point2, Vector2};
+
+use render::{Renderer, Rect};
+use scripting::{ScriptRuntime};
+use template::{Color, TemplateValue};
+use {ComponentId, Error};
+
+/// How an image is mapped onto the area it's rendered in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillMode {
+    /// Stretches the image to exactly fill the area, ignoring its aspect ratio.
+    Stretch,
+    /// Repeats the image at its original size, starting from the top left.
+    Tile,
+    /// Scales the image while keeping its aspect ratio to cover the full area, cropping the
+    /// parts that fall outside of it.
+    Cover,
+    /// Scales the image while keeping its aspect ratio to fit inside the area, centered.
+    Contain,
+}
+
+impl FillMode {
+    pub fn from_value(value: &TemplateValue, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        match value.as_string(runtime)?.as_str() {
+            "stretch" => Ok(FillMode::Stretch),
+            "tile" => Ok(FillMode::Tile),
+            "cover" => Ok(FillMode::Cover),
+            "contain" => Ok(FillMode::Contain),
+            _ => Err(
+                "Value must be either \"stretch\", \"tile\", \"cover\", or \"contain\"".into()
+            )
+        }
+    }
+}
+
+/// Renders an image to an area of the component's cache using a fill mode, tinted by a color.
+pub fn image(
+    renderer: &mut Renderer, id: ComponentId,
+    image: &String, area: Rect, fill_mode: FillMode, color: Color,
+) -> Result<(), Error> {
+    let full = Rect::new(Point2::new(0.0, 0.0), Vector2::new(1.0, 1.0));
+
+    // Stretching doesn't need to know anything about the image
+    if fill_mode == FillMode::Stretch {
+        return renderer.image(id, image, full, area, color)
+    }
+
+    let image_size = renderer.image_size(image)?;
+    let image_size = Vector2::new(image_size.x as f32, image_size.y as f32);
+    if image_size.x <= 0.0 || image_size.y <= 0.0 || area.size.x <= 0.0 || area.size.y <= 0.0 {
+        return Ok(())
+    }
+
+    match fill_mode {
+        FillMode::Stretch => unreachable!(),
+        FillMode::Tile => {
+            // Every tile is cropped to the area, so the tiles on the far edges are partial
+            let tiles = Vector2::new(
+                (area.size.x / image_size.x).ceil() as i32,
+                (area.size.y / image_size.y).ceil() as i32,
+            );
+            for y in 0..tiles.y {
+                for x in 0..tiles.x {
+                    let tile = Rect::new(
+                        area.position +
+                            Vector2::new(x as f32 * image_size.x, y as f32 * image_size.y),
+                        image_size,
+                    );
+                    let destination = match tile.intersection(&area) {
+                        Some(destination) => destination,
+                        None => continue,
+                    };
+                    let source = Rect::new(
+                        Point2::new(0.0, 0.0),
+                        Vector2::new(
+                            destination.size.x / image_size.x, destination.size.y / image_size.y,
+                        ),
+                    );
+
+                    renderer.image(id, image, source, destination, color)?;
+                }
+            }
+
+            Ok(())
+        },
+        FillMode::Cover => {
+            // Crop the part of the image that falls outside of the area, keeping it centered
+            let scale = (area.size.x / image_size.x).max(area.size.y / image_size.y);
+            let visible = Vector2::new(
+                area.size.x / (image_size.x * scale), area.size.y / (image_size.y * scale),
+            );
+            let source = Rect::new(
+                Point2::new((1.0 - visible.x) * 0.5, (1.0 - visible.y) * 0.5), visible,
+            );
+
+            renderer.image(id, image, source, area, color)
+        },
+        FillMode::Contain => {
+            let scale = (area.size.x / image_size.x).min(area.size.y / image_size.y);
+            let size = image_size * scale;
+            let destination = Rect::new(area.position + (area.size - size) * 0.5, size);
+
+            renderer.image(id, image, full, destination, color)
+        },
+    }
+}
@@ -1,12 +1,52 @@
 //! Rendering functions and backend traits.
 
+mod blend;
+mod debug;
+mod direct;
+mod effect;
+mod image;
+pub mod path;
+mod pixels;
+mod recording;
+mod rect;
+mod retained;
+mod shapes;
+mod stats;
+mod text;
+mod transform;
+
+pub use self::blend::{BlendMode};
+pub use self::effect::{Effect};
+pub use self::image::{FillMode, image};
+pub use self::pixels::{PixelBuffer};
+pub use self::recording::{RecordingRenderer, RenderCommand};
+pub use self::rect::{Rect};
+pub use self::shapes::{
+    CornerRadii, Sides, rectangle, rounded_rectangle, border,
+    ellipse_geometry, ring_geometry, line_geometry,
+};
+pub use self::stats::{RenderStats};
+pub use self::text::{
+    RichText, TextSpan, TextStyle, TextEffects, TextOutline, TextShadow, rich_text,
+    measure_rich_text,
+};
+pub use self::transform::{RootTransform};
+
+use std::any::{Any};
+use std::time::{Instant};
+
 use nalgebra::{Point2, Vector2};
+use self::stats::{StatsRenderer};
 use template::{Color};
-use {ComponentId, Ui, Error, ComponentFlow};
+use {ComponentId, Ui, Error, ComponentFlow, Warnings, Warning};
 
 /// A renderer backend, implements how individual rendering operations are done.
 pub trait Renderer {
-    fn render_cache_to_target(&mut self, id: ComponentId) -> Result<(), Error>;
+    /// Renders a layer's cache to the target, scaled by the UI's root transform and then moved
+    /// by its offset.
+    fn render_cache_to_target(
+        &mut self, id: ComponentId, transform: RootTransform,
+    ) -> Result<(), Error>;
 
     /// Returns true if the cache is empty.
     fn create_resize_cache(
@@ -15,11 +55,53 @@ pub trait Renderer {
 
     fn clear_cache(&mut self, id: ComponentId) -> Result<(), Error>;
 
+    /// Frees the cache of a component that was removed from its UI. The default implementation
+    /// does nothing, for backends that don't hold on to resources per component.
+    fn remove_cache(&mut self, _id: ComponentId) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Reads back the pixels of the component's cache, used for screenshots and image based
+    /// tests. Backends that can't read back their caches return an error, which is what the
+    /// default implementation does.
+    fn read_cache_pixels(&mut self, _id: ComponentId) -> Result<PixelBuffer, Error> {
+        Err("Reading cache pixels is not supported by this renderer".into())
+    }
+
+    /// Renders the cache of a source component to the component's cache, with the source's alpha
+    /// multiplied by the given alpha, combined with what's already rendered using a blend mode.
+    /// Backends that don't support a blend mode should render with `BlendMode::Alpha` instead.
     fn render_cache(
         &mut self, id: ComponentId,
-        source_id: ComponentId, position: Point2<f32>
+        source_id: ComponentId, position: Point2<f32>, alpha: f32, blend_mode: BlendMode,
     ) -> Result<(), Error>;
 
+    /// Renders the cache of a source component to the component's cache with a post-effect
+    /// applied. Backends that don't support the effect should render the cache without it, which
+    /// is what the default implementation does.
+    fn composite_with_effect(
+        &mut self, id: ComponentId,
+        source_id: ComponentId, position: Point2<f32>, alpha: f32, _effect: &Effect,
+    ) -> Result<(), Error> {
+        self.render_cache(id, source_id, position, alpha, BlendMode::Alpha)
+    }
+
+    /// Starts clipping rendering to the component's cache to an area, until the matching
+    /// `pop_clip`. Clips can be nested, in which case the new clip is limited to the area of the
+    /// clip it's nested in.
+    fn push_clip(&mut self, id: ComponentId, rect: Rect) -> Result<(), Error>;
+
+    /// Stops clipping to the area set by the last `push_clip`.
+    fn pop_clip(&mut self, id: ComponentId) -> Result<(), Error>;
+
+    /// Starts rendering directly to the target, used by the direct render mode. Until
+    /// `end_direct` is called, operations for the given ID should be rendered to the target
+    /// rather than to a cache.
+    fn begin_direct(&mut self, id: ComponentId) -> Result<(), Error>;
+
+    /// Stops rendering directly to the target.
+    fn end_direct(&mut self, id: ComponentId) -> Result<(), Error>;
+
     /// Renders text centered in an area to the component's cache.
     /// Font is a string identifier that should be resolved by the renderer's font cache.
     fn text(
@@ -28,37 +110,279 @@ pub trait Renderer {
         position: Point2<f32>, size: Vector2<f32>, color: Color,
     ) -> Result<(), Error>;
 
+    /// Measures the size text would take up if rendered.
+    /// Font is a string identifier that should be resolved by the renderer's font cache.
+    fn measure_text(
+        &mut self, text: &String, text_font: Option<&String>, text_size: Option<i32>,
+    ) -> Result<Vector2<f32>, Error>;
+
+    /// Returns true if the font has glyphs for every character in the text, used to fall back
+    /// to other fonts for characters the font doesn't have. Backends that can't tell assume the
+    /// font has every glyph, which is what the default implementation does.
+    /// Font is a string identifier that should be resolved by the renderer's font cache.
+    fn has_glyphs(
+        &mut self, _text: &String, _text_font: Option<&String>,
+    ) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    /// Renders the outline and shadow of text centered in an area to the component's cache,
+    /// called before rendering the text itself. The default implementation renders the text
+    /// again at offsets in the effects' colors, backends with native text effects can override
+    /// this.
+    /// Font is a string identifier that should be resolved by the renderer's font cache.
+    fn text_effects(
+        &mut self, id: ComponentId,
+        text: &String, text_font: Option<&String>, text_size: Option<i32>,
+        position: Point2<f32>, size: Vector2<f32>, effects: &TextEffects,
+    ) -> Result<(), Error> {
+        if let Some(shadow) = effects.shadow {
+            self.text(
+                id, text, text_font, text_size, position + shadow.offset, size, shadow.color,
+            )?;
+        }
+
+        if let Some(outline) = effects.outline {
+            for offset in outline.offsets() {
+                self.text(id, text, text_font, text_size, position + offset, size, outline.color)?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Renders vertices to the component's cache.
     fn vertices(
         &mut self, id: ComponentId,
         vertices: &[Point2<f32>], indices: &[u16], color: Color,
     ) -> Result<(), Error>;
+
+    /// Renders part of an image to an area of the component's cache, tinted by a color. The
+    /// source is in coordinates relative to the image's size, from 0.0 to 1.0.
+    /// Image is a string identifier that should be resolved by the renderer's image cache.
+    fn image(
+        &mut self, _id: ComponentId,
+        _image: &String, _source: Rect, _destination: Rect, _color: Color,
+    ) -> Result<(), Error> {
+        Err("Images are not supported by this renderer".into())
+    }
+
+    /// Gets the size of an image in pixels.
+    /// Image is a string identifier that should be resolved by the renderer's image cache.
+    fn image_size(&mut self, _image: &String) -> Result<Vector2<u32>, Error> {
+        Err("Images are not supported by this renderer".into())
+    }
+
+    /// Renders a filled ellipse to the component's cache. The default implementation tessellates
+    /// the ellipse and renders it as vertices, backends with a native ellipse primitive can
+    /// override this.
+    fn ellipse(
+        &mut self, id: ComponentId, center: Point2<f32>, radii: Vector2<f32>, color: Color,
+    ) -> Result<(), Error> {
+        let (vertices, indices) = ellipse_geometry(center, radii);
+        self.vertices(id, &vertices, &indices, color)
+    }
+
+    /// Renders a line through a sequence of points with a width to the component's cache. The
+    /// default implementation tessellates the line and renders it as vertices, backends with a
+    /// native line primitive can override this.
+    fn line(
+        &mut self, id: ComponentId, points: &[Point2<f32>], width: f32, color: Color,
+    ) -> Result<(), Error> {
+        let (vertices, indices) = line_geometry(points, width);
+        self.vertices(id, &vertices, &indices, color)
+    }
+
+    /// Renders a backend-specific command to the component's cache, for component classes that
+    /// need drawing the other operations can't do, such as particles or video frames. Backends
+    /// downcast the command to the types they know and ignore anything else, which is what the
+    /// default implementation does.
+    ///
+    /// ```rust,ignore
+    /// fn custom(&mut self, id: ComponentId, command: &Any) -> Result<(), Error> {
+    ///     if let Some(particles) = command.downcast_ref::<Particles>() {
+    ///         self.draw_particles(id, particles)?;
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// Custom commands can't be recorded or moved to the component's position, so they're only
+    /// rendered in the cached render mode.
+    fn custom(&mut self, _id: ComponentId, _command: &Any) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// How a UI is rendered using the renderer backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Every component has its own cache, which is only re-rendered when the component changes.
+    /// This is the fastest mode for mostly static UIs, but needs a render target per component.
+    Cached,
+    /// Components record the operations they render into retained command lists, which are
+    /// only re-recorded when the component changes. The lists are replayed every frame into a
+    /// single cache, avoiding a render target per component.
+    Retained,
+    /// Components are rendered straight to the target every frame, without any caches. This
+    /// avoids the overhead of render targets entirely, which can be faster on low-end targets
+    /// with large UIs.
+    Direct,
 }
 
 /// Renders a UI using a renderer backend.
 pub fn render<R: Renderer>(
     renderer: &mut R, ui: &mut Ui
 ) -> Result<(), Error> {
-    // TODO: Clear the cache of elements that don't exist anymore
+    render_ui(renderer, ui, &mut RenderStats::default(), false)?;
+    Ok(())
+}
 
-    let root_id = ui.root_id();
+/// Renders a UI using a renderer backend, but only renders to the target if anything in the UI
+/// changed since the last render. Returns true if the target was rendered to.
+///
+/// This is useful for targets that keep their contents between frames, such as a static menu
+/// where the game skips presenting when nothing changed, to avoid redrawing needlessly.
+pub fn render_changed<R: Renderer>(
+    renderer: &mut R, ui: &mut Ui
+) -> Result<bool, Error> {
+    render_ui(renderer, ui, &mut RenderStats::default(), true)
+}
 
-    // Update the components' caches recursively, then render the final cache to the target
-    let size = ui.target_size();
-    update_component_cache(renderer, ui, root_id, size)?;
-    renderer.render_cache_to_target(root_id)?;
+/// Renders a UI using a renderer backend, the same as `render`, while gathering statistics on
+/// what was rendered and how long it took.
+pub fn render_with_stats<R: Renderer>(
+    renderer: &mut R, ui: &mut Ui
+) -> Result<RenderStats, Error> {
+    let mut stats = RenderStats::default();
+    let mut renderer = StatsRenderer {
+        inner: renderer,
+        primitives: 0,
+        caches_composited: 0,
+    };
+
+    render_ui(&mut renderer, ui, &mut stats, false)?;
+
+    stats.primitives = renderer.primitives;
+    stats.caches_composited = renderer.caches_composited;
+    Ok(stats)
+}
+
+/// Renders a UI into its root cache and reads back the pixels, without rendering to the target.
+/// This can be used to take screenshots of a UI, or to compare against known good images in
+/// tests.
+///
+/// The renderer backend has to support reading cache pixels, and the UI can't be using the
+/// direct render mode, as that doesn't have any caches to read back.
+pub fn render_to_image<R: Renderer>(
+    renderer: &mut R, ui: &mut Ui
+) -> Result<PixelBuffer, Error> {
+    if ui.render_mode() == RenderMode::Direct {
+        return Err("Can not render to an image in the direct render mode".into())
+    }
+
+    let layers = ui.visible_layers();
+    let base_id = *layers.first().ok_or("Can not render to an image without visible layers")?;
+    update_caches(renderer, ui, &layers, &mut RenderStats::default(), false)?;
+
+    if layers.len() == 1 {
+        return renderer.read_cache_pixels(base_id)
+    }
+
+    // Flatten the layers on top of the lowest one, which will need to be rendered again after
+    for layer_id in &layers[1..] {
+        renderer.render_cache(base_id, *layer_id, Point2::new(0.0, 0.0), 1.0, BlendMode::Alpha)?;
+    }
+    let pixels = renderer.read_cache_pixels(base_id);
+    ui.get_mut(base_id).unwrap().mark_needs_rendering();
+
+    pixels
+}
+
+fn render_ui<R: Renderer>(
+    renderer: &mut R, ui: &mut Ui, stats: &mut RenderStats, only_changed: bool,
+) -> Result<bool, Error> {
+    // Free the caches of components that don't exist anymore
+    for id in ui.take_removed() {
+        renderer.remove_cache(id)?;
+    }
+
+    let layers = ui.visible_layers();
+
+    // Direct rendering has nothing to keep track of between frames, so it always renders
+    if ui.render_mode() == RenderMode::Direct {
+        let start = Instant::now();
+        for layer_id in &layers {
+            direct::render(renderer, ui, *layer_id, stats)?;
+        }
+        render_debug_overlay(renderer, ui, &layers)?;
+        stats.target_time = start.elapsed();
+
+        ui.mark_all_rendered();
+        return Ok(true)
+    }
+
+    let changed = update_caches(renderer, ui, &layers, stats, only_changed)?;
+
+    // Render the final caches of the layers to the target, if any layer changed the target has
+    // to be rendered again completely, the debug overlay can change without the UI changing so
+    // it always has to be rendered again
+    let start = Instant::now();
+    let render_target = changed || !only_changed || ui.debug_overlay();
+    if render_target {
+        for layer_id in &layers {
+            renderer.render_cache_to_target(*layer_id, ui.root_transform())?;
+        }
+        render_debug_overlay(renderer, ui, &layers)?;
+    }
+    stats.target_time = start.elapsed();
+
+    Ok(render_target)
+}
+
+fn render_debug_overlay<R: Renderer>(
+    renderer: &mut R, ui: &Ui, layers: &[ComponentId],
+) -> Result<(), Error> {
+    if ui.debug_overlay() {
+        for layer_id in layers {
+            debug::render(renderer, ui, *layer_id)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Updates the components' caches, or command lists, recursively, leaving each layer in its root
+/// component's cache.
+fn update_caches<R: Renderer>(
+    renderer: &mut R, ui: &mut Ui, layers: &[ComponentId], stats: &mut RenderStats,
+    only_changed: bool,
+) -> Result<bool, Error> {
+    let start = Instant::now();
+    let mut changed = ui.layers_changed();
+    for layer_id in layers {
+        changed |= if ui.render_mode() == RenderMode::Cached {
+            let size = ui.target_size();
+            update_component_cache(renderer, ui, *layer_id, size, stats)?
+        } else {
+            retained::update(renderer, ui, *layer_id, only_changed, stats)?
+        };
+    }
+    stats.update_time = start.elapsed();
 
     // Mark all components all not needing updating anymore
     ui.mark_all_rendered();
 
-    Ok(())
+    Ok(changed)
 }
 
 fn update_component_cache<R: Renderer>(
     renderer: &mut R, ui: &Ui, component_id: ComponentId, parent_size: Vector2<f32>,
+    stats: &mut RenderStats,
 ) -> Result<bool, Error> {
+    stats.components_visited += 1;
     let component = ui.get(component_id).unwrap();
-    let computed_size = component.attributes().compute_size(parent_size);
+    let computed_size = component.compute_size(parent_size);
 
     // Make sure this component's cache is created and of the correct size
     let cache_empty = renderer.create_resize_cache(component_id, Vector2::new(
@@ -68,27 +392,78 @@ fn update_component_cache<R: Renderer>(
 
     // Make sure all children's caches are up-to-date
     let mut child_updated = false;
-    for child_id in component.children() {
-        child_updated |= update_component_cache(renderer, ui, *child_id, computed_size)?;
+    for (index, child_id) in component.children().iter().enumerate() {
+        // Invisible children don't need a cache, but their parent does need to render again
+        // without them if they just changed
+        let child = ui.get(*child_id).unwrap();
+        if !child.attributes().is_visible() {
+            child_updated |= child.needs_rendering();
+            continue
+        }
+
+        let area_size = component.child_area(index, computed_size).size;
+        child_updated |= update_component_cache(renderer, ui, *child_id, area_size, stats)?;
     }
 
     // Only render if we need to
     if cache_empty || child_updated || component.needs_rendering() {
+        stats.caches_rendered += 1;
+        trace!("Rendering cache of component {:?} ({})", component_id, component.label());
         renderer.clear_cache(component_id)?;
 
         // Let the component's class render itself to the component's cache
-        component.render(component_id, computed_size, renderer)?;
+        warn_missing_resource(
+            component.render(component_id, computed_size, renderer), ui.warnings(),
+        )?;
 
-        // Render all children caches in sequence to this component
-        let mut flow = ComponentFlow::new(computed_size);
+        // Render all children caches in sequence to this component, clipped to this component so
+        // children larger than it don't overflow
+        renderer.push_clip(component_id, Rect::new(Point2::new(0.0, 0.0), computed_size))?;
+        let mut flow = ComponentFlow::for_children(component, computed_size);
         for child_id in component.children() {
             let child = ui.get(*child_id).unwrap();
-            let computed_position = child.attributes().compute_position(computed_size, &mut flow);
-            renderer.render_cache(component_id, *child_id, computed_position)?;
+            let child_rect = child.compute_layout(&mut flow);
+            let computed_position = child_rect.position;
+            if !child.attributes().is_visible() {
+                continue
+            }
+
+            let opacity = child.attributes().opacity;
+
+            // Shadows fall outside of the child's own cache, so they're rendered by the parent
+            if let Some(ref shadow) = child.attributes().shadow {
+                shadow.render(component_id, child_rect, opacity, renderer)?;
+            }
+
+            if let Some(effect) = child.effect() {
+                renderer.composite_with_effect(
+                    component_id, *child_id, computed_position, opacity, effect,
+                )?;
+            } else {
+                renderer.render_cache(
+                    component_id, *child_id, computed_position, opacity,
+                    child.attributes().blend_mode,
+                )?;
+            }
         }
+        renderer.pop_clip(component_id)?;
 
         Ok(true)
     } else {
         Ok(false)
     }
 }
+
+/// Turns errors about missing resources into warnings, so a missing image doesn't stop the rest
+/// of the UI from rendering.
+pub(crate) fn warn_missing_resource(
+    result: Result<(), Error>, warnings: &Warnings,
+) -> Result<(), Error> {
+    match result {
+        Err(Error::Resource { resource, error }) => {
+            warnings.push(Warning::MissingResource { resource, error });
+            Ok(())
+        },
+        result => result,
+    }
+}
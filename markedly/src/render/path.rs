@@ -0,0 +1,257 @@
+//! Arbitrary filled and stroked paths, tessellated for rendering through `Renderer::vertices`.
+
+use nalgebra::{Point2, Vector2};
+use lyon::math::{point};
+use lyon::path::{Path as LPath, Builder as LBuilder};
+use lyon::path::builder::{FlatPathBuilder, PathBuilder as LPathBuilder};
+use lyon::tessellation as lt;
+
+use render::{Renderer, Rect};
+use template::{Color};
+use {ComponentId, Error};
+
+/// A path made up of lines and curves, which can be filled or stroked.
+///
+/// ```rust,ignore
+/// let path = Path::builder()
+///     .move_to(Point2::new(0.0, 0.0))
+///     .line_to(Point2::new(10.0, 0.0))
+///     .quadratic_to(Point2::new(10.0, 10.0), Point2::new(0.0, 10.0))
+///     .close()
+///     .build();
+/// path.fill(renderer, id, color)?;
+/// ```
+pub struct Path {
+    path: LPath,
+}
+
+impl Path {
+    pub fn builder() -> PathBuilder {
+        PathBuilder {
+            builder: LPath::builder(),
+        }
+    }
+
+    /// Parses SVG path data, such as `"M 0 0 L 10 0 L 5 10 Z"`, mapping its coordinates from a
+    /// view box onto an area. Move, line, horizontal, vertical, quadratic, cubic, and close
+    /// commands are supported, both absolute and relative, but arcs and smooth curves aren't.
+    pub fn from_svg(data: &str, view_box: Rect, area: Rect) -> Result<Path, Error> {
+        let scale = Vector2::new(
+            area.size.x / view_box.size.x, area.size.y / view_box.size.y,
+        );
+        let map = |p: Point2<f32>| area.position + (p - view_box.position).component_mul(&scale);
+
+        let tokens = tokenize(data)?;
+        let mut builder = Path::builder();
+        let mut current = Point2::new(0.0, 0.0);
+        let mut start = current;
+        let mut command = None;
+        let mut i = 0;
+
+        while i < tokens.len() {
+            let c = match tokens[i] {
+                SvgToken::Command(c) => {
+                    i += 1;
+                    c
+                },
+                // Numbers without a command repeat the last one, with moves repeating as lines
+                SvgToken::Number(_) => match command {
+                    Some('M') => 'L',
+                    Some('m') => 'l',
+                    Some('Z') | Some('z') | None =>
+                        return Err("Path data has a number without a command".into()),
+                    Some(c) => c,
+                },
+            };
+            command = Some(c);
+
+            let origin = if c.is_ascii_lowercase() { current.coords } else { Vector2::zeros() };
+            let at = |n: &[f32], offset: usize| {
+                Point2::new(origin.x + n[offset], origin.y + n[offset + 1])
+            };
+
+            match c.to_ascii_uppercase() {
+                'M' => {
+                    let n = svg_numbers(&tokens, &mut i, 2)?;
+                    current = at(&n, 0);
+                    start = current;
+                    builder = builder.move_to(map(current));
+                },
+                'L' => {
+                    let n = svg_numbers(&tokens, &mut i, 2)?;
+                    current = at(&n, 0);
+                    builder = builder.line_to(map(current));
+                },
+                'H' => {
+                    let n = svg_numbers(&tokens, &mut i, 1)?;
+                    current = Point2::new(origin.x + n[0], current.y);
+                    builder = builder.line_to(map(current));
+                },
+                'V' => {
+                    let n = svg_numbers(&tokens, &mut i, 1)?;
+                    current = Point2::new(current.x, origin.y + n[0]);
+                    builder = builder.line_to(map(current));
+                },
+                'Q' => {
+                    let n = svg_numbers(&tokens, &mut i, 4)?;
+                    let control = at(&n, 0);
+                    current = at(&n, 2);
+                    builder = builder.quadratic_to(map(control), map(current));
+                },
+                'C' => {
+                    let n = svg_numbers(&tokens, &mut i, 6)?;
+                    let control1 = at(&n, 0);
+                    let control2 = at(&n, 2);
+                    current = at(&n, 4);
+                    builder = builder.cubic_to(map(control1), map(control2), map(current));
+                },
+                'Z' => {
+                    current = start;
+                    builder = builder.close();
+                },
+                _ => return Err(format!("Path command '{}' is not supported", c).into()),
+            }
+        }
+
+        Ok(builder.build())
+    }
+
+    /// Renders the path filled to the component's cache.
+    pub fn fill(
+        &self, renderer: &mut Renderer, id: ComponentId, color: Color,
+    ) -> Result<(), Error> {
+        let mut geometry = lt::VertexBuffers::new();
+        lt::FillTessellator::new().tessellate_path(
+            self.path.path_iter(),
+            &lt::FillOptions::tolerance(0.1),
+            &mut lt::geometry_builder::simple_builder(&mut geometry),
+        ).map_err(|_| Error::from("Path could not be tessellated for filling"))?;
+
+        let vertices: Vec<_> = geometry.vertices.into_iter()
+            .map(|v| Point2::new(v.position.x, v.position.y)).collect();
+        renderer.vertices(id, &vertices, &geometry.indices, color)
+    }
+
+    /// Renders the outline of the path with a width to the component's cache.
+    pub fn stroke(
+        &self, renderer: &mut Renderer, id: ComponentId, width: f32, color: Color,
+    ) -> Result<(), Error> {
+        if !width.is_finite() || width < 0.0 {
+            return Err(format!("Path can not be stroked with a width of {}", width).into())
+        }
+
+        let mut geometry = lt::VertexBuffers::new();
+        lt::StrokeTessellator::new().tessellate_path(
+            self.path.path_iter(),
+            &lt::StrokeOptions::tolerance(0.1).with_line_width(width),
+            &mut lt::geometry_builder::simple_builder(&mut geometry),
+        ).map_err(|_| Error::from("Path could not be tessellated for stroking"))?;
+
+        let vertices: Vec<_> = geometry.vertices.into_iter()
+            .map(|v| Point2::new(v.position.x, v.position.y)).collect();
+        renderer.vertices(id, &vertices, &geometry.indices, color)
+    }
+}
+
+enum SvgToken {
+    Command(char),
+    Number(f32),
+}
+
+/// Splits SVG path data into commands and numbers, which may be separated by spaces, commas, or
+/// nothing at all if the next number starts with a sign.
+fn tokenize(data: &str) -> Result<Vec<SvgToken>, Error> {
+    let mut tokens = Vec::new();
+    let mut number = String::new();
+
+    for c in data.chars() {
+        if c.is_ascii_digit() || c == '.' || ((c == '-' || c == '+') && number.is_empty()) {
+            number.push(c);
+            continue
+        }
+
+        push_number(&mut tokens, &mut number)?;
+        if c == '-' || c == '+' {
+            number.push(c);
+        } else if c.is_ascii_alphabetic() {
+            tokens.push(SvgToken::Command(c));
+        } else if !c.is_whitespace() && c != ',' {
+            return Err(format!("Path data has an unexpected character '{}'", c).into())
+        }
+    }
+    push_number(&mut tokens, &mut number)?;
+
+    Ok(tokens)
+}
+
+fn push_number(tokens: &mut Vec<SvgToken>, number: &mut String) -> Result<(), Error> {
+    if !number.is_empty() {
+        let value = number.parse()
+            .map_err(|_| format!("Path data has an invalid number \"{}\"", number))?;
+        tokens.push(SvgToken::Number(value));
+        number.clear();
+    }
+
+    Ok(())
+}
+
+/// Reads the numbers a path command takes.
+fn svg_numbers(tokens: &[SvgToken], i: &mut usize, count: usize) -> Result<Vec<f32>, Error> {
+    let mut numbers = Vec::with_capacity(count);
+    for _ in 0..count {
+        match tokens.get(*i) {
+            Some(&SvgToken::Number(value)) => numbers.push(value),
+            _ => return Err("Path data has a command with too few numbers".into()),
+        }
+        *i += 1;
+    }
+
+    Ok(numbers)
+}
+
+/// Builds up a path segment by segment, created using `Path::builder`.
+pub struct PathBuilder {
+    builder: LBuilder,
+}
+
+impl PathBuilder {
+    /// Starts a new sub-path at a position, without connecting it to the previous one.
+    pub fn move_to(mut self, to: Point2<f32>) -> Self {
+        self.builder.move_to(point(to.x, to.y));
+        self
+    }
+
+    /// Adds a straight line to a position.
+    pub fn line_to(mut self, to: Point2<f32>) -> Self {
+        self.builder.line_to(point(to.x, to.y));
+        self
+    }
+
+    /// Adds a quadratic bezier curve to a position, bending towards the control point.
+    pub fn quadratic_to(mut self, control: Point2<f32>, to: Point2<f32>) -> Self {
+        self.builder.quadratic_bezier_to(point(control.x, control.y), point(to.x, to.y));
+        self
+    }
+
+    /// Adds a cubic bezier curve to a position, bending towards the two control points.
+    pub fn cubic_to(
+        mut self, control1: Point2<f32>, control2: Point2<f32>, to: Point2<f32>,
+    ) -> Self {
+        self.builder.cubic_bezier_to(
+            point(control1.x, control1.y), point(control2.x, control2.y), point(to.x, to.y),
+        );
+        self
+    }
+
+    /// Closes the current sub-path with a straight line back to where it started.
+    pub fn close(mut self) -> Self {
+        self.builder.close();
+        self
+    }
+
+    pub fn build(self) -> Path {
+        Path {
+            path: self.builder.build(),
+        }
+    }
+}
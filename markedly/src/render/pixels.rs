@@ -0,0 +1,48 @@
+use nalgebra::{Vector2};
+
+use template::{Color};
+use {Error};
+
+/// RGBA8 pixel data read back from a renderer, with rows ordered from top to bottom.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PixelBuffer {
+    size: Vector2<u32>,
+    data: Vec<u8>,
+}
+
+impl PixelBuffer {
+    /// Creates a pixel buffer, the data must contain exactly 4 bytes for every pixel.
+    pub fn new(size: Vector2<u32>, data: Vec<u8>) -> Result<Self, Error> {
+        if data.len() != (size.x * size.y * 4) as usize {
+            return Err(format!(
+                "Pixel data is {} bytes, expected {} for {}x{} pixels",
+                data.len(), size.x * size.y * 4, size.x, size.y,
+            ).into())
+        }
+
+        Ok(PixelBuffer {
+            size,
+            data,
+        })
+    }
+
+    pub fn size(&self) -> Vector2<u32> {
+        self.size
+    }
+
+    /// Gets the raw RGBA8 data, for example to encode it to an image file.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Gets the color of a pixel, or None if it's out of bounds.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<Color> {
+        if x >= self.size.x || y >= self.size.y {
+            return None
+        }
+
+        let index = ((y * self.size.x + x) * 4) as usize;
+        let channel = |offset: usize| self.data[index + offset] as f32 / 255.0;
+        Some(Color::new(channel(0), channel(1), channel(2), channel(3)))
+    }
+}
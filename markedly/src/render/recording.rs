@@ -0,0 +1,586 @@
+use nalgebra::{Point2, Vector2};
+use metrohash::{MetroHashMap};
+
+use render::{Renderer, Rect, Effect, BlendMode, RootTransform};
+use template::{Color};
+use {ComponentId, Error};
+
+/// A rendering operation recorded by the `RecordingRenderer`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderCommand {
+    RenderCache {
+        source_id: ComponentId, position: Point2<f32>, alpha: f32, effect: Option<Effect>,
+        blend_mode: BlendMode,
+    },
+    PushClip { rect: Rect },
+    PopClip,
+    Text {
+        text: String, font: Option<String>, text_size: Option<i32>,
+        position: Point2<f32>, size: Vector2<f32>, color: Color,
+    },
+    Vertices { vertices: Vec<Point2<f32>>, indices: Vec<u16>, color: Color },
+    Ellipse { center: Point2<f32>, radii: Vector2<f32>, color: Color },
+    Line { points: Vec<Point2<f32>>, width: f32, color: Color },
+    Image { image: String, source: Rect, destination: Rect, color: Color },
+}
+
+struct RecordedCache {
+    size: Vector2<u32>,
+    commands: Vec<RenderCommand>,
+}
+
+/// A headless renderer that records all operations per component, rather than drawing them.
+/// This allows asserting on rendering output in tests without needing a GPU.
+///
+/// Text is measured as if every character is half as wide as the text size is tall, and images
+/// are all 16 by 16 pixels, so results are deterministic.
+pub struct RecordingRenderer {
+    caches: MetroHashMap<ComponentId, RecordedCache>,
+    target: Option<ComponentId>,
+    target_transform: RootTransform,
+    default_text_size: i32,
+    /// The characters fonts have glyphs for, fonts not in here have every glyph.
+    font_glyphs: MetroHashMap<Option<String>, String>,
+}
+
+impl RecordingRenderer {
+    pub fn new() -> Self {
+        RecordingRenderer {
+            caches: MetroHashMap::default(),
+            target: None,
+            target_transform: RootTransform::identity(),
+            default_text_size: 14,
+            font_glyphs: MetroHashMap::default(),
+        }
+    }
+
+    /// Limits the characters a font has glyphs for, to test falling back to other fonts. Fonts
+    /// that aren't limited have a glyph for every character.
+    pub fn set_font_glyphs(&mut self, font: Option<&str>, characters: &str) {
+        self.font_glyphs.insert(font.map(|f| f.into()), characters.into());
+    }
+
+    /// Gets the commands recorded for a component since its cache was last cleared.
+    pub fn commands(&self, id: ComponentId) -> Option<&Vec<RenderCommand>> {
+        self.caches.get(&id).map(|c| &c.commands)
+    }
+
+    /// Gets the size of a component's cache.
+    pub fn cache_size(&self, id: ComponentId) -> Option<Vector2<u32>> {
+        self.caches.get(&id).map(|c| c.size)
+    }
+
+    /// Gets the component that was last rendered to the target.
+    pub fn target(&self) -> Option<ComponentId> {
+        self.target
+    }
+
+    /// Gets the root transform the target was last rendered with.
+    pub fn target_transform(&self) -> RootTransform {
+        self.target_transform
+    }
+
+    fn cache_mut(&mut self, id: ComponentId) -> Result<&mut RecordedCache, Error> {
+        self.caches.get_mut(&id)
+            .ok_or_else(|| format!("No cache exists for component {:?}", id).into())
+    }
+}
+
+impl Renderer for RecordingRenderer {
+    fn render_cache_to_target(
+        &mut self, id: ComponentId, transform: RootTransform,
+    ) -> Result<(), Error> {
+        self.cache_mut(id)?;
+        self.target = Some(id);
+        self.target_transform = transform;
+        Ok(())
+    }
+
+    fn begin_direct(&mut self, id: ComponentId) -> Result<(), Error> {
+        // Direct rendering is recorded as a cache for the ID that's rendered to the target
+        self.caches.insert(id, RecordedCache {
+            size: Vector2::new(0, 0),
+            commands: Vec::new(),
+        });
+        Ok(())
+    }
+
+    fn end_direct(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.target = Some(id);
+        Ok(())
+    }
+
+    fn create_resize_cache(
+        &mut self, id: ComponentId, size: Vector2<u32>
+    ) -> Result<bool, Error> {
+        if let Some(cache) = self.caches.get(&id) {
+            if cache.size == size {
+                return Ok(false)
+            }
+        }
+
+        self.caches.insert(id, RecordedCache {
+            size,
+            commands: Vec::new(),
+        });
+        Ok(true)
+    }
+
+    fn clear_cache(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.cache_mut(id)?.commands.clear();
+        Ok(())
+    }
+
+    fn remove_cache(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.caches.remove(&id);
+        Ok(())
+    }
+
+    fn render_cache(
+        &mut self, id: ComponentId,
+        source_id: ComponentId, position: Point2<f32>, alpha: f32, blend_mode: BlendMode,
+    ) -> Result<(), Error> {
+        self.cache_mut(id)?.commands.push(RenderCommand::RenderCache {
+            source_id, position, alpha, effect: None, blend_mode,
+        });
+        Ok(())
+    }
+
+    fn composite_with_effect(
+        &mut self, id: ComponentId,
+        source_id: ComponentId, position: Point2<f32>, alpha: f32, effect: &Effect,
+    ) -> Result<(), Error> {
+        self.cache_mut(id)?.commands.push(RenderCommand::RenderCache {
+            source_id, position, alpha, effect: Some(effect.clone()),
+            blend_mode: BlendMode::Alpha,
+        });
+        Ok(())
+    }
+
+    fn push_clip(&mut self, id: ComponentId, rect: Rect) -> Result<(), Error> {
+        self.cache_mut(id)?.commands.push(RenderCommand::PushClip { rect });
+        Ok(())
+    }
+
+    fn pop_clip(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.cache_mut(id)?.commands.push(RenderCommand::PopClip);
+        Ok(())
+    }
+
+    fn text(
+        &mut self, id: ComponentId,
+        text: &String, text_font: Option<&String>, text_size: Option<i32>,
+        position: Point2<f32>, size: Vector2<f32>, color: Color,
+    ) -> Result<(), Error> {
+        self.cache_mut(id)?.commands.push(RenderCommand::Text {
+            text: text.clone(),
+            font: text_font.cloned(),
+            text_size,
+            position, size, color,
+        });
+        Ok(())
+    }
+
+    fn measure_text(
+        &mut self, text: &String, _text_font: Option<&String>, text_size: Option<i32>,
+    ) -> Result<Vector2<f32>, Error> {
+        let text_size = text_size.unwrap_or(self.default_text_size) as f32;
+        Ok(Vector2::new(text.chars().count() as f32 * text_size * 0.5, text_size))
+    }
+
+    fn has_glyphs(
+        &mut self, text: &String, text_font: Option<&String>,
+    ) -> Result<bool, Error> {
+        Ok(self.font_glyphs.get(&text_font.cloned())
+            .map(|glyphs| text.chars().all(|c| glyphs.contains(c)))
+            .unwrap_or(true))
+    }
+
+    fn vertices(
+        &mut self, id: ComponentId,
+        vertices: &[Point2<f32>], indices: &[u16], color: Color,
+    ) -> Result<(), Error> {
+        self.cache_mut(id)?.commands.push(RenderCommand::Vertices {
+            vertices: vertices.to_vec(),
+            indices: indices.to_vec(),
+            color,
+        });
+        Ok(())
+    }
+
+    fn ellipse(
+        &mut self, id: ComponentId, center: Point2<f32>, radii: Vector2<f32>, color: Color,
+    ) -> Result<(), Error> {
+        self.cache_mut(id)?.commands.push(RenderCommand::Ellipse { center, radii, color });
+        Ok(())
+    }
+
+    fn image(
+        &mut self, id: ComponentId,
+        image: &String, source: Rect, destination: Rect, color: Color,
+    ) -> Result<(), Error> {
+        self.cache_mut(id)?.commands.push(RenderCommand::Image {
+            image: image.clone(),
+            source, destination, color,
+        });
+        Ok(())
+    }
+
+    fn image_size(&mut self, _image: &String) -> Result<Vector2<u32>, Error> {
+        Ok(Vector2::new(16, 16))
+    }
+
+    fn line(
+        &mut self, id: ComponentId, points: &[Point2<f32>], width: f32, color: Color,
+    ) -> Result<(), Error> {
+        self.cache_mut(id)?.commands.push(RenderCommand::Line {
+            points: points.to_vec(),
+            width,
+            color,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Point2, Vector2};
+
+    use class::{ButtonClass, VectorImageClass, GaugeClass, ChartClass};
+    use input::{Input, Modifiers, PressPosition};
+    use render::{self, RecordingRenderer, RenderCommand, BlendMode, RootTransform};
+    use scripting::{ScriptTable, ScriptValue};
+    use template::{Template, Style, Color};
+    use {Ui, Context};
+
+    #[test]
+    fn it_records_background_and_children() {
+        let context = Context::for_test();
+        let (mut ui, _tree) = Ui::for_test(
+            "container { color: (255, 0, 0) }\n    container { size: (10.0, 10.0) }\n",
+            "", &context,
+        );
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+
+        let root_id = ui.root_id();
+        assert_eq!(renderer.target(), Some(root_id));
+        assert_eq!(renderer.cache_size(root_id), Some(Vector2::new(100, 50)));
+
+        let commands = renderer.commands(root_id).unwrap();
+        match commands[0] {
+            RenderCommand::Vertices { ref vertices, .. } => assert_eq!(vertices.len(), 4),
+            ref command => panic!("Unexpected command {:?}", command),
+        }
+        assert!(commands.iter().any(|c| match *c {
+            RenderCommand::RenderCache { .. } => true,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn it_records_circle_backgrounds_as_ellipses() {
+        let context = Context::for_test();
+        let (mut ui, _tree) = Ui::for_test(
+            "container { color: (255, 0, 0), shape: \"circle\" }\n", "", &context,
+        );
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+
+        match renderer.commands(ui.root_id()).unwrap()[0] {
+            RenderCommand::Ellipse { center, radii, .. } => {
+                assert_eq!(center, Point2::new(50.0, 25.0));
+                assert_eq!(radii, Vector2::new(25.0, 25.0));
+            },
+            ref command => panic!("Unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn it_records_background_images_under_the_color() {
+        let context = Context::for_test();
+        let (mut ui, _tree) = Ui::for_test(
+            "container { image: \"panel.png\", color: (0, 0, 0, 0.5) }\n", "", &context,
+        );
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+
+        let commands = renderer.commands(ui.root_id()).unwrap();
+        match commands[0] {
+            RenderCommand::Image { ref image, destination, .. } => {
+                assert_eq!(image, "panel.png");
+                assert_eq!(destination.size, Vector2::new(100.0, 50.0));
+            },
+            ref command => panic!("Unexpected command {:?}", command),
+        }
+        match commands[1] {
+            RenderCommand::Vertices { .. } => {},
+            ref command => panic!("Unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn it_records_borders_per_side() {
+        let context = Context::for_test();
+        let (mut ui, _tree) = Ui::for_test(&[
+            "container {",
+            "    border-color: ((255, 0, 0), (0, 255, 0), \"#00f\", (0, 0, 0)),",
+            "    border-width: (2, 1, 0, 1), border-radius: (8, 8, 0, 0),",
+            "}",
+            "",
+        ].join("\n"), "", &context);
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+
+        // Sides without a width are left out, and the top side is mitered into the corners
+        let sides: Vec<_> = renderer.commands(ui.root_id()).unwrap().iter()
+            .filter_map(|c| match *c {
+                RenderCommand::Vertices { ref vertices, color, .. } => Some((vertices, color)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(sides.len(), 3);
+        assert_eq!(sides[0].1, Color::new_u8(255, 0, 0, 255));
+        assert_eq!(sides[0].0[..], [
+            Point2::new(0.0, 0.0), Point2::new(100.0, 0.0),
+            Point2::new(99.0, 2.0), Point2::new(1.0, 2.0),
+        ]);
+        assert_eq!(sides[2].1, Color::new_u8(0, 0, 0, 255));
+    }
+
+    #[test]
+    fn it_fits_vector_images_into_the_component() {
+        let mut context = Context::for_test();
+        context.classes.register::<VectorImageClass>("vector-image");
+        let (mut ui, tree) = Ui::for_test(&[
+            "container",
+            "    vector-image { path: \"M 0 0 h 10 v 10 Z\", view-box: (0, 0, 10, 10) }",
+            "",
+        ].join("\n"), "", &context);
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+
+        // The square view box is scaled to the component's height and centered
+        let image = ui.get(tree.root()).unwrap().children()[0];
+        match renderer.commands(image).unwrap()[0] {
+            RenderCommand::Vertices { ref vertices, .. } => {
+                assert!(vertices.contains(&Point2::new(25.0, 0.0)));
+                assert!(vertices.contains(&Point2::new(75.0, 50.0)));
+            },
+            ref command => panic!("Unexpected command {:?}", command),
+        }
+
+        let arc = Template::from_str("vector-image { path: \"M 0 0 A 1 1 0 0 0 5 5\" }\n")
+            .unwrap();
+        let style = Style::from_str("").unwrap();
+        assert!(Ui::new(&arc, None, style, Vector2::new(100.0, 50.0), &context).is_err());
+    }
+
+    #[test]
+    fn it_fills_gauges_by_their_value() {
+        let mut context = Context::for_test();
+        context.classes.register::<GaugeClass>("gauge");
+        let (mut ui, _tree) = Ui::for_test(
+            "gauge { value: 5, max: 20, thickness: 5, track-color: (0, 0, 0) }\n", "", &context,
+        );
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+
+        // A quarter of the ring is filled, going clockwise from straight up to the right
+        let commands = renderer.commands(ui.root_id()).unwrap();
+        assert_eq!(commands.len(), 2);
+        match commands[1] {
+            RenderCommand::Vertices { ref vertices, color, .. } => {
+                assert_eq!(color, Color::new(1.0, 1.0, 1.0, 1.0));
+                let first = vertices[1];
+                let last = vertices[vertices.len() - 1];
+                assert!((first - Point2::new(50.0, 0.0)).norm() < 0.001);
+                assert!((last - Point2::new(75.0, 25.0)).norm() < 0.001);
+                assert!((vertices[vertices.len() - 2] - Point2::new(70.0, 25.0)).norm() < 0.001);
+            },
+            ref command => panic!("Unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn it_plots_model_lists_as_bars() {
+        let mut context = Context::for_test();
+        context.classes.register::<ChartClass>("chart");
+        let template = Template::from_str(
+            "chart { values: ={model.values}, chart-type: \"bar\" }\n"
+        ).unwrap();
+        let style = Style::from_str("").unwrap();
+        let mut model = ScriptTable::new();
+        model.set("values", ScriptValue::List(vec![
+            ScriptValue::Number(1.0), ScriptValue::Number(-1.0), ScriptValue::Number(2.0),
+        ]));
+        let (mut ui, _tree) = Ui::new(
+            &template, Some(&model), style, Vector2::new(100.0, 50.0), &context,
+        ).unwrap();
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+
+        // The range fits the values, and the negative bar hangs below zero
+        let commands = renderer.commands(ui.root_id()).unwrap();
+        assert_eq!(commands.len(), 3);
+        match commands[1] {
+            RenderCommand::Vertices { ref vertices, .. } => {
+                assert!((vertices[0] - Point2::new(34.0, 50.0 / 3.0 * 2.0)).norm() < 0.001);
+                assert!((vertices[2] - Point2::new(66.0, 50.0)).norm() < 0.001);
+            },
+            ref command => panic!("Unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn it_frees_caches_of_removed_trees() {
+        let context = Context::for_test();
+        let dialog = Template::from_str("container { }\n    container { }\n").unwrap();
+        let (mut ui, _tree) = Ui::for_test("container.dialogs { }\n", "", &context);
+        let dialog_tree = ui.insert_template(&dialog, None, "dialogs", &context).unwrap();
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+
+        let removed = ui.remove_tree(&dialog_tree).unwrap();
+        assert_eq!(removed.len(), 2);
+        assert!(removed.iter().all(|id| renderer.cache_size(*id).is_some()));
+
+        render::render(&mut renderer, &mut ui).unwrap();
+        assert!(removed.iter().all(|id| renderer.cache_size(*id).is_none()));
+        assert!(ui.remove_tree(&dialog_tree).is_err());
+    }
+
+    #[test]
+    fn it_shares_one_renderer_between_uis() {
+        let context = Context::for_test();
+        let (mut ui, tree) = Ui::for_test("container { color: (255, 0, 0) }\n", "", &context);
+        let (mut other_ui, other_tree) = Ui::for_test(
+            "container { color: (0, 0, 255) }\n", "", &context,
+        );
+
+        // Components of different UIs never have the same ID, even if they're in the same spot
+        assert!(tree.root() != other_tree.root());
+        assert!(ui.get(other_tree.root()).is_none());
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+        render::render(&mut renderer, &mut other_ui).unwrap();
+        let color = |id| match renderer.commands(id).unwrap()[0] {
+            RenderCommand::Vertices { color, .. } => color,
+            ref command => panic!("Unexpected command {:?}", command),
+        };
+        assert_eq!(color(tree.root()), Color::new(1.0, 0.0, 0.0, 1.0));
+        assert_eq!(color(other_tree.root()), Color::new(0.0, 0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn it_composites_children_with_their_blend_mode() {
+        let context = Context::for_test();
+        let (mut ui, tree) = Ui::for_test(
+            "container { }\n    container { blend-mode: \"add\" }\n", "", &context,
+        );
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+
+        let child = ui.get(tree.root()).unwrap().children()[0];
+        let composited = renderer.commands(tree.root()).unwrap().iter().any(|c| match *c {
+            RenderCommand::RenderCache { source_id, blend_mode, .. } =>
+                source_id == child && blend_mode == BlendMode::Add,
+            _ => false,
+        });
+        assert!(composited);
+    }
+
+    #[test]
+    fn it_renders_layers_in_order() {
+        let context = Context::for_test();
+        let template = Template::from_str("container { }\n").unwrap();
+        let (mut ui, tree) = Ui::for_test("container { }\n", "", &context);
+        let overlay = ui.add_layer(&template, None, 10, &context).unwrap();
+        let hud = ui.add_layer(&template, None, 5, &context).unwrap();
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+        assert_eq!(renderer.target(), Some(overlay.root()));
+        assert_eq!(renderer.cache_size(hud.root()), Some(Vector2::new(100, 50)));
+
+        ui.set_layer_visible(overlay.root(), false).unwrap();
+        assert!(render::render_changed(&mut renderer, &mut ui).unwrap());
+        assert_eq!(renderer.target(), Some(hud.root()));
+
+        ui.set_layer_order(hud.root(), -1).unwrap();
+        render::render(&mut renderer, &mut ui).unwrap();
+        assert_eq!(renderer.target(), Some(tree.root()));
+        assert!(ui.remove_layer(tree.root()).is_err());
+    }
+
+    #[test]
+    fn it_renders_the_debug_overlay_over_every_component() {
+        let context = Context::for_test();
+        let (mut ui, _tree) = Ui::for_test(
+            "container\n    container { size: (10.0, 10.0), margin: 2.0 }\n", "", &context,
+        );
+        ui.set_debug_overlay(true);
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+
+        // The overlay is rendered directly to the target for the layer, after the layer itself
+        let commands = renderer.commands(ui.root_id()).unwrap();
+        let labels: Vec<_> = commands.iter().filter_map(|c| match *c {
+            RenderCommand::Text { ref text, .. } => Some(text.as_str()),
+            _ => None,
+        }).collect();
+        assert_eq!(labels, vec!["container", "container"]);
+
+        // Both components are outlined, and the child's margin as well
+        let outlines = commands.iter().filter(|c| match **c {
+            RenderCommand::Line { .. } => true,
+            _ => false,
+        }).count();
+        assert_eq!(outlines, 3);
+    }
+
+    #[test]
+    fn it_transforms_the_root_and_inverts_it_for_input() {
+        let mut context = Context::for_test();
+        context.classes.register::<ButtonClass>("button");
+        let (mut ui, tree) = Ui::for_test(
+            "container\n    button { position: (20.0, 10.0), size: (30.0, 20.0), \
+            on-pressed: \"pressed\" }\n",
+            "", &context,
+        );
+        let mut renderer = RecordingRenderer::new();
+        let mut input = Input::new();
+
+        ui.set_root_transform(2.0, Vector2::new(10.0, 5.0));
+        render::render(&mut renderer, &mut ui).unwrap();
+        assert_eq!(
+            renderer.target_transform(), RootTransform::new(2.0, Vector2::new(10.0, 5.0)),
+        );
+
+        // The button ends up at (50, 25) on the target, twice as large
+        let position = Point2::new(60.0, 35.0);
+        input.handle_drag_started(position, Modifiers::none(), &mut ui);
+        input.handle_drag_ended(position, Modifiers::none(), &mut ui);
+        assert_eq!(input.cursor_position(), Some(Point2::new(25.0, 15.0)));
+        let event = tree.event_sink().next_event().unwrap();
+        assert_eq!(event.position, Some(PressPosition {
+            absolute: Point2::new(25.0, 15.0),
+            local: Point2::new(5.0, 5.0),
+        }));
+
+        input.handle_drag_started(Point2::new(45.0, 20.0), Modifiers::none(), &mut ui);
+        input.handle_drag_ended(Point2::new(45.0, 20.0), Modifiers::none(), &mut ui);
+        assert_eq!(tree.event_sink().next_event(), None);
+    }
+}
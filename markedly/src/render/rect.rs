@@ -0,0 +1,48 @@
+use nalgebra::{Point2, Vector2};
+
+/// An axis aligned rectangle, used for areas such as clipping regions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub position: Point2<f32>,
+    pub size: Vector2<f32>,
+}
+
+impl Rect {
+    pub fn new(position: Point2<f32>, size: Vector2<f32>) -> Self {
+        Rect {
+            position,
+            size,
+        }
+    }
+
+    /// Returns the position of the bottom right corner of the rectangle.
+    pub fn end(&self) -> Point2<f32> {
+        self.position + self.size
+    }
+
+    /// Returns true if the point is inside of the rectangle, including its edges.
+    pub fn contains(&self, point: Point2<f32>) -> bool {
+        let end = self.end();
+        point.x >= self.position.x && point.y >= self.position.y &&
+            point.x <= end.x && point.y <= end.y
+    }
+
+    /// Returns the area where this rectangle and the other rectangle overlap, or None if they
+    /// don't overlap.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let start = Point2::new(
+            self.position.x.max(other.position.x),
+            self.position.y.max(other.position.y),
+        );
+        let end = Point2::new(
+            self.end().x.min(other.end().x),
+            self.end().y.min(other.end().y),
+        );
+
+        if start.x < end.x && start.y < end.y {
+            Some(Rect::new(start, end - start))
+        } else {
+            None
+        }
+    }
+}
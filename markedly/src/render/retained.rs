@@ -0,0 +1,290 @@
+use nalgebra::{Point2, Vector2};
+
+use render::{self, Renderer, RenderCommand, RenderStats, Rect, BlendMode, RootTransform};
+use template::{Color};
+use {ComponentId, Ui, Error, ComponentFlow};
+
+/// Re-records the command lists of components in a layer that changed, and replays all command
+/// lists into the layer's root component's cache. Returns true if anything changed.
+pub(crate) fn update<R: Renderer>(
+    renderer: &mut R, ui: &mut Ui, root_id: ComponentId, only_changed: bool,
+    stats: &mut RenderStats,
+) -> Result<bool, Error> {
+    let size = ui.target_size();
+
+    let mut changed = record_component(renderer, ui, root_id, size, stats)?;
+
+    // Everything is replayed into the root's cache, if it was just created it needs to be filled
+    changed |= renderer.create_resize_cache(root_id, Vector2::new(
+        size.x.ceil() as u32,
+        size.y.ceil() as u32,
+    ))?;
+
+    if changed || !only_changed {
+        renderer.clear_cache(root_id)?;
+        replay_component(renderer, ui, root_id, root_id, Point2::new(0.0, 0.0), size, 1.0)?;
+    }
+
+    Ok(changed)
+}
+
+fn record_component<R: Renderer>(
+    renderer: &mut R, ui: &mut Ui, id: ComponentId, parent_size: Vector2<f32>,
+    stats: &mut RenderStats,
+) -> Result<bool, Error> {
+    stats.components_visited += 1;
+
+    let (computed_size, children, needs_recording) = {
+        let component = ui.get(id).unwrap();
+        let computed_size = component.compute_size(parent_size);
+
+        // If the size changed since the last recording, the commands are no longer accurate
+        let needs_recording = component.needs_rendering() ||
+            component.retained_size() != Some(computed_size);
+
+        (computed_size, component.children().clone(), needs_recording)
+    };
+
+    let mut changed = false;
+    for (index, child_id) in children.into_iter().enumerate() {
+        // Invisible children aren't recorded, but the UI does need to be replayed without them if
+        // they just changed
+        let (visible, child_needs_rendering) = {
+            let child = ui.get(child_id).unwrap();
+            (child.attributes().is_visible(), child.needs_rendering())
+        };
+        if !visible {
+            changed |= child_needs_rendering;
+            continue
+        }
+
+        let area_size = ui.get(id).unwrap().child_area(index, computed_size).size;
+        changed |= record_component(renderer, ui, child_id, area_size, stats)?;
+    }
+
+    if needs_recording {
+        stats.caches_rendered += 1;
+        trace!("Recording commands of component {:?} ({})", id, ui.get(id).unwrap().label());
+
+        let commands = {
+            let mut recorder = Recorder {
+                inner: renderer,
+                commands: Vec::new(),
+            };
+            let result = ui.get(id).unwrap().render(id, computed_size, &mut recorder);
+            render::warn_missing_resource(result, ui.warnings())?;
+            recorder.commands
+        };
+        ui.get_mut(id).unwrap().set_retained(computed_size, commands);
+
+        changed = true;
+    }
+
+    Ok(changed)
+}
+
+fn replay_component<R: Renderer>(
+    renderer: &mut R, ui: &Ui, target_id: ComponentId, id: ComponentId,
+    position: Point2<f32>, size: Vector2<f32>, opacity: f32,
+) -> Result<(), Error> {
+    let component = ui.get(id).unwrap();
+
+    if let Some(commands) = component.retained_commands() {
+        for command in commands {
+            let result = replay_command(renderer, target_id, command, position.coords, opacity);
+            render::warn_missing_resource(result, ui.warnings())?;
+        }
+    }
+
+    // Opacity is applied to every command individually, so overlapping children of a translucent
+    // component will show through each other, unlike in cached mode
+    renderer.push_clip(target_id, Rect::new(position, size))?;
+    let mut flow = ComponentFlow::for_children(component, size);
+    for child_id in component.children() {
+        let child = ui.get(*child_id).unwrap();
+        let child_rect = child.compute_layout(&mut flow);
+        if !child.attributes().is_visible() {
+            continue
+        }
+        let child_position = position + child_rect.position.coords;
+        let child_size = child_rect.size;
+        let child_opacity = opacity * child.attributes().opacity;
+
+        if let Some(ref shadow) = child.attributes().shadow {
+            shadow.render(
+                target_id, Rect::new(child_position, child_size), child_opacity, renderer,
+            )?;
+        }
+
+        replay_component(
+            renderer, ui, target_id, *child_id, child_position, child_size, child_opacity,
+        )?;
+    }
+    renderer.pop_clip(target_id)?;
+
+    Ok(())
+}
+
+fn replay_command<R: Renderer>(
+    renderer: &mut R, target_id: ComponentId,
+    command: &RenderCommand, offset: Vector2<f32>, opacity: f32,
+) -> Result<(), Error> {
+    match *command {
+        RenderCommand::Text { ref text, ref font, text_size, position, size, color } =>
+            renderer.text(
+                target_id, text, font.as_ref(), text_size,
+                position + offset, size, fade(color, opacity),
+            ),
+        RenderCommand::Vertices { ref vertices, ref indices, color } => {
+            let vertices: Vec<_> = vertices.iter().map(|v| *v + offset).collect();
+            renderer.vertices(target_id, &vertices, indices, fade(color, opacity))
+        },
+        RenderCommand::Ellipse { center, radii, color } =>
+            renderer.ellipse(target_id, center + offset, radii, fade(color, opacity)),
+        RenderCommand::Line { ref points, width, color } => {
+            let points: Vec<_> = points.iter().map(|v| *v + offset).collect();
+            renderer.line(target_id, &points, width, fade(color, opacity))
+        },
+        RenderCommand::Image { ref image, source, destination, color } =>
+            renderer.image(
+                target_id, image, source,
+                Rect::new(destination.position + offset, destination.size), fade(color, opacity),
+            ),
+        RenderCommand::PushClip { rect } =>
+            renderer.push_clip(target_id, Rect::new(rect.position + offset, rect.size)),
+        RenderCommand::PopClip =>
+            renderer.pop_clip(target_id),
+        RenderCommand::RenderCache { .. } =>
+            Err("Rendering caches can not be replayed in retained mode".into()),
+    }
+}
+
+fn fade(mut color: Color, opacity: f32) -> Color {
+    color.alpha *= opacity;
+    color
+}
+
+/// Records the operations a component renders, text measuring is still done by the real
+/// renderer.
+struct Recorder<'r, R: Renderer + 'r> {
+    inner: &'r mut R,
+    commands: Vec<RenderCommand>,
+}
+
+impl<'r, R: Renderer> Renderer for Recorder<'r, R> {
+    fn render_cache_to_target(
+        &mut self, _id: ComponentId, _transform: RootTransform,
+    ) -> Result<(), Error> {
+        Err("Caches can not be used while recording".into())
+    }
+
+    fn create_resize_cache(
+        &mut self, _id: ComponentId, _size: Vector2<u32>
+    ) -> Result<bool, Error> {
+        Err("Caches can not be used while recording".into())
+    }
+
+    fn clear_cache(&mut self, _id: ComponentId) -> Result<(), Error> {
+        Err("Caches can not be used while recording".into())
+    }
+
+    fn remove_cache(&mut self, _id: ComponentId) -> Result<(), Error> {
+        Err("Caches can not be used while recording".into())
+    }
+
+    fn render_cache(
+        &mut self, _id: ComponentId,
+        _source_id: ComponentId, _position: Point2<f32>, _alpha: f32, _blend_mode: BlendMode,
+    ) -> Result<(), Error> {
+        Err("Caches can not be used while recording".into())
+    }
+
+    fn push_clip(&mut self, _id: ComponentId, rect: Rect) -> Result<(), Error> {
+        self.commands.push(RenderCommand::PushClip { rect });
+        Ok(())
+    }
+
+    fn pop_clip(&mut self, _id: ComponentId) -> Result<(), Error> {
+        self.commands.push(RenderCommand::PopClip);
+        Ok(())
+    }
+
+    fn begin_direct(&mut self, _id: ComponentId) -> Result<(), Error> {
+        Err("Direct rendering can not be used while recording".into())
+    }
+
+    fn end_direct(&mut self, _id: ComponentId) -> Result<(), Error> {
+        Err("Direct rendering can not be used while recording".into())
+    }
+
+    fn text(
+        &mut self, _id: ComponentId,
+        text: &String, text_font: Option<&String>, text_size: Option<i32>,
+        position: Point2<f32>, size: Vector2<f32>, color: Color,
+    ) -> Result<(), Error> {
+        self.commands.push(RenderCommand::Text {
+            text: text.clone(),
+            font: text_font.cloned(),
+            text_size,
+            position, size, color,
+        });
+        Ok(())
+    }
+
+    fn measure_text(
+        &mut self, text: &String, text_font: Option<&String>, text_size: Option<i32>,
+    ) -> Result<Vector2<f32>, Error> {
+        self.inner.measure_text(text, text_font, text_size)
+    }
+
+    fn has_glyphs(
+        &mut self, text: &String, text_font: Option<&String>,
+    ) -> Result<bool, Error> {
+        self.inner.has_glyphs(text, text_font)
+    }
+
+    fn vertices(
+        &mut self, _id: ComponentId,
+        vertices: &[Point2<f32>], indices: &[u16], color: Color,
+    ) -> Result<(), Error> {
+        self.commands.push(RenderCommand::Vertices {
+            vertices: vertices.to_vec(),
+            indices: indices.to_vec(),
+            color,
+        });
+        Ok(())
+    }
+
+    fn ellipse(
+        &mut self, _id: ComponentId, center: Point2<f32>, radii: Vector2<f32>, color: Color,
+    ) -> Result<(), Error> {
+        self.commands.push(RenderCommand::Ellipse { center, radii, color });
+        Ok(())
+    }
+
+    fn image(
+        &mut self, _id: ComponentId,
+        image: &String, source: Rect, destination: Rect, color: Color,
+    ) -> Result<(), Error> {
+        self.commands.push(RenderCommand::Image {
+            image: image.clone(),
+            source, destination, color,
+        });
+        Ok(())
+    }
+
+    fn image_size(&mut self, image: &String) -> Result<Vector2<u32>, Error> {
+        self.inner.image_size(image)
+    }
+
+    fn line(
+        &mut self, _id: ComponentId, points: &[Point2<f32>], width: f32, color: Color,
+    ) -> Result<(), Error> {
+        self.commands.push(RenderCommand::Line {
+            points: points.to_vec(),
+            width,
+            color,
+        });
+        Ok(())
+    }
+}
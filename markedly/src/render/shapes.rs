@@ -0,0 +1,276 @@
+use std::f32::consts::{PI};
+
+use nalgebra::{Point2, Vector2};
+use lyon::math::rect;
+use lyon::tessellation as lt;
+
+use render::{Renderer, Rect};
+use template::{Color};
+use {ComponentId, Error};
+
+/// The radii of the rounded corners of a rectangle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CornerRadii {
+    pub top_left: f32,
+    pub top_right: f32,
+    pub bottom_right: f32,
+    pub bottom_left: f32,
+}
+
+impl CornerRadii {
+    /// Creates radii that are the same for every corner.
+    pub fn new(radius: f32) -> Self {
+        CornerRadii {
+            top_left: radius,
+            top_right: radius,
+            bottom_right: radius,
+            bottom_left: radius,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.top_left == 0.0 && self.top_right == 0.0 &&
+            self.bottom_right == 0.0 && self.bottom_left == 0.0
+    }
+
+    /// Shrinks every radius by a distance, such as to follow a rounded edge on the inside.
+    pub fn shrink(&self, distance: f32) -> Self {
+        CornerRadii {
+            top_left: (self.top_left - distance).max(0.0),
+            top_right: (self.top_right - distance).max(0.0),
+            bottom_right: (self.bottom_right - distance).max(0.0),
+            bottom_left: (self.bottom_left - distance).max(0.0),
+        }
+    }
+
+    fn to_lyon(&self) -> lt::basic_shapes::BorderRadii {
+        lt::basic_shapes::BorderRadii {
+            top_left: self.top_left,
+            top_right: self.top_right,
+            bottom_left: self.bottom_left,
+            bottom_right: self.bottom_right,
+        }
+    }
+}
+
+/// A value for every side of a rectangle, such as the widths of a border.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sides<T> {
+    pub top: T,
+    pub right: T,
+    pub bottom: T,
+    pub left: T,
+}
+
+impl<T: Copy + PartialEq> Sides<T> {
+    /// Creates sides that all have the same value.
+    pub fn new(value: T) -> Self {
+        Sides {
+            top: value,
+            right: value,
+            bottom: value,
+            left: value,
+        }
+    }
+
+    /// Returns true if every side has the same value.
+    pub fn is_uniform(&self) -> bool {
+        self.top == self.right && self.top == self.bottom && self.top == self.left
+    }
+}
+
+/// Renders a filled rectangle with optionally rounded corners to the component's cache.
+pub fn rectangle(
+    renderer: &mut Renderer, id: ComponentId, area: Rect, border_radius: f32, color: Color,
+) -> Result<(), Error> {
+    rounded_rectangle(renderer, id, area, CornerRadii::new(border_radius), color)
+}
+
+/// Renders a filled rectangle with a separate radius for every corner to the component's cache.
+pub fn rounded_rectangle(
+    renderer: &mut Renderer, id: ComponentId, area: Rect, radii: CornerRadii, color: Color,
+) -> Result<(), Error> {
+    let start = area.position;
+    let end = area.end();
+
+    if radii.is_zero() {
+        // Simple rectangle fast path
+        renderer.vertices(id, &[
+            Point2::new(start.x, start.y),
+            Point2::new(start.x, end.y),
+            Point2::new(end.x, end.y),
+            Point2::new(end.x, start.y),
+        ], &[0, 1, 3, 2, 3, 1], color)
+    } else {
+        // Generate the rounded rectangle
+        let mut geometry = lt::VertexBuffers::new();
+        let options = lt::FillOptions::tolerance(0.1);
+        lt::basic_shapes::fill_rounded_rectangle(
+            &rect(start.x, start.y, area.size.x, area.size.y),
+            &radii.to_lyon(),
+            &options,
+            &mut lt::geometry_builder::simple_builder(&mut geometry),
+        );
+
+        // Send it over to the renderer
+        let vertices: Vec<_> = geometry.vertices.into_iter()
+            .map(|v| Point2::new(v.position.x, v.position.y)).collect();
+        renderer.vertices(id, &vertices, &geometry.indices, color)
+    }
+}
+
+/// Renders a border along the inside of a rectangle's edges to the component's cache. Corners
+/// are only rounded if every side has the same width and color, otherwise the sides meet at
+/// square corners, split diagonally between them.
+pub fn border(
+    renderer: &mut Renderer, id: ComponentId,
+    area: Rect, radii: CornerRadii, widths: Sides<f32>, colors: Sides<Color>,
+) -> Result<(), Error> {
+    if widths.is_uniform() && colors.is_uniform() && !radii.is_zero() {
+        let width = widths.top.min(area.size.x * 0.5).min(area.size.y * 0.5);
+        if width <= 0.0 {
+            return Ok(())
+        }
+
+        // The stroke is centered on its path, so the path is inset by half the width
+        let half = width * 0.5;
+        let mut geometry = lt::VertexBuffers::new();
+        lt::basic_shapes::stroke_rounded_rectangle(
+            &rect(
+                area.position.x + half, area.position.y + half,
+                area.size.x - width, area.size.y - width,
+            ),
+            &radii.shrink(half).to_lyon(),
+            &lt::StrokeOptions::tolerance(0.1).with_line_width(width),
+            &mut lt::geometry_builder::simple_builder(&mut geometry),
+        );
+
+        let vertices: Vec<_> = geometry.vertices.into_iter()
+            .map(|v| Point2::new(v.position.x, v.position.y)).collect();
+        return renderer.vertices(id, &vertices, &geometry.indices, colors.top)
+    }
+
+    let start = area.position;
+    let end = area.end();
+    let outer = [
+        Point2::new(start.x, start.y), Point2::new(end.x, start.y),
+        Point2::new(end.x, end.y), Point2::new(start.x, end.y),
+    ];
+    let inner = [
+        Point2::new(start.x + widths.left, start.y + widths.top),
+        Point2::new(end.x - widths.right, start.y + widths.top),
+        Point2::new(end.x - widths.right, end.y - widths.bottom),
+        Point2::new(start.x + widths.left, end.y - widths.bottom),
+    ];
+
+    // Every side goes from the corner at its start to the next one clockwise
+    let sides = [
+        (widths.top, colors.top), (widths.right, colors.right),
+        (widths.bottom, colors.bottom), (widths.left, colors.left),
+    ];
+    for (side, &(width, color)) in sides.iter().enumerate() {
+        if width <= 0.0 {
+            continue
+        }
+
+        let next = (side + 1) % 4;
+        renderer.vertices(id, &[
+            outer[side], outer[next], inner[next], inner[side],
+        ], &[0, 1, 2, 0, 2, 3], color)?;
+    }
+
+    Ok(())
+}
+
+/// Tessellates a filled ellipse into a triangle fan, with enough segments that the edge is
+/// within a tenth of a unit of the real curve.
+pub fn ellipse_geometry(center: Point2<f32>, radii: Vector2<f32>) -> (Vec<Point2<f32>>, Vec<u16>) {
+    let tolerance = 0.1;
+    let radius = radii.x.max(radii.y);
+    let segments = if radius <= tolerance {
+        3
+    } else {
+        (PI / (1.0 - tolerance / radius).acos()).ceil().max(3.0).min(256.0) as u16
+    };
+
+    let mut vertices = vec![center];
+    let mut indices = Vec::new();
+    for i in 0..segments {
+        let angle = (i as f32 / segments as f32) * PI * 2.0;
+        vertices.push(center + Vector2::new(angle.cos() * radii.x, angle.sin() * radii.y));
+        indices.extend_from_slice(&[0, i + 1, (i + 1) % segments + 1]);
+    }
+
+    (vertices, indices)
+}
+
+/// Tessellates part of a ring between two radii, going clockwise from the start to the end angle
+/// in radians, with enough segments that the outer edge is within a tenth of a unit of the real
+/// curve.
+pub fn ring_geometry(
+    center: Point2<f32>, inner: f32, outer: f32, start: f32, end: f32,
+) -> (Vec<Point2<f32>>, Vec<u16>) {
+    let tolerance = 0.1;
+    let full_segments = if outer <= tolerance {
+        3.0
+    } else {
+        PI / (1.0 - tolerance / outer).acos()
+    };
+    let span = (end - start).abs() / (PI * 2.0);
+    let segments = (full_segments * span).ceil().max(1.0).min(256.0) as u16;
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for i in 0..(segments + 1) {
+        let angle = start + (end - start) * i as f32 / segments as f32;
+        let direction = Vector2::new(angle.cos(), angle.sin());
+        vertices.push(center + direction * inner);
+        vertices.push(center + direction * outer);
+
+        if i != 0 {
+            let base = i * 2;
+            indices.extend_from_slice(&[base - 2, base - 1, base + 1, base - 2, base + 1, base]);
+        }
+    }
+
+    (vertices, indices)
+}
+
+/// Tessellates a line through a sequence of points into triangles, with every segment as a quad
+/// and bevels filling the gaps at the corners.
+pub fn line_geometry(points: &[Point2<f32>], width: f32) -> (Vec<Point2<f32>>, Vec<u16>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let half_width = width * 0.5;
+
+    let mut previous: Option<(u16, u16)> = None;
+    for segment in points.windows(2) {
+        let direction = segment[1] - segment[0];
+        let length = direction.norm();
+        if length == 0.0 {
+            continue
+        }
+        let normal = Vector2::new(-direction.y, direction.x) / length * half_width;
+
+        let start = vertices.len() as u16;
+        vertices.extend_from_slice(&[
+            segment[0] + normal, segment[0] - normal,
+            segment[1] + normal, segment[1] - normal,
+        ]);
+        indices.extend_from_slice(&[start, start + 1, start + 2, start + 2, start + 1, start + 3]);
+
+        // Fill the gap on both sides between the end of the last segment and this one
+        if let Some((previous_left, previous_right)) = previous {
+            let center = vertices.len() as u16;
+            vertices.push(segment[0]);
+            indices.extend_from_slice(&[
+                center, previous_left, start,
+                center, previous_right, start + 1,
+            ]);
+        }
+
+        previous = Some((start + 2, start + 3));
+    }
+
+    (vertices, indices)
+}
@@ -0,0 +1,159 @@
+use std::any::{Any};
+use std::time::{Duration};
+
+use nalgebra::{Point2, Vector2};
+
+use render::{Renderer, Rect, Effect, BlendMode, PixelBuffer, TextEffects, RootTransform};
+use template::{Color};
+use {ComponentId, Error};
+
+/// Statistics gathered during a single render of a UI, to help diagnose slow UIs.
+#[derive(Debug, Clone, Default)]
+pub struct RenderStats {
+    /// The amount of components whose cache was checked for being up-to-date.
+    pub components_visited: usize,
+    /// The amount of component caches that were re-rendered.
+    pub caches_rendered: usize,
+    /// The amount of primitives, such as text and vertices, issued to the renderer.
+    pub primitives: usize,
+    /// The amount of times a cache was composited into another cache.
+    pub caches_composited: usize,
+    /// The time spent updating the components' caches.
+    pub update_time: Duration,
+    /// The time spent rendering the root cache to the target.
+    pub target_time: Duration,
+}
+
+/// Wraps a renderer to count the operations issued to it.
+pub(crate) struct StatsRenderer<'r, R: Renderer + 'r> {
+    pub inner: &'r mut R,
+    pub primitives: usize,
+    pub caches_composited: usize,
+}
+
+impl<'r, R: Renderer> Renderer for StatsRenderer<'r, R> {
+    fn render_cache_to_target(
+        &mut self, id: ComponentId, transform: RootTransform,
+    ) -> Result<(), Error> {
+        self.inner.render_cache_to_target(id, transform)
+    }
+
+    fn create_resize_cache(
+        &mut self, id: ComponentId, size: Vector2<u32>
+    ) -> Result<bool, Error> {
+        self.inner.create_resize_cache(id, size)
+    }
+
+    fn clear_cache(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.inner.clear_cache(id)
+    }
+
+    fn remove_cache(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.inner.remove_cache(id)
+    }
+
+    fn read_cache_pixels(&mut self, id: ComponentId) -> Result<PixelBuffer, Error> {
+        self.inner.read_cache_pixels(id)
+    }
+
+    fn render_cache(
+        &mut self, id: ComponentId,
+        source_id: ComponentId, position: Point2<f32>, alpha: f32, blend_mode: BlendMode,
+    ) -> Result<(), Error> {
+        self.caches_composited += 1;
+        self.inner.render_cache(id, source_id, position, alpha, blend_mode)
+    }
+
+    fn custom(&mut self, id: ComponentId, command: &Any) -> Result<(), Error> {
+        self.primitives += 1;
+        self.inner.custom(id, command)
+    }
+
+    fn composite_with_effect(
+        &mut self, id: ComponentId,
+        source_id: ComponentId, position: Point2<f32>, alpha: f32, effect: &Effect,
+    ) -> Result<(), Error> {
+        self.caches_composited += 1;
+        self.inner.composite_with_effect(id, source_id, position, alpha, effect)
+    }
+
+    fn push_clip(&mut self, id: ComponentId, rect: Rect) -> Result<(), Error> {
+        self.inner.push_clip(id, rect)
+    }
+
+    fn pop_clip(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.inner.pop_clip(id)
+    }
+
+    fn begin_direct(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.inner.begin_direct(id)
+    }
+
+    fn end_direct(&mut self, id: ComponentId) -> Result<(), Error> {
+        self.inner.end_direct(id)
+    }
+
+    fn text(
+        &mut self, id: ComponentId,
+        text: &String, text_font: Option<&String>, text_size: Option<i32>,
+        position: Point2<f32>, size: Vector2<f32>, color: Color,
+    ) -> Result<(), Error> {
+        self.primitives += 1;
+        self.inner.text(id, text, text_font, text_size, position, size, color)
+    }
+
+    fn measure_text(
+        &mut self, text: &String, text_font: Option<&String>, text_size: Option<i32>,
+    ) -> Result<Vector2<f32>, Error> {
+        self.inner.measure_text(text, text_font, text_size)
+    }
+
+    fn has_glyphs(
+        &mut self, text: &String, text_font: Option<&String>,
+    ) -> Result<bool, Error> {
+        self.inner.has_glyphs(text, text_font)
+    }
+
+    fn text_effects(
+        &mut self, id: ComponentId,
+        text: &String, text_font: Option<&String>, text_size: Option<i32>,
+        position: Point2<f32>, size: Vector2<f32>, effects: &TextEffects,
+    ) -> Result<(), Error> {
+        self.primitives += 1;
+        self.inner.text_effects(id, text, text_font, text_size, position, size, effects)
+    }
+
+    fn vertices(
+        &mut self, id: ComponentId,
+        vertices: &[Point2<f32>], indices: &[u16], color: Color,
+    ) -> Result<(), Error> {
+        self.primitives += 1;
+        self.inner.vertices(id, vertices, indices, color)
+    }
+
+    fn ellipse(
+        &mut self, id: ComponentId, center: Point2<f32>, radii: Vector2<f32>, color: Color,
+    ) -> Result<(), Error> {
+        self.primitives += 1;
+        self.inner.ellipse(id, center, radii, color)
+    }
+
+    fn image(
+        &mut self, id: ComponentId,
+        image: &String, source: Rect, destination: Rect, color: Color,
+    ) -> Result<(), Error> {
+        self.primitives += 1;
+        self.inner.image(id, image, source, destination, color)
+    }
+
+    fn image_size(&mut self, image: &String) -> Result<Vector2<u32>, Error> {
+        self.inner.image_size(image)
+    }
+
+    fn line(
+        &mut self, id: ComponentId, points: &[Point2<f32>], width: f32, color: Color,
+    ) -> Result<(), Error> {
+        self.primitives += 1;
+        self.inner.line(id, points, width, color)
+    }
+}
@@ -0,0 +1,602 @@
+use nalgebra::{Point2, Vector2};
+
+use render::{Renderer};
+use scripting::{ScriptRuntime};
+use template::{Attributes, Color, TemplateValue, parse_hex_color};
+use {ComponentId, Error};
+
+/// Text split up into spans that each have their own styling, parsed from inline markup such as
+/// `"Press [b]A[/b] to [color=#ff0]jump[/color]"`.
+///
+/// Supported tags are `[b]`, `[i]`, `[color=#rrggbb]`, `[font=name]`, and `[size=14]`, each
+/// closed by their matching `[/tag]`. `[[` results in a literal `[`, and anything in brackets
+/// that isn't a supported tag is kept as text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RichText {
+    pub spans: Vec<TextSpan>,
+}
+
+/// A part of rich text with its own styling, styling not set by the span falls back to the
+/// component's text style.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextSpan {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+    pub color: Option<Color>,
+    pub font: Option<String>,
+    pub size: Option<i32>,
+}
+
+/// The base styling of text, used for anything rich text spans do not override.
+pub struct TextStyle<'a> {
+    pub font: Option<&'a String>,
+    /// The font used for `[b]` spans, if not set the regular font will be used.
+    pub font_bold: Option<&'a String>,
+    /// The font used for `[i]` spans, if not set the regular font will be used.
+    pub font_italic: Option<&'a String>,
+    /// Fonts used in order for characters that the span's font doesn't have a glyph for.
+    pub fallback_fonts: &'a [String],
+    pub size: Option<i32>,
+    pub color: Color,
+    pub effects: TextEffects,
+    /// Extra space added between every letter.
+    pub letter_spacing: f32,
+    /// The height of lines relative to the height of their text, if not set lines are as tall
+    /// as their text.
+    pub line_height: Option<f32>,
+}
+
+/// Effects rendered behind text, to keep it readable over any background.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TextEffects {
+    pub outline: Option<TextOutline>,
+    pub shadow: Option<TextShadow>,
+}
+
+impl TextEffects {
+    /// Loads the effects from the `text-outline` and `text-shadow` attributes.
+    pub fn load(attributes: &Attributes, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        Ok(TextEffects {
+            outline: attributes.attribute_optional(
+                "text-outline", |v| TextOutline::from_value(v, runtime)
+            )?,
+            shadow: attributes.attribute_optional(
+                "text-shadow", |v| TextShadow::from_value(v, runtime)
+            )?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outline.is_none() && self.shadow.is_none()
+    }
+}
+
+/// An outline around the glyphs of text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextOutline {
+    pub width: f32,
+    pub color: Color,
+}
+
+impl TextOutline {
+    /// Reads an outline from a tuple of the width and color.
+    pub fn from_value(value: &TemplateValue, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        let values = value.as_vec()?;
+        if values.len() != 2 {
+            return Err("Tuple is incorrect size".into())
+        }
+
+        Ok(TextOutline {
+            width: values[0].as_float(runtime).map_err(|e| Error::new_value("Value 1", e))?,
+            color: values[1].as_color(runtime).map_err(|e| Error::new_value("Value 2", e))?,
+        })
+    }
+
+    /// Gets the offsets text is rendered at to draw the outline, in eight directions around it.
+    pub fn offsets(&self) -> Vec<Vector2<f32>> {
+        let diagonal = self.width * ::std::f32::consts::FRAC_1_SQRT_2;
+        vec![
+            Vector2::new(-self.width, 0.0), Vector2::new(self.width, 0.0),
+            Vector2::new(0.0, -self.width), Vector2::new(0.0, self.width),
+            Vector2::new(-diagonal, -diagonal), Vector2::new(diagonal, -diagonal),
+            Vector2::new(-diagonal, diagonal), Vector2::new(diagonal, diagonal),
+        ]
+    }
+}
+
+/// A copy of text offset behind it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextShadow {
+    pub offset: Vector2<f32>,
+    pub color: Color,
+}
+
+impl TextShadow {
+    /// Reads a shadow from a tuple of the x and y offset and the color.
+    pub fn from_value(value: &TemplateValue, runtime: &ScriptRuntime) -> Result<Self, Error> {
+        let values = value.as_vec()?;
+        if values.len() != 3 {
+            return Err("Tuple is incorrect size".into())
+        }
+
+        Ok(TextShadow {
+            offset: Vector2::new(
+                values[0].as_float(runtime).map_err(|e| Error::new_value("Value 1", e))?,
+                values[1].as_float(runtime).map_err(|e| Error::new_value("Value 2", e))?,
+            ),
+            color: values[2].as_color(runtime).map_err(|e| Error::new_value("Value 3", e))?,
+        })
+    }
+}
+
+impl RichText {
+    /// Parses rich text from a string containing inline markup.
+    pub fn parse(text: &str) -> Self {
+        let mut parser = RichTextParser {
+            spans: Vec::new(),
+            current: String::new(),
+            bold: 0,
+            italic: 0,
+            colors: Vec::new(),
+            fonts: Vec::new(),
+            sizes: Vec::new(),
+        };
+
+        let mut remaining = text;
+        while let Some(start) = remaining.find('[') {
+            parser.current.push_str(&remaining[..start]);
+            remaining = &remaining[start..];
+
+            // A double bracket is an escaped bracket
+            if remaining.starts_with("[[") {
+                parser.current.push('[');
+                remaining = &remaining[2..];
+                continue
+            }
+
+            // Find the end of the tag, if there is none it's just text
+            let end = match remaining.find(']') {
+                Some(end) => end,
+                None => break,
+            };
+
+            if !parser.apply_tag(&remaining[1..end]) {
+                parser.current.push_str(&remaining[..end+1]);
+            }
+            remaining = &remaining[end+1..];
+        }
+        parser.current.push_str(remaining);
+        parser.flush();
+
+        RichText {
+            spans: parser.spans,
+        }
+    }
+
+    /// Returns the text without any styling.
+    pub fn plain_text(&self) -> String {
+        self.spans.iter().map(|s| s.text.as_str()).collect()
+    }
+}
+
+struct RichTextParser {
+    spans: Vec<TextSpan>,
+    current: String,
+    bold: usize,
+    italic: usize,
+    colors: Vec<Color>,
+    fonts: Vec<String>,
+    sizes: Vec<i32>,
+}
+
+impl RichTextParser {
+    /// Applies a tag to the current styling, returns false if it's not a valid tag.
+    fn apply_tag(&mut self, tag: &str) -> bool {
+        // Closing tags need to have something to close
+        let valid = match tag {
+            "/b" => self.bold != 0,
+            "/i" => self.italic != 0,
+            "/color" => self.colors.len() != 0,
+            "/font" => self.fonts.len() != 0,
+            "/size" => self.sizes.len() != 0,
+            "b" | "i" => true,
+            _ => {
+                if tag.starts_with("color=") {
+                    parse_hex_color(&tag[6..]).is_ok()
+                } else if tag.starts_with("font=") {
+                    tag.len() > 5
+                } else if tag.starts_with("size=") {
+                    tag[5..].parse::<i32>().is_ok()
+                } else {
+                    false
+                }
+            }
+        };
+        if !valid {
+            return false
+        }
+
+        // The styling changes, so everything before this tag has to become its own span
+        self.flush();
+
+        match tag {
+            "b" => self.bold += 1,
+            "/b" => self.bold -= 1,
+            "i" => self.italic += 1,
+            "/i" => self.italic -= 1,
+            "/color" => { self.colors.pop(); },
+            "/font" => { self.fonts.pop(); },
+            "/size" => { self.sizes.pop(); },
+            _ => {
+                if tag.starts_with("color=") {
+                    self.colors.push(parse_hex_color(&tag[6..]).unwrap());
+                } else if tag.starts_with("font=") {
+                    self.fonts.push(tag[5..].into());
+                } else {
+                    self.sizes.push(tag[5..].parse().unwrap());
+                }
+            }
+        }
+
+        true
+    }
+
+    fn flush(&mut self) {
+        if self.current.len() == 0 {
+            return
+        }
+
+        self.spans.push(TextSpan {
+            text: ::std::mem::replace(&mut self.current, String::new()),
+            bold: self.bold != 0,
+            italic: self.italic != 0,
+            color: self.colors.last().cloned(),
+            font: self.fonts.last().cloned(),
+            size: self.sizes.last().cloned(),
+        });
+    }
+}
+
+/// Renders rich text centered in an area to the component's cache, spans are laid out in
+/// sequence, starting a new line at every line break.
+pub fn rich_text(
+    renderer: &mut Renderer, id: ComponentId,
+    text: &RichText, style: &TextStyle,
+    position: Point2<f32>, size: Vector2<f32>,
+) -> Result<(), Error> {
+    let lines = layout(renderer, text, style)?;
+
+    // A single line is centered by the backend in the full area, the same as plain text,
+    // multiple lines are stacked in the middle of it
+    let mut y = position.y;
+    if lines.len() != 1 || style.line_height.is_some() {
+        let total_height: f32 = lines.iter().map(|line| line.height).sum();
+        y += (size.y - total_height) * 0.5;
+    }
+
+    let mut placed = Vec::new();
+    for line in &lines {
+        let height = if lines.len() == 1 && style.line_height.is_none() {
+            size.y
+        } else {
+            line.height
+        };
+
+        let mut x = position.x + (size.x - line.width) * 0.5;
+        for run in &line.runs {
+            placed.push((run, Point2::new(x, y), Vector2::new(run.width, height)));
+            x += run.width + style.letter_spacing;
+        }
+        y += height;
+    }
+
+    // Effects go behind all of the text, so the outline of a run can't cover the one before it
+    if !style.effects.is_empty() {
+        for &(run, position, size) in &placed {
+            renderer.text_effects(
+                id, &run.text, run.font, run.span.size.or(style.size), position, size,
+                &style.effects,
+            )?;
+        }
+    }
+
+    for &(run, position, size) in &placed {
+        renderer.text(
+            id, &run.text, run.font, run.span.size.or(style.size), position, size,
+            run.span.color.unwrap_or(style.color),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Measures the size rich text takes up when rendered with `rich_text`.
+pub fn measure_rich_text(
+    renderer: &mut Renderer, text: &RichText, style: &TextStyle,
+) -> Result<Vector2<f32>, Error> {
+    let lines = layout(renderer, text, style)?;
+    Ok(Vector2::new(
+        lines.iter().map(|line| line.width).fold(0.0, f32::max),
+        lines.iter().map(|line| line.height).sum(),
+    ))
+}
+
+/// Text that's rendered in a single call, with the font it's rendered in.
+struct Run<'a> {
+    text: String,
+    font: Option<&'a String>,
+    span: &'a TextSpan,
+    width: f32,
+}
+
+struct Line<'a> {
+    runs: Vec<Run<'a>>,
+    /// The width of the line, including the letter spacing between its runs.
+    width: f32,
+    /// The height of the line, including the line height.
+    height: f32,
+}
+
+/// Splits rich text up into lines of runs, and measures them.
+fn layout<'a>(
+    renderer: &mut Renderer, text: &'a RichText, style: &TextStyle<'a>,
+) -> Result<Vec<Line<'a>>, Error> {
+    let mut lines = vec![Vec::new()];
+    for span in &text.spans {
+        for (i, part) in span.text.split('\n').enumerate() {
+            if i != 0 {
+                lines.push(Vec::new());
+            }
+
+            // Split up spans where their characters need a fallback font
+            let part = part.to_string();
+            for (text, font) in font_runs(renderer, &part, span_font(span, style), style)? {
+                // Backends don't space out letters, so spaced text is rendered a letter at a time
+                if style.letter_spacing != 0.0 {
+                    for c in text.chars() {
+                        lines.last_mut().unwrap().push((c.to_string(), font, span));
+                    }
+                } else {
+                    lines.last_mut().unwrap().push((text, font, span));
+                }
+            }
+        }
+    }
+
+    let line_height = style.line_height.unwrap_or(1.0);
+    let mut measured = Vec::new();
+    for line in lines {
+        let mut runs = Vec::new();
+        let mut width = 0.0;
+        let mut height: f32 = 0.0;
+        for (text, font, span) in line {
+            let run_size = renderer.measure_text(&text, font, span.size.or(style.size))?;
+            if runs.len() != 0 {
+                width += style.letter_spacing;
+            }
+            width += run_size.x;
+            height = height.max(run_size.y);
+            runs.push(Run { text, font, span, width: run_size.x });
+        }
+
+        // Empty lines still take up the height of the text
+        if runs.len() == 0 {
+            height = renderer.measure_text(&" ".to_string(), style.font, style.size)?.y;
+        }
+
+        measured.push(Line { runs, width, height: height * line_height });
+    }
+
+    Ok(measured)
+}
+
+/// Splits text into runs of characters that can be rendered with the same font, picking the
+/// first font in the fallback chain that has a glyph for each character.
+fn font_runs<'a>(
+    renderer: &mut Renderer, text: &String, font: Option<&'a String>, style: &TextStyle<'a>,
+) -> Result<Vec<(String, Option<&'a String>)>, Error> {
+    // Most text is fully covered by its font, which only needs to be checked once
+    if style.fallback_fonts.len() == 0 || renderer.has_glyphs(text, font)? {
+        return Ok(vec![(text.clone(), font)])
+    }
+
+    let mut runs: Vec<(String, Option<&'a String>)> = Vec::new();
+    for c in text.chars() {
+        let character = c.to_string();
+        let mut char_font = font;
+        if !renderer.has_glyphs(&character, font)? {
+            for fallback in style.fallback_fonts {
+                if renderer.has_glyphs(&character, Some(fallback))? {
+                    char_font = Some(fallback);
+                    break
+                }
+            }
+        }
+
+        // Characters no font has are left to the span's own font to render however it can
+        let same_font = runs.last().map(|&(_, run_font)| run_font == char_font).unwrap_or(false);
+        if same_font {
+            runs.last_mut().unwrap().0.push(c);
+        } else {
+            runs.push((character, char_font));
+        }
+    }
+
+    Ok(runs)
+}
+
+fn span_font<'a>(span: &'a TextSpan, style: &TextStyle<'a>) -> Option<&'a String> {
+    if span.font.is_some() {
+        span.font.as_ref()
+    } else if span.bold && style.font_bold.is_some() {
+        style.font_bold
+    } else if span.italic && style.font_italic.is_some() {
+        style.font_italic
+    } else {
+        style.font
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Point2, Vector2};
+
+    use render::{
+        self, Renderer, RecordingRenderer, RenderCommand, RichText, TextStyle, TextEffects,
+        TextOutline, TextShadow,
+    };
+    use template::{Color};
+    use {ComponentId};
+
+    #[test]
+    fn it_parses_plain_text() {
+        let text = RichText::parse("Hello world");
+        assert_eq!(text.spans.len(), 1);
+        assert_eq!(text.spans[0].text, "Hello world");
+        assert!(!text.spans[0].bold);
+    }
+
+    #[test]
+    fn it_parses_styled_spans() {
+        let text = RichText::parse("Press [b]A[/b] to [color=#ff0]jump[/color]");
+        assert_eq!(text.spans.len(), 4);
+        assert_eq!(text.spans[0].text, "Press ");
+        assert_eq!(text.spans[1].text, "A");
+        assert!(text.spans[1].bold);
+        assert_eq!(text.spans[2].text, " to ");
+        assert!(!text.spans[2].bold);
+        assert_eq!(text.spans[3].text, "jump");
+        assert_eq!(text.spans[3].color, Some(Color::new_u8(255, 255, 0, 255)));
+    }
+
+    #[test]
+    fn it_keeps_unknown_tags_and_escapes_as_text() {
+        let text = RichText::parse("[[b] [1/2] [/b]");
+        assert_eq!(text.plain_text(), "[b] [1/2] [/b]");
+        assert!(text.spans.iter().all(|s| !s.bold));
+    }
+
+    #[test]
+    fn it_nests_tags() {
+        let text = RichText::parse("[size=20][font=mono]a[/font]b[/size]c");
+        assert_eq!(text.spans.len(), 3);
+        assert_eq!(text.spans[0].font, Some("mono".into()));
+        assert_eq!(text.spans[0].size, Some(20));
+        assert_eq!(text.spans[1].font, None);
+        assert_eq!(text.spans[1].size, Some(20));
+        assert_eq!(text.spans[2].size, None);
+    }
+
+    #[test]
+    fn it_falls_back_to_fonts_with_missing_glyphs() {
+        let id = ComponentId::placeholder();
+        let mut renderer = RecordingRenderer::new();
+        renderer.create_resize_cache(id, Vector2::new(100, 20)).unwrap();
+        renderer.set_font_glyphs(Some("display"), "Score: ");
+
+        let fonts = vec!["cjk".to_string()];
+        let display = "display".to_string();
+        let style = TextStyle {
+            font: Some(&display),
+            font_bold: None,
+            font_italic: None,
+            fallback_fonts: &fonts,
+            size: None,
+            color: Color::new(1.0, 1.0, 1.0, 1.0),
+            effects: TextEffects::default(),
+            letter_spacing: 0.0,
+            line_height: None,
+        };
+        let text = RichText::parse("Score: 得点");
+        render::rich_text(
+            &mut renderer, id, &text, &style, Point2::new(0.0, 0.0), Vector2::new(100.0, 20.0),
+        ).unwrap();
+
+        let runs: Vec<_> = renderer.commands(id).unwrap().iter().filter_map(|c| match *c {
+            RenderCommand::Text { ref text, ref font, .. } => Some((text.clone(), font.clone())),
+            _ => None,
+        }).collect();
+        assert_eq!(runs, vec![
+            ("Score: ".to_string(), Some("display".to_string())),
+            ("得点".to_string(), Some("cjk".to_string())),
+        ]);
+    }
+
+    #[test]
+    fn it_renders_shadows_and_outlines_behind_text() {
+        let id = ComponentId::placeholder();
+        let mut renderer = RecordingRenderer::new();
+        renderer.create_resize_cache(id, Vector2::new(100, 20)).unwrap();
+
+        let fonts = Vec::new();
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0, 1.0);
+        let shadow = Color::new(0.0, 0.0, 0.0, 0.5);
+        let style = TextStyle {
+            font: None,
+            font_bold: None,
+            font_italic: None,
+            fallback_fonts: &fonts,
+            size: None,
+            color: white,
+            effects: TextEffects {
+                outline: Some(TextOutline { width: 1.0, color: black }),
+                shadow: Some(TextShadow { offset: Vector2::new(2.0, 2.0), color: shadow }),
+            },
+            letter_spacing: 0.0,
+            line_height: None,
+        };
+        render::rich_text(
+            &mut renderer, id, &RichText::parse("Score"), &style,
+            Point2::new(0.0, 0.0), Vector2::new(100.0, 20.0),
+        ).unwrap();
+
+        let colors: Vec<_> = renderer.commands(id).unwrap().iter().filter_map(|c| match *c {
+            RenderCommand::Text { color, .. } => Some(color),
+            _ => None,
+        }).collect();
+        assert_eq!(colors.len(), 10);
+        assert_eq!(colors[0], shadow);
+        assert!(colors[1..9].iter().all(|c| *c == black));
+        assert_eq!(colors[9], white);
+    }
+
+    #[test]
+    fn it_spaces_letters_and_lines() {
+        let id = ComponentId::placeholder();
+        let mut renderer = RecordingRenderer::new();
+        renderer.create_resize_cache(id, Vector2::new(100, 100)).unwrap();
+
+        let fonts = Vec::new();
+        let style = TextStyle {
+            font: None,
+            font_bold: None,
+            font_italic: None,
+            fallback_fonts: &fonts,
+            size: None,
+            color: Color::new(1.0, 1.0, 1.0, 1.0),
+            effects: TextEffects::default(),
+            letter_spacing: 2.0,
+            line_height: Some(1.5),
+        };
+        let text = RichText::parse("ab\nc");
+
+        // Letters are 7 wide and lines 14 tall in the recording renderer
+        let size = render::measure_rich_text(&mut renderer, &text, &style).unwrap();
+        assert_eq!(size, Vector2::new(16.0, 42.0));
+
+        render::rich_text(
+            &mut renderer, id, &text, &style, Point2::new(0.0, 0.0), Vector2::new(100.0, 100.0),
+        ).unwrap();
+        let positions: Vec<_> = renderer.commands(id).unwrap().iter().filter_map(|c| match *c {
+            RenderCommand::Text { ref text, position, .. } => Some((text.clone(), position)),
+            _ => None,
+        }).collect();
+        assert_eq!(positions, vec![
+            ("a".to_string(), Point2::new(42.0, 29.0)),
+            ("b".to_string(), Point2::new(51.0, 29.0)),
+            ("c".to_string(), Point2::new(46.5, 50.0)),
+        ]);
+    }
+}
@@ -0,0 +1,33 @@
+use nalgebra::{Point2, Vector2};
+
+/// A scale and offset applied to a whole UI when its layers are rendered to the target, and
+/// inverted for input, see `Ui::set_root_transform`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RootTransform {
+    pub scale: f32,
+    pub offset: Vector2<f32>,
+}
+
+impl RootTransform {
+    pub fn new(scale: f32, offset: Vector2<f32>) -> Self {
+        RootTransform {
+            scale,
+            offset,
+        }
+    }
+
+    /// A transform that leaves the UI as it is.
+    pub fn identity() -> Self {
+        RootTransform::new(1.0, Vector2::new(0.0, 0.0))
+    }
+
+    /// Converts a position in the UI to where it ends up on the target.
+    pub fn apply(&self, position: Point2<f32>) -> Point2<f32> {
+        position * self.scale + self.offset
+    }
+
+    /// Converts a position on the target, such as the cursor's, to where it is in the UI.
+    pub fn invert(&self, position: Point2<f32>) -> Point2<f32> {
+        (position - self.offset) / self.scale
+    }
+}
@@ -1,7 +1,9 @@
 //! Scripting runtime types and helpers for interacting with it.
 
+mod model;
 mod runtime;
 mod value;
 
+pub use self::model::{ScriptModel, ScriptField};
 pub use self::runtime::{ScriptRuntime};
 pub use self::value::{ScriptTable, ScriptValue};
@@ -0,0 +1,191 @@
+use scripting::{ScriptTable, ScriptValue};
+
+/// A Rust type that's turned into a model, so game state can be synced into a UI with
+/// `Ui::sync_model`. Usually implemented with `#[derive(ScriptModel)]` from the
+/// `markedly-derive` crate, which also implements `Into<ScriptTable>`.
+///
+/// Every field is compared against the value already in the model with `ScriptField`, without
+/// converting it, so only fields that changed are converted and set.
+pub trait ScriptModel {
+    /// Gets which fields differ from the values in a model, as a flag for every field in the
+    /// order they're declared in.
+    fn changed_fields(&self, model: &ScriptTable) -> Vec<bool>;
+
+    /// Sets the fields of a model that differ from this value, leaving everything else in the
+    /// model alone, such as computed values.
+    /// Returns true if any field was changed.
+    fn sync_model(&self, model: &mut ScriptTable) -> bool;
+
+    /// Creates a new model holding the fields.
+    fn to_model(&self) -> ScriptTable {
+        let mut model = ScriptTable::new();
+        self.sync_model(&mut model);
+        model
+    }
+}
+
+/// A value that can be a field of a `ScriptModel`, which can be compared against a value in a
+/// model without being converted first.
+pub trait ScriptField {
+    /// Checks if a value in a model is the same as this.
+    fn matches(&self, value: &ScriptValue) -> bool;
+
+    /// Converts this to a value to store in a model.
+    fn to_script_value(&self) -> ScriptValue;
+}
+
+impl ScriptTable {
+    /// Checks if a field differs from the value with the given key in the model, or if the model
+    /// doesn't have that key yet.
+    pub fn field_changed<F: ScriptField + ?Sized>(&self, key: &str, field: &F) -> bool {
+        self.get(key).map(|value| !field.matches(value)).unwrap_or(true)
+    }
+
+    /// Sets the value with the given key in the model to a field, if it differs.
+    /// Returns true if the model changed.
+    pub fn sync_field<F: ScriptField + ?Sized>(&mut self, key: &str, field: &F) -> bool {
+        if !self.field_changed(key, field) {
+            return false
+        }
+
+        self.set(key, field.to_script_value());
+        true
+    }
+}
+
+impl ScriptField for bool {
+    fn matches(&self, value: &ScriptValue) -> bool {
+        *value == ScriptValue::Bool(*self)
+    }
+
+    fn to_script_value(&self) -> ScriptValue {
+        ScriptValue::Bool(*self)
+    }
+}
+
+impl ScriptField for str {
+    fn matches(&self, value: &ScriptValue) -> bool {
+        match *value {
+            ScriptValue::String(ref value) => value == self,
+            _ => false,
+        }
+    }
+
+    fn to_script_value(&self) -> ScriptValue {
+        ScriptValue::String(self.into())
+    }
+}
+
+impl ScriptField for String {
+    fn matches(&self, value: &ScriptValue) -> bool {
+        self.as_str().matches(value)
+    }
+
+    fn to_script_value(&self) -> ScriptValue {
+        self.as_str().to_script_value()
+    }
+}
+
+macro_rules! impl_number_field {
+    ($($ty:ty),*) => {
+        $(impl ScriptField for $ty {
+            fn matches(&self, value: &ScriptValue) -> bool {
+                *value == ScriptValue::Number(*self as f64)
+            }
+
+            fn to_script_value(&self) -> ScriptValue {
+                ScriptValue::Number(*self as f64)
+            }
+        })*
+    }
+}
+
+impl_number_field!(f64, f32, i32, u32);
+
+impl<T: ScriptField> ScriptField for Vec<T> {
+    fn matches(&self, value: &ScriptValue) -> bool {
+        match *value {
+            ScriptValue::List(ref values) =>
+                values.len() == self.len() &&
+                    self.iter().zip(values).all(|(field, value)| field.matches(value)),
+            _ => false,
+        }
+    }
+
+    fn to_script_value(&self) -> ScriptValue {
+        ScriptValue::List(self.iter().map(|field| field.to_script_value()).collect())
+    }
+}
+
+impl ScriptField for ScriptTable {
+    fn matches(&self, value: &ScriptValue) -> bool {
+        match *value {
+            ScriptValue::Table(ref value) => value == self,
+            _ => false,
+        }
+    }
+
+    fn to_script_value(&self) -> ScriptValue {
+        ScriptValue::Table(self.clone())
+    }
+}
+
+impl ScriptField for ScriptValue {
+    fn matches(&self, value: &ScriptValue) -> bool {
+        value == self
+    }
+
+    fn to_script_value(&self) -> ScriptValue {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Vector2};
+
+    use scripting::{ScriptTable, ScriptModel};
+    use template::{Template, Style};
+    use {Ui, Context};
+
+    struct Hud {
+        health: f32,
+        name: String,
+    }
+
+    impl ScriptModel for Hud {
+        fn changed_fields(&self, model: &ScriptTable) -> Vec<bool> {
+            vec![
+                model.field_changed("health", &self.health),
+                model.field_changed("name", &self.name),
+            ]
+        }
+
+        fn sync_model(&self, model: &mut ScriptTable) -> bool {
+            let health = model.sync_field("health", &self.health);
+            let name = model.sync_field("name", &self.name);
+            health || name
+        }
+    }
+
+    #[test]
+    fn it_syncs_only_changed_fields() {
+        let context = Context::for_test();
+        let template = Template::from_str("container { visible: ={model.health > 0} }\n")
+            .unwrap();
+        let style = Style::from_str("").unwrap();
+        let mut hud = Hud { health: 10.0, name: "Layl".into() };
+        let (mut ui, tree) = Ui::new(
+            &template, Some(&hud.to_model()), style, Vector2::new(100.0, 50.0), &context,
+        ).unwrap();
+
+        let mut model = ScriptTable::new();
+        model.set("name", "Layl");
+        assert_eq!(hud.changed_fields(&model), vec![true, false]);
+
+        assert!(!ui.sync_model(&tree, &hud, &context).unwrap());
+        hud.health = 0.0;
+        assert!(ui.sync_model(&tree, &hud, &context).unwrap());
+        assert!(!ui.get(tree.root()).unwrap().attributes().visible);
+    }
+}
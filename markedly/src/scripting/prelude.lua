@@ -0,0 +1,85 @@
+-- Helpers available to every script in templates and styles, loaded by `ScriptRuntime::new`.
+
+function clamp(value, min, max)
+    if value < min then return min end
+    if value > max then return max end
+    return value
+end
+
+function lerp(from, to, t)
+    return from + (to - from) * t
+end
+
+-- Formats a number with a fixed amount of decimals and thousands separators, such as
+-- fmt_number(1234.5, 2) giving "1,234.50".
+function fmt_number(value, decimals)
+    local text = string.format("%." .. (decimals or 0) .. "f", math.abs(value))
+    local whole, fraction = text:match("^(%d+)(.*)$")
+    whole = whole:reverse():gsub("(%d%d%d)", "%1,"):reverse():gsub("^,", "")
+    if value < 0 then
+        whole = "-" .. whole
+    end
+    return whole .. fraction
+end
+
+-- Formats a duration in seconds as minutes and seconds, such as "3:07", with the hours in front
+-- if it's an hour or longer, such as "1:03:07".
+function fmt_time(seconds)
+    seconds = math.floor(math.max(0, seconds))
+    local hours = seconds // 3600
+    local minutes = seconds // 60 % 60
+    if hours > 0 then
+        return string.format("%d:%02d:%02d", hours, minutes, seconds % 60)
+    end
+    return string.format("%d:%02d", minutes, seconds % 60)
+end
+
+-- Colors are hexadecimal strings, the same as in templates, such as "#4488ff".
+
+local function parse_color(color)
+    local hex = color:gsub("^#", "")
+    if #hex == 3 or #hex == 4 then
+        hex = hex:gsub(".", "%0%0")
+    end
+
+    local alpha = 255
+    if #hex == 8 then
+        alpha = tonumber(hex:sub(7, 8), 16)
+    end
+    return tonumber(hex:sub(1, 2), 16), tonumber(hex:sub(3, 4), 16),
+        tonumber(hex:sub(5, 6), 16), alpha
+end
+
+local function format_color(red, green, blue, alpha)
+    local function channel(value)
+        return math.floor(clamp(value, 0, 255) + 0.5)
+    end
+    return string.format(
+        "#%02x%02x%02x%02x", channel(red), channel(green), channel(blue), channel(alpha)
+    )
+end
+
+-- Blends between two colors, by t from 0.0 to 1.0.
+function color_mix(from, to, t)
+    local r1, g1, b1, a1 = parse_color(from)
+    local r2, g2, b2, a2 = parse_color(to)
+    return format_color(lerp(r1, r2, t), lerp(g1, g2, t), lerp(b1, b2, t), lerp(a1, a2, t))
+end
+
+-- Blends a color towards white, by an amount from 0.0 to 1.0.
+function color_lighten(color, amount)
+    local _, _, _, alpha = parse_color(color)
+    return color_mix(color, format_color(255, 255, 255, alpha), amount)
+end
+
+-- Blends a color towards black, by an amount from 0.0 to 1.0.
+function color_darken(color, amount)
+    local _, _, _, alpha = parse_color(color)
+    return color_mix(color, format_color(0, 0, 0, alpha), amount)
+end
+
+-- Changes the opacity of a color, from 0.0 to 1.0.
+function color_alpha(color, alpha)
+    local red, green, blue = parse_color(color)
+    return format_color(red, green, blue, alpha * 255)
+end
@@ -1,7 +1,11 @@
-use rlua::{Lua};
+use rlua::{Lua, Value, FromLuaMulti};
 
-use scripting::{ScriptTable};
-use {Error};
+use scripting::{ScriptTable, ScriptValue};
+use template::{TemplateValue};
+use {Error, EventSink};
+
+/// Helper functions for formatting, math, and colors, available to every script.
+const PRELUDE: &str = include_str!("prelude.lua");
 
 /// Keeps track of the scripting engine and data in it.
 pub struct ScriptRuntime {
@@ -9,8 +13,19 @@ pub struct ScriptRuntime {
 }
 
 impl ScriptRuntime {
-    /// Creates a new runtime.
+    /// Creates a new runtime, with the prelude of helper functions loaded. The prelude provides
+    /// `fmt_number`, `fmt_time`, `clamp`, `lerp`, `color_mix`, `color_lighten`,
+    /// `color_darken`, and `color_alpha`.
     pub fn new() -> Self {
+        let runtime = Self::without_prelude();
+        runtime.lua.exec::<()>(PRELUDE, Some("prelude"))
+            .expect("Failed to load the script prelude");
+        runtime
+    }
+
+    /// Creates a new runtime without the prelude of helper functions, such as when a game
+    /// defines its own functions with the same names.
+    pub fn without_prelude() -> Self {
         let lua = Lua::new();
 
         ScriptRuntime {
@@ -27,23 +42,119 @@ impl ScriptRuntime {
         Ok(())
     }
 
+    /// Evaluates a script to a plain template value, so it can be compared and interpolated.
+    pub(crate) fn eval_template_value(&self, source: &str) -> Result<TemplateValue, Error> {
+        match self.eval(source)? {
+            Value::Boolean(value) => Ok(TemplateValue::Bool(value)),
+            Value::Integer(value) => Ok(TemplateValue::Integer(value as i32)),
+            Value::Number(value) => Ok(TemplateValue::Float(value as f32)),
+            Value::String(value) => Ok(TemplateValue::String(value.to_str()?.into())),
+            _ => Err("Script did not result in a boolean, number, or string".into()),
+        }
+    }
+
     pub(crate) fn eval_bool(&self, source: &str) -> Result<bool, Error> {
-        let value = self.lua.eval(source, None)?;
-        Ok(value)
+        self.eval(source)
     }
 
     pub(crate) fn eval_integer(&self, source: &str) -> Result<i32, Error> {
-        let value = self.lua.eval(source, None)?;
-        Ok(value)
+        self.eval(source)
     }
 
     pub(crate) fn eval_float(&self, source: &str) -> Result<f32, Error> {
-        let value = self.lua.eval(source, None)?;
-        Ok(value)
+        self.eval(source)
     }
 
     pub(crate) fn eval_string(&self, source: &str) -> Result<String, Error> {
-        let value = self.lua.eval(source, None)?;
-        Ok(value)
+        self.eval(source)
+    }
+
+    pub(crate) fn eval_floats(&self, source: &str) -> Result<Vec<f32>, Error> {
+        self.eval(source)
+    }
+
+    /// Runs a script statement, such as an event hook. The statement can call `raise(name)` or
+    /// `raise(name, payload)` to raise events on the event sink, with a boolean, number, or
+    /// string as payload.
+    pub(crate) fn run_statement(&self, source: &str, event_sink: &EventSink) -> Result<(), Error> {
+        let result = self.lua.scope(|scope| {
+            let raise = scope.create_function(|_, (name, payload): (String, Value)| {
+                event_sink.raise_script_event(name, ScriptValue::from_lua_value(payload)?);
+                Ok(())
+            })?;
+
+            // The function only lives as long as the scope, so it's removed again afterwards
+            let globals = self.lua.globals();
+            globals.set("raise", raise)?;
+            let result = self.lua.exec::<()>(source, None);
+            globals.set("raise", Value::Nil)?;
+            result
+        });
+
+        result.map_err(|error| {
+            warn!("Script `{}` failed: {}", source, error);
+            error.into()
+        })
+    }
+
+    fn eval<'lua, R: FromLuaMulti<'lua>>(&'lua self, source: &str) -> Result<R, Error> {
+        self.lua.eval(source, None).map_err(|error| {
+            warn!("Script `{}` failed: {}", source, error);
+            error.into()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use scripting::{ScriptRuntime, ScriptTable, ScriptValue};
+
+    #[test]
+    fn it_provides_prelude_helpers() {
+        let runtime = ScriptRuntime::new();
+
+        assert_eq!(runtime.eval_string("fmt_number(1234567.891, 2)").unwrap(), "1,234,567.89");
+        assert_eq!(runtime.eval_string("fmt_number(-999, 0)").unwrap(), "-999");
+        assert_eq!(runtime.eval_string("fmt_time(187)").unwrap(), "3:07");
+        assert_eq!(runtime.eval_string("fmt_time(3725)").unwrap(), "1:02:05");
+        assert_eq!(runtime.eval_float("clamp(5, 0, 3)").unwrap(), 3.0);
+        assert_eq!(runtime.eval_float("lerp(10, 20, 0.25)").unwrap(), 12.5);
+        assert_eq!(
+            runtime.eval_string("color_mix(\"#000\", \"#ffffff\", 0.5)").unwrap(), "#808080ff",
+        );
+        assert_eq!(runtime.eval_string("color_alpha(\"#4488ff\", 0.0)").unwrap(), "#4488ff00");
+    }
+    #[test]
+    fn it_caches_computed_model_values() {
+        let runtime = ScriptRuntime::new();
+        let computations = Arc::new(AtomicUsize::new(0));
+        let counter = computations.clone();
+
+        let mut model = ScriptTable::new();
+        model.set("price", 3.0);
+        model.set("amount", 2.0);
+        model.set_computed("total", move |model| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            match (model.get("price"), model.get("amount")) {
+                (Some(&ScriptValue::Number(price)), Some(&ScriptValue::Number(amount))) =>
+                    ScriptValue::Number(price * amount),
+                _ => ScriptValue::Number(0.0),
+            }
+        });
+        model.set_computed_script("label", "fmt_number(model.price, 2)");
+
+        runtime.set_model(&model).unwrap();
+        runtime.set_model(&model.clone()).unwrap();
+        assert_eq!(runtime.eval_float("model.total").unwrap(), 6.0);
+        assert_eq!(runtime.eval_string("model.label").unwrap(), "3.00");
+        assert_eq!(computations.load(Ordering::SeqCst), 1);
+
+        model.set("amount", 4.0);
+        runtime.set_model(&model).unwrap();
+        assert_eq!(runtime.eval_float("model.total").unwrap(), 24.0);
+        assert_eq!(computations.load(Ordering::SeqCst), 2);
     }
 }
@@ -1,10 +1,35 @@
 use std::collections::{HashMap};
-use rlua::{Lua, Table};
+use std::fmt::{self, Debug, Formatter};
+use rlua::{self, Lua, Table, Value, Function};
+use class::{MaybeSend};
+use shared::{self, Shared, lock};
 use {Error};
 
+// Trait objects can't be bound by `MaybeSend`, so the bound is picked here instead
+#[cfg(not(feature = "send"))]
+type ComputeFn = Fn(&ScriptTable) -> ScriptValue;
+#[cfg(feature = "send")]
+type ComputeFn = Fn(&ScriptTable) -> ScriptValue + Send;
+
 /// Tracks values to be converted to a model for use by the scripting language.
+#[derive(Clone, Debug)]
 pub struct ScriptTable {
     values: HashMap<String, ScriptValue>,
+    computed: HashMap<String, Computed>,
+}
+
+/// A value derived from the other values in a model, cached until they change.
+#[derive(Clone)]
+struct Computed {
+    source: ComputedSource,
+    /// Shared between clones of the model, as long as neither changes.
+    cached: Shared<Option<ScriptValue>>,
+}
+
+#[derive(Clone)]
+enum ComputedSource {
+    Function(Shared<Box<ComputeFn>>),
+    Script(String),
 }
 
 impl ScriptTable {
@@ -12,6 +37,7 @@ impl ScriptTable {
     pub fn new() -> Self {
         ScriptTable {
             values: HashMap::new(),
+            computed: HashMap::new(),
         }
     }
 
@@ -19,25 +45,143 @@ impl ScriptTable {
         let model_table = lua.create_table()?;
 
         for (key, value) in &self.values {
-            match *value {
-                ScriptValue::Bool(value) => model_table.set(key.as_str(), value)?,
-                ScriptValue::String(ref value) => model_table.set(key.as_str(), value.as_str())?,
-            }
+            model_table.set(key.as_str(), value.to_lua_value(lua)?)?;
+        }
+
+        // Computed values only see the plain values, so they can be computed in any order
+        let mut computed_values = Vec::new();
+        for (key, computed) in &self.computed {
+            let cached = lock(&computed.cached).clone();
+            let value = match cached {
+                Some(value) => value,
+                None => {
+                    let value = match computed.source {
+                        ComputedSource::Function(ref function) => (lock(function))(self),
+                        ComputedSource::Script(ref source) => {
+                            let function: Function = lua.eval(
+                                &format!("function(model) return {} end", source), None,
+                            )?;
+                            let value = function.call(model_table.clone())?;
+                            ScriptValue::from_lua_value(value)?.ok_or_else(|| format!(
+                                "Computed value \"{}\" did not result in a boolean, number, or \
+                                string", key,
+                            ))?
+                        },
+                    };
+                    *lock(&computed.cached) = Some(value.clone());
+                    value
+                },
+            };
+            computed_values.push((key, value.to_lua_value(lua)?));
+        }
+        for (key, value) in computed_values {
+            model_table.set(key.as_str(), value)?;
         }
 
         Ok(model_table)
     }
 
+    /// Gets the value of the field with given key in the model. Computed values are not
+    /// included.
+    pub fn get(&self, key: &str) -> Option<&ScriptValue> {
+        self.values.get(key)
+    }
+
     /// Sets the field with given key in the model to the given value.
     pub fn set<V: Into<ScriptValue>>(&mut self, key: &str, value: V) {
         self.values.insert(key.into(), value.into());
+        self.invalidate_computed();
+    }
+
+    /// Sets the field with given key in the model to a value computed from the model's other
+    /// values. The value is only computed when the model is used, and is cached until a value
+    /// in the model is set again, so expensive aggregations are not run for every attribute
+    /// bound to it.
+    pub fn set_computed<F>(&mut self, key: &str, function: F)
+        where F: Fn(&ScriptTable) -> ScriptValue + MaybeSend + 'static
+    {
+        let function: Box<ComputeFn> = Box::new(function);
+        self.insert_computed(key, ComputedSource::Function(shared::new(function)));
+    }
+
+    /// Sets the field with given key in the model to a value computed by a script expression,
+    /// such as `model.price * model.amount`, cached the same way as `set_computed`.
+    pub fn set_computed_script(&mut self, key: &str, source: &str) {
+        self.insert_computed(key, ComputedSource::Script(source.into()));
+    }
+
+    fn insert_computed(&mut self, key: &str, source: ComputedSource) {
+        self.computed.insert(key.into(), Computed {
+            source,
+            cached: Default::default(),
+        });
+    }
+
+    fn invalidate_computed(&mut self) {
+        // Clones of the model may still use the old cache, so it's replaced rather than cleared
+        for computed in self.computed.values_mut() {
+            computed.cached = Default::default();
+        }
+    }
+}
+
+impl PartialEq for ScriptTable {
+    fn eq(&self, other: &ScriptTable) -> bool {
+        // Computed values are derived from the plain values, so only their keys are compared
+        self.values == other.values &&
+            self.computed.len() == other.computed.len() &&
+            self.computed.keys().all(|key| other.computed.contains_key(key))
+    }
+}
+
+impl Debug for Computed {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.source {
+            ComputedSource::Function(_) => write!(f, "Computed(Function)"),
+            ComputedSource::Script(ref source) => write!(f, "Computed({:?})", source),
+        }
     }
 }
 
 /// A generic value stored in the model.
+#[derive(Clone, Debug, PartialEq)]
 pub enum ScriptValue {
     Bool(bool),
     String(String),
+    Number(f64),
+    /// An ordered list, indexed from 1 in scripts like any other Lua sequence.
+    List(Vec<ScriptValue>),
+    Table(ScriptTable),
+}
+
+impl ScriptValue {
+    /// Converts a value given by a script, returns None for values that can't be stored in a
+    /// model, such as nil and functions.
+    pub(crate) fn from_lua_value(value: Value) -> Result<Option<ScriptValue>, rlua::Error> {
+        Ok(match value {
+            Value::Boolean(value) => Some(ScriptValue::Bool(value)),
+            Value::Integer(value) => Some(ScriptValue::Number(value as f64)),
+            Value::Number(value) => Some(ScriptValue::Number(value)),
+            Value::String(value) => Some(ScriptValue::String(value.to_str()?.into())),
+            _ => None,
+        })
+    }
+
+    fn to_lua_value<'l>(&self, lua: &'l Lua) -> Result<Value<'l>, Error> {
+        Ok(match *self {
+            ScriptValue::Bool(value) => Value::Boolean(value),
+            ScriptValue::String(ref value) => Value::String(lua.create_string(value)?),
+            ScriptValue::Number(value) => Value::Number(value),
+            ScriptValue::List(ref values) => {
+                let table = lua.create_table()?;
+                for (i, value) in values.iter().enumerate() {
+                    table.set((i + 1) as i64, value.to_lua_value(lua)?)?;
+                }
+                Value::Table(table)
+            },
+            ScriptValue::Table(ref table) => Value::Table(table.to_lua_table(lua)?),
+        })
+    }
 }
 
 impl From<bool> for ScriptValue {
@@ -51,3 +195,45 @@ impl From<String> for ScriptValue {
         ScriptValue::String(value)
     }
 }
+
+impl<'a> From<&'a str> for ScriptValue {
+    fn from(value: &'a str) -> Self {
+        ScriptValue::String(value.into())
+    }
+}
+
+impl From<f64> for ScriptValue {
+    fn from(value: f64) -> Self {
+        ScriptValue::Number(value)
+    }
+}
+
+impl From<f32> for ScriptValue {
+    fn from(value: f32) -> Self {
+        ScriptValue::Number(value as f64)
+    }
+}
+
+impl From<i32> for ScriptValue {
+    fn from(value: i32) -> Self {
+        ScriptValue::Number(value as f64)
+    }
+}
+
+impl From<u32> for ScriptValue {
+    fn from(value: u32) -> Self {
+        ScriptValue::Number(value as f64)
+    }
+}
+
+impl From<Vec<ScriptValue>> for ScriptValue {
+    fn from(value: Vec<ScriptValue>) -> Self {
+        ScriptValue::List(value)
+    }
+}
+
+impl From<ScriptTable> for ScriptValue {
+    fn from(value: ScriptTable) -> Self {
+        ScriptValue::Table(value)
+    }
+}
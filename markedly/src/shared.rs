@@ -0,0 +1,42 @@
+//! Shared mutable data, which is thread-safe if the `send` feature is enabled.
+
+pub use self::implementation::{Shared, new, lock, same};
+
+#[cfg(not(feature = "send"))]
+mod implementation {
+    use std::rc::{Rc};
+    use std::cell::{RefCell, RefMut};
+
+    pub type Shared<T> = Rc<RefCell<T>>;
+
+    pub fn new<T>(value: T) -> Shared<T> {
+        Rc::new(RefCell::new(value))
+    }
+
+    pub fn lock<T>(value: &Shared<T>) -> RefMut<T> {
+        value.borrow_mut()
+    }
+
+    pub fn same<T>(a: &Shared<T>, b: &Shared<T>) -> bool {
+        Rc::ptr_eq(a, b)
+    }
+}
+
+#[cfg(feature = "send")]
+mod implementation {
+    use std::sync::{Arc, Mutex, MutexGuard};
+
+    pub type Shared<T> = Arc<Mutex<T>>;
+
+    pub fn new<T>(value: T) -> Shared<T> {
+        Arc::new(Mutex::new(value))
+    }
+
+    pub fn lock<T>(value: &Shared<T>) -> MutexGuard<T> {
+        value.lock().unwrap()
+    }
+
+    pub fn same<T>(a: &Shared<T>, b: &Shared<T>) -> bool {
+        Arc::ptr_eq(a, b)
+    }
+}
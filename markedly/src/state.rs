@@ -0,0 +1,83 @@
+use std::collections::{BTreeMap};
+
+/// Persistent runtime state of all components in a UI, such as scroll offsets, captured with
+/// `Ui::save_state` so it can be restored with `Ui::restore_state` after the UI is built again.
+///
+/// Components are matched up by their position in the UI and their class, so state is only
+/// restored to components that are in the same place as before.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct UiState {
+    pub components: BTreeMap<String, ComponentState>,
+}
+
+impl UiState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Persistent runtime state of a single component, saved and restored by its class.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct ComponentState {
+    pub values: BTreeMap<String, StateValue>,
+}
+
+impl ComponentState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the value with the given key, if it was saved.
+    pub fn get(&self, key: &str) -> Option<&StateValue> {
+        self.values.get(key)
+    }
+
+    /// Sets the value with the given key.
+    pub fn set<V: Into<StateValue>>(&mut self, key: &str, value: V) {
+        self.values.insert(key.into(), value.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// A single value in a component's state.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StateValue {
+    Bool(bool),
+    Float(f32),
+    String(String),
+}
+
+impl StateValue {
+    pub fn as_bool(&self) -> Option<bool> {
+        if let StateValue::Bool(value) = *self { Some(value) } else { None }
+    }
+
+    pub fn as_float(&self) -> Option<f32> {
+        if let StateValue::Float(value) = *self { Some(value) } else { None }
+    }
+
+    pub fn as_string(&self) -> Option<&String> {
+        if let StateValue::String(ref value) = *self { Some(value) } else { None }
+    }
+}
+
+impl From<bool> for StateValue {
+    fn from(value: bool) -> Self {
+        StateValue::Bool(value)
+    }
+}
+
+impl From<f32> for StateValue {
+    fn from(value: f32) -> Self {
+        StateValue::Float(value)
+    }
+}
+
+impl From<String> for StateValue {
+    fn from(value: String) -> Self {
+        StateValue::String(value)
+    }
+}
@@ -0,0 +1,23 @@
+use std::collections::{BTreeMap};
+
+/// A snapshot of what a UI is made up of, gathered with `Ui::stats`, to keep an eye on a UI
+/// growing over time, such as from inserted trees that are never removed.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct UiStats {
+    /// The amount of components, including the roots of layers and inserted trees.
+    pub components: usize,
+    /// The amount of components of every component class, by class name.
+    pub components_per_class: BTreeMap<String, usize>,
+    /// The amount of trees, being the root tree, layers, and inserted templates.
+    pub trees: usize,
+    /// The amount of components that changed since they were last rendered.
+    pub dirty_components: usize,
+    /// How deeply components are nested, a lone root component has a depth of 1.
+    pub depth: usize,
+    /// The amount of distinct templates components were created from. Components created from
+    /// the same template share it, so this only grows when different templates are inserted.
+    pub templates: usize,
+    /// The amount of attribute values kept by all components, both resolved from templates and
+    /// styles, and set at runtime.
+    pub attributes: usize,
+}
@@ -0,0 +1,172 @@
+//! Interned strings, for names that are repeated a lot such as component classes and attribute
+//! keys.
+
+use std::borrow::{Borrow};
+use std::cmp::{Ordering};
+use std::collections::{HashSet};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref};
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "serialize")]
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+
+/// An interned string. Every symbol with the same text shares the same allocation, so cloning a
+/// symbol doesn't allocate and comparing symbols only compares pointers.
+#[derive(Clone)]
+pub struct Symbol {
+    text: Arc<str>,
+}
+
+impl Symbol {
+    /// Finds the symbol for the text, interning it if this is the first time it's used.
+    pub fn new(text: &str) -> Self {
+        let mut interner = INTERNER.lock().unwrap();
+
+        if let Some(text) = interner.get(text) {
+            return Symbol { text: text.clone() }
+        }
+
+        let text: Arc<str> = text.into();
+        interner.insert(text.clone());
+        Symbol { text }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+lazy_static! {
+    /// The global interner, created the first time a symbol is made and never freed.
+    static ref INTERNER: Mutex<HashSet<Arc<str>>> = Mutex::new(HashSet::new());
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Symbol) -> bool {
+        // Symbols are interned, so equal text always means the same allocation
+        Arc::ptr_eq(&self.text, &other.text)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // This has to match the hash of str, so symbols can be looked up by str
+        self.as_str().hash(state)
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Symbol) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Symbol) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for Symbol {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'a> From<&'a str> for Symbol {
+    fn from(text: &'a str) -> Self {
+        Symbol::new(text)
+    }
+}
+
+impl<'a> From<&'a String> for Symbol {
+    fn from(text: &'a String) -> Self {
+        Symbol::new(text)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(text: String) -> Self {
+        Symbol::new(&text)
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(self.as_str(), f)
+    }
+}
+
+impl Debug for Symbol {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Deserialized symbols have to be interned again, or they wouldn't compare equal
+        let text = String::deserialize(deserializer)?;
+        Ok(Symbol::new(&text))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {Symbol};
+
+    #[test]
+    fn it_shares_equal_symbols() {
+        let a = Symbol::new("color");
+        let b = Symbol::from(String::from("color"));
+        let c = Symbol::new("colour");
+
+        assert_eq!(a, b);
+        assert!(a != c);
+        assert_eq!(a, "color");
+    }
+}
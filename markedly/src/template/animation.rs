@@ -0,0 +1,35 @@
+use std::sync::{Arc};
+
+use template::{TemplateAttribute, SourceLocation};
+
+/// A named keyframe animation defined in a style, played on components using the `animation`
+/// attribute.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct AnimationTemplate {
+    pub(crate) name: String,
+    /// The keyframes, in the order they were defined in.
+    pub(crate) keyframes: Vec<KeyframeTemplate>,
+    /// Where this animation is in the source markup.
+    pub(crate) location: SourceLocation,
+}
+
+impl AnimationTemplate {
+    pub(crate) fn set_source_name(&mut self, name: &Arc<str>) {
+        self.location.name = Some(name.clone());
+        for keyframe in &mut self.keyframes {
+            for attribute in &mut keyframe.attributes {
+                attribute.location.name = Some(name.clone());
+            }
+        }
+    }
+}
+
+/// The attributes a component has at a point in an animation.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct KeyframeTemplate {
+    /// The position in the animation, from 0.0 to 1.0.
+    pub(crate) position: f32,
+    pub(crate) attributes: Vec<TemplateAttribute>,
+}
@@ -1,33 +1,65 @@
-use std::collections::{HashMap};
+use std::cell::{RefCell};
+use std::collections::{HashMap, HashSet};
 
-use template::{ComponentTemplate, Style, TemplateValue};
-use {Error, Context};
+use class::{ComponentClasses};
+use template::{ComponentTemplate, Style, TemplateValue, SourceLocation};
+use {Error, Context, Symbol, Warning};
 
 /// A generated attribute bundle for a component, used by the component and its class to receive
 /// data from templates and styles.
+#[derive(Clone)]
 pub struct Attributes {
-    attributes: HashMap<String, TemplateValue>,
-    component_class: String,
-    component_line: usize,
+    attributes: HashMap<Symbol, TemplateValue>,
+    /// Where in the template or style each attribute was set, if it was set from markup.
+    sources: HashMap<Symbol, SourceLocation>,
+    /// The keys of attributes that have been read, to find attributes nothing uses.
+    used: RefCell<HashSet<Symbol>>,
+    component_class: Symbol,
+    component_name: Option<String>,
+    component_location: SourceLocation,
 }
 
 impl Attributes {
-    /// Resolves the final attributes of the current component from its template and the style.
-    pub fn resolve(
-        template: &ComponentTemplate, style: &Style, context: &Context,
+    /// Resolves the final attributes of the current component from what it inherited from its
+    /// parent, its template, and the style entries matching its style classes.
+    pub(crate) fn resolve(
+        template: &ComponentTemplate, style_classes: &[Symbol], style: &Style,
+        inherited: &InheritedAttributes, context: &Context,
     ) -> Result<Self, Error> {
         let mut attributes = HashMap::new();
+        let mut sources = HashMap::new();
 
         // Attributes should always be added, and thus overwritten, in the sequence they were in in
         // the template
 
-        // Add any styles from the stylesheet
-        for component in &style.components {
-            if component.class == template.class {
-                for attribute in &component.attributes {
-                    if attribute.check_conditional(&context.runtime)? {
-                        attributes.insert(attribute.key.clone(), attribute.value.clone());
-                    }
+        // Inherited attributes come first, so the style and template can still override them
+        for (key, &(ref value, ref source)) in &inherited.values {
+            attributes.insert(key.clone(), value.clone());
+            if let Some(ref source) = *source {
+                sources.insert(key.clone(), source.clone());
+            }
+        }
+
+        // Add any styles from the stylesheet, those for a style class after those for the entire
+        // component class so they take priority, and those for any component class before those
+        // for this one
+        let has_style_class = |component: &&ComponentTemplate| {
+            component.style_class.as_ref()
+                .map(|class| style_classes.contains(class))
+                .unwrap_or(false)
+        };
+        let styles = style.components_of_class("*")
+            .filter(|component| component.style_class.is_none())
+            .chain(style.components_of_class(&template.class)
+                .filter(|component| component.style_class.is_none()))
+            .chain(style.components_of_class("*").filter(&has_style_class))
+            .chain(style.components_of_class(&template.class).filter(&has_style_class));
+        for component in styles {
+            for attribute in &component.attributes {
+                if attribute.check_conditional(&context.runtime)? {
+                    let value = resolve_value(&attribute.value, style, context)?;
+                    attributes.insert(attribute.key.clone(), value);
+                    sources.insert(attribute.key.clone(), attribute.location.clone());
                 }
             }
         }
@@ -35,37 +67,109 @@ impl Attributes {
         // Overwrite any style resolved attributes with this component's set attributes
         for attribute in &template.attributes {
             if attribute.check_conditional(&context.runtime)? {
-                attributes.insert(attribute.key.clone(), attribute.value.clone());
+                let value = resolve_value(&attribute.value, style, context)?;
+                attributes.insert(attribute.key.clone(), value);
+                sources.insert(attribute.key.clone(), attribute.location.clone());
             }
         }
 
+        // The name is needed to tell components apart in errors, even before it's loaded
+        let component_name = attributes.get("name")
+            .and_then(|value| value.as_string(&context.runtime).ok());
+
         Ok(Attributes {
             component_class: template.class.clone(),
-            component_line: template.line,
+            component_name,
+            component_location: template.location.clone(),
             attributes,
+            sources,
+            used: RefCell::new(HashSet::new()),
         })
     }
 
+    /// Gets the attributes to pass down to the component's children, the ones that are inherited.
+    pub(crate) fn inherited(&self, classes: &ComponentClasses) -> InheritedAttributes {
+        let values = self.attributes.iter()
+            .filter(|&(key, _)| classes.is_inherited(key))
+            .map(|(key, value)| (key.clone(), (value.clone(), self.sources.get(key).cloned())))
+            .collect();
+        InheritedAttributes { values }
+    }
+
+    /// Gets the raw value of an attribute, if it was set.
+    pub fn get(&self, key: &str) -> Option<&TemplateValue> {
+        self.mark_used(key);
+        self.attributes.get(key)
+    }
+
+    /// Sets an attribute, overwriting whatever was resolved from the template and style. The
+    /// value is still considered to come from where the original value was set, such as for
+    /// values partway through a transition.
+    pub(crate) fn set<K: Into<Symbol>>(&mut self, key: K, value: TemplateValue) {
+        self.attributes.insert(key.into(), value);
+    }
+
+    /// Sets an attribute that wasn't set through markup, such as at runtime.
+    pub(crate) fn set_override<K: Into<Symbol>>(&mut self, key: K, value: TemplateValue) {
+        let key = key.into();
+        self.sources.remove(&key);
+        self.attributes.insert(key, value);
+    }
+
+    /// Gets the amount of attributes that are set.
+    pub(crate) fn len(&self) -> usize {
+        self.attributes.len()
+    }
+
+    /// Gets where in the template or style an attribute was set.
+    pub(crate) fn source(&self, key: &str) -> Option<&SourceLocation> {
+        self.sources.get(key)
+    }
+
+    /// Gets the keys of all attributes that haven't been read since they were resolved, sorted
+    /// by key.
+    pub(crate) fn unused_keys(&self) -> Vec<Symbol> {
+        let used = self.used.borrow();
+        let mut keys: Vec<Symbol> = self.attributes.keys()
+            .filter(|key| !used.contains(*key))
+            .cloned()
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    fn mark_used(&self, key: &str) {
+        // Only attributes that are set can be unused, so only those need to be marked
+        if let Some((key, _)) = self.attributes.get_key_value(key) {
+            self.used.borrow_mut().insert(key.clone());
+        }
+    }
+
+    fn error(&self, key: &str, error: Error) -> Error {
+        Error::Attribute {
+            component: self.component_class.to_string(),
+            name: self.component_name.clone(),
+            location: self.component_location.clone(),
+            field: key.into(),
+            source: self.sources.get(key).cloned(),
+            inner: Box::new(error),
+        }
+    }
+
     pub fn attribute<O, F: FnOnce(&TemplateValue) -> Result<O, Error>>(
         &self, key: &str, map: F, default: O
     ) -> Result<O, Error> {
+        self.mark_used(key);
         self.attributes.get(key)
             .map(map)
             .unwrap_or(Ok(default))
-            // Error reporting here is done by what component is being resolved, rather than
-            // where the attribute came from, for example a style file. Both of these are relevant
-            // information for resolving the error, so this needs to be changed to both.
-            .map_err(|error| Error::Attribute {
-                component: self.component_class.clone(),
-                line: self.component_line,
-                field: key.into(),
-                inner: Box::new(error),
-            })
+            .map_err(|error| self.error(key, error))
     }
 
     pub fn attribute_optional<O, F: FnOnce(&TemplateValue) -> Result<O, Error>>(
         &self, key: &str, map: F,
     ) -> Result<Option<O>, Error> {
+        self.mark_used(key);
         self.attributes.get(key)
             .map(|value| {
                 if *value == TemplateValue::Default {
@@ -75,14 +179,62 @@ impl Attributes {
                 }
             })
             .unwrap_or(Ok(None))
-            // Error reporting here is done by what component is being resolved, rather than
-            // where the attribute came from, for example a style file. Both of these are relevant
-            // information for resolving the error, so this needs to be changed to both.
-            .map_err(|error| Error::Attribute {
-                component: self.component_class.clone(),
-                line: self.component_line,
-                field: key.into(),
-                inner: Box::new(error),
-            })
+            .map_err(|error| self.error(key, error))
     }
 }
+
+/// Attributes passed down from a component to its descendants, such as text styling set on a
+/// container, with where they were set.
+#[derive(Clone, Default, PartialEq)]
+pub(crate) struct InheritedAttributes {
+    values: HashMap<Symbol, (TemplateValue, Option<SourceLocation>)>,
+}
+
+/// Replaces theme variables with their value in the style, including those in tuples, and
+/// translates texts, so classes only ever see the final value.
+pub(crate) fn resolve_value(
+    value: &TemplateValue, style: &Style, context: &Context,
+) -> Result<TemplateValue, Error> {
+    match *value {
+        TemplateValue::ThemeVariable(ref name) => {
+            let value = style.theme.get(name)
+                .ok_or_else(|| format!("Theme variable ${} is not defined", name))?;
+            localize(value, context)
+        },
+        TemplateValue::Tuple(ref values) => {
+            let mut resolved = Vec::new();
+            for value in values {
+                resolved.push(resolve_value(value, style, context)?);
+            }
+            Ok(TemplateValue::Tuple(resolved))
+        },
+        _ => localize(value, context),
+    }
+}
+
+/// Looks up the text of a translation value, so classes only ever see the translated string.
+/// Missing texts are reported as a warning and shown as their key instead.
+fn localize(value: &TemplateValue, context: &Context) -> Result<TemplateValue, Error> {
+    let (key, arguments) = match *value {
+        TemplateValue::Translation { ref key, ref arguments } => (key, arguments),
+        _ => return Ok(value.clone()),
+    };
+
+    let mut values = Vec::new();
+    for argument in arguments {
+        values.push(match *argument {
+            TemplateValue::ScriptValue(ref script) =>
+                context.runtime.eval_template_value(script)?,
+            ref argument => argument.clone(),
+        });
+    }
+
+    let text = context.localization.translate(key, &values).unwrap_or_else(|| {
+        context.warnings.push(Warning::MissingTranslation {
+            key: key.clone(),
+            locale: context.localization.locale().into(),
+        });
+        key.clone()
+    });
+    Ok(TemplateValue::String(text))
+}
@@ -1,28 +1,71 @@
+use std::sync::{Arc};
+
 use scripting::{ScriptRuntime};
-use template::{TemplateValue};
-use {Error};
+use template::{TemplateValue, SourceLocation};
+use {Error, Symbol};
 
 /// A template for a component.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct ComponentTemplate {
     /// The component class this component has.
-    pub(crate) class: String,
+    pub(crate) class: Symbol,
     /// The style class this component has.
-    pub(crate) style_class: Option<String>,
+    pub(crate) style_class: Option<Symbol>,
     /// The attributes given to this component.
     pub(crate) attributes: Vec<TemplateAttribute>,
-    /// The children of this component.
-    pub(crate) children: Vec<ComponentTemplate>,
-    /// The line this component is at in the source markup.
-    pub(crate) line: usize,
+    /// The children of this component, shared with the components created from them.
+    pub(crate) children: Vec<Arc<ComponentTemplate>>,
+    /// Where this component is in the source markup.
+    pub(crate) location: SourceLocation,
+}
+
+impl ComponentTemplate {
+    /// Gets the name given to this component as a plain string, if any, used to match it up
+    /// with the component created from it when the template is reloaded.
+    pub(crate) fn name(&self) -> Option<&str> {
+        self.attributes.iter()
+            .find(|attribute| attribute.key == "name" && attribute.script_conditional.is_none())
+            .and_then(|attribute| match attribute.value {
+                TemplateValue::String(ref name) => Some(name.as_str()),
+                _ => None,
+            })
+    }
+
+    /// Checks if this component has the same class, style class, and attributes as another,
+    /// regardless of where they are in the source markup or of their children.
+    pub(crate) fn same_attributes(&self, other: &ComponentTemplate) -> bool {
+        self.class == other.class &&
+            self.style_class == other.style_class &&
+            self.attributes.len() == other.attributes.len() &&
+            self.attributes.iter().zip(&other.attributes).all(|(a, b)| {
+                a.key == b.key && a.value == b.value &&
+                    a.script_conditional == b.script_conditional
+            })
+    }
+
+    /// Sets the source name of the locations of this component, its attributes, and its
+    /// children.
+    pub(crate) fn set_source_name(&mut self, name: &Arc<str>) {
+        self.location.name = Some(name.clone());
+        for attribute in &mut self.attributes {
+            attribute.location.name = Some(name.clone());
+        }
+        for child in &mut self.children {
+            Arc::make_mut(child).set_source_name(name);
+        }
+    }
 }
 
 /// An attribute-value-conditional combination in a component template.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub(crate) struct TemplateAttribute {
-    pub key: String,
+    pub key: Symbol,
     pub value: TemplateValue,
     pub script_conditional: Option<String>,
+    /// Where this attribute is in the source markup.
+    pub location: SourceLocation,
 }
 
 impl TemplateAttribute {
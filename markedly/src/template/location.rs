@@ -0,0 +1,33 @@
+use std::fmt::{self, Display, Formatter};
+use std::sync::{Arc};
+
+/// A location in template or style markup, used to point at where something came from in
+/// errors.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct SourceLocation {
+    /// The name of the markup's source, such as its file name, if one was given.
+    pub name: Option<Arc<str>>,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl SourceLocation {
+    pub(crate) fn new(line: usize, column: usize) -> Self {
+        SourceLocation {
+            name: None,
+            line,
+            column,
+        }
+    }
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if let Some(ref name) = self.name {
+            write!(f, "{}:{}:{}", name, self.line, self.column)
+        } else {
+            write!(f, "line {}, column {}", self.line, self.column)
+        }
+    }
+}
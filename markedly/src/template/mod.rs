@@ -1,16 +1,21 @@
 //! Templates parsed in from markup.
 
+mod animation;
 mod attributes;
 mod component;
+mod location;
 mod parse;
 mod style;
 mod template;
 mod value;
 
+pub(crate) use self::attributes::{InheritedAttributes, resolve_value};
 pub(crate) use self::component::{TemplateAttribute};
 
+pub use self::animation::{AnimationTemplate, KeyframeTemplate};
 pub use self::attributes::{Attributes};
 pub use self::component::{ComponentTemplate};
+pub use self::location::{SourceLocation};
 pub use self::style::{Style};
 pub use self::template::{Template};
-pub use self::value::{TemplateValue, Color, EventHook, Coordinates, Coordinate};
+pub use self::value::{TemplateValue, Color, EventHook, Coordinates, Coordinate, parse_hex_color};
@@ -1,19 +1,45 @@
+use std::sync::{Arc};
+
 use pest::iterators::{Pair};
 
-use template::{ComponentTemplate, TemplateAttribute, TemplateValue};
+use template::{
+    ComponentTemplate, TemplateAttribute, TemplateValue, AnimationTemplate, KeyframeTemplate,
+    SourceLocation,
+};
+use {Symbol};
 
 #[derive(Parser)]
 #[grammar = "template/language.pest"]
 pub struct TemplateParser;
 
-pub fn parse_document(document_pair: Pair<Rule>) -> Result<Vec<ComponentTemplate>, String> {
+/// Everything defined in a parsed template or style document.
+pub struct Document {
+    pub components: Vec<ComponentTemplate>,
+    pub animations: Vec<AnimationTemplate>,
+    /// The attributes of every `@theme` block, in the order they were defined in.
+    pub theme: Vec<TemplateAttribute>,
+}
+
+pub fn parse_document(document_pair: Pair<Rule>) -> Result<Document, String> {
     assert_eq!(document_pair.as_rule(), Rule::template);
 
     let mut components = Vec::new();
+    let mut animations = Vec::new();
+    let mut theme = Vec::new();
 
     let mut parent_stack: Vec<ComponentTemplate> = Vec::new();
     let mut last_indentation = 0;
     for pair in document_pair.into_inner() {
+        if pair.as_rule() == Rule::animation {
+            animations.push(parse_animation(pair)?);
+            continue
+        }
+        if pair.as_rule() == Rule::theme {
+            let attributes_pair = pair.into_inner().next().unwrap();
+            theme.extend(parse_attributes(attributes_pair)?);
+            continue
+        }
+
         let (component, indentation) = parse_component(pair.clone())?;
 
         // Prevent first component starting at wrong indentation level
@@ -39,8 +65,10 @@ pub fn parse_document(document_pair: Pair<Rule>) -> Result<Vec<ComponentTemplate
 
         // If our indentation has increased by more than one, we need to give an error for that
         if indentation > last_indentation && indentation - last_indentation > 1 {
-            let (line, _col) = pair.into_span().start_pos().line_col();
-            return Err(format!("Excessive increase in indentation at line {}", line))
+            let (line, column) = pair.into_span().start_pos().line_col();
+            return Err(format!(
+                "Excessive increase in indentation at line {}, column {}", line, column,
+            ))
         }
 
         parent_stack.push(component);
@@ -52,7 +80,7 @@ pub fn parse_document(document_pair: Pair<Rule>) -> Result<Vec<ComponentTemplate
     parent_stack.reverse();
     for mut component in parent_stack {
         if let Some(child_component) = last_component.take() {
-            component.children.push(child_component);
+            component.children.push(Arc::new(child_component));
         }
         last_component = Some(component);
     }
@@ -60,7 +88,214 @@ pub fn parse_document(document_pair: Pair<Rule>) -> Result<Vec<ComponentTemplate
         components.push(component);
     }
 
-    Ok(components)
+    Ok(Document {
+        components,
+        animations,
+        theme,
+    })
+}
+
+/// Parses a document in the stylesheet grammar, where every rule's selectors are turned into a
+/// component defining default attributes, after which its nested rules follow.
+pub fn parse_stylesheet(document_pair: Pair<Rule>) -> Result<Document, String> {
+    assert_eq!(document_pair.as_rule(), Rule::stylesheet);
+
+    let mut components = Vec::new();
+    let mut animations = Vec::new();
+    let mut theme = Vec::new();
+
+    for pair in document_pair.into_inner() {
+        match pair.as_rule() {
+            Rule::style_theme => {
+                let block_pair = pair.into_inner()
+                    .find(|pair| pair.as_rule() == Rule::style_block).unwrap();
+                theme.extend(parse_flat_style_block(block_pair, "@theme")?);
+            },
+            Rule::style_animation => animations.push(parse_animation(pair)?),
+            Rule::style_rule => parse_style_rule(pair, &[], &mut components)?,
+            Rule::style_comment => {},
+            _ => unreachable!(),
+        }
+    }
+
+    Ok(Document {
+        components,
+        animations,
+        theme,
+    })
+}
+
+/// What components a style rule applies to.
+#[derive(Clone)]
+struct Selector {
+    /// The component class, `None` if the selector starts with a style class or pseudo-state.
+    class: Option<Symbol>,
+    style_class: Option<Symbol>,
+    /// The suffix added to the keys of attributes for the selector's pseudo-state, such as
+    /// `-hovering` for `:hover`.
+    state_suffix: Option<&'static str>,
+    location: SourceLocation,
+}
+
+fn parse_style_rule(
+    pair: Pair<Rule>, parents: &[Selector], components: &mut Vec<ComponentTemplate>,
+) -> Result<(), String> {
+    assert_eq!(pair.as_rule(), Rule::style_rule);
+    let mut selectors = Vec::new();
+    let mut block_pair = None;
+
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::selector => selectors.push(parse_selector(pair)?),
+            Rule::style_block => block_pair = Some(pair),
+            _ => {}
+        }
+    }
+
+    // Nested selectors narrow down every selector of the rule they're in
+    if parents.len() != 0 {
+        let mut combined = Vec::new();
+        for parent in parents {
+            for selector in &selectors {
+                combined.push(combine_selectors(parent, selector)?);
+            }
+        }
+        selectors = combined;
+    }
+
+    let (attributes, nested) = parse_style_block(block_pair.unwrap())?;
+    for selector in &selectors {
+        let attributes = attributes.iter().map(|attribute| {
+            let mut attribute = attribute.clone();
+            if let Some(suffix) = selector.state_suffix {
+                attribute.key = Symbol::new(&format!("{}{}", attribute.key, suffix));
+            }
+            attribute
+        }).collect();
+
+        components.push(ComponentTemplate {
+            class: selector.class.clone().unwrap_or_else(|| Symbol::new("*")),
+            style_class: selector.style_class.clone(),
+            attributes,
+            children: Vec::new(),
+            location: selector.location.clone(),
+        });
+    }
+
+    for rule_pair in nested {
+        parse_style_rule(rule_pair, &selectors, components)?;
+    }
+
+    Ok(())
+}
+
+fn parse_selector(pair: Pair<Rule>) -> Result<Selector, String> {
+    assert_eq!(pair.as_rule(), Rule::selector);
+    let (line, column) = pair.clone().into_span().start_pos().line_col();
+    let mut selector = Selector {
+        class: None,
+        style_class: None,
+        state_suffix: None,
+        location: SourceLocation::new(line, column),
+    };
+
+    for pair in pair.into_inner() {
+        let (line, column) = pair.clone().into_span().start_pos().line_col();
+        match pair.as_rule() {
+            Rule::selector_class => selector.class = Some(pair.as_str().into()),
+            Rule::style_class => {
+                if selector.style_class.is_some() {
+                    return Err(format!(
+                        "Selector has more than one style class at line {}, column {}",
+                        line, column,
+                    ))
+                }
+                selector.style_class = Some(Symbol::new(&pair.as_str()[1..]));
+            },
+            Rule::pseudo_state => {
+                if selector.state_suffix.is_some() {
+                    return Err(format!(
+                        "Selector has more than one pseudo-state at line {}, column {}",
+                        line, column,
+                    ))
+                }
+                selector.state_suffix = Some(match &pair.as_str()[1..] {
+                    "hover" => "-hovering",
+                    state => return Err(format!(
+                        "Unknown pseudo-state \":{}\" at line {}, column {}", state, line, column,
+                    )),
+                });
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    Ok(selector)
+}
+
+fn combine_selectors(parent: &Selector, selector: &Selector) -> Result<Selector, String> {
+    let location = &selector.location;
+    if selector.class.is_some() {
+        return Err(format!(
+            "Nested selector doesn't start with a style class or pseudo-state at line {}, \
+             column {}", location.line, location.column,
+        ))
+    }
+    if parent.style_class.is_some() && selector.style_class.is_some() {
+        return Err(format!(
+            "Nested selector adds a second style class at line {}, column {}",
+            location.line, location.column,
+        ))
+    }
+    if parent.state_suffix.is_some() && selector.state_suffix.is_some() {
+        return Err(format!(
+            "Nested selector adds a second pseudo-state at line {}, column {}",
+            location.line, location.column,
+        ))
+    }
+
+    Ok(Selector {
+        class: parent.class.clone(),
+        style_class: selector.style_class.clone().or_else(|| parent.style_class.clone()),
+        state_suffix: selector.state_suffix.or(parent.state_suffix),
+        location: location.clone(),
+    })
+}
+
+/// Parses the attributes of a style block, and gets the rules nested in it.
+fn parse_style_block(
+    pair: Pair<Rule>,
+) -> Result<(Vec<TemplateAttribute>, Vec<Pair<Rule>>), String> {
+    assert_eq!(pair.as_rule(), Rule::style_block);
+    let mut attributes = Vec::new();
+    let mut nested = Vec::new();
+
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::key_value => attributes.push(parse_key_value(pair)),
+            Rule::style_rule => nested.push(pair),
+            Rule::style_comment => {},
+            _ => unreachable!(),
+        }
+    }
+
+    Ok((attributes, nested))
+}
+
+/// Parses the attributes of a style block that rules can't be nested in, the kind of block, such
+/// as `@theme`, is named in the error if they are.
+fn parse_flat_style_block(
+    pair: Pair<Rule>, kind: &str,
+) -> Result<Vec<TemplateAttribute>, String> {
+    let (line, column) = pair.clone().into_span().start_pos().line_col();
+    let (attributes, nested) = parse_style_block(pair)?;
+    if nested.len() != 0 {
+        return Err(format!(
+            "Rules can't be nested in {} at line {}, column {}", kind, line, column,
+        ))
+    }
+
+    Ok(attributes)
 }
 
 fn finish_sibling(
@@ -73,7 +308,7 @@ fn finish_sibling(
         // file, so instead it needs to be added to the final components list
         if let Some(mut parent) = parent_stack.pop() {
             // However if both of those things are not the case, just add it to our parent
-            parent.children.push(sibling);
+            parent.children.push(Arc::new(sibling));
             parent_stack.push(parent);
         } else {
             components.push(sibling);
@@ -85,15 +320,19 @@ fn parse_component(pair: Pair<Rule>) -> Result<(ComponentTemplate, usize), Strin
     assert_eq!(pair.as_rule(), Rule::component);
     let mut indentation = 0;
     let mut class = None;
-    let mut style_class: Option<String> = None;
+    let mut style_class: Option<Symbol> = None;
     let mut attributes = None;
-    let (line, _col) = pair.clone().into_span().start_pos().line_col();
+    let mut location = None;
 
     for pair in pair.into_inner() {
         match pair.as_rule() {
             Rule::indentation => indentation = parse_indentation(pair)?,
-            Rule::identifier => class = Some(pair.as_str().into()),
-            Rule::style_class => style_class = Some(pair.as_str()[1..].into()),
+            Rule::identifier => {
+                let (line, column) = pair.clone().into_span().start_pos().line_col();
+                location = Some(SourceLocation::new(line, column));
+                class = Some(pair.as_str().into());
+            },
+            Rule::style_class => style_class = Some(Symbol::new(&pair.as_str()[1..])),
             Rule::attributes => attributes = Some(parse_attributes(pair)?),
             _ => {}
         }
@@ -104,10 +343,71 @@ fn parse_component(pair: Pair<Rule>) -> Result<(ComponentTemplate, usize), Strin
         style_class,
         attributes: attributes.unwrap_or_else(|| Vec::new()),
         children: Vec::new(),
-        line,
+        location: location.unwrap(),
     }, indentation))
 }
 
+fn parse_animation(pair: Pair<Rule>) -> Result<AnimationTemplate, String> {
+    assert!(pair.as_rule() == Rule::animation || pair.as_rule() == Rule::style_animation);
+    let mut name = None;
+    let mut keyframes = Vec::new();
+    let (line, column) = pair.clone().into_span().start_pos().line_col();
+
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::identifier => name = Some(pair.as_str().into()),
+            Rule::keyframes | Rule::style_keyframes => {
+                for keyframe_pair in pair.into_inner() {
+                    if keyframe_pair.as_rule() != Rule::style_comment {
+                        keyframes.push(parse_keyframe(keyframe_pair)?);
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+
+    Ok(AnimationTemplate {
+        name: name.unwrap(),
+        keyframes,
+        location: SourceLocation::new(line, column),
+    })
+}
+
+fn parse_keyframe(pair: Pair<Rule>) -> Result<KeyframeTemplate, String> {
+    assert!(pair.as_rule() == Rule::keyframe || pair.as_rule() == Rule::style_keyframe);
+    let mut position = 0.0;
+    let mut attributes = Vec::new();
+
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::percentage => {
+                let pair_str = pair.as_str();
+                let (line, column) = pair.into_span().start_pos().line_col();
+                let percentage: i32 = pair_str[0..pair_str.len()-1].parse()
+                    .map_err(|_| format!(
+                        "Invalid keyframe percentage \"{}\" at line {}, column {}",
+                        pair_str, line, column,
+                    ))?;
+                if percentage < 0 || percentage > 100 {
+                    return Err(format!(
+                        "Keyframe outside of 0% to 100% at line {}, column {}", line, column,
+                    ))
+                }
+                position = percentage as f32 / 100.0;
+            },
+            Rule::attributes => attributes = parse_attributes(pair)?,
+            Rule::style_block => attributes = parse_flat_style_block(pair, "keyframes")?,
+            _ => {}
+        }
+    }
+
+    Ok(KeyframeTemplate {
+        position,
+        attributes,
+    })
+}
+
 fn parse_indentation(pair: Pair<Rule>) -> Result<usize, String> {
     // Count the spacing, including tabs
     let mut spacing = 0;
@@ -121,8 +421,11 @@ fn parse_indentation(pair: Pair<Rule>) -> Result<usize, String> {
 
     // Fail indentation that isn't divisible by 4
     if spacing % 4 != 0 {
-        let (line, _col) = pair.into_span().start_pos().line_col();
-        return Err(format!("Bad amount of indentation spacing, must be divisible by 4, at line {}", line))
+        let (line, column) = pair.into_span().start_pos().line_col();
+        return Err(format!(
+            "Bad amount of indentation spacing, must be divisible by 4, at line {}, column {}",
+            line, column,
+        ))
     }
 
     Ok(spacing/4)
@@ -131,38 +434,38 @@ fn parse_indentation(pair: Pair<Rule>) -> Result<usize, String> {
 fn parse_attributes(pair: Pair<Rule>) -> Result<Vec<TemplateAttribute>, String> {
     assert_eq!(pair.as_rule(), Rule::attributes);
 
-    let mut attributes: Vec<TemplateAttribute> = Vec::new();
+    // We allow duplicate keys, when attributes are resolved it will pick the last one
+    Ok(pair.into_inner().map(parse_key_value).collect())
+}
 
-    for key_value_pair in pair.into_inner() {
-        assert_eq!(key_value_pair.as_rule(), Rule::key_value);
+fn parse_key_value(key_value_pair: Pair<Rule>) -> TemplateAttribute {
+    assert_eq!(key_value_pair.as_rule(), Rule::key_value);
 
-        let mut key: Option<String> = None;
-        let mut value: Option<TemplateValue> = None;
-        let mut script_conditional: Option<String> = None;
+    let mut key: Option<Symbol> = None;
+    let mut value: Option<TemplateValue> = None;
+    let mut script_conditional: Option<String> = None;
+    let (line, column) = key_value_pair.clone().into_span().start_pos().line_col();
 
-        for pair in key_value_pair.clone().into_inner() {
-            match pair.as_rule() {
-                Rule::identifier =>
-                    key = Some(pair.as_str().into()),
-                Rule::value =>
-                    value = Some(parse_value(pair)),
-                Rule::script_conditional => {
-                    let pair_str = pair.as_str();
-                    script_conditional = Some(pair_str[2..pair_str.len()-1].into());
-                }
-                _ => unreachable!(),
+    for pair in key_value_pair.into_inner() {
+        match pair.as_rule() {
+            Rule::identifier =>
+                key = Some(pair.as_str().into()),
+            Rule::value =>
+                value = Some(parse_value(pair)),
+            Rule::script_conditional => {
+                let pair_str = pair.as_str();
+                script_conditional = Some(pair_str[2..pair_str.len()-1].into());
             }
+            _ => unreachable!(),
         }
-
-        // We allow duplicate keys, when attributes are resolved it will pick the last one
-        attributes.push(TemplateAttribute {
-            key: key.unwrap(),
-            value: value.unwrap(),
-            script_conditional,
-        });
     }
 
-    Ok(attributes)
+    TemplateAttribute {
+        key: key.unwrap(),
+        value: value.unwrap(),
+        script_conditional,
+        location: SourceLocation::new(line, column),
+    }
 }
 
 fn parse_value(pair: Pair<Rule>) -> TemplateValue {
@@ -186,12 +489,24 @@ fn parse_value(pair: Pair<Rule>) -> TemplateValue {
             }
             TemplateValue::Tuple(values)
         },
+        Rule::boolean =>
+            TemplateValue::Bool(pair_str == "true"),
         Rule::default =>
             TemplateValue::Default,
+        Rule::translation => {
+            let mut pairs = pair.into_inner();
+            let key = pairs.next().unwrap().as_str();
+            TemplateValue::Translation {
+                key: key[1..key.len()-1].into(),
+                arguments: pairs.map(parse_value).collect(),
+            }
+        },
+        Rule::theme_variable =>
+            TemplateValue::ThemeVariable(pair_str[1..].into()),
         Rule::script_value =>
             TemplateValue::ScriptValue(pair_str[2..pair_str.len()-1].into()),
         Rule::script_statement =>
-            TemplateValue::ScriptStatement(pair_str[1..pair_str.len()-1].into()),
+            TemplateValue::ScriptStatement(pair_str[2..pair_str.len()-1].into()),
         _ => unreachable!(),
     }
 }
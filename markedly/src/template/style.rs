@@ -1,14 +1,29 @@
+use std::collections::{HashMap};
 use std::io::{Read};
+use std::sync::{Arc};
 
+use metrohash::{MetroHashMap};
 use pest::{Parser};
 
-use template::parse::{self, TemplateParser, Rule};
-use template::{ComponentTemplate};
+use template::parse::{self, TemplateParser, Rule, Document};
+use template::{ComponentTemplate, AnimationTemplate, TemplateValue};
+use {Symbol};
 
 /// A style template, used to define default values and style classes for use in templates.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(from = "StyleData"))]
 pub struct Style {
+    /// The components defining default attributes, `Style::reindex` has to be called after
+    /// changing these.
     pub components: Vec<ComponentTemplate>,
+    pub animations: Vec<AnimationTemplate>,
+    /// The theme variables defined in `@theme` blocks by name, which attributes can use as
+    /// `$name`.
+    pub theme: HashMap<String, TemplateValue>,
+    /// The indices of components by their component class, in the order they were defined in.
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    class_index: MetroHashMap<Symbol, Vec<usize>>,
 }
 
 impl Style {
@@ -19,36 +34,216 @@ impl Style {
         Self::from_str(&text)
     }
 
-    /// Parses a style from a string.
+    /// Parses a style from a string, in the stylesheet grammar.
+    ///
+    /// With the `legacy-styles` feature, styles written in the template grammar, where every
+    /// component defines default attributes, are accepted as well.
     pub fn from_str(text: &str) -> Result<Self, String> {
-        // Parse and extract the template pair
-        let pairs = TemplateParser::parse(Rule::template, text)
+        let document = match TemplateParser::parse(Rule::stylesheet, text) {
+            Ok(pairs) => parse::parse_stylesheet(pairs.into_iter().next().unwrap())?,
             // This gives a pretty error to our caller
-            .map_err(|e| format!("{}", e))?;
-        let template_pair = pairs.into_iter().next().unwrap();
+            Err(error) => Self::parse_legacy(text).ok_or_else(|| format!("{}", error))??,
+        };
 
-        let document = parse::parse_document(template_pair)?;
+        // Later definitions of a variable replace earlier ones
+        let theme = document.theme.into_iter()
+            .map(|attribute| (attribute.key.to_string(), attribute.value))
+            .collect();
 
-        Ok(Style {
-            components: document,
-        })
+        Ok(Self::new(document.components, document.animations, theme))
+    }
+
+    /// Parses a style in the template grammar, if it's valid in it.
+    #[cfg(feature = "legacy-styles")]
+    fn parse_legacy(text: &str) -> Option<Result<Document, String>> {
+        let pairs = TemplateParser::parse(Rule::template, text).ok()?;
+        Some(parse::parse_document(pairs.into_iter().next().unwrap()))
+    }
+
+    #[cfg(not(feature = "legacy-styles"))]
+    fn parse_legacy(_text: &str) -> Option<Result<Document, String>> {
+        None
+    }
+
+    fn new(
+        components: Vec<ComponentTemplate>, animations: Vec<AnimationTemplate>,
+        theme: HashMap<String, TemplateValue>,
+    ) -> Self {
+        let mut style = Style {
+            components,
+            animations,
+            theme,
+            class_index: MetroHashMap::default(),
+        };
+        style.reindex();
+        style
+    }
+
+    /// Rebuilds the lookup of components by their component class, after the components were
+    /// changed.
+    pub fn reindex(&mut self) {
+        self.class_index.clear();
+        for (index, component) in self.components.iter().enumerate() {
+            self.class_index.entry(component.class.clone())
+                .or_insert_with(Vec::new)
+                .push(index);
+        }
+    }
+
+    /// Gets the components defining default attributes for a component class, in the order
+    /// they were defined in.
+    pub fn components_of_class<'a>(
+        &'a self, class: &str,
+    ) -> impl Iterator<Item=&'a ComponentTemplate> + 'a {
+        let indices = self.class_index.get(class).map(|v| v.as_slice()).unwrap_or(&[]);
+        indices.iter().map(move |i| &self.components[*i])
+    }
+
+    /// Sets the name of the style's source, such as its file name, to be shown in errors about
+    /// attributes set in the style.
+    pub fn set_source_name<S: Into<String>>(&mut self, name: S) {
+        let name: Arc<str> = name.into().into();
+        for component in &mut self.components {
+            component.set_source_name(&name);
+        }
+        for animation in &mut self.animations {
+            animation.set_source_name(&name);
+        }
+    }
+
+    /// Finds an animation defined in this style by name.
+    pub fn animation(&self, name: &str) -> Option<&AnimationTemplate> {
+        self.animations.iter().find(|a| a.name == name)
+    }
+}
+
+/// The serialized form of a style, the class index is rebuilt instead of being stored.
+#[cfg(feature = "serialize")]
+#[derive(Deserialize)]
+struct StyleData {
+    components: Vec<ComponentTemplate>,
+    animations: Vec<AnimationTemplate>,
+    #[serde(default)]
+    theme: HashMap<String, TemplateValue>,
+}
+
+#[cfg(feature = "serialize")]
+impl From<StyleData> for Style {
+    fn from(data: StyleData) -> Self {
+        Style::new(data.components, data.animations, data.theme)
     }
 }
 
 #[cfg(test)]
 mod test {
-    use template::{Style};
-    use {Value};
+    use template::{Style, TemplateValue};
 
+    #[cfg(feature = "legacy-styles")]
     #[test]
     fn it_parses_multiple_roots() {
         let result = Style::from_str("root1\nroot2\n");
 
-        println!("Result: {:?}", result);
         assert!(result.is_ok());
         let style = result.unwrap();
         assert_eq!(style.components.len(), 2);
         assert_eq!(style.components[0].class, "root1");
         assert_eq!(style.components[1].class, "root2");
     }
+
+    #[cfg(feature = "legacy-styles")]
+    #[test]
+    fn it_parses_animations() {
+        let result = Style::from_str(
+            "@animation pulse { 0%: { size: (10.0, 10.0) }, 100%: { size: (20.0, 20.0) } }\n\
+             root\n"
+        );
+
+        assert!(result.is_ok());
+        let style = result.unwrap();
+        assert_eq!(style.components.len(), 1);
+        let animation = style.animation("pulse").unwrap();
+        assert_eq!(animation.keyframes.len(), 2);
+        assert_eq!(animation.keyframes[1].position, 1.0);
+    }
+
+    #[test]
+    fn it_parses_theme_variables() {
+        let style = Style::from_str(
+            "@theme { primary: \"#4488ff\", spacing: 8 }\n@theme { spacing: 4 }\n\
+             button { color: $primary, size: ($spacing, 10.0) }\n"
+        ).unwrap();
+
+        assert_eq!(style.theme["primary"], TemplateValue::String("#4488ff".into()));
+        assert_eq!(style.theme["spacing"], TemplateValue::Integer(4));
+        assert_eq!(
+            style.components[0].attributes[0].value,
+            TemplateValue::ThemeVariable("primary".into()),
+        );
+    }
+
+    #[test]
+    fn it_parses_selectors_and_nested_rules() {
+        let style = Style::from_str(&[
+            "// Comments are allowed between rules",
+            "button, container {",
+            "    color: (255, 0, 0);",
+            "    :hover { color: (0, 255, 0) }",
+            "    .primary {",
+            "        size: (10.0, 10.0)",
+            "        text-color: (0, 0, 255)",
+            "    }",
+            "}",
+            ".selected { margin: 2.0 }",
+            "@animation pulse { 0%: { opacity: 1.0 }, 100%: { opacity: 0.5 } }",
+            "",
+        ].join("\n")).unwrap();
+
+        let selectors: Vec<_> = style.components.iter().map(|component| (
+            component.class.to_string(),
+            component.style_class.as_ref().map(|class| class.to_string()),
+            component.attributes.iter().map(|a| a.key.to_string()).collect::<Vec<_>>(),
+        )).collect();
+        assert_eq!(selectors, vec![
+            ("button".into(), None, vec!["color".to_string()]),
+            ("container".into(), None, vec!["color".to_string()]),
+            ("button".into(), None, vec!["color-hovering".to_string()]),
+            ("container".into(), None, vec!["color-hovering".to_string()]),
+            ("button".into(), Some("primary".into()), vec!["size".into(), "text-color".into()]),
+            ("container".into(), Some("primary".into()), vec!["size".into(), "text-color".into()]),
+            ("*".into(), Some("selected".into()), vec!["margin".to_string()]),
+        ]);
+        assert_eq!(style.components[4].attributes[1].location.line, 7);
+        assert_eq!(style.animation("pulse").unwrap().keyframes.len(), 2);
+    }
+
+    #[test]
+    fn it_rejects_keyframe_percentages_too_large_to_parse() {
+        let result = Style::from_str("@animation pulse { 99999999999%: { opacity: 1.0 } }\n");
+        assert_eq!(result.err(), Some(
+            "Invalid keyframe percentage \"99999999999%\" at line 1, column 20".into(),
+        ));
+    }
+
+    #[test]
+    fn it_rejects_invalid_selectors() {
+        assert!(Style::from_str("button:pressed { color: (255, 0, 0) }\n").is_err());
+        assert!(Style::from_str("button.a.b { color: (255, 0, 0) }\n").is_err());
+        assert!(Style::from_str("button.a { .b { color: (255, 0, 0) } }\n").is_err());
+        assert!(Style::from_str("container { button { color: (255, 0, 0) } }\n").is_err());
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn it_serializes_and_rebuilds_the_class_index() {
+        let style = Style::from_str(
+            "button { color: (255, 0, 0) }\ncontainer\nbutton { size: (10.0, 10.0) }\n"
+        ).unwrap();
+
+        let bytes = ::bincode::serialize(&style).unwrap();
+        let style: Style = ::bincode::deserialize(&bytes).unwrap();
+
+        assert_eq!(style.components.len(), 3);
+        assert_eq!(style.components_of_class("button").count(), 2);
+        assert_eq!(style.components_of_class("container").count(), 1);
+    }
 }
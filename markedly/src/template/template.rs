@@ -1,4 +1,5 @@
 use std::io::{Read};
+use std::sync::{Arc};
 
 use pest::{Parser};
 
@@ -7,9 +8,12 @@ use template::{ComponentTemplate};
 
 /// A template, used to define how a group of components should be layouted and initialized based
 /// on model data.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Template {
-    pub root: ComponentTemplate,
+    /// The root component, shared with the components created from it so cloning a template is
+    /// cheap.
+    pub root: Arc<ComponentTemplate>,
 }
 
 impl Template {
@@ -28,7 +32,14 @@ impl Template {
             .map_err(|e| format!("{}", e))?;
         let template_pair = pairs.into_iter().next().unwrap();
 
-        let document = parse::parse_document(template_pair)?;
+        let parse::Document { components: document, animations, theme } =
+            parse::parse_document(template_pair)?;
+        if animations.len() != 0 {
+            return Err("Animations can only be defined in styles".into())
+        }
+        if theme.len() != 0 {
+            return Err("Themes can only be defined in styles".into())
+        }
         if document.len() == 0 {
             return Err("No component found in template".into())
         }
@@ -37,15 +48,24 @@ impl Template {
         }
 
         Ok(Template {
-            root: document.into_iter().next().unwrap(),
+            root: Arc::new(document.into_iter().next().unwrap()),
         })
     }
+
+    /// Sets the name of the template's source, such as its file name, to be shown in errors.
+    pub fn set_source_name<S: Into<String>>(&mut self, name: S) {
+        let name: Arc<str> = name.into().into();
+        Arc::make_mut(&mut self.root).set_source_name(&name);
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use template::{Template};
-    use {Value};
+    use template::{Template, ComponentTemplate, TemplateValue};
+
+    fn attribute<'a>(component: &'a ComponentTemplate, key: &str) -> Option<&'a TemplateValue> {
+        component.attributes.iter().find(|a| a.key == key).map(|a| &a.value)
+    }
 
     #[test]
     fn it_parses_single_root() {
@@ -119,7 +139,7 @@ mod test {
         let component = result.unwrap().root;
         assert_eq!(component.class, "root");
         assert_eq!(component.attributes.len(), 1);
-        assert_eq!(component.attributes.get("key"), Some(&Value::String("value".into())));
+        assert_eq!(attribute(&component, "key"), Some(&TemplateValue::String("value".into())));
     }
 
     #[test]
@@ -150,9 +170,9 @@ r#"root {
         let component = result.unwrap().root;
         assert_eq!(component.class, "root");
         assert_eq!(component.attributes.len(), 3);
-        assert_eq!(component.attributes.get("key1"), Some(&Value::Integer(5)));
-        assert_eq!(component.attributes.get("key2"), Some(&Value::Float(2.5)));
-        assert_eq!(component.attributes.get("key3"), Some(&Value::Percentage(69)));
+        assert_eq!(attribute(&component, "key1"), Some(&TemplateValue::Integer(5)));
+        assert_eq!(attribute(&component, "key2"), Some(&TemplateValue::Float(2.5)));
+        assert_eq!(attribute(&component, "key3"), Some(&TemplateValue::Percentage(69)));
     }
 
     #[test]
@@ -165,11 +185,24 @@ r#"root {
         assert_eq!(component.class, "root");
         assert_eq!(component.attributes.len(), 1);
         assert_eq!(
-            component.attributes.get("key"),
-            Some(&Value::Tuple(vec!(Value::Integer(50), Value::String("text".into()))))
+            attribute(&component, "key"),
+            Some(&TemplateValue::Tuple(vec!(
+                TemplateValue::Integer(50), TemplateValue::String("text".into()),
+            )))
         );
     }
 
+    #[test]
+    fn it_parses_boolean_attributes() {
+        let result = Template::from_str("root { key1: true, key2: false }\n");
+
+        println!("Result: {:?}", result);
+        assert!(result.is_ok());
+        let component = result.unwrap().root;
+        assert_eq!(attribute(&component, "key1"), Some(&TemplateValue::Bool(true)));
+        assert_eq!(attribute(&component, "key2"), Some(&TemplateValue::Bool(false)));
+    }
+
     #[test]
     fn it_fails_two_roots() {
         let result = Template::from_str("root\nroot2\n");
@@ -206,10 +239,14 @@ r#"root {
     }
 
     #[test]
-    fn it_fails_duplicate_keys() {
+    fn it_keeps_duplicate_keys() {
+        // Duplicate keys are allowed, the last one is picked when attributes are resolved
         let result = Template::from_str("root { key1: 5, key1: 10 }\n");
 
         println!("Result: {:?}", result);
-        assert!(result.is_err());
+        assert!(result.is_ok());
+        let component = result.unwrap().root;
+        assert_eq!(component.attributes.len(), 2);
+        assert_eq!(component.attributes[1].value, TemplateValue::Integer(10));
     }
 }
@@ -4,6 +4,7 @@ use {Error};
 
 /// A template value, to be interpreted by components when created or updated.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum TemplateValue {
     /// A string text value.
     String(String),
@@ -13,10 +14,18 @@ pub enum TemplateValue {
     Float(f32),
     /// An integer percentage value.
     Percentage(i32),
+    /// A boolean value.
+    Bool(bool),
     /// A tuple of values.
     Tuple(Vec<TemplateValue>),
     /// A null value.
     Default,
+    /// A text looked up in the context's localization, with arguments to fill into the text.
+    /// This is turned into a string when attributes are resolved.
+    Translation { key: String, arguments: Vec<TemplateValue> },
+    /// A variable defined in the style's theme, such as `$primary`.
+    /// This is replaced with the variable's value when attributes are resolved.
+    ThemeVariable(String),
     /// A script that will be evaluated by the scripting engine.
     ScriptValue(String),
     /// A script statement that will be executed by the scripting engine.
@@ -47,11 +56,64 @@ impl TemplateValue {
     pub fn as_float(&self, runtime: &ScriptRuntime) -> Result<f32, Error> {
         match *self {
             TemplateValue::Float(value) => Ok(value),
+            TemplateValue::Integer(value) => Ok(value as f32),
             TemplateValue::ScriptValue(ref script) => runtime.eval_float(script),
             _ => Err("Value is not a float".into()),
         }
     }
 
+    /// Gets the boolean content of this value, or returns an error.
+    pub fn as_bool(&self, runtime: &ScriptRuntime) -> Result<bool, Error> {
+        match *self {
+            TemplateValue::Bool(value) => Ok(value),
+            TemplateValue::ScriptValue(ref script) => runtime.eval_bool(script),
+            _ => Err("Value is not a boolean".into()),
+        }
+    }
+
+    /// Gets the opacity content of this value, which can be either a float from 0.0 to 1.0 or a
+    /// percentage, or returns an error.
+    pub fn as_opacity(&self, runtime: &ScriptRuntime) -> Result<f32, Error> {
+        let value = match *self {
+            TemplateValue::Percentage(value) => value as f32 / 100.0,
+            _ => self.as_float(runtime)?,
+        };
+        range_f(value, "Opacity", 0.0, 1.0)?;
+        Ok(value)
+    }
+
+    /// Gets a list of strings from either a single string or a tuple of strings, such as an
+    /// ordered list of fonts, or returns an error.
+    pub fn as_strings(&self, runtime: &ScriptRuntime) -> Result<Vec<String>, Error> {
+        if let TemplateValue::Tuple(ref values) = *self {
+            let mut strings = Vec::new();
+            for (i, value) in values.iter().enumerate() {
+                strings.push(value.as_string(runtime)
+                    .map_err(|e| Error::new_value(&format!("Value {}", i + 1), e))?);
+            }
+            Ok(strings)
+        } else {
+            Ok(vec![self.as_string(runtime)?])
+        }
+    }
+
+    /// Gets a list of floats from either a tuple of numbers, or a script resulting in a list of
+    /// numbers such as a model list, or returns an error.
+    pub fn as_floats(&self, runtime: &ScriptRuntime) -> Result<Vec<f32>, Error> {
+        match *self {
+            TemplateValue::Tuple(ref values) => {
+                let mut floats = Vec::new();
+                for (i, value) in values.iter().enumerate() {
+                    floats.push(value.as_float(runtime)
+                        .map_err(|e| Error::new_value(&format!("Value {}", i + 1), e))?);
+                }
+                Ok(floats)
+            },
+            TemplateValue::ScriptValue(ref script) => runtime.eval_floats(script),
+            _ => Err("Value is not a tuple or script".into()),
+        }
+    }
+
     pub fn as_vec(&self) -> Result<&Vec<TemplateValue>, Error> {
         if let TemplateValue::Tuple(ref values) = *self {
             Ok(values)
@@ -94,8 +156,17 @@ impl TemplateValue {
         }
     }
 
-    /// Gets the color content of this value, or returns an error.
+    /// Gets the color content of this value, either a tuple of channels or a hexadecimal string,
+    /// or returns an error.
     pub fn as_color(&self, runtime: &ScriptRuntime) -> Result<Color, Error> {
+        // Hexadecimal strings can also come from scripts, such as the prelude's color helpers
+        match *self {
+            TemplateValue::String(ref value) => return parse_hex_color(value),
+            TemplateValue::ScriptValue(ref script) =>
+                return parse_hex_color(&runtime.eval_string(script)?),
+            _ => {},
+        }
+
         if let TemplateValue::Tuple(ref values) = *self {
             let has_alpha = values.len() == 4;
             if values.len() == 3 || has_alpha {
@@ -123,7 +194,7 @@ impl TemplateValue {
                 Err("Tuple is incorrect size".into())
             }
         } else {
-            Err("Value is not a tuple".into())
+            Err("Value is not a tuple or string".into())
         }
     }
 
@@ -155,6 +226,31 @@ fn range_f(value: f32, err_id: &str, min: f32, max: f32) -> Result<(), String> {
     }
 }
 
+/// Parses a hexadecimal color in the `#rgb`, `#rgba`, `#rrggbb`, or `#rrggbbaa` format.
+pub fn parse_hex_color(value: &str) -> Result<Color, Error> {
+    if !value.starts_with('#') || !value.is_ascii() {
+        return Err(format!("Color \"{}\" is not in hexadecimal format", value).into())
+    }
+    let digits = &value[1..];
+
+    // Short formats repeat every digit, so "f80" becomes "ff8800"
+    let expanded: String = match digits.len() {
+        3 | 4 => digits.chars().flat_map(|c| vec![c, c]).collect(),
+        6 | 8 => digits.into(),
+        _ => return Err(format!("Color \"{}\" has an incorrect amount of digits", value).into()),
+    };
+
+    let mut channels = Vec::new();
+    for i in 0..(expanded.len() / 2) {
+        let channel = u8::from_str_radix(&expanded[i*2..i*2+2], 16)
+            .map_err(|_| format!("Color \"{}\" contains invalid digits", value))?;
+        channels.push(channel);
+    }
+    let alpha = channels.get(3).cloned().unwrap_or(255);
+
+    Ok(Color::new_u8(channels[0], channels[1], channels[2], alpha))
+}
+
 /// Re-export of palette's color for convenience so you don't have to add palette to your own
 /// crate unless you need more complex color functionality.
 pub type Color = ::palette::Srgba;
@@ -164,7 +260,7 @@ pub enum EventHook {
     Script(String),
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Coordinate {
     Exact(f32),
     RelativeToParent(f32),
@@ -179,7 +275,7 @@ impl Coordinate {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Coordinates {
     pub x: Coordinate,
     pub y: Coordinate,
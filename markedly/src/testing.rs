@@ -0,0 +1,284 @@
+//! Snapshots of laid out and rendered UIs, to write regression tests for templates and styles.
+//!
+//! ```rust,ignore
+//! let snapshot = testing::snapshot(&mut ui, Vector2::new(320.0, 240.0))?;
+//! assert_eq!(snapshot.to_string(), include_str!("snapshots/main_menu.txt"));
+//! ```
+
+use std::fmt::{self, Display, Formatter};
+
+use nalgebra::{Point2, Vector2};
+use metrohash::{MetroHashMap};
+
+use render::{self, RecordingRenderer, RenderCommand, RenderMode, BlendMode, Rect};
+use template::{Color};
+use {ComponentId, ComponentFlow, Error, Ui};
+
+/// The layout and rendering of every visible component in a UI, in the order they're rendered.
+///
+/// Components are identified by their path rather than their ID, and rendering is done by the
+/// `RecordingRenderer`, so snapshots are the same between runs and machines. A snapshot's text
+/// form, from `to_string`, is meant to be stored next to tests and compared against.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Snapshot {
+    pub components: Vec<ComponentSnapshot>,
+}
+
+impl Snapshot {
+    /// Finds a component in the snapshot by its path.
+    pub fn find(&self, path: &str) -> Option<&ComponentSnapshot> {
+        self.components.iter().find(|component| component.path == path)
+    }
+}
+
+impl Display for Snapshot {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for component in &self.components {
+            writeln!(
+                f, "{} {} {}", component.path,
+                point(component.position), size(component.size),
+            )?;
+            for command in &component.commands {
+                writeln!(f, "    {}", command)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A component in a snapshot.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct ComponentSnapshot {
+    /// Where the component is in the UI, from the root of its layer down, such as
+    /// `container[0] > container#menu > button[2]`. Every part is the component's class,
+    /// followed by its name if it has one, otherwise its index in its parent or the index of its
+    /// layer.
+    pub path: String,
+    /// The position of the component relative to the target.
+    pub position: (f32, f32),
+    pub size: (f32, f32),
+    /// The operations rendered to the component's cache, described as text.
+    pub commands: Vec<String>,
+}
+
+/// Lays out and renders a UI at a target size with a `RecordingRenderer`, and takes a snapshot
+/// of it. The UI keeps the target size, and its components are rendered again by the next
+/// render.
+pub fn snapshot(ui: &mut Ui, target_size: Vector2<f32>) -> Result<Snapshot, Error> {
+    // Every component needs to be rendered in its own cache, even if already rendered elsewhere
+    let render_mode = ui.render_mode();
+    ui.set_target_size(target_size);
+    ui.set_render_mode(RenderMode::Cached);
+    ui.mark_all_needs_rendering();
+
+    let mut renderer = RecordingRenderer::new();
+    let result = render::render(&mut renderer, ui);
+    ui.set_render_mode(render_mode);
+    ui.mark_all_needs_rendering();
+    result?;
+
+    let mut found = Vec::new();
+    for (index, layer_id) in ui.visible_layers().into_iter().enumerate() {
+        let mut flow = ComponentFlow::new(target_size);
+        find_components(
+            ui, layer_id, format!("[{}]", index), "", Point2::new(0.0, 0.0), &mut flow,
+            &mut found,
+        );
+    }
+
+    // Composited caches refer to their children by path, so all paths are needed first
+    let paths: MetroHashMap<ComponentId, String> = found.iter()
+        .map(|&(id, ref path, _)| (id, path.clone()))
+        .collect();
+
+    let components = found.iter().map(|&(id, ref path, rect)| ComponentSnapshot {
+        path: path.clone(),
+        position: (rect.position.x, rect.position.y),
+        size: (rect.size.x, rect.size.y),
+        commands: renderer.commands(id).map(|commands| {
+            commands.iter().map(|command| describe(command, &paths)).collect()
+        }).unwrap_or_else(Vec::new),
+    }).collect();
+
+    Ok(Snapshot { components })
+}
+
+fn find_components(
+    ui: &Ui, id: ComponentId, suffix: String, parent_path: &str,
+    parent_position: Point2<f32>, parent_flow: &mut ComponentFlow,
+    found: &mut Vec<(ComponentId, String, Rect)>,
+) {
+    let component = ui.get(id).unwrap();
+    let rect = component.compute_layout(parent_flow);
+    if !component.attributes().is_visible() {
+        return
+    }
+
+    let part = match component.attributes().name {
+        Some(ref name) => format!("{}#{}", component.class_name(), name),
+        None => format!("{}{}", component.class_name(), suffix),
+    };
+    let path = if parent_path.is_empty() {
+        part
+    } else {
+        format!("{} > {}", parent_path, part)
+    };
+    let position = parent_position + rect.position.coords;
+    found.push((id, path.clone(), Rect::new(position, rect.size)));
+
+    let mut flow = ComponentFlow::for_children(component, rect.size);
+    for (index, child_id) in component.children().iter().enumerate() {
+        find_components(
+            ui, *child_id, format!("[{}]", index), &path, position, &mut flow, found,
+        );
+    }
+}
+
+fn describe(command: &RenderCommand, paths: &MetroHashMap<ComponentId, String>) -> String {
+    match *command {
+        RenderCommand::RenderCache { source_id, position, alpha, ref effect, blend_mode } => {
+            let source = paths.get(&source_id).map(|path| path.as_str()).unwrap_or("?");
+            let mut description = format!(
+                "cache {} at {} alpha {}", source, point((position.x, position.y)), number(alpha),
+            );
+            if let Some(ref effect) = *effect {
+                let values: Vec<_> = effect.values.iter().map(|v| number(*v)).collect();
+                description += &format!(" effect {}({})", effect.name, values.join(", "));
+            }
+            if blend_mode != BlendMode::Alpha {
+                description += &format!(" blend {:?}", blend_mode);
+            }
+            description
+        },
+        RenderCommand::PushClip { rect } =>
+            format!("push-clip {}", rect_text(rect)),
+        RenderCommand::PopClip =>
+            "pop-clip".to_string(),
+        RenderCommand::Text { ref text, ref font, text_size, position, size: area, color } => {
+            let mut description = format!("text {:?}", text);
+            if let Some(ref font) = *font {
+                description += &format!(" font {:?}", font);
+            }
+            if let Some(text_size) = text_size {
+                description += &format!(" size {}", text_size);
+            }
+            description + &format!(
+                " at {} {} color {}", point((position.x, position.y)), size((area.x, area.y)),
+                color_text(color),
+            )
+        },
+        RenderCommand::Vertices { ref vertices, ref indices, color } => {
+            // Tessellation is summarized by its bounds, so small changes in it don't matter
+            let start = vertices.iter()
+                .fold(Point2::new(::std::f32::MAX, ::std::f32::MAX), |a, v| {
+                    Point2::new(a.x.min(v.x), a.y.min(v.y))
+                });
+            let end = vertices.iter()
+                .fold(Point2::new(::std::f32::MIN, ::std::f32::MIN), |a, v| {
+                    Point2::new(a.x.max(v.x), a.y.max(v.y))
+                });
+            let bounds = if vertices.is_empty() {
+                "empty".to_string()
+            } else {
+                rect_text(Rect::new(start, end - start))
+            };
+            format!(
+                "vertices {} triangles within {} color {}",
+                indices.len() / 3, bounds, color_text(color),
+            )
+        },
+        RenderCommand::Ellipse { center, radii, color } =>
+            format!(
+                "ellipse at {} radii {} color {}",
+                point((center.x, center.y)), size((radii.x, radii.y)), color_text(color),
+            ),
+        RenderCommand::Line { ref points, width, color } => {
+            let points: Vec<_> = points.iter().map(|p| point((p.x, p.y))).collect();
+            format!(
+                "line {} width {} color {}", points.join(" "), number(width), color_text(color),
+            )
+        },
+        RenderCommand::Image { ref image, source, destination, color } =>
+            format!(
+                "image {:?} from {} to {} color {}",
+                image, rect_text(source), rect_text(destination), color_text(color),
+            ),
+    }
+}
+
+/// Formats a number rounded to two decimals, so float noise doesn't change snapshots.
+fn number(value: f32) -> String {
+    let rounded = (value * 100.0).round() / 100.0;
+    // Negative zero would otherwise show up as "-0"
+    format!("{}", if rounded == 0.0 { 0.0 } else { rounded })
+}
+
+fn point(value: (f32, f32)) -> String {
+    format!("({}, {})", number(value.0), number(value.1))
+}
+
+fn size(value: (f32, f32)) -> String {
+    format!("{}x{}", number(value.0), number(value.1))
+}
+
+fn rect_text(rect: Rect) -> String {
+    format!(
+        "{} {}", point((rect.position.x, rect.position.y)), size((rect.size.x, rect.size.y)),
+    )
+}
+
+fn color_text(color: Color) -> String {
+    let channel = |value: f32| (value.max(0.0).min(1.0) * 255.0).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}{:02x}",
+        channel(color.red), channel(color.green), channel(color.blue), channel(color.alpha),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Vector2};
+
+    use class::{ButtonClass};
+    use template::{Template, Style};
+    use {Ui, Context, testing};
+
+    #[test]
+    fn it_snapshots_layout_and_rendering() {
+        let mut context = Context::for_test();
+        context.classes.register::<ButtonClass>("button");
+        let template = Template::from_str(&[
+            "container { }",
+            "    container { size: (40, 20), color: (255, 0, 0) }",
+            "    button { name: \"ok\", size: (20, 10), text: \"Ok\" }",
+            "",
+        ].join("\n")).unwrap();
+        let style = Style::from_str("").unwrap();
+        let (mut ui, _) = Ui::new(
+            &template, None, style, Vector2::new(10.0, 10.0), &context,
+        ).unwrap();
+
+        let snapshot = testing::snapshot(&mut ui, Vector2::new(100.0, 50.0)).unwrap();
+        let paths: Vec<_> = snapshot.components.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec![
+            "container[0]", "container[0] > container[0]", "container[0] > button#ok",
+        ]);
+        let panel = snapshot.find("container[0] > container[0]").unwrap();
+        assert!(panel.commands[0].ends_with("within (0, 0) 40x20 color #ff0000ff"));
+
+        let button = snapshot.find("container[0] > button#ok").unwrap();
+        assert_eq!(button.position, (40.0, 0.0));
+        assert_eq!(button.commands, vec![
+            "text \"Ok\" at (3, 0) 14x10 color #000000ff".to_string(),
+        ]);
+        assert!(snapshot.to_string().contains(
+            "\n    cache container[0] > button#ok at (40, 0) alpha 1\n"
+        ));
+
+        // Snapshots don't depend on what was rendered before
+        assert_eq!(testing::snapshot(&mut ui, Vector2::new(100.0, 50.0)).unwrap(), snapshot);
+    }
+}
@@ -1,10 +1,20 @@
-use nalgebra::{Vector2};
+use std::collections::{HashMap};
+use std::sync::{Arc};
+
+use nalgebra::{Point2, Vector2};
 use metrohash::{MetroHashMap, MetroHashSet};
 
+use arena::{Components};
 use class::{ComponentClasses};
-use scripting::{ScriptTable, ScriptRuntime};
-use template::{Style, Template, ComponentTemplate};
-use {Component, EventSink, Error};
+use input::{Shortcuts};
+use render::{RenderMode, RootTransform};
+use scripting::{ScriptTable, ScriptRuntime, ScriptModel};
+use template::{Style, Template, ComponentTemplate, TemplateValue, InheritedAttributes};
+use {
+    Component, ComponentId, EventSink, Error, UiState, ComponentState, UiCommands, UiCommand,
+    Warnings, Warning, Localization, Symbol, UiStats, ComponentFlow,
+    LayoutReport, LayoutStep, PositionSource,
+};
 
 /// A self-contained UI, to be rendered to a single target, be that full screen, in-world, or used
 /// in some other way.
@@ -12,11 +22,28 @@ pub struct Ui {
     style: Style,
     target_size: Vector2<f32>,
     root_id: ComponentId,
+    render_mode: RenderMode,
+    root_transform: RootTransform,
 
-    components: MetroHashMap<ComponentId, Component>,
-    next_id: ComponentId,
+    components: Components,
 
     tree_roots: MetroHashSet<ComponentId>,
+    shortcuts: Shortcuts,
+    removed: Vec<ComponentId>,
+    /// The model of every tree, by the tree's root.
+    models: MetroHashMap<ComponentId, ScriptTable>,
+    commands: UiCommands,
+    focused: Option<ComponentId>,
+    /// The component the cursor is over, as last found by input handling.
+    hovered: Option<ComponentId>,
+    warnings: Warnings,
+    debug_overlay: bool,
+    /// The version of the localization the components' texts were last resolved with.
+    localization_version: u64,
+
+    /// Independent root trees rendered on top of each other, sorted by their order.
+    layers: Vec<Layer>,
+    layers_changed: bool,
 }
 
 impl Ui {
@@ -28,24 +55,45 @@ impl Ui {
         let mut ui = Ui {
             style,
             target_size,
-            root_id: ComponentId(0),
+            root_id: ComponentId::placeholder(),
+            render_mode: RenderMode::Cached,
+            root_transform: RootTransform::identity(),
 
-            components: MetroHashMap::default(),
-            next_id: ComponentId(0),
+            components: Components::new(),
 
             tree_roots: MetroHashSet::default(),
+            shortcuts: Shortcuts::new(),
+            removed: Vec::new(),
+            models: MetroHashMap::default(),
+            commands: UiCommands::new(),
+            focused: None,
+            hovered: None,
+            warnings: context.warnings.clone(),
+            debug_overlay: false,
+            localization_version: context.localization.version(),
+
+            layers: Vec::new(),
+            layers_changed: false,
         };
 
-        // Prepare the scripting engine with the model data
-        let default_table = ScriptTable::new();
-        let model = model.unwrap_or(&default_table);
+        check_style(&ui.style, context);
+
+        // Prepare the scripting engine with the model data, it's kept for the tree so it can be
+        // bound again when the tree is resolved again
+        let model = model.cloned().unwrap_or_else(ScriptTable::new);
         context.runtime.set_model(&model)?;
 
         // Create the root component from the template
-        let event_sink = EventSink::new();
-        ui.root_id = ui.load_component(&template.root, event_sink.clone(), context)?;
+        let event_sink = EventSink::new(ui.commands.clone());
+        ui.root_id = ui.load_component(
+            &template.root, event_sink.clone(), InheritedAttributes::default(), context,
+        )?;
 
+        // The root template is the base layer everything else is rendered on top of
         let root = ui.root_id;
+        ui.layers.push(Layer { root, order: 0, visible: true });
+        ui.models.insert(root, model);
+
         Ok((ui, Tree { root, event_sink, }))
     }
 
@@ -53,14 +101,352 @@ impl Ui {
         self.target_size
     }
 
+    pub fn render_mode(&self) -> RenderMode {
+        self.render_mode
+    }
+
+    /// Sets the size of the target the UI is rendered to, such as when the window is resized.
+    /// Components that change size as a result will be rendered again.
+    pub fn set_target_size(&mut self, target_size: Vector2<f32>) {
+        if self.target_size != target_size {
+            let previous_size = self.target_size;
+            self.target_size = target_size;
+
+            for layer in &self.layers {
+                Self::mark_resized_recursive(
+                    &mut self.components, layer.root, previous_size, target_size,
+                );
+            }
+        }
+    }
+
+    /// Sets how this UI should be rendered, see `RenderMode` for the available modes.
+    pub fn set_render_mode(&mut self, render_mode: RenderMode) {
+        if self.render_mode != render_mode {
+            self.render_mode = render_mode;
+
+            // Nothing rendered in the previous mode can be reused
+            self.mark_all_needs_rendering();
+        }
+    }
+
+    /// Gets the scale and offset the whole UI is rendered to the target with.
+    pub fn root_transform(&self) -> RootTransform {
+        self.root_transform
+    }
+
+    /// Scales the whole UI and then moves it by an offset when it's rendered to the target, such
+    /// as to letterbox a UI with a fixed 1920 by 1080 target size in a window of any size. Input
+    /// positions are converted back from the target to the UI by `Input`.
+    ///
+    /// The UI is laid out and rendered at its target size, and only the finished layers are
+    /// scaled, so a large scale makes it blurry. In the direct render mode and in the debug
+    /// overlay only the offset is applied.
+    pub fn set_root_transform(&mut self, scale: f32, offset: Vector2<f32>) {
+        // A scale of zero couldn't be inverted for input
+        let transform = RootTransform::new(scale.max(0.001), offset);
+        if self.root_transform != transform {
+            self.root_transform = transform;
+            self.layers_changed = true;
+        }
+    }
+
+    /// Marks every component to be rendered again, such as when switching to a renderer that
+    /// doesn't have any of their caches.
+    pub(crate) fn mark_all_needs_rendering(&mut self) {
+        for (_key, value) in self.components.iter_mut() {
+            value.mark_needs_rendering();
+        }
+    }
+
+    /// Progresses animations in the UI by a frame, `delta` being the time since the last frame
+    /// in seconds, and translates texts again if the localization changed. This should be
+    /// called every frame, before rendering.
+    pub fn update(&mut self, delta: f32, context: &Context) -> Result<(), Error> {
+        // Translated texts have to be looked up again if the locale or texts changed
+        if self.localization_version != context.localization.version() {
+            self.localization_version = context.localization.version();
+            self.update_all_trees(context)?;
+        }
+
+        let mut animating = MetroHashSet::default();
+        for (key, component) in self.components.iter_mut() {
+            component.update(delta);
+
+            if component.is_animating() {
+                animating.insert(key);
+            }
+        }
+        if animating.is_empty() {
+            return Ok(())
+        }
+
+        // Animated attributes are loaded again, which needs the model of their tree to be bound,
+        // so every tree is walked once to find the animating components in it
+        let roots: Vec<_> = self.models.keys().cloned().collect();
+        for root in roots {
+            let mut in_tree = Vec::new();
+            self.collect_tree(root, &mut in_tree);
+            in_tree.retain(|id| animating.contains(id));
+            if in_tree.is_empty() {
+                continue
+            }
+
+            context.runtime.set_model(&self.models[&root])?;
+            for id in in_tree {
+                self.get_mut(id).unwrap().advance_animations(delta, &context.runtime)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets a handle to the queue of changes to make to this UI, applied with `apply_commands`.
+    pub fn commands(&self) -> UiCommands {
+        self.commands.clone()
+    }
+
+    /// Gets the collector for problems found in this UI, shared with the context it was created
+    /// in.
+    pub fn warnings(&self) -> &Warnings {
+        &self.warnings
+    }
+
+    /// Makes all changes queued in the UI's commands, in the order they were queued. If a
+    /// change fails, the error is returned and the changes queued after it are discarded.
+    pub fn apply_commands(&mut self, context: &Context) -> Result<(), Error> {
+        for command in self.commands.take() {
+            match command {
+                UiCommand::InsertTemplate { template, parent, index } =>
+                    self.insert_into_tree(&template, parent, index, context)?,
+                UiCommand::RemoveComponent { id } => {
+                    self.remove_component(id)?;
+                },
+                UiCommand::SetAttribute { id, key, value } =>
+                    self.set_attribute(id, &key, value, context)?,
+                UiCommand::Focus { id } =>
+                    self.set_focused(Some(id))?,
+                UiCommand::RunScript { script, event_sink } =>
+                    self.run_script(&script, &event_sink, context)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs a script statement with the model of the tree the event sink belongs to. Scripts of
+    /// trees that were removed since they were queued aren't run.
+    fn run_script(
+        &self, script: &str, event_sink: &EventSink, context: &Context,
+    ) -> Result<(), Error> {
+        let model = self.models.iter()
+            .find(|&(root, _)| {
+                self.get(*root).map(|c| c.event_sink().is_same(event_sink)).unwrap_or(false)
+            })
+            .map(|(_, model)| model);
+
+        if let Some(model) = model {
+            context.runtime.set_model(model)?;
+            context.runtime.run_statement(script, event_sink)?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the component that currently has focus, if any.
+    pub fn focused(&self) -> Option<ComponentId> {
+        self.focused
+    }
+
+    /// Gives a component focus, or removes focus if `None` is given.
+    pub fn set_focused(&mut self, id: Option<ComponentId>) -> Result<(), Error> {
+        if let Some(id) = id {
+            if !self.components.contains(id) {
+                return Err(format!("Unable to find component {:?}", id).into())
+            }
+        }
+        if self.focused == id {
+            return Ok(())
+        }
+
+        if let Some(component) = self.focused.and_then(|old| self.components.get_mut(old)) {
+            component.raise_focus_changed_event(false);
+        }
+        if let Some(component) = id.and_then(|new| self.components.get_mut(new)) {
+            component.raise_focus_changed_event(true);
+        }
+
+        self.focused = id;
+        Ok(())
+    }
+
+    /// Returns true if the debug overlay is rendered on top of the UI.
+    pub fn debug_overlay(&self) -> bool {
+        self.debug_overlay
+    }
+
+    /// Sets if a debug overlay should be rendered on top of the UI, showing the bounds, margin,
+    /// and class of every component, and highlighting the component the cursor is over. This
+    /// helps with finding out why components aren't laid out as expected.
+    ///
+    /// While the overlay is shown the UI is rendered to the target every frame, even with
+    /// `render_changed`.
+    pub fn set_debug_overlay(&mut self, enabled: bool) {
+        self.debug_overlay = enabled;
+    }
+
+    /// Gathers statistics on the components currently in the UI. This goes over every component,
+    /// so it's meant for occasional diagnostics rather than every frame.
+    pub fn stats(&self) -> UiStats {
+        let mut stats = UiStats::default();
+        let mut templates = MetroHashSet::default();
+
+        for (_, component) in self.components.iter() {
+            stats.components += 1;
+            *stats.components_per_class.entry(component.class_name().to_string())
+                .or_insert(0) += 1;
+            if component.needs_rendering() {
+                stats.dirty_components += 1;
+            }
+            templates.insert(&**component.template() as *const ComponentTemplate);
+            stats.attributes += component.attribute_count();
+        }
+
+        stats.trees = self.models.len();
+        stats.templates = templates.len();
+        stats.depth = self.layers.iter()
+            .map(|layer| self.depth(layer.root))
+            .max().unwrap_or(0);
+
+        stats
+    }
+
+    /// Explains how a component's position and size were computed, and those of every component
+    /// above it, to find out why it isn't laid out as expected. The layout is traced only when
+    /// this is called, so it doesn't cost anything otherwise.
+    pub fn explain_layout(&self, id: ComponentId) -> Result<LayoutReport, Error> {
+        if !self.components.contains(id) {
+            return Err(format!("Component {:?} is not in the UI", id).into())
+        }
+
+        // Find the components from the component up to the root of its layer
+        let mut ids = vec![id];
+        loop {
+            let current = *ids.last().unwrap();
+            if self.layers.iter().any(|layer| layer.root == current) {
+                break
+            }
+            ids.push(self.find_parent(current)?.0);
+        }
+        ids.reverse();
+
+        let root = self.components.get(ids[0]).unwrap();
+        let mut path = vec![LayoutStep::new(
+            ids[0], root, self.target_size, Point2::new(0.0, 0.0), PositionSource::LayerRoot,
+        )];
+
+        // The siblings before every component have to be laid out too, to find where the flow
+        // placed it
+        for pair in ids.windows(2) {
+            let (parent_id, child_id) = (pair[0], pair[1]);
+            let parent_size = path.last().unwrap().size;
+            let parent = self.components.get(parent_id).unwrap();
+            let mut flow = ComponentFlow::for_children(parent, parent_size);
+
+            let siblings = parent.children();
+            for (index, sibling_id) in siblings.iter().enumerate() {
+                let sibling = self.components.get(*sibling_id).unwrap();
+                let attributes = sibling.attributes();
+                let position = sibling.compute_layout(&mut flow).position;
+                if *sibling_id != child_id {
+                    continue
+                }
+
+                let source = if let Some(position) = attributes.position {
+                    PositionSource::Attribute { position, docking: attributes.docking }
+                } else if attributes.collapsed {
+                    PositionSource::Collapsed
+                } else {
+                    PositionSource::Flow { margin: attributes.margin, preceding_siblings: index }
+                };
+                let area_size = parent.child_area(index, parent_size).size;
+                path.push(LayoutStep::new(child_id, sibling, area_size, position, source));
+                break
+            }
+        }
+
+        Ok(LayoutReport { path })
+    }
+
+    /// Finds every visible component under a position, ordered from the one rendered on top to
+    /// the one at the bottom, such as for inspectors to cycle through overlapping components.
+    /// Unlike input, this includes components that don't capture the cursor. Children outside
+    /// of their parent aren't found, the same as with input.
+    pub fn components_at(&self, position: Point2<f32>) -> Vec<ComponentId> {
+        let mut found = Vec::new();
+        for layer_id in self.visible_layers() {
+            let mut flow = ComponentFlow::new(self.target_size);
+            self.find_components_at(
+                position, layer_id, Point2::new(0.0, 0.0), &mut flow, &mut found,
+            );
+        }
+
+        // Components were found in the order they're rendered in, from the bottom up
+        found.reverse();
+        found
+    }
+
+    fn find_components_at(
+        &self, position: Point2<f32>, id: ComponentId,
+        parent_position: Point2<f32>, parent_flow: &mut ComponentFlow,
+        found: &mut Vec<ComponentId>,
+    ) {
+        let component = self.components.get(id).unwrap();
+        let rect = component.compute_layout(parent_flow);
+        let computed_position = parent_position + rect.position.coords;
+        let end = computed_position + rect.size;
+
+        if !component.attributes().is_visible() ||
+            position.x < computed_position.x || position.y < computed_position.y ||
+            position.x > end.x || position.y > end.y {
+            return
+        }
+
+        found.push(id);
+        let mut flow = ComponentFlow::for_children(component, rect.size);
+        for child_id in component.children() {
+            self.find_components_at(position, *child_id, computed_position, &mut flow, found);
+        }
+    }
+
+    /// Gets how deeply components are nested in a component, including the component itself.
+    fn depth(&self, id: ComponentId) -> usize {
+        let children = self.components.get(id).unwrap().children();
+        1 + children.iter().map(|child_id| self.depth(*child_id)).max().unwrap_or(0)
+    }
+
+    /// Gets the component the cursor is over, if any.
+    pub(crate) fn hovered(&self) -> Option<ComponentId> {
+        self.hovered
+    }
+
+    pub(crate) fn set_hovered(&mut self, id: Option<ComponentId>) {
+        self.hovered = id;
+    }
+
     /// Gets a component from its ID.
     pub(crate) fn get(&self, id: ComponentId) -> Option<&Component> {
-        self.components.get(&id)
+        self.components.get(id)
     }
 
     /// Gets a component as mutable from its ID.
     pub(crate) fn get_mut(&mut self, id: ComponentId) -> Option<&mut Component> {
-        self.components.get_mut(&id)
+        self.components.get_mut(id)
+    }
+
+    /// Gets the registry of shortcuts declared by components.
+    pub(crate) fn shortcuts(&self) -> &Shortcuts {
+        &self.shortcuts
     }
 
     /// Gets the root component's ID.
@@ -68,8 +454,96 @@ impl Ui {
         self.root_id
     }
 
-    /// Inserts a template into the UI as a child of the first found component that has the given
-    /// style class.
+    /// Gets the root component IDs of all visible layers, in the order they should be rendered.
+    pub(crate) fn visible_layers(&self) -> Vec<ComponentId> {
+        self.layers.iter().filter(|l| l.visible).map(|l| l.root).collect()
+    }
+
+    /// Returns true if layers have been added, removed, shown, hidden, or reordered since the
+    /// last render.
+    pub(crate) fn layers_changed(&self) -> bool {
+        self.layers_changed
+    }
+
+    /// Adds a template as a new layer, an independent root tree the size of the target that's
+    /// rendered on top of layers with a lower order, and receives input before them. The root
+    /// template is the base layer, with order 0. Layers with the same order are rendered in the
+    /// sequence they were added.
+    pub fn add_layer(
+        &mut self,
+        template: &Template, model: Option<&ScriptTable>,
+        order: i32,
+        context: &Context,
+    ) -> Result<Tree, Error> {
+        // Prepare the scripting engine with the model data, it's kept for the tree so it can be
+        // bound again when the tree is resolved again
+        let model = model.cloned().unwrap_or_else(ScriptTable::new);
+        context.runtime.set_model(&model)?;
+
+        let event_sink = EventSink::new(self.commands.clone());
+        let root = self.load_component(
+            &template.root, event_sink.clone(), InheritedAttributes::default(), context,
+        )?;
+        self.tree_roots.insert(root);
+        self.models.insert(root, model);
+
+        let index = self.layers.iter().position(|l| l.order > order)
+            .unwrap_or(self.layers.len());
+        self.layers.insert(index, Layer { root, order, visible: true });
+        self.layers_changed = true;
+
+        Ok(Tree { root, event_sink, })
+    }
+
+    /// Removes a layer added with `add_layer` from the UI, returning the IDs of all components
+    /// that were removed. The base layer can not be removed.
+    pub fn remove_layer(&mut self, layer: ComponentId) -> Result<Vec<ComponentId>, Error> {
+        if layer == self.root_id {
+            return Err("The base layer can not be removed".into())
+        }
+        let index = self.layer_index(layer)?;
+        self.layers.remove(index);
+        self.layers_changed = true;
+
+        let mut removed = Vec::new();
+        self.remove_component_recursive(layer, &mut removed);
+        self.removed.extend_from_slice(&removed);
+
+        Ok(removed)
+    }
+
+    /// Shows or hides a whole layer, hidden layers aren't rendered and don't receive input.
+    pub fn set_layer_visible(&mut self, layer: ComponentId, visible: bool) -> Result<(), Error> {
+        let index = self.layer_index(layer)?;
+        if self.layers[index].visible != visible {
+            self.layers[index].visible = visible;
+            self.layers_changed = true;
+        }
+
+        Ok(())
+    }
+
+    /// Changes the order of a layer, placing it after any other layers with the same order.
+    pub fn set_layer_order(&mut self, layer: ComponentId, order: i32) -> Result<(), Error> {
+        let index = self.layer_index(layer)?;
+        let mut entry = self.layers.remove(index);
+        entry.order = order;
+
+        let index = self.layers.iter().position(|l| l.order > order)
+            .unwrap_or(self.layers.len());
+        self.layers.insert(index, entry);
+        self.layers_changed = true;
+
+        Ok(())
+    }
+
+    fn layer_index(&self, layer: ComponentId) -> Result<usize, Error> {
+        self.layers.iter().position(|l| l.root == layer)
+            .ok_or(format!("Component {:?} is not the root of a layer", layer).into())
+    }
+
+    /// Inserts a template into the UI as the last child of the first component that has the given
+    /// style class, in the order components are stored.
     pub fn insert_template(
         &mut self,
         template: &Template, model: Option<&ScriptTable>,
@@ -77,101 +551,836 @@ impl Ui {
         context: &Context,
     ) -> Result<Tree, Error> {
         // Find the first component that has a style class matching what we were asked for
-        let mut found_parent_id = None;
-        for (key, component) in &self.components {
-            if let Some(component_style_class) = component.style_class() {
-                if component_style_class == style_class {
-                    found_parent_id = Some(*key);
-                }
-            }
-        }
+        let found_parent_id = self.components.iter()
+            .find(|&(_, component)| component.has_style_class(style_class))
+            .map(|(key, _)| key);
 
         // Make sure we found something and retrieve some basic data we need
         let parent_id = found_parent_id
             .ok_or(format!("Unable to find component with style class {}", style_class))?;
 
-        // Prepare the scripting engine with the model data
-        let default_table = ScriptTable::new();
-        let model = model.unwrap_or(&default_table);
+        self.insert_template_at(template, model, parent_id, None, context)
+    }
+
+    /// Inserts a template into the UI as the last child of a component.
+    pub fn insert_template_into(
+        &mut self,
+        template: &Template, model: Option<&ScriptTable>,
+        parent: ComponentId,
+        context: &Context,
+    ) -> Result<Tree, Error> {
+        if !self.components.contains(parent) {
+            return Err(format!("Unable to find component {:?}", parent).into())
+        }
+
+        self.insert_template_at(template, model, parent, None, context)
+    }
+
+    /// Inserts a template into the UI as a sibling directly before a component.
+    pub fn insert_template_before(
+        &mut self,
+        template: &Template, model: Option<&ScriptTable>,
+        sibling: ComponentId,
+        context: &Context,
+    ) -> Result<Tree, Error> {
+        let (parent_id, index) = self.find_parent(sibling)?;
+        self.insert_template_at(template, model, parent_id, Some(index), context)
+    }
+
+    /// Inserts a template into the UI as a sibling directly after a component.
+    pub fn insert_template_after(
+        &mut self,
+        template: &Template, model: Option<&ScriptTable>,
+        sibling: ComponentId,
+        context: &Context,
+    ) -> Result<Tree, Error> {
+        let (parent_id, index) = self.find_parent(sibling)?;
+        self.insert_template_at(template, model, parent_id, Some(index + 1), context)
+    }
+
+    pub(crate) fn insert_template_at(
+        &mut self,
+        template: &Template, model: Option<&ScriptTable>,
+        parent_id: ComponentId, index: Option<usize>,
+        context: &Context,
+    ) -> Result<Tree, Error> {
+        // Prepare the scripting engine with the model data, it's kept for the tree so it can be
+        // bound again when the tree is resolved again
+        let model = model.cloned().unwrap_or_else(ScriptTable::new);
         context.runtime.set_model(&model)?;
 
-        // Recursively add the template
-        let event_sink = EventSink::new();
-        let id = self.load_component(&template.root, event_sink.clone(), context)?;
+        // Recursively add the template, inheriting from the parent it's added to
+        let inherited = self.get(parent_id)
+            .ok_or(format!("Unable to find component {:?}", parent_id))?
+            .inherited_for_children(&context.classes);
+        let event_sink = EventSink::new(self.commands.clone());
+        let id = self.load_component(&template.root, event_sink.clone(), inherited, context)?;
 
-        // Add the component tree we just added to the children of the component we had found
-        self.get_mut(parent_id).unwrap().add_child(id);
+        // Add the component tree we just added to the children of the parent
+        {
+            let parent = self.get_mut(parent_id).unwrap();
+            if let Some(index) = index {
+                parent.insert_child(index, id);
+            } else {
+                parent.add_child(id);
+            }
+            parent.mark_needs_rendering();
+        }
+        self.tree_roots.insert(id);
+        self.models.insert(id, model);
 
         Ok(Tree { root: id, event_sink, })
     }
 
+    /// Inserts a template as part of the tree the parent is in, rather than as a new tree.
+    fn insert_into_tree(
+        &mut self,
+        template: &Template, parent_id: ComponentId, index: Option<usize>,
+        context: &Context,
+    ) -> Result<(), Error> {
+        let (event_sink, inherited) = {
+            let parent = self.get(parent_id)
+                .ok_or(format!("Unable to find component {:?}", parent_id))?;
+            (parent.event_sink().clone(), parent.inherited_for_children(&context.classes))
+        };
+        let tree_root = self.find_tree_root(parent_id)?;
+        context.runtime.set_model(&self.models[&tree_root])?;
+
+        let id = self.load_component(&template.root, event_sink, inherited, context)?;
+
+        let parent = self.get_mut(parent_id).unwrap();
+        let length = parent.children().len();
+        parent.insert_child(index.unwrap_or(length).min(length), id);
+        parent.mark_needs_rendering();
+
+        Ok(())
+    }
+
+    /// Inserts a copy of a component and its children as the last child of a component, as a new
+    /// tree with its own event sink, such as to add another row to a list without keeping the
+    /// row's template around. The copies are resolved again, using a copy of the model of the
+    /// tree the original is in, and keep the style classes and attributes set at runtime, but
+    /// start out without the runtime state of the originals. Trees inserted into the original
+    /// have their own models and event sinks, so they aren't copied.
+    pub fn clone_subtree(
+        &mut self, id: ComponentId, new_parent: ComponentId, context: &Context,
+    ) -> Result<Tree, Error> {
+        if !self.components.contains(id) {
+            return Err(format!("Unable to find component {:?}", id).into())
+        }
+        if !self.components.contains(new_parent) {
+            return Err(format!("Unable to find component {:?}", new_parent).into())
+        }
+
+        let model = self.models[&self.find_tree_root(id)?].clone();
+        context.runtime.set_model(&model)?;
+
+        let inherited = self.get(new_parent).unwrap().inherited_for_children(&context.classes);
+        let event_sink = EventSink::new(self.commands.clone());
+        let clone_id = self.clone_component(id, event_sink.clone(), inherited, context)?;
+
+        {
+            let parent = self.get_mut(new_parent).unwrap();
+            parent.add_child(clone_id);
+            parent.mark_needs_rendering();
+        }
+        self.tree_roots.insert(clone_id);
+        self.models.insert(clone_id, model);
+
+        Ok(Tree { root: clone_id, event_sink, })
+    }
+
+    /// Removes a tree inserted with `insert_template` from the UI, returning the IDs of all
+    /// components that were removed.
+    pub fn remove_tree(&mut self, tree: &Tree) -> Result<Vec<ComponentId>, Error> {
+        self.remove_component(tree.root)
+    }
+
+    /// Removes a component and all its children from the UI, returning the IDs of all components
+    /// that were removed. The root components of layers can not be removed, use `remove_layer`
+    /// instead.
+    ///
+    /// Render caches of removed components are cleaned up on the next render.
+    pub fn remove_component(&mut self, id: ComponentId) -> Result<Vec<ComponentId>, Error> {
+        if self.layers.iter().any(|l| l.root == id) {
+            return Err("The root component of a layer can not be removed".into())
+        }
+        if !self.components.contains(id) {
+            return Err(format!("Unable to find component {:?}", id).into())
+        }
+
+        // Detach it from its parent, which will need to render again without it
+        let (parent_id, _) = self.find_parent(id)?;
+        let parent = self.get_mut(parent_id).unwrap();
+        parent.remove_child(id);
+        parent.mark_needs_rendering();
+
+        let mut removed = Vec::new();
+        self.remove_component_recursive(id, &mut removed);
+        self.removed.extend_from_slice(&removed);
+
+        Ok(removed)
+    }
+
+    /// Moves a component and its children to another parent, inserting it at an index of the new
+    /// parent's children, counted after it's been removed from the old parent. An index past the
+    /// end adds it as the last child.
+    ///
+    /// A component that isn't the root of an inserted tree becomes part of the tree of its new
+    /// parent, and is resolved again using that tree's model and the attributes its new parent
+    /// passes down.
+    pub fn reparent(
+        &mut self, child: ComponentId, new_parent: ComponentId, index: usize, context: &Context,
+    ) -> Result<(), Error> {
+        if !self.components.contains(new_parent) {
+            return Err(format!("Unable to find component {:?}", new_parent).into())
+        }
+        if self.layers.iter().any(|l| l.root == child) {
+            return Err("The root component of a layer can not be moved".into())
+        }
+
+        // Make sure we're not moving a component into itself
+        let mut current = new_parent;
+        loop {
+            if current == child {
+                return Err("A component can not be moved into itself or its children".into())
+            }
+            match self.find_parent(current) {
+                Ok((parent_id, _)) => current = parent_id,
+                Err(_) => break,
+            }
+        }
+
+        let (old_parent, _) = self.find_parent(child)?;
+        {
+            let old_parent = self.get_mut(old_parent).unwrap();
+            old_parent.remove_child(child);
+            old_parent.mark_needs_rendering();
+        }
+        {
+            let new_parent = self.get_mut(new_parent).unwrap();
+            let index = index.min(new_parent.children().len());
+            new_parent.insert_child(index, child);
+            new_parent.mark_needs_rendering();
+        }
+
+        // The child's layout may depend on the size of its parent
+        self.get_mut(child).unwrap().mark_needs_rendering();
+
+        // Moving between children of the same parent doesn't change what the child inherits or
+        // which tree it's in
+        if old_parent == new_parent {
+            return Ok(())
+        }
+
+        let inherited = self.get(new_parent).unwrap().inherited_for_children(&context.classes);
+        self.get_mut(child).unwrap().set_inherited(inherited);
+
+        let tree_root = self.find_tree_root(child)?;
+        context.runtime.set_model(&self.models[&tree_root])?;
+        Self::update_component_recursive(
+            &mut self.components, child, &self.tree_roots, &mut self.shortcuts, &self.style,
+            context,
+        )
+    }
+
+    /// Updates the model of a tree and resolves its components again. Every tree keeps its own
+    /// model, so trees inserted into this tree are not affected.
     pub fn update_model(
         &mut self, tree: &Tree, model: &ScriptTable, context: &Context,
     ) -> Result<(), Error> {
+        if !self.models.contains_key(&tree.root) {
+            return Err("Unable to find tree, it may have been removed".into())
+        }
+
         // Reloading everything isn't very efficient, it should be changed to
         // detect which model values components have been bound to and only update the
         // relevant ones
         context.runtime.set_model(&model)?;
+        self.models.insert(tree.root, model.clone());
 
         Self::update_component_recursive(
-            &mut self.components, tree.root, &self.tree_roots, &self.style, context
+            &mut self.components, tree.root, &self.tree_roots, &mut self.shortcuts,
+            &self.style, context,
         )?;
 
         Ok(())
     }
 
-    pub(crate) fn mark_all_rendered(&mut self) {
-        for (_key, value) in &mut self.components {
-            value.mark_rendered();
-        }
-    }
+    /// Syncs a value into the model of a tree, only setting the fields that changed, and
+    /// resolves the tree's components again if any did. This is cheap to call every frame with
+    /// game state that rarely changes.
+    /// Returns true if the model changed.
+    pub fn sync_model<M: ScriptModel>(
+        &mut self, tree: &Tree, value: &M, context: &Context,
+    ) -> Result<bool, Error> {
+        let model = {
+            let model = self.models.get_mut(&tree.root)
+                .ok_or("Unable to find tree, it may have been removed")?;
+            if !value.sync_model(model) {
+                return Ok(false)
+            }
+            model.clone()
+        };
 
-    fn load_component(
-        &mut self,
-        template: &ComponentTemplate,
-        event_sink: EventSink,
-        context: &Context,
-    ) -> Result<ComponentId, Error> {
-        // Load the component itself from the template
-        let mut component = Component::from_template(
-            template, event_sink.clone(), &self.style, context,
-        )?;
-        let id = self.next_id;
-        self.next_id.0 += 1;
+        self.update_model(tree, &model, context)?;
+        Ok(true)
+    }
 
-        // Also load all the children
-        for child in &template.children {
-            let id = self.load_component(child, event_sink.clone(), context)?;
-            component.add_child(id);
+    /// Applies a changed template to a tree, such as after its markup was edited, instead of
+    /// building it again from scratch. Components are matched up with the new template by
+    /// their name, or otherwise by their position among their siblings, and keep their IDs,
+    /// event sinks, and runtime state. Components the new template no longer has are removed,
+    /// and components it adds are created. Trees inserted into the tree are kept, after the
+    /// components from the template.
+    ///
+    /// Only components whose attributes changed in the template, or inherit changed attributes,
+    /// are resolved and rendered again. Other components are left as they are, so transitions
+    /// and animations keep playing and values their class changed at runtime, such as the text
+    /// of a text input, are kept.
+    pub fn reload_tree(
+        &mut self, tree: &Tree, template: &Template, context: &Context,
+    ) -> Result<(), Error> {
+        if !self.models.contains_key(&tree.root) {
+            return Err("Unable to find tree, it may have been removed".into())
         }
 
-        // Add the component itself
-        self.components.insert(id, component);
+        self.reload_root(tree.root, template, context)
+    }
 
-        Ok(id)
+    /// Applies a changed template to the tree with the given root component.
+    fn reload_root(
+        &mut self, root: ComponentId, template: &Template, context: &Context,
+    ) -> Result<(), Error> {
+        context.runtime.set_model(&self.models[&root])?;
+        let inherited = self.components.get(root)
+            .ok_or(format!("Unable to find component {:?}", root))?
+            .inherited().clone();
+        self.reload_component(root, &template.root, inherited, context)
     }
 
-    fn update_component_recursive(
-        components: &mut MetroHashMap<ComponentId, Component>, key: ComponentId,
-        tree_roots: &MetroHashSet<ComponentId>,
-        style: &Style, context: &Context,
+    /// Replaces the style and applies a changed template to the tree with the given root
+    /// together. If either fails, the previous style is put back, so the tree isn't left with
+    /// the new style on the old template.
+    #[cfg(feature = "reload")]
+    pub(crate) fn reload_root_with_style(
+        &mut self, root: ComponentId, template: &Template, style: Style, context: &Context,
     ) -> Result<(), Error> {
-        for child_i in 0..components.get(&key).unwrap().children().len() {
-            let child_id = components.get(&key).unwrap().children()[child_i];
+        check_style(&style, context);
+        let previous = ::std::mem::replace(&mut self.style, style);
 
-            // Do not go deeper if we're at an inserted template's root
-            if !tree_roots.contains(&child_id) {
-                Self::update_component_recursive(
-                    components, child_id, tree_roots, style, context
-                )?;
-            }
+        let result = self.update_all_trees(context)
+            .and_then(|_| self.reload_root(root, template, context));
+        if let Err(error) = result {
+            // The components were resolved with the previous style before, so they can be again
+            self.style = previous;
+            self.update_all_trees(context)?;
+            return Err(error)
         }
 
-        components.get_mut(&key).unwrap().update_attributes(style, context)?;
-
         Ok(())
     }
-}
+
+    /// Resolves the components of every tree again, with each tree's own model.
+    fn update_all_trees(&mut self, context: &Context) -> Result<(), Error> {
+        for (root, model) in &self.models {
+            context.runtime.set_model(model)?;
+            Self::update_component_recursive(
+                &mut self.components, *root, &self.tree_roots, &mut self.shortcuts,
+                &self.style, context,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the style, such as to switch to a high-contrast style, and resolves the
+    /// attributes of every component again. Theme variables set with `set_theme` are replaced
+    /// by the new style's theme as well.
+    pub fn set_style(&mut self, style: Style, context: &Context) -> Result<(), Error> {
+        check_style(&style, context);
+        self.style = style;
+        self.update_all_trees(context)
+    }
+
+    /// Overrides the values of theme variables, such as to switch between palettes, and resolves
+    /// the attributes of every component again. Variables that aren't given keep their values.
+    ///
+    /// A palette can be written as a style with just a `@theme` block, and given as its `theme`.
+    pub fn set_theme(
+        &mut self, theme: HashMap<String, TemplateValue>, context: &Context,
+    ) -> Result<(), Error> {
+        self.style.theme.extend(theme);
+        self.update_all_trees(context)
+    }
+
+    /// Sets an attribute on a component, overriding the value from its template and style, and
+    /// marks it for rendering. The override stays in place when the model is updated.
+    ///
+    /// This is useful for values that are simpler to set directly than to bind to the model,
+    /// such as a label's text.
+    pub fn set_attribute(
+        &mut self, id: ComponentId, key: &str, value: TemplateValue, context: &Context,
+    ) -> Result<(), Error> {
+        // The attributes are resolved using the model of the tree the component is in
+        let tree_root = self.find_tree_root(id)?;
+        context.runtime.set_model(&self.models[&tree_root])?;
+
+        {
+            let component = self.components.get_mut(id)
+                .ok_or(format!("Unable to find component {:?}", id))?;
+            component.set_attribute(key.into(), value, &self.style, context)?;
+
+            // The shortcut may have changed with the attributes, so re-register it
+            self.shortcuts.unregister(id);
+            if let Some(ref shortcut) = component.attributes().shortcut {
+                self.shortcuts.register(shortcut.clone(), id);
+            }
+        }
+
+        // Descendants that inherit the attribute have to be resolved again to receive it
+        if context.classes.is_inherited(key) {
+            Self::update_children_recursive(
+                &mut self.components, id, &self.tree_roots, &mut self.shortcuts, &self.style,
+                context,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces all style classes of a component with one, and resolves its attributes again
+    /// with the style entries for its new class, keeping its children and runtime state.
+    pub fn set_style_class(
+        &mut self, id: ComponentId, style_class: &str, context: &Context,
+    ) -> Result<(), Error> {
+        self.change_style_classes(id, context, |classes| {
+            classes.clear();
+            classes.push(style_class.into());
+        })
+    }
+
+    /// Adds a style class to a component, such as to mark a selected menu entry, and resolves
+    /// its attributes again. Style entries of classes added later take priority.
+    pub fn add_style_class(
+        &mut self, id: ComponentId, style_class: &str, context: &Context,
+    ) -> Result<(), Error> {
+        self.change_style_classes(id, context, |classes| {
+            if !classes.iter().any(|class| class == style_class) {
+                classes.push(style_class.into());
+            }
+        })
+    }
+
+    /// Removes a style class from a component, and resolves its attributes again.
+    pub fn remove_style_class(
+        &mut self, id: ComponentId, style_class: &str, context: &Context,
+    ) -> Result<(), Error> {
+        self.change_style_classes(id, context, |classes| {
+            classes.retain(|class| class != style_class);
+        })
+    }
+
+    fn change_style_classes<F: FnOnce(&mut Vec<Symbol>)>(
+        &mut self, id: ComponentId, context: &Context, change: F,
+    ) -> Result<(), Error> {
+        // The attributes are resolved using the model of the tree the component is in
+        let tree_root = self.find_tree_root(id)?;
+        context.runtime.set_model(&self.models[&tree_root])?;
+
+        {
+            let component = self.components.get_mut(id)
+                .ok_or(format!("Unable to find component {:?}", id))?;
+            let mut classes = component.style_classes().to_vec();
+            change(&mut classes);
+            component.set_style_classes(classes);
+        }
+
+        // Descendants may inherit attributes that changed, so they're resolved again as well
+        Self::update_component_recursive(
+            &mut self.components, id, &self.tree_roots, &mut self.shortcuts, &self.style, context,
+        )
+    }
+
+    /// Captures the persistent runtime state of all components, such as scroll offsets, so it
+    /// can be restored after the UI is built again.
+    pub fn save_state(&self) -> UiState {
+        let mut state = UiState::new();
+
+        for (id, key) in self.state_keys() {
+            let mut component_state = ComponentState::new();
+            self.get(id).unwrap().save_state(&mut component_state);
+
+            if !component_state.is_empty() {
+                state.components.insert(key, component_state);
+            }
+        }
+
+        state
+    }
+
+    /// Restores the persistent runtime state of components captured with `save_state`.
+    /// Components are matched up by their layer, position in their parent, and class, anything
+    /// in the state that doesn't match a component is ignored.
+    pub fn restore_state(&mut self, state: &UiState) {
+        for (id, key) in self.state_keys() {
+            if let Some(component_state) = state.components.get(key) {
+                self.get_mut(id).unwrap().restore_state(component_state);
+            }
+        }
+    }
+
+    /// Gets the keys components' state is stored with, describing where they are in the UI.
+    fn state_keys(&self) -> Vec<(ComponentId, String)> {
+        let mut keys = Vec::new();
+        for (index, layer) in self.layers.iter().enumerate() {
+            let key = format!("{}:{}", index, self.get(layer.root).unwrap().class_name());
+            self.state_keys_recursive(layer.root, key, &mut keys);
+        }
+        keys
+    }
+
+    fn state_keys_recursive(
+        &self, id: ComponentId, key: String, keys: &mut Vec<(ComponentId, String)>,
+    ) {
+        for (index, child_id) in self.get(id).unwrap().children().iter().enumerate() {
+            let child_key = format!(
+                "{}/{}:{}", key, index, self.get(*child_id).unwrap().class_name(),
+            );
+            self.state_keys_recursive(*child_id, child_key, keys);
+        }
+
+        keys.push((id, key));
+    }
+
+    /// Takes the IDs of components removed since the last call, so their caches can be cleaned
+    /// up.
+    pub(crate) fn take_removed(&mut self) -> Vec<ComponentId> {
+        ::std::mem::replace(&mut self.removed, Vec::new())
+    }
+
+    pub(crate) fn mark_all_rendered(&mut self) {
+        for (_key, value) in self.components.iter_mut() {
+            value.mark_rendered();
+        }
+        self.layers_changed = false;
+    }
+
+    fn load_component(
+        &mut self,
+        template: &Arc<ComponentTemplate>,
+        event_sink: EventSink,
+        inherited: InheritedAttributes,
+        context: &Context,
+    ) -> Result<ComponentId, Error> {
+        // Load the component itself from the template
+        let mut component = Component::from_template(
+            template, event_sink.clone(), &self.style, inherited, context,
+        )?;
+        let id = self.components.reserve();
+
+        // Also load all the children
+        let children_inherited = component.inherited_for_children(&context.classes);
+        for child in &template.children {
+            match self.load_component(
+                child, event_sink.clone(), children_inherited.clone(), context,
+            ) {
+                Ok(child_id) => component.add_child(child_id),
+                Err(error) => {
+                    self.abandon_component(id, component.children());
+                    return Err(error)
+                },
+            }
+        }
+
+        // Add the component itself
+        if let Some(ref shortcut) = component.attributes().shortcut {
+            self.shortcuts.register(shortcut.clone(), id);
+        }
+        trace!("Created component {:?} ({})", id, component.label());
+        self.components.insert(id, component);
+
+        Ok(id)
+    }
+
+    fn clone_component(
+        &mut self,
+        source_id: ComponentId,
+        event_sink: EventSink,
+        inherited: InheritedAttributes,
+        context: &Context,
+    ) -> Result<ComponentId, Error> {
+        let (mut component, children) = {
+            let source = self.components.get(source_id).unwrap();
+            let component = source.duplicate(
+                event_sink.clone(), &self.style, inherited, context,
+            )?;
+            let children: Vec<_> = source.children().iter()
+                .filter(|child| !self.tree_roots.contains(child))
+                .cloned()
+                .collect();
+            (component, children)
+        };
+        let id = self.components.reserve();
+
+        let children_inherited = component.inherited_for_children(&context.classes);
+        for child in children {
+            match self.clone_component(
+                child, event_sink.clone(), children_inherited.clone(), context,
+            ) {
+                Ok(child_id) => component.add_child(child_id),
+                Err(error) => {
+                    self.abandon_component(id, component.children());
+                    return Err(error)
+                },
+            }
+        }
+
+        if let Some(ref shortcut) = component.attributes().shortcut {
+            self.shortcuts.register(shortcut.clone(), id);
+        }
+        trace!("Created component {:?} ({}) as a copy", id, component.label());
+        self.components.insert(id, component);
+
+        Ok(id)
+    }
+
+    /// Undoes loading a component after one of its children failed to load, giving back its
+    /// reserved ID and removing the children that were already loaded.
+    fn abandon_component(&mut self, id: ComponentId, children: &[ComponentId]) {
+        let mut removed = Vec::new();
+        for child_id in children {
+            self.remove_component_recursive(*child_id, &mut removed);
+        }
+        self.components.release(id);
+    }
+
+    fn reload_component(
+        &mut self,
+        id: ComponentId,
+        template: &Arc<ComponentTemplate>,
+        inherited: InheritedAttributes,
+        context: &Context,
+    ) -> Result<(), Error> {
+        let (old_children, event_sink, children_inherited) = {
+            let component = self.components.get_mut(id).unwrap();
+            let changed = !component.template().same_attributes(template) ||
+                *component.inherited() != inherited;
+            component.set_inherited(inherited);
+
+            if *component.class_name() == template.class {
+                // Components that didn't change aren't resolved again, so their class keeps its
+                // state and their transitions, animation, and cache carry on undisturbed
+                component.set_template(template.clone());
+                if changed {
+                    component.update_attributes(&self.style, context)?;
+                }
+            } else {
+                // A different class can't take over the old one's state, so the component is
+                // created again, but keeps its ID
+                let mut replacement = Component::from_template(
+                    template, component.event_sink().clone(), &self.style,
+                    component.inherited().clone(), context,
+                )?;
+                replacement.set_children(component.children().clone());
+                *component = replacement;
+            }
+
+            self.shortcuts.unregister(id);
+            if let Some(ref shortcut) = component.attributes().shortcut {
+                self.shortcuts.register(shortcut.clone(), id);
+            }
+
+            (
+                component.children().clone(),
+                component.event_sink().clone(),
+                component.inherited_for_children(&context.classes),
+            )
+        };
+
+        // Inserted trees aren't part of the template, so they're left as they are
+        let (inserted, old_children): (Vec<_>, Vec<_>) = old_children.into_iter()
+            .partition(|child| self.tree_roots.contains(child));
+        let mut unclaimed: Vec<_> = old_children.into_iter().map(Some).collect();
+
+        let mut children = Vec::new();
+        for (index, child_template) in template.children.iter().enumerate() {
+            let name = child_template.name();
+
+            // Named components are found wherever they moved to, other components are matched
+            // with whatever unnamed component is in their old place
+            let by_name = name.and_then(|name| unclaimed.iter().position(|child| {
+                child.map(|child| self.components.get(child).unwrap().name() == Some(name))
+                    .unwrap_or(false)
+            }));
+            let by_position = unclaimed.get(index).and_then(|child| *child)
+                .filter(|child| self.components.get(*child).unwrap().name() == name)
+                .map(|_| index);
+            let matched = by_name.or(by_position).and_then(|index| unclaimed[index].take());
+
+            let child_id = if let Some(child_id) = matched {
+                self.reload_component(
+                    child_id, child_template, children_inherited.clone(), context,
+                )?;
+                child_id
+            } else {
+                self.load_component(
+                    child_template, event_sink.clone(), children_inherited.clone(), context,
+                )?
+            };
+            children.push(child_id);
+        }
+
+        let mut removed = Vec::new();
+        for child_id in unclaimed.into_iter().filter_map(|child| child) {
+            self.remove_component_recursive(child_id, &mut removed);
+        }
+        self.removed.extend_from_slice(&removed);
+
+        children.extend(inserted);
+        let component = self.components.get_mut(id).unwrap();
+        if *component.children() != children {
+            component.set_children(children);
+        }
+
+        Ok(())
+    }
+
+    /// Finds the parent of a component, and the index of the component in the parent's children.
+    fn find_parent(&self, id: ComponentId) -> Result<(ComponentId, usize), Error> {
+        for (key, component) in self.components.iter() {
+            if let Some(index) = component.children().iter().position(|child| *child == id) {
+                return Ok((key, index))
+            }
+        }
+
+        Err(format!("Unable to find the parent of component {:?}", id).into())
+    }
+
+    /// Collects the components in the tree with the given root, leaving out trees inserted into
+    /// it.
+    fn collect_tree(&self, id: ComponentId, components: &mut Vec<ComponentId>) {
+        components.push(id);
+        for child_id in self.get(id).unwrap().children() {
+            if !self.tree_roots.contains(child_id) {
+                self.collect_tree(*child_id, components);
+            }
+        }
+    }
+
+    /// Finds the root of the tree a component was inserted with.
+    fn find_tree_root(&self, id: ComponentId) -> Result<ComponentId, Error> {
+        let mut current = id;
+        while !self.models.contains_key(&current) {
+            current = self.find_parent(current)?.0;
+        }
+
+        Ok(current)
+    }
+
+    fn remove_component_recursive(&mut self, id: ComponentId, removed: &mut Vec<ComponentId>) {
+        let component = self.components.remove(id).unwrap();
+        for child_id in component.children() {
+            self.remove_component_recursive(*child_id, removed);
+        }
+
+        self.shortcuts.unregister(id);
+        self.tree_roots.remove(&id);
+        self.models.remove(&id);
+        if self.focused == Some(id) {
+            self.focused = None;
+        }
+        trace!("Removed component {:?} ({})", id, component.label());
+        removed.push(id);
+    }
+
+    fn mark_resized_recursive(
+        components: &mut Components, key: ComponentId,
+        previous_parent_size: Vector2<f32>, parent_size: Vector2<f32>,
+    ) {
+        let children = {
+            let component = components.get_mut(key).unwrap();
+            let previous_size = component.compute_size(previous_parent_size);
+            let size = component.compute_size(parent_size);
+
+            // Children are laid out relative to this component, so if it didn't change neither
+            // did any of them
+            if previous_size == size {
+                return
+            }
+
+            component.mark_needs_rendering();
+            component.children().iter().enumerate()
+                .map(|(index, child_id)| (
+                    *child_id,
+                    component.child_area(index, previous_size).size,
+                    component.child_area(index, size).size,
+                ))
+                .collect::<Vec<_>>()
+        };
+
+        for (child_id, previous_area_size, area_size) in children {
+            Self::mark_resized_recursive(components, child_id, previous_area_size, area_size);
+        }
+    }
+
+    fn update_component_recursive(
+        components: &mut Components, key: ComponentId,
+        tree_roots: &MetroHashSet<ComponentId>, shortcuts: &mut Shortcuts,
+        style: &Style, context: &Context,
+    ) -> Result<(), Error> {
+        // Parents are updated before their children, as children inherit from them
+        {
+            let component = components.get_mut(key).unwrap();
+            component.update_attributes(style, context)?;
+
+            // The shortcut may have changed with the attributes, so re-register it
+            shortcuts.unregister(key);
+            if let Some(ref shortcut) = component.attributes().shortcut {
+                shortcuts.register(shortcut.clone(), key);
+            }
+        }
+
+        Self::update_children_recursive(components, key, tree_roots, shortcuts, style, context)
+    }
+
+    /// Passes the attributes a component's children inherit down to them, and updates the
+    /// children's attributes.
+    fn update_children_recursive(
+        components: &mut Components, key: ComponentId,
+        tree_roots: &MetroHashSet<ComponentId>, shortcuts: &mut Shortcuts,
+        style: &Style, context: &Context,
+    ) -> Result<(), Error> {
+        let inherited = components.get(key).unwrap().inherited_for_children(&context.classes);
+
+        for child_i in 0..components.get(key).unwrap().children().len() {
+            let child_id = components.get(key).unwrap().children()[child_i];
+            components.get_mut(child_id).unwrap().set_inherited(inherited.clone());
+
+            // Do not go deeper if we're at an inserted template's root, those are updated with
+            // their own model
+            if !tree_roots.contains(&child_id) {
+                Self::update_component_recursive(
+                    components, child_id, tree_roots, shortcuts, style, context
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Warns about parts of the style that will never be used.
+fn check_style(style: &Style, context: &Context) {
+    for component in &style.components {
+        if component.class != "*" && !context.classes.is_registered(&component.class) {
+            context.warnings.push(Warning::UnmatchedStyle {
+                class: component.class.to_string(),
+                location: component.location.clone(),
+            });
+        }
+    }
+}
 
 /// The context UIs should be processed and rendered in, this defines the overall UI system's
 /// configuration, such as what component classes are available and how the scripting runtime is
@@ -179,11 +1388,44 @@ impl Ui {
 pub struct Context {
     pub classes: ComponentClasses,
     pub runtime: ScriptRuntime,
+    /// Collects problems found while processing UIs that don't stop them from working.
+    pub warnings: Warnings,
+    /// The texts translation values in templates and styles are looked up in.
+    pub localization: Localization,
 }
 
-/// An ID pointing to a component in a UI.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct ComponentId(pub i32);
+#[cfg(test)]
+impl Context {
+    /// Creates the context tests run in, with only the container class registered and English
+    /// localization. Tests register the other classes they need themselves.
+    pub(crate) fn for_test() -> Self {
+        let mut classes = ComponentClasses::new();
+        classes.register::<::class::ContainerClass>("container");
+        Context {
+            classes,
+            runtime: ScriptRuntime::new(),
+            warnings: Warnings::new(),
+            localization: Localization::new("en"),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Ui {
+    /// Creates the UI most tests run in, from template and style markup without a model, on a
+    /// 100 by 50 target.
+    pub(crate) fn for_test(template: &str, style: &str, context: &Context) -> (Self, Tree) {
+        let template = Template::from_str(template).unwrap();
+        let style = Style::from_str(style).unwrap();
+        Ui::new(&template, None, style, Vector2::new(100.0, 50.0), context).unwrap()
+    }
+}
+
+struct Layer {
+    root: ComponentId,
+    order: i32,
+    visible: bool,
+}
 
 /// An handle for a tree of components in a UI.
 pub struct Tree {
@@ -192,7 +1434,620 @@ pub struct Tree {
 }
 
 impl Tree {
+    /// Gets the ID of the tree's root component.
+    pub fn root(&self) -> ComponentId {
+        self.root
+    }
+
     pub fn event_sink(&self) -> &EventSink {
         &self.event_sink
     }
 }
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Point2, Vector2};
+
+    use class::{ButtonClass};
+    use input::{Input, Modifiers};
+    use render::{self, RecordingRenderer, RenderCommand};
+    use scripting::{ScriptTable, ScriptValue};
+    use template::{Template, Style, TemplateValue, Color};
+    use {Ui, Context, Error, Warning, PositionSource};
+
+    #[test]
+    fn it_does_not_reuse_ids_of_removed_components() {
+        let context = Context::for_test();
+        let dialog = Template::from_str("container { }\n").unwrap();
+        let (mut ui, _tree) = Ui::for_test("container.dialogs { }\n", "", &context);
+
+        let old_tree = ui.insert_template(&dialog, None, "dialogs", &context).unwrap();
+        ui.remove_tree(&old_tree).unwrap();
+        let new_tree = ui.insert_template(&dialog, None, "dialogs", &context).unwrap();
+
+        assert!(old_tree.root() != new_tree.root());
+        assert!(ui.remove_component(old_tree.root()).is_err());
+        assert!(ui.remove_component(new_tree.root()).is_ok());
+    }
+
+    #[test]
+    fn it_cleans_up_templates_that_fail_to_load() {
+        let context = Context::for_test();
+        let dialog = Template::from_str(&[
+            "container",
+            "    container",
+            "        container",
+            "    container { size: \"large\" }",
+            "",
+        ].join("\n")).unwrap();
+        let (mut ui, _tree) = Ui::for_test("container.dialogs { }\n", "", &context);
+
+        // The children loaded before the failing one are removed again
+        assert!(ui.insert_template(&dialog, None, "dialogs", &context).is_err());
+        assert_eq!(ui.stats().components, 1);
+    }
+
+    #[test]
+    fn it_inserts_templates_next_to_siblings() {
+        let context = Context::for_test();
+        let item = Template::from_str("container { }\n").unwrap();
+        let (mut ui, tree) = Ui::for_test("container { }\n", "", &context);
+
+        let middle = ui.insert_template_into(&item, None, tree.root(), &context).unwrap();
+        let first = ui.insert_template_before(&item, None, middle.root(), &context).unwrap();
+        let last = ui.insert_template_after(&item, None, middle.root(), &context).unwrap();
+
+        let children = ui.get(tree.root()).unwrap().children().clone();
+        assert_eq!(children, vec![first.root(), middle.root(), last.root()]);
+        assert!(ui.insert_template_before(&item, None, tree.root(), &context).is_err());
+    }
+
+    #[test]
+    fn it_applies_queued_commands() {
+        let context = Context::for_test();
+        let template = Template::from_str("container { }\n").unwrap();
+        let (mut ui, tree) = Ui::for_test("container { }\n", "", &context);
+
+        let commands = tree.event_sink().commands();
+        commands.insert_template(template.clone(), tree.root(), None);
+        commands.insert_template(template.clone(), tree.root(), Some(0));
+        assert!(ui.get(tree.root()).unwrap().children().is_empty());
+
+        ui.apply_commands(&context).unwrap();
+        let children = ui.get(tree.root()).unwrap().children().clone();
+        assert_eq!(children.len(), 2);
+
+        commands.remove_component(children[0]);
+        commands.focus(children[1]);
+        ui.apply_commands(&context).unwrap();
+        assert_eq!(ui.get(tree.root()).unwrap().children(), &vec![children[1]]);
+        assert_eq!(ui.focused(), Some(children[1]));
+    }
+
+    #[test]
+    fn it_moves_components_between_parents() {
+        let context = Context::for_test();
+        let (mut ui, tree) = Ui::for_test(
+            "container { }\n    container { }\n        container { }\n    container { }\n",
+            "", &context,
+        );
+
+        let root_children = ui.get(tree.root()).unwrap().children().clone();
+        let (first, second) = (root_children[0], root_children[1]);
+        let moved = ui.get(first).unwrap().children()[0];
+
+        ui.reparent(moved, second, 10, &context).unwrap();
+        assert!(ui.get(first).unwrap().children().is_empty());
+        assert_eq!(ui.get(second).unwrap().children(), &vec![moved]);
+
+        ui.reparent(second, tree.root(), 0, &context).unwrap();
+        assert_eq!(ui.get(tree.root()).unwrap().children(), &vec![second, first]);
+        assert!(ui.reparent(second, moved, 0, &context).is_err());
+    }
+
+    #[test]
+    fn it_inherits_from_the_new_parent_when_moving_components() {
+        let mut context = Context::for_test();
+        context.classes.register::<ButtonClass>("button");
+        let (mut ui, tree) = Ui::for_test(&[
+            "container",
+            "    container { text-size: 10 }",
+            "        button { text: \"Moved\" }",
+            "    container { text-size: 20 }",
+            "",
+        ].join("\n"), "", &context);
+        let parents = ui.get(tree.root()).unwrap().children().clone();
+        let button = ui.get(parents[0]).unwrap().children()[0];
+        let text_size = |renderer: &RecordingRenderer| {
+            renderer.commands(button).unwrap().iter().filter_map(|c| match *c {
+                RenderCommand::Text { text_size, .. } => Some(text_size),
+                _ => None,
+            }).next().unwrap()
+        };
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+        assert_eq!(text_size(&renderer), Some(10));
+
+        ui.reparent(button, parents[1], 0, &context).unwrap();
+        render::render_changed(&mut renderer, &mut ui).unwrap();
+        assert_eq!(text_size(&renderer), Some(20));
+    }
+
+    #[test]
+    fn it_clones_subtrees() {
+        let mut context = Context::for_test();
+        context.classes.register::<ButtonClass>("button");
+        let (mut ui, tree) = Ui::for_test(
+            "container\n    container { size: (50.0, 50.0) }\n        \
+            button { position: (10.0, 10.0), size: (20.0, 20.0), on-pressed: \"pressed\" }\n",
+            "", &context,
+        );
+        let panel = ui.get(tree.root()).unwrap().children()[0];
+        let red = TemplateValue::Tuple(vec![
+            TemplateValue::Integer(255), TemplateValue::Integer(0), TemplateValue::Integer(0),
+        ]);
+        ui.set_attribute(panel, "color", red, &context).unwrap();
+
+        let clone = ui.clone_subtree(panel, tree.root(), &context).unwrap();
+        assert_ne!(clone.root(), panel);
+        assert_eq!(ui.get(tree.root()).unwrap().children(), &vec![panel, clone.root()]);
+        let button = ui.get(panel).unwrap().children()[0];
+        let button_clone = ui.get(clone.root()).unwrap().children()[0];
+        assert_ne!(button_clone, button);
+        assert_eq!(ui.get(button_clone).unwrap().class_name(), "button");
+
+        // Attributes set at runtime are kept
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+        match renderer.commands(clone.root()).unwrap()[0] {
+            RenderCommand::Vertices { color, .. } =>
+                assert_eq!(color, Color::new(1.0, 0.0, 0.0, 1.0)),
+            ref command => panic!("Unexpected command {:?}", command),
+        }
+
+        // Presses on the copy go to its own event sink
+        let position = TemplateValue::Tuple(vec![
+            TemplateValue::Float(50.0), TemplateValue::Float(0.0),
+        ]);
+        ui.set_attribute(clone.root(), "position", position, &context).unwrap();
+        let mut input = Input::new();
+        input.handle_drag_started(Point2::new(65.0, 15.0), Modifiers::none(), &mut ui);
+        input.handle_drag_ended(Point2::new(65.0, 15.0), Modifiers::none(), &mut ui);
+        assert_eq!(clone.event_sink().next_event().unwrap().name, "pressed");
+        assert!(tree.event_sink().next_event().is_none());
+
+        assert!(ui.clone_subtree(panel, button_clone, &context).is_ok());
+        ui.remove_tree(&clone).unwrap();
+        assert!(ui.clone_subtree(panel, clone.root(), &context).is_err());
+    }
+
+    #[test]
+    fn it_renders_attributes_set_at_runtime() {
+        let context = Context::for_test();
+        let (mut ui, tree) = Ui::for_test("container { color: (255, 0, 0) }\n", "", &context);
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+
+        let blue = TemplateValue::Tuple(vec![
+            TemplateValue::Integer(0), TemplateValue::Integer(0), TemplateValue::Integer(255),
+        ]);
+        ui.set_attribute(tree.root(), "color", blue, &context).unwrap();
+        assert!(render::render_changed(&mut renderer, &mut ui).unwrap());
+
+        match renderer.commands(tree.root()).unwrap()[0] {
+            RenderCommand::Vertices { color, .. } =>
+                assert_eq!(color, Color::new(0.0, 0.0, 1.0, 1.0)),
+            ref command => panic!("Unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn it_keeps_components_usable_after_rejecting_attributes() {
+        let context = Context::for_test();
+        let (mut ui, tree) = Ui::for_test("container { color: (255, 0, 0) }\n", "", &context);
+        let blue = TemplateValue::Tuple(vec![
+            TemplateValue::Integer(0), TemplateValue::Integer(0), TemplateValue::Integer(255),
+        ]);
+        ui.set_attribute(tree.root(), "color", blue, &context).unwrap();
+
+        let text = TemplateValue::String("blue".into());
+        assert!(ui.set_attribute(tree.root(), "color", text, &context).is_err());
+
+        // The previous override is kept, and the component can still be updated
+        ui.update_model(&tree, &ScriptTable::new(), &context).unwrap();
+        ui.set_style(Style::from_str("").unwrap(), &context).unwrap();
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+        match renderer.commands(tree.root()).unwrap()[0] {
+            RenderCommand::Vertices { color, .. } =>
+                assert_eq!(color, Color::new(0.0, 0.0, 1.0, 1.0)),
+            ref command => panic!("Unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn it_gathers_stats_on_components() {
+        let mut context = Context::for_test();
+        context.classes.register::<ButtonClass>("button");
+        let dialog = Template::from_str("button { text: \"Ok\" }\n").unwrap();
+        let (mut ui, _) = Ui::for_test(
+            "container.dialogs { }\n    container { color: (255, 0, 0) }\n", "", &context,
+        );
+        ui.insert_template(&dialog, None, "dialogs", &context).unwrap();
+        ui.insert_template(&dialog, None, "dialogs", &context).unwrap();
+
+        let stats = ui.stats();
+        assert_eq!(stats.components, 4);
+        assert_eq!(stats.components_per_class.get("container"), Some(&2));
+        assert_eq!(stats.components_per_class.get("button"), Some(&2));
+        assert_eq!(stats.trees, 3);
+        assert_eq!(stats.dirty_components, 4);
+        assert_eq!(stats.depth, 2);
+        assert_eq!(stats.templates, 3);
+        assert_eq!(stats.attributes, 3);
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+        assert_eq!(ui.stats().dirty_components, 0);
+    }
+
+    #[test]
+    fn it_explains_how_components_were_laid_out() {
+        let mut context = Context::for_test();
+        context.classes.register::<ButtonClass>("button");
+        let (ui, tree) = Ui::for_test(&[
+            "container { }",
+            "    container { size: (40, 20) }",
+            "    container { size: (50%, 10), margin: 5.0 }",
+            "        button { position: (10, 0), docking: (\"end\", \"start\"), size: (20, 10) }",
+            "",
+        ].join("\n"), "", &context);
+        let panel = ui.get(tree.root()).unwrap().children()[1];
+        let button = ui.get(panel).unwrap().children()[0];
+
+        let report = ui.explain_layout(button).unwrap();
+        assert_eq!(report.path.len(), 3);
+        assert_eq!(report.path[0].position_source, PositionSource::LayerRoot);
+        assert_eq!(
+            report.path[1].position_source,
+            PositionSource::Flow { margin: 5.0, preceding_siblings: 1 },
+        );
+        assert_eq!(report.path[1].position, Point2::new(45.0, 5.0));
+        assert_eq!(report.path[1].size, Vector2::new(50.0, 10.0));
+        assert_eq!(report.component().id, button);
+        assert_eq!(report.component().position, Point2::new(40.0, 0.0));
+        assert_eq!(report.absolute_position(), Point2::new(85.0, 5.0));
+    }
+
+    #[test]
+    fn it_finds_all_components_under_a_position() {
+        let context = Context::for_test();
+        let markup = [
+            "container { }",
+            "    container { size: (40, 20) }",
+            "        container { size: (10, 10) }",
+            "    container { position: (20, 0), size: (40, 40) }",
+            "",
+        ].join("\n");
+        let template = Template::from_str(&markup).unwrap();
+        let (mut ui, tree) = Ui::for_test(&markup, "", &context);
+        let overlay = ui.add_layer(&template, None, 1, &context).unwrap();
+        let children = ui.get(tree.root()).unwrap().children().clone();
+        let nested = ui.get(children[0]).unwrap().children()[0];
+
+        // Later siblings and layers are on top, the same as they're rendered
+        let found = ui.components_at(Point2::new(25.0, 5.0));
+        assert_eq!(found[0..3].to_vec(), vec![
+            ui.get(overlay.root()).unwrap().children()[1],
+            ui.get(overlay.root()).unwrap().children()[0],
+            overlay.root(),
+        ]);
+        assert_eq!(found[3..].to_vec(), vec![children[1], children[0], tree.root()]);
+
+        ui.set_layer_visible(overlay.root(), false).unwrap();
+        assert_eq!(
+            ui.components_at(Point2::new(5.0, 5.0)), vec![nested, children[0], tree.root()],
+        );
+        assert_eq!(ui.components_at(Point2::new(90.0, 45.0)), vec![tree.root()]);
+    }
+
+    #[test]
+    fn it_keeps_models_per_tree() {
+        let context = Context::for_test();
+        let template = Template::from_str(
+            "container.dialogs { }\n    container { visible: ={model.shown} }\n"
+        ).unwrap();
+        let dialog = Template::from_str("container { visible: ={model.shown} }\n").unwrap();
+        let style = Style::from_str("").unwrap();
+
+        let mut shown = ScriptTable::new();
+        shown.set("shown", true);
+        let mut hidden = ScriptTable::new();
+        hidden.set("shown", false);
+
+        let (mut ui, tree) = Ui::new(
+            &template, Some(&shown), style, Vector2::new(100.0, 50.0), &context,
+        ).unwrap();
+        let dialog_tree = ui.insert_template(&dialog, Some(&hidden), "dialogs", &context).unwrap();
+
+        // Resolving the first tree again should not pick up the model of the second
+        let child = ui.get(tree.root()).unwrap().children()[0];
+        let red = TemplateValue::Tuple(vec![
+            TemplateValue::Integer(255), TemplateValue::Integer(0), TemplateValue::Integer(0),
+        ]);
+        ui.set_attribute(child, "color", red, &context).unwrap();
+        assert!(ui.get(child).unwrap().attributes().visible);
+        assert!(!ui.get(dialog_tree.root()).unwrap().attributes().visible);
+    }
+
+    #[test]
+    fn it_keeps_components_when_reloading_trees() {
+        let context = Context::for_test();
+        let dialog = Template::from_str("container\n").unwrap();
+        let (mut ui, tree) = Ui::for_test(&[
+            "container.dialogs",
+            "    container { name: \"a\" }",
+            "    container { opacity: 1.0 }",
+            "    container { name: \"gone\" }",
+            "",
+        ].join("\n")).unwrap();
+        let reloaded = Template::from_str(&[
+            "container.dialogs",
+            "    container { name: \"b\" }",
+            "    container { opacity: 0.5 }",
+            "    container { name: \"a\" }",
+            "",
+        ].join("\n"), "", &context);
+        let dialog_tree = ui.insert_template(&dialog, None, "dialogs", &context).unwrap();
+        let old = ui.get(tree.root()).unwrap().children().clone();
+
+        ui.reload_tree(&tree, &reloaded, &context).unwrap();
+
+        // Named components are found after moving, unnamed ones by their place, and inserted
+        // trees stay at the end
+        let children = ui.get(tree.root()).unwrap().children().clone();
+        assert_eq!(children.len(), 4);
+        assert!(!old.contains(&children[0]));
+        assert_eq!(&children[1..], &[old[1], old[0], dialog_tree.root()]);
+        assert_eq!(ui.get(old[1]).unwrap().attributes().opacity, 0.5);
+        assert!(ui.get(old[2]).is_none());
+    }
+
+    #[test]
+    fn it_only_updates_changed_components_when_reloading_trees() {
+        let context = Context::for_test();
+        let (mut ui, tree) = Ui::for_test(&[
+            "container",
+            "    container { color: (255, 0, 0) }",
+            "        container { opacity: 1.0 }",
+            "    container { color: (0, 255, 0) }",
+            "",
+        ].join("\n")).unwrap();
+        let reloaded = Template::from_str(&[
+            "container",
+            "",
+            "    container { color: (255, 0, 0) }",
+            "        container { opacity: 1.0 }",
+            "    container { color: (0, 0, 255) }",
+            "",
+        ].join("\n"), "", &context);
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+        let children = ui.get(tree.root()).unwrap().children().clone();
+        let nested = ui.get(children[0]).unwrap().children()[0];
+
+        ui.reload_tree(&tree, &reloaded, &context).unwrap();
+
+        // Moving in the markup doesn't count as a change, so those components keep their cache
+        assert!(!ui.get(tree.root()).unwrap().needs_rendering());
+        assert!(!ui.get(children[0]).unwrap().needs_rendering());
+        assert!(!ui.get(nested).unwrap().needs_rendering());
+        assert!(ui.get(children[1]).unwrap().needs_rendering());
+        assert_eq!(
+            ui.get(children[0]).unwrap().template().location,
+            reloaded.root.children[0].location,
+        );
+    }
+
+    #[test]
+    fn it_reports_where_bad_attributes_were_set() {
+        let context = Context::for_test();
+        let template = Template::from_str("container\n").unwrap();
+        let mut style = Style::from_str("container { size: \"large\" }\n").unwrap();
+        style.set_source_name("style.mark");
+
+        match Ui::new(&template, None, style, Vector2::new(100.0, 50.0), &context) {
+            Err(Error::Attribute { location, source: Some(source), .. }) => {
+                assert_eq!(location.line, 1);
+                assert_eq!(location.name, None);
+                assert_eq!(source.name.as_ref().map(|n| &**n), Some("style.mark"));
+                assert_eq!((source.line, source.column), (1, 13));
+            },
+            _ => panic!("Expected an attribute error with a source"),
+        }
+    }
+
+    #[test]
+    fn it_names_components_in_attribute_errors() {
+        let context = Context::for_test();
+        let (ui, tree) = Ui::for_test(
+            "container { name: \"toolbar\", size: \"large\" }\n"
+        ).unwrap();
+        let style = Style::from_str("").unwrap();
+
+        match Ui::new(&template, None, style, Vector2::new(100.0, 50.0), &context) {
+            Err(Error::Attribute { component, name, field, .. }) => {
+                assert_eq!(component, "container");
+                assert_eq!(name, Some("toolbar".into()));
+                assert_eq!(field, "size");
+            },
+            _ => panic!("Expected an attribute error"),
+        }
+
+        let template = Template::from_str("container { name: \"toolbar\" }\n",
+            "", &context,
+        );
+        let root = ui.get(tree.root()).unwrap();
+        assert_eq!(root.name(), Some("toolbar"));
+        assert_eq!(root.label(), "container \"toolbar\"");
+    }
+
+    #[test]
+    fn it_warns_about_unused_attributes_and_styles() {
+        let context = Context::for_test();
+        let (ui, _tree) = Ui::for_test(
+            "container { colour: (255, 0, 0) }\n", "contaner { color: (255, 0, 0) }\n", &context,
+        );
+
+        let warnings = ui.warnings().take();
+        assert_eq!(warnings.len(), 2);
+        match warnings[0] {
+            Warning::UnmatchedStyle { ref class, .. } => assert_eq!(class, "contaner"),
+            ref other => panic!("Unexpected warning {:?}", other),
+        }
+        match warnings[1] {
+            Warning::UnknownAttribute { ref component, ref key, .. } => {
+                assert_eq!(component, "container");
+                assert_eq!(key, "colour");
+            },
+            ref other => panic!("Unexpected warning {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_switches_theme_palettes() {
+        let context = Context::for_test();
+        let (mut ui, tree) = Ui::for_test(
+            "container { color: $primary }\n", "@theme { primary: (255, 0, 0) }\n", &context,
+        );
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+        match renderer.commands(tree.root()).unwrap()[0] {
+            RenderCommand::Vertices { color, .. } =>
+                assert_eq!(color, Color::new(1.0, 0.0, 0.0, 1.0)),
+            ref command => panic!("Unexpected command {:?}", command),
+        }
+
+        let palette = Style::from_str("@theme { primary: (0, 0, 255) }\n").unwrap();
+        ui.set_theme(palette.theme, &context).unwrap();
+        assert!(render::render_changed(&mut renderer, &mut ui).unwrap());
+        match renderer.commands(tree.root()).unwrap()[0] {
+            RenderCommand::Vertices { color, .. } =>
+                assert_eq!(color, Color::new(0.0, 0.0, 1.0, 1.0)),
+            ref command => panic!("Unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn it_switches_styles_at_runtime() {
+        let context = Context::for_test();
+        let (mut ui, tree) = Ui::for_test(
+            "container\n    container\n", "container { color: (255, 0, 0) }\n", &context,
+        );
+        let child = ui.get(tree.root()).unwrap().children()[0];
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+
+        let style = Style::from_str("container { color: (0, 0, 255) }\n").unwrap();
+        ui.set_style(style, &context).unwrap();
+        assert!(render::render_changed(&mut renderer, &mut ui).unwrap());
+        match renderer.commands(child).unwrap()[0] {
+            RenderCommand::Vertices { color, .. } =>
+                assert_eq!(color, Color::new(0.0, 0.0, 1.0, 1.0)),
+            ref command => panic!("Unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn it_inherits_text_attributes_from_parents() {
+        let mut context = Context::for_test();
+        context.classes.register::<ButtonClass>("button");
+        let (mut ui, tree) = Ui::for_test(
+            "container { text-font: \"serif\", text-size: 20 }\n    \
+                button { text: \"Inherited\" }\n    \
+                button { text: \"Overridden\", text-size: 12 }\n",
+            "", &context,
+        );
+        let children = ui.get(tree.root()).unwrap().children().to_vec();
+
+        // The container doesn't use the attributes itself, but isn't warned about them
+        assert_eq!(context.warnings.next(), None);
+
+        let text = |renderer: &RecordingRenderer, id| {
+            renderer.commands(id).unwrap().iter().filter_map(|c| match *c {
+                RenderCommand::Text { ref font, text_size, .. } => Some((font.clone(), text_size)),
+                _ => None,
+            }).next().unwrap()
+        };
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+        assert_eq!(text(&renderer, children[0]), (Some("serif".into()), Some(20)));
+        assert_eq!(text(&renderer, children[1]), (Some("serif".into()), Some(12)));
+
+        // Changing the attribute at runtime passes it down as well
+        let size = TemplateValue::Integer(30);
+        ui.set_attribute(tree.root(), "text-size", size, &context).unwrap();
+        render::render_changed(&mut renderer, &mut ui).unwrap();
+        assert_eq!(text(&renderer, children[0]), (Some("serif".into()), Some(30)));
+        assert_eq!(text(&renderer, children[1]), (Some("serif".into()), Some(12)));
+    }
+
+    #[test]
+    fn it_restyles_components_by_style_class() {
+        let context = Context::for_test();
+        let (mut ui, tree) = Ui::for_test(
+            "container\n",
+            "container { color: (255, 0, 0) }\ncontainer.selected { color: (0, 0, 255) }\n",
+            &context,
+        );
+        let root = tree.root();
+        let color = |renderer: &RecordingRenderer| match renderer.commands(root).unwrap()[0] {
+            RenderCommand::Vertices { color, .. } => color,
+            ref command => panic!("Unexpected command {:?}", command),
+        };
+
+        let mut renderer = RecordingRenderer::new();
+        render::render(&mut renderer, &mut ui).unwrap();
+        assert_eq!(color(&renderer), Color::new(1.0, 0.0, 0.0, 1.0));
+
+        ui.add_style_class(tree.root(), "selected", &context).unwrap();
+        assert!(render::render_changed(&mut renderer, &mut ui).unwrap());
+        assert_eq!(color(&renderer), Color::new(0.0, 0.0, 1.0, 1.0));
+
+        ui.remove_style_class(tree.root(), "selected", &context).unwrap();
+        assert!(render::render_changed(&mut renderer, &mut ui).unwrap());
+        assert_eq!(color(&renderer), Color::new(1.0, 0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn it_raises_events_with_payloads_from_scripts() {
+        let mut context = Context::for_test();
+        context.classes.register::<ButtonClass>("button");
+        let template = Template::from_str(
+            "button { on-pressed: @{ raise(\"buy\", model.item) } }\n"
+        ).unwrap();
+        let style = Style::from_str("").unwrap();
+        let mut model = ScriptTable::new();
+        model.set("item", "sword".to_string());
+        let (mut ui, tree) = Ui::new(
+            &template, Some(&model), style, Vector2::new(100.0, 50.0), &context,
+        ).unwrap();
+        let mut input = Input::new();
+        let position = Point2::new(5.0, 5.0);
+
+        // Scripts run when the commands are applied, as they need the context
+        input.handle_drag_started(position, Modifiers::none(), &mut ui);
+        input.handle_drag_ended(position, Modifiers::none(), &mut ui);
+        assert_eq!(tree.event_sink().next_event(), None);
+
+        ui.apply_commands(&context).unwrap();
+        let event = tree.event_sink().next_event().unwrap();
+        assert_eq!(event.name, "buy");
+        assert_eq!(event.payload, Some(ScriptValue::String("sword".into())));
+    }
+}
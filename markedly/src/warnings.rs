@@ -0,0 +1,56 @@
+use std::collections::{VecDeque};
+
+use shared::{Shared, lock};
+use template::{SourceLocation};
+
+/// A collector for problems that don't stop a UI from working, but are likely mistakes, such as
+/// typos in attribute names. These are collected rather than returned as errors so a single
+/// mistake only affects the part of the UI it's in.
+///
+/// Every handle to the same collector shares it, so it can be cloned freely.
+#[derive(Clone)]
+pub struct Warnings {
+    warnings: Shared<VecDeque<Warning>>,
+}
+
+impl Warnings {
+    pub fn new() -> Self {
+        Warnings {
+            warnings: Default::default(),
+        }
+    }
+
+    /// Adds a warning.
+    pub fn push(&self, warning: Warning) {
+        warn!("UI warning: {:?}", warning);
+        lock(&self.warnings).push_back(warning);
+    }
+
+    /// Retrieves the next warning, or returns None.
+    pub fn next(&self) -> Option<Warning> {
+        lock(&self.warnings).pop_front()
+    }
+
+    /// Takes all warnings, in the order they were added.
+    pub fn take(&self) -> Vec<Warning> {
+        lock(&self.warnings).drain(..).collect()
+    }
+}
+
+/// A problem that doesn't stop a UI from working, collected in `Warnings`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Warning {
+    /// An attribute was set on a component that neither the component nor its class uses, and is
+    /// ignored.
+    UnknownAttribute { component: String, key: String, source: Option<SourceLocation> },
+    /// A resource, such as an image, couldn't be found by the renderer, and isn't shown.
+    MissingResource { resource: Option<String>, error: String },
+    /// A component in the style has a component class that isn't registered, so it never applies
+    /// to any component.
+    UnmatchedStyle { class: String, location: SourceLocation },
+    /// A translation was used that the active locale doesn't have a text for, so its key is
+    /// shown instead.
+    MissingTranslation { key: String, locale: String },
+    /// Markup that changed on disk couldn't be loaded, so the UI was left as it was.
+    ReloadFailed { error: String },
+}